@@ -94,10 +94,10 @@ async fn edit_register(
             println!("Editing Register {printing_name} with: {entry}");
             match register.write_online(entry.as_bytes(), verify_store).await {
                 Ok(()) => {}
-                Err(ref err @ ClientError::ContentBranchDetected(ref branches)) => {
+                Err(ref err @ ClientError::ContentBranchDetected(ref resolver)) => {
                     println!(
                         "We need to merge {} branches in Register entries: {err}",
-                        branches.len()
+                        resolver.branches().len()
                     );
                     register
                         .write_merging_branches_online(entry.as_bytes(), verify_store)
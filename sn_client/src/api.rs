@@ -7,27 +7,47 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::{
+    chunks::compute_data_map,
     error::{Error, Result},
-    Client, ClientEvent, ClientEventsChannel, ClientEventsReceiver, ClientRegister,
+    register::tagged_register_meta,
+    Client, ClientConfig, ClientEvent, ClientEventsChannel, ClientEventsReceiver, ClientRegister,
+    ConfigOverrides, RegisterSpec,
 };
 
 use bls::{PublicKey, SecretKey, Signature};
+use bytes::Bytes;
+use futures::stream::{self, StreamExt};
 use indicatif::ProgressBar;
-use libp2p::{kad::Record, Multiaddr};
-use sn_dbc::{DbcId, SignedSpend, Token};
-use sn_networking::{multiaddr_is_global, NetworkEvent, SwarmDriver, CLOSE_GROUP_SIZE};
+use libp2p::{kad::Record, Multiaddr, PeerId};
+use self_encryption::DataMap;
+use sn_dbc::{DbcId, DbcTransaction, SignedSpend, Token};
+use sn_networking::{
+    close_group_majority, multiaddr_is_global, sort_peers_by_address, NetworkEvent, SwarmDriver,
+    CLOSE_GROUP_SIZE,
+};
 use sn_protocol::{
     error::Error as ProtocolError,
-    messages::PaymentProof,
+    messages::{
+        Cmd, CmdOk, CmdResponse, PaymentProof, Query, QueryResponse, RegisterBatch, ReplicatedData,
+        Request, Response,
+    },
     storage::{
         try_deserialize_record, try_serialize_record, Chunk, ChunkAddress, ChunkWithPayment,
-        DbcAddress, RecordHeader, RecordKind, RegisterAddress,
+        DbcAddress, RecordHeader, RecordKind, RegisterAddress, SignedData, SignedDataAddress,
     },
     NetworkAddress, PrettyPrintRecordKey,
 };
-use sn_registers::SignedRegister;
-use sn_transfers::client_transfers::SpendRequest;
-use std::time::Duration;
+use sn_registers::{Entry, EntryHash, Permissions, RegisterOp, RegisterVersion, SignedRegister};
+use sn_transfers::{
+    client_transfers::SpendRequest,
+    dbc_genesis::{is_genesis_parent_tx, GENESIS_DBC},
+    spends::validate_spend,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    future::Future,
+    time::Duration,
+};
 use tokio::task::spawn;
 use tracing::trace;
 use xor_name::XorName;
@@ -35,12 +55,49 @@ use xor_name::XorName;
 /// The timeout duration for the client to receive any response from the network.
 const INACTIVITY_TIMEOUT: std::time::Duration = tokio::time::Duration::from_secs(30);
 
+/// The interval between polls of the close group while waiting for a spend to be confirmed.
+const SPEND_CONFIRMATION_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// The longest the check of whether a record is already stored may delay its upload.
+const STORED_RECORD_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The maximum number of Registers being created concurrently by `create_registers`.
+const REGISTERS_BATCH_MAX_SIZE: usize = 8;
+
+/// The maximum number of spends, or parent spends, being handled concurrently by `spend_multi`.
+const SPENDS_BATCH_MAX_SIZE: usize = 8;
+
+/// How well a write got replicated, as reported by the close group of the data right after it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WriteReport {
+    /// Number of peers confirming they hold the written data
+    pub replicas_acked: usize,
+    /// Number of peers the data is meant to be held by
+    pub replicas_targeted: usize,
+    /// Whether the peers holding the data hold diverging versions of it
+    pub divergent: bool,
+}
+
 impl Client {
     /// Instantiate a new client.
     pub async fn new(
         signer: SecretKey,
         peers: Option<Vec<Multiaddr>>,
         req_response_timeout: Option<Duration>,
+    ) -> Result<Self> {
+        let mut config = ClientConfig::default();
+        if let Some(timeout) = req_response_timeout {
+            config.request_timeout = timeout;
+        }
+        Self::new_with_config(signer, peers, config).await
+    }
+
+    /// Instantiate a new client with the given retry and timeout settings,
+    /// usually those of a `Profile`, see `ClientConfig::profile`.
+    pub async fn new_with_config(
+        signer: SecretKey,
+        peers: Option<Vec<Multiaddr>>,
+        config: ClientConfig,
     ) -> Result<Self> {
         // If any of our contact peers has a global address, we'll assume we're in a global network.
         let local = match peers {
@@ -52,7 +109,8 @@ impl Client {
         info!("Starting Kad swarm in client mode...");
 
         let (network, mut network_event_receiver, swarm_driver) =
-            SwarmDriver::new_client(local, req_response_timeout)?;
+            SwarmDriver::new_client(local, Some(config.request_timeout))?;
+        network.set_request_attempts(config.request_attempts);
         info!("Client constructed network and swarm_driver");
         let events_channel = ClientEventsChannel::default();
 
@@ -63,6 +121,7 @@ impl Client {
             peers_added: 0,
             progress: Some(Self::setup_connection_progress()),
             network_store_cost: 0,
+            config,
         };
 
         // subscribe to our events channel first, so we don't have intermittent
@@ -222,6 +281,40 @@ impl Client {
         self.signer.public_key()
     }
 
+    /// Compute the `DataMap` the self-encryption of `bytes` results in, without storing
+    /// anything to the network. Self-encryption is deterministic, so the same bytes always result
+    /// in the same `DataMap`, hence in the same chunks once stored.
+    /// The bytes must be at least `MIN_ENCRYPTABLE_BYTES` long, smaller files not being
+    /// self-encrypted.
+    pub fn compute_data_map(&self, bytes: Bytes) -> Result<DataMap> {
+        Ok(compute_data_map(bytes)?)
+    }
+
+    /// Prefer the given peers, e.g. fast or trusted ones, for all the operations of this client
+    /// and its clones: when among the close group of an item, they're contacted first, and only
+    /// them when a single response will do, unless none of them responds.
+    /// The peers outside of the close group of an item are never contacted for it.
+    pub fn set_preferred_peers(&self, peers: Vec<PeerId>) {
+        self.network.set_preferred_peers(peers);
+    }
+
+    /// Compute the close group of peers expected to be responsible for the `address`, out of
+    /// the peers of our local routing table, without querying the network.
+    ///
+    /// The result reflects our own, possibly stale or partial, view of the network, hence may
+    /// differ from the close group the network currently agrees on.
+    pub async fn responsible_peers(&self, address: NetworkAddress) -> Result<Vec<PeerId>> {
+        let our_peer_id = self.network.peer_id;
+        let peers = self
+            .network
+            .get_all_local_peers()
+            .await?
+            .into_iter()
+            .filter(|peer| *peer != our_peer_id)
+            .collect();
+        close_group_among(peers, &address)
+    }
+
     /// Get a register from network
     pub async fn get_signed_register_from_network(
         &self,
@@ -253,12 +346,85 @@ impl Client {
         }
     }
 
+    /// Get the most complete replica of a register from the network.
+    /// The close group is asked for their replicas, and all the valid ones are merged, rather
+    /// than keeping the first one to arrive.
+    /// Falls back to `get_signed_register_from_network` should none be valid.
+    pub async fn get_most_complete_signed_register_from_network(
+        &self,
+        address: RegisterAddress,
+    ) -> Result<SignedRegister> {
+        let request = Request::Query(Query::GetReplicatedData {
+            requester: NetworkAddress::from_peer(self.network.peer_id),
+            address: NetworkAddress::from_register_address(address),
+        });
+        let responses = self.send_to_closest(&request, true).await?;
+
+        if let Some(register) = merge_register_replicas(responses, address) {
+            return Ok(register);
+        }
+
+        debug!("Could not fetch any replica of Register {address:?} from its close group");
+        self.get_signed_register_from_network(address).await
+    }
+
+    /// Get the ops of the Register at `address` which are missing from a replica whose current
+    /// entries are `known_heads`, as held by the close group of the Register.
+    pub async fn get_register_delta(
+        &self,
+        address: RegisterAddress,
+        known_heads: BTreeSet<EntryHash>,
+    ) -> Result<BTreeSet<RegisterOp>> {
+        let request = Request::Query(Query::GetRegisterDelta {
+            address,
+            known_heads,
+        });
+        let responses = self.send_to_closest(&request, true).await?;
+
+        // The close group may not be fully in sync, so we take all the ops any of them has.
+        let mut found = false;
+        let mut ops = BTreeSet::new();
+        for response in responses {
+            match response {
+                Ok(Response::Query(QueryResponse::RegisterDelta(Ok(delta)))) => {
+                    found = true;
+                    ops.extend(delta);
+                }
+                other => trace!("No Register delta for {address:?} from a peer: {other:?}"),
+            }
+        }
+
+        if !found {
+            return Err(ProtocolError::RegisterNotFound(Box::new(address)).into());
+        }
+        debug!("Got Register delta of {} ops for {address:?}", ops.len());
+        Ok(ops)
+    }
+
     /// Retrieve a Register from the network.
     pub async fn get_register(&self, address: RegisterAddress) -> Result<ClientRegister> {
         info!("Retrieving a Register replica at {address}");
         ClientRegister::retrieve(self.clone(), address).await
     }
 
+    /// Retrieve the history of the Register named `name` and tagged `tag`, owned by us, from the
+    /// network, i.e. the one whose meta is `tagged_register_meta(name, tag)`: every entry it holds,
+    /// including those overwritten, each listed after the entries it was written atop of, see
+    /// `ClientRegister::changelog`.
+    pub async fn get_register_history(
+        &self,
+        name: XorName,
+        tag: u64,
+    ) -> Result<Vec<(EntryHash, Entry)>> {
+        let address = RegisterAddress::new(tagged_register_meta(name, tag), self.signer_pk());
+        let register = self.get_register(address).await?;
+        Ok(register
+            .changelog()
+            .into_iter()
+            .map(|entry| (entry.hash, entry.value))
+            .collect())
+    }
+
     /// Create a new Register on the Network.
     pub async fn create_register(
         &self,
@@ -269,7 +435,126 @@ impl Client {
         ClientRegister::create_online(self.clone(), meta, verify_store).await
     }
 
+    /// Create several Registers on the Network, with up to `REGISTERS_BATCH_MAX_SIZE` of them
+    /// being created concurrently.
+    /// The Registers sharing a close group are sent to it as one `Cmd::RegisterBatch` of up to
+    /// `REGISTERS_BATCH_MAX_SIZE` Registers, while a Register alone in its close group is
+    /// created on its own.
+    /// A result is returned for each of the `specs`, in the same order.
+    pub async fn create_registers(
+        &self,
+        specs: Vec<RegisterSpec>,
+        verify_store: bool,
+    ) -> Vec<Result<ClientRegister>> {
+        info!("Instantiating {} new Register replicas", specs.len());
+        let close_groups = create_in_batches(specs, |spec| async move {
+            let register = ClientRegister::create_with_spec(self.clone(), spec)?;
+            let signed_register = register.signed_register()?;
+            let dst = NetworkAddress::from_register_address(*register.address());
+            let close_group = self.network.client_get_closest_peers(&dst).await?;
+            Ok((close_group, (register, signed_register)))
+        })
+        .await;
+
+        let (failed, batches) = batches_by_close_group(close_groups);
+        let mut results: BTreeMap<usize, Result<ClientRegister>> = failed
+            .into_iter()
+            .map(|(index, err)| (index, Err(err)))
+            .collect();
+        let created: Vec<_> = stream::iter(batches)
+            .map(|(close_group, batch)| {
+                self.create_register_batch(close_group, batch, verify_store)
+            })
+            .buffer_unordered(REGISTERS_BATCH_MAX_SIZE)
+            .collect()
+            .await;
+        results.extend(created.into_iter().flatten());
+        results.into_values().collect()
+    }
+
+    // Create the Registers of the `batch`, which share the `close_group`, returning the result of
+    // each along with its index in the `batch`. A Register alone in its close group is created
+    // on its own rather than as a batch.
+    async fn create_register_batch(
+        &self,
+        close_group: Vec<PeerId>,
+        mut batch: Vec<(usize, (ClientRegister, SignedRegister))>,
+        verify_store: bool,
+    ) -> Vec<(usize, Result<ClientRegister>)> {
+        if batch.len() == 1 {
+            if let Some((index, (mut register, _))) = batch.pop() {
+                let result = register.sync(verify_store).await.map(|_| register);
+                return vec![(index, result)];
+            }
+        }
+
+        let addresses: Vec<RegisterAddress> = batch
+            .iter()
+            .map(|(_, (register, _))| *register.address())
+            .collect();
+        let dst = match addresses.first() {
+            Some(address) => NetworkAddress::from_register_address(*address),
+            None => return vec![],
+        };
+        let (registers, signed_registers): (Vec<_>, Vec<_>) = batch
+            .into_iter()
+            .map(|(index, (register, signed_register))| ((index, register), signed_register))
+            .unzip();
+        debug!(
+            "Sending a batch of {} Registers to {dst:?}",
+            registers.len()
+        );
+        let request = Request::Cmd(Cmd::RegisterBatch(RegisterBatch {
+            dst,
+            registers: signed_registers,
+        }));
+        let responses = self
+            .network
+            .send_and_get_responses(close_group, &request, true)
+            .await;
+
+        let mut created = Vec::with_capacity(registers.len());
+        let stored = register_batch_results(responses, &addresses);
+        for ((index, register), stored) in registers.into_iter().zip(stored) {
+            let result = match stored {
+                Ok(()) if verify_store => self
+                    .get_signed_register_from_network(*register.address())
+                    .await
+                    .map(|_| register),
+                Ok(()) => Ok(register),
+                Err(err) => Err(err),
+            };
+            created.push((index, result));
+        }
+        created
+    }
+
+    /// Retrieve the Register with the given `meta` and owned by us from the network, or create it
+    /// with the provided `permissions` and send it to the network should it not exist yet.
+    /// If the creation collides with a concurrent creation of the same Register, the Register
+    /// stored by the latter is retrieved instead.
+    pub async fn open_or_create_register(
+        &self,
+        meta: XorName,
+        permissions: Permissions,
+        verify_store: bool,
+    ) -> Result<ClientRegister> {
+        let address = RegisterAddress::new(meta, self.signer_pk());
+        info!("Opening or creating the Register at {address}");
+        open_or_create(
+            || self.get_register(address),
+            || {
+                let spec = RegisterSpec { meta, permissions };
+                ClientRegister::create_online_with_spec(self.clone(), spec, verify_store)
+            },
+        )
+        .await
+    }
+
     /// Store `Chunk` as a record.
+    /// Chunks are content-addressed, so an upload, be it the first attempt or a retry, is skipped
+    /// if a majority of the close group already holds the chunk, e.g. a previous attempt had in
+    /// fact succeeded.
     pub(super) async fn store_chunk(
         &self,
         chunk: Chunk,
@@ -277,7 +562,8 @@ impl Client {
         verify_store: bool,
     ) -> Result<()> {
         info!("Store chunk: {:?}", chunk.address());
-        let key = NetworkAddress::from_chunk_address(*chunk.address()).to_record_key();
+        let address = NetworkAddress::from_chunk_address(*chunk.address());
+        let key = address.to_record_key();
         let chunk_with_payment = ChunkWithPayment { chunk, payment };
         let record = Record {
             key,
@@ -286,7 +572,94 @@ impl Client {
             expires: None,
         };
 
-        Ok(self.network.put_record(record, verify_store).await?)
+        store_with_retries_unless_held(
+            self.config.store_retries,
+            || self.is_record_held_by_quorum(&address),
+            || async {
+                Ok(self
+                    .network
+                    .put_record(record.clone(), verify_store)
+                    .await?)
+            },
+        )
+        .await
+    }
+
+    /// Same as storing a `Chunk` with `upload_chunks_in_batches`, but returning a `WriteReport`
+    /// of the peers of the chunk's close group holding it right after the store.
+    pub async fn store_chunk_with_report(
+        &self,
+        chunk: Chunk,
+        payment: PaymentProof,
+        verify_store: bool,
+    ) -> Result<WriteReport> {
+        let address = NetworkAddress::from_chunk_address(*chunk.address());
+        self.store_chunk(chunk, payment, verify_store).await?;
+
+        let request = Request::Query(Query::HasRecord(address));
+        let responses = self.send_to_closest(&request, true).await?;
+        Ok(chunk_write_report(responses))
+    }
+
+    /// Ask the close group of the Register at `address` for their replica's version, reporting
+    /// how many of them hold at least `op_count` ops, and whether their replicas diverge.
+    pub(crate) async fn register_write_report(
+        &self,
+        address: RegisterAddress,
+        op_count: usize,
+    ) -> Result<WriteReport> {
+        let request = Request::Query(Query::GetRegisterVersion(address));
+        let responses = self.send_to_closest(&request, true).await?;
+        Ok(register_write_report(responses, op_count))
+    }
+
+    // Send the `request` to the close group of its destination, each attempt timing out after
+    // the `query_timeout`, and retryable failures being retried up to `query_retries` times.
+    async fn send_to_closest(
+        &self,
+        request: &Request,
+        expect_all_responses: bool,
+    ) -> Result<Vec<std::result::Result<Response, sn_networking::Error>>> {
+        self.send_to_closest_with(
+            request,
+            expect_all_responses,
+            self.config.query_timeout,
+            self.config.query_retries,
+        )
+        .await
+    }
+
+    // Same as `send_to_closest`, with the given `timeout` and `retries` instead of the client's.
+    async fn send_to_closest_with(
+        &self,
+        request: &Request,
+        expect_all_responses: bool,
+        timeout: Duration,
+        retries: usize,
+    ) -> Result<Vec<std::result::Result<Response, sn_networking::Error>>> {
+        with_retries(retries, || {
+            with_timeout(timeout, async {
+                Ok(self
+                    .network
+                    .client_send_to_closest(request, expect_all_responses)
+                    .await?)
+            })
+        })
+        .await
+    }
+
+    // Check whether a majority of the close group of `address` already holds the record.
+    // The check is a single attempt of at most `STORED_RECORD_CHECK_TIMEOUT`, as it delays the upload.
+    async fn is_record_held_by_quorum(&self, address: &NetworkAddress) -> bool {
+        let request = Request::Query(Query::HasRecord(address.clone()));
+        let timeout = self.config.query_timeout.min(STORED_RECORD_CHECK_TIMEOUT);
+        match self.send_to_closest_with(&request, false, timeout, 0).await {
+            Ok(responses) => is_held_by_quorum(responses),
+            Err(err) => {
+                trace!("Could not check whether {address:?} is already stored: {err:?}");
+                false
+            }
+        }
     }
 
     /// Retrieve a `Chunk` from the kad network.
@@ -306,11 +679,57 @@ impl Client {
         }
     }
 
-    /// Send a `SpendDbc` request to the network
+    /// Store `value` under `name`, signed by the owner's `owner_sk`.
+    /// Signed data is immutable: storing different data at an address already in use fails.
+    pub async fn store_signed_data(
+        &self,
+        name: XorName,
+        owner_sk: &SecretKey,
+        value: Bytes,
+        verify_store: bool,
+    ) -> Result<SignedDataAddress> {
+        let data = SignedData::new(name, owner_sk, value);
+        let address = data.address();
+        info!("Store signed data: {address:?}");
+        let record = Record {
+            key: NetworkAddress::from_signed_data_address(address).to_record_key(),
+            value: try_serialize_record(&data, RecordKind::SignedData)?,
+            publisher: None,
+            expires: None,
+        };
+
+        with_retries(self.config.store_retries, || async {
+            Ok(self
+                .network
+                .put_record(record.clone(), verify_store)
+                .await?)
+        })
+        .await?;
+        Ok(address)
+    }
+
+    /// Retrieve the value stored under `name` by the owner `owner_pk`,
+    /// having checked it is signed by that owner.
+    pub async fn get_signed_data(&self, name: XorName, owner_pk: PublicKey) -> Result<Bytes> {
+        let address = SignedDataAddress::from_owner_and_name(&owner_pk, &name);
+        info!("Getting signed data: {address:?}");
+        let key = NetworkAddress::from_signed_data_address(address).to_record_key();
+        let record = self
+            .network
+            .get_record_from_network(key, None, false)
+            .await?;
+        let data = signed_data_from_record(&record, address)?;
+        Ok(data.value().clone())
+    }
+
+    /// Send a `SpendDbc` request to the network.
+    /// Should `replicas` be provided, the spend is sent to that many of the closest peers rather
+    /// than to the close group only, see `Network::put_record_with_redundancy`.
     pub(crate) async fn network_store_spend(
         &self,
         spend: SpendRequest,
         verify_store: bool,
+        replicas: Option<usize>,
     ) -> Result<()> {
         let dbc_id = *spend.signed_spend.dbc_id();
         let dbc_addr = DbcAddress::from_dbc_id(&dbc_id);
@@ -324,7 +743,85 @@ impl Client {
             publisher: None,
             expires: None,
         };
-        Ok(self.network.put_record(record, verify_store).await?)
+        with_retries(self.config.store_retries, || async {
+            match replicas {
+                Some(replicas) => Ok(self
+                    .network
+                    .put_record_with_redundancy(record.clone(), replicas, verify_store)
+                    .await?),
+                None => Ok(self
+                    .network
+                    .put_record(record.clone(), verify_store)
+                    .await?),
+            }
+        })
+        .await
+    }
+
+    /// Validate a spend as the network would, without submitting it.
+    ///
+    /// The parent spends of the `source_tx` (the tx the spent dbc was created in) and any spend
+    /// already recorded for the same dbc are fetched from the network, and the spend is then
+    /// checked locally with the same rules nodes apply before storing it.
+    /// Returns the `ProtocolError` a node would respond with if it would reject the spend.
+    pub async fn dry_run_spend(
+        &self,
+        signed_spend: &SignedSpend,
+        source_tx: &DbcTransaction,
+    ) -> Result<()> {
+        let dbc_id = signed_spend.dbc_id();
+        trace!("Dry running spend of {dbc_id:?}");
+
+        let parent_spends = fetch_parent_spends([signed_spend], |parent_dbc_id| async move {
+            self.get_spend_from_network(&parent_dbc_id).await.ok()
+        })
+        .await;
+
+        // As nodes do, treat any failure to get a spend for the dbc as there being none.
+        let network_spends = self
+            .get_spend_from_network(dbc_id)
+            .await
+            .map(|spend| vec![spend])
+            .unwrap_or_default();
+
+        validate_with_parents(signed_spend, source_tx, &parent_spends, network_spends)?;
+        trace!("Dry run of spend of {dbc_id:?} succeeded");
+        Ok(())
+    }
+
+    /// Validate and send several spends, such as those of a transaction with many outputs,
+    /// with up to `SPENDS_BATCH_MAX_SIZE` of them being handled concurrently.
+    ///
+    /// Each spend is validated as in `dry_run_spend` before being sent, the parent spends shared
+    /// by several of the spends being fetched only once for the whole batch.
+    /// A result is returned for each of the `spends`, in the same order.
+    pub async fn spend_multi(&self, spends: Vec<(SignedSpend, DbcTransaction)>) -> Vec<Result<()>> {
+        trace!("Submitting a batch of {} spends", spends.len());
+        let signed_spends = spends.iter().map(|(signed_spend, _)| signed_spend);
+        let parent_spends = fetch_parent_spends(signed_spends, |parent_dbc_id| async move {
+            self.get_spend_from_network(&parent_dbc_id).await.ok()
+        })
+        .await;
+
+        let parent_spends = &parent_spends;
+        stream::iter(spends)
+            .map(|(signed_spend, source_tx)| async move {
+                let network_spends = self
+                    .get_spend_from_network(signed_spend.dbc_id())
+                    .await
+                    .map(|spend| vec![spend])
+                    .unwrap_or_default();
+                validate_with_parents(&signed_spend, &source_tx, parent_spends, network_spends)?;
+
+                let spend = SpendRequest {
+                    signed_spend,
+                    parent_tx: source_tx,
+                };
+                self.network_store_spend(spend, false, None).await
+            })
+            .buffered(SPENDS_BATCH_MAX_SIZE)
+            .collect()
+            .await
     }
 
     /// Get a dbc spend from network
@@ -400,6 +897,86 @@ impl Client {
         }
     }
 
+    /// Wait until at least `quorum` peers of the close group of the `address` report holding
+    /// the same spend, i.e. the spend has been durably recorded by the network.
+    ///
+    /// The close group is polled until then, or until the `timeout` elapses, in which case
+    /// an `Error::ResponseTimeout` is returned. Only the spends of the `address` signed by their
+    /// owner are counted. The `quorum` must be within `1..=CLOSE_GROUP_SIZE`, or
+    /// `Error::InvalidSpendQuorum` is returned.
+    pub async fn wait_for_spend(
+        &self,
+        address: DbcAddress,
+        quorum: usize,
+        timeout: Duration,
+    ) -> Result<()> {
+        let config = ClientConfig {
+            spend_quorum: quorum,
+            spend_confirmation_timeout: timeout,
+            ..self.config.clone()
+        };
+        self.wait_for_spend_with_config(address, &config).await
+    }
+
+    /// Wait until the spend at `address` is recorded by the network, i.e. held by the
+    /// `spend_quorum` of the client's `ClientConfig`, or by that of the `overrides` if set.
+    /// The close group is polled with the `query_timeout` and `query_retries` of the overrides
+    /// too, should they be set.
+    pub async fn wait_for_spend_recorded(
+        &self,
+        address: DbcAddress,
+        overrides: &ConfigOverrides,
+    ) -> Result<()> {
+        let config = self.config.with_overrides(overrides);
+        self.wait_for_spend_with_config(address, &config).await
+    }
+
+    // Wait for the `spend_quorum` of the `config` to hold the spend at `address`, polling the
+    // close group with the `config`'s query settings until its `spend_confirmation_timeout`.
+    async fn wait_for_spend_with_config(
+        &self,
+        address: DbcAddress,
+        config: &ClientConfig,
+    ) -> Result<()> {
+        let quorum = config.spend_quorum;
+        trace!("Waiting for {quorum} peers to hold the spend at {address:?}");
+        let request = Request::Query(Query::GetReplicatedData {
+            requester: NetworkAddress::from_peer(self.network.peer_id),
+            address: NetworkAddress::from_dbc_address(address),
+        });
+
+        let poll = || async {
+            match self
+                .send_to_closest_with(&request, true, config.query_timeout, config.query_retries)
+                .await
+            {
+                Ok(responses) => responses
+                    .into_iter()
+                    .filter_map(|response| match response {
+                        Ok(Response::Query(QueryResponse::GetReplicatedData(Ok((
+                            _holder,
+                            ReplicatedData::DbcSpend(spends),
+                        ))))) => Some(spends),
+                        _ => None,
+                    })
+                    .collect(),
+                Err(err) => {
+                    warn!("Failed to poll the close group of {address:?}: {err:?}");
+                    vec![]
+                }
+            }
+        };
+
+        wait_for_spend_quorum(
+            poll,
+            address,
+            quorum,
+            SPEND_CONFIRMATION_POLL_INTERVAL,
+            config.spend_confirmation_timeout,
+        )
+        .await
+    }
+
     /// Get the store cost at a given address
     /// Replaces current network_store_cost with the new one, unless average is set to true
     pub async fn get_store_cost_at_address(
@@ -431,3 +1008,1097 @@ impl Client {
         Ok(Token::from_nano(cost))
     }
 }
+
+// Create an item for each of the `specs` using the provided `create` fn, keeping up to
+// `REGISTERS_BATCH_MAX_SIZE` creations in flight, and returning the results in order.
+async fn create_in_batches<S, T, F, Fut>(specs: Vec<S>, create: F) -> Vec<Result<T>>
+where
+    F: FnMut(S) -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    stream::iter(specs)
+        .map(create)
+        .buffered(REGISTERS_BATCH_MAX_SIZE)
+        .collect()
+        .await
+}
+
+// A batch of items sharing a close group, each item along with its index.
+type CloseGroupBatch<T> = (Vec<PeerId>, Vec<(usize, T)>);
+
+// Split the items, each with the close group it's stored by, into batches of up to
+// `REGISTERS_BATCH_MAX_SIZE` items sharing a close group, each item along with its index.
+// The items whose close group couldn't be looked up are returned aside, with the error.
+fn batches_by_close_group<T>(
+    items: Vec<Result<(Vec<PeerId>, T)>>,
+) -> (Vec<(usize, Error)>, Vec<CloseGroupBatch<T>>) {
+    let mut failed = vec![];
+    let mut groups: BTreeMap<Vec<PeerId>, Vec<(usize, T)>> = BTreeMap::new();
+    for (index, item) in items.into_iter().enumerate() {
+        match item {
+            Ok((mut close_group, item)) => {
+                close_group.sort();
+                groups.entry(close_group).or_default().push((index, item));
+            }
+            Err(err) => failed.push((index, err)),
+        }
+    }
+
+    let mut batches = vec![];
+    for (close_group, mut group) in groups {
+        while group.len() > REGISTERS_BATCH_MAX_SIZE {
+            let rest = group.split_off(REGISTERS_BATCH_MAX_SIZE);
+            batches.push((close_group.clone(), group));
+            group = rest;
+        }
+        batches.push((close_group, group));
+    }
+    (failed, batches)
+}
+
+// Whether each of the Registers at the `addresses`, sent as a `Cmd::RegisterBatch`, was stored
+// by a majority of its close group, as per the `responses` of the peers of the latter.
+// A Register not stored is reported with the first error a peer returned for it, if any.
+fn register_batch_results(
+    responses: Vec<std::result::Result<Response, sn_networking::Error>>,
+    addresses: &[RegisterAddress],
+) -> Vec<Result<()>> {
+    let peer_results: Vec<Vec<std::result::Result<CmdOk, ProtocolError>>> = responses
+        .into_iter()
+        .filter_map(|response| match response {
+            Ok(Response::Cmd(CmdResponse::RegisterBatch(Ok(results)))) => Some(results),
+            Ok(other) => {
+                trace!("Unexpected response to a RegisterBatch: {other:?}");
+                None
+            }
+            Err(err) => {
+                trace!("Failed to send a RegisterBatch: {err:?}");
+                None
+            }
+        })
+        .collect();
+
+    addresses
+        .iter()
+        .enumerate()
+        .map(|(index, address)| {
+            let results = || peer_results.iter().filter_map(|results| results.get(index));
+            if results().filter(|result| result.is_ok()).count() >= close_group_majority() {
+                return Ok(());
+            }
+            let err = results()
+                .find_map(|result| result.clone().err())
+                .unwrap_or_else(|| ProtocolError::RegisterNotStored(Box::new(*address)));
+            Err(err.into())
+        })
+        .collect()
+}
+
+// Run `open`, falling back to `create` should the item not exist. Should `create` collide with a
+// concurrent creation of the same item, `open` is run again to retrieve the item created by the latter.
+async fn open_or_create<T, O, OFut, C, CFut>(open: O, create: C) -> Result<T>
+where
+    O: Fn() -> OFut,
+    OFut: Future<Output = Result<T>>,
+    C: FnOnce() -> CFut,
+    CFut: Future<Output = Result<T>>,
+{
+    match open().await {
+        Err(Error::Network(sn_networking::Error::RecordNotFound)) => {}
+        result => return result,
+    }
+
+    match create().await {
+        Err(err) if is_creation_collision(&err) => {
+            debug!("Creation collided with a concurrent one, opening instead: {err:?}");
+            open().await
+        }
+        result => result,
+    }
+}
+
+// Whether a creation failed due to the item having been created concurrently, i.e. the stored
+// item is not the one we created.
+fn is_creation_collision(err: &Error) -> bool {
+    matches!(
+        err,
+        Error::Protocol(ProtocolError::RegisterAlreadyClaimed(_))
+            | Error::Network(
+                sn_networking::Error::FailedToVerifyRecordWasStored(_)
+                    | sn_networking::Error::ReturnedRecordDoesNotMatch(_)
+            )
+    )
+}
+
+// Run `op`, retrying it up to `retries` times should it fail with a retryable error.
+async fn with_retries<T, F, Fut>(retries: usize, mut op: F) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    let mut attempt = 0;
+    loop {
+        match op().await {
+            Err(err) if err.is_retryable() && attempt < retries => {
+                attempt += 1;
+                warn!("Attempt failed, retrying ({attempt}/{retries}): {err:?}");
+            }
+            result => return result,
+        }
+    }
+}
+
+// Run `op`, failing with `Error::ResponseTimeout` should it not complete within `timeout`.
+async fn with_timeout<T>(timeout: Duration, op: impl Future<Output = Result<T>>) -> Result<T> {
+    match tokio::time::timeout(timeout, op).await {
+        Ok(result) => result,
+        Err(elapsed) => Err(elapsed.into()),
+    }
+}
+
+// Run `store`, retrying it up to `retries` times should it fail with a retryable error. Before each
+// attempt, `held` is checked, the attempt being skipped should the record be stored already.
+async fn store_with_retries_unless_held<H, HFut, S, SFut>(
+    retries: usize,
+    held: H,
+    store: S,
+) -> Result<()>
+where
+    H: Fn() -> HFut,
+    HFut: Future<Output = bool>,
+    S: Fn() -> SFut,
+    SFut: Future<Output = Result<()>>,
+{
+    let (held, store) = (&held, &store);
+    with_retries(retries, || async move {
+        if held().await {
+            debug!("Record already held by a majority of its close group, skipping the upload");
+            return Ok(());
+        }
+        store().await
+    })
+    .await
+}
+
+// Whether a majority of the close group reported holding the record in their responses to a
+// `HasRecord` query.
+fn is_held_by_quorum(responses: Vec<std::result::Result<Response, sn_networking::Error>>) -> bool {
+    holders_of_record(responses) >= close_group_majority()
+}
+
+// Count the peers which reported holding the record in their responses to a `HasRecord` query.
+fn holders_of_record(responses: Vec<std::result::Result<Response, sn_networking::Error>>) -> usize {
+    responses
+        .into_iter()
+        .filter(|response| {
+            matches!(
+                response,
+                Ok(Response::Query(QueryResponse::HasRecord(Ok(true))))
+            )
+        })
+        .count()
+}
+
+// Build the `WriteReport` of a chunk out of the responses of its close group to a `HasRecord` query.
+// Chunks are content-addressed, hence replicas never diverge.
+fn chunk_write_report(
+    responses: Vec<std::result::Result<Response, sn_networking::Error>>,
+) -> WriteReport {
+    WriteReport {
+        replicas_targeted: responses.len(),
+        replicas_acked: holders_of_record(responses),
+        divergent: false,
+    }
+}
+
+// Build the `WriteReport` of a Register out of the responses of its close group to a
+// `GetRegisterVersion` query, a replica acknowledging the write when holding at least `op_count` ops.
+fn register_write_report(
+    responses: Vec<std::result::Result<Response, sn_networking::Error>>,
+    op_count: usize,
+) -> WriteReport {
+    let replicas_targeted = responses.len();
+    let versions: Vec<RegisterVersion> = responses
+        .into_iter()
+        .filter_map(|response| match response {
+            Ok(Response::Query(QueryResponse::RegisterVersion(Ok((_, version))))) => Some(version),
+            _ => None,
+        })
+        .collect();
+    let state_hashes: BTreeSet<XorName> = versions.iter().map(|v| v.state_hash).collect();
+
+    WriteReport {
+        replicas_acked: versions.iter().filter(|v| v.op_count >= op_count).count(),
+        replicas_targeted,
+        divergent: state_hashes.len() > 1,
+    }
+}
+
+// Deserialize the `SignedData` held by `record`, checking it is the one signed by the owner
+// at `address`, as any node could have answered in its place.
+fn signed_data_from_record(record: &Record, address: SignedDataAddress) -> Result<SignedData> {
+    let header = RecordHeader::from_record(record)?;
+    if let RecordKind::SignedData = header.kind {
+        let data: SignedData = try_deserialize_record(record)?;
+        data.verify_with_address(address)?;
+        Ok(data)
+    } else {
+        Err(ProtocolError::RecordKindMismatch(RecordKind::SignedData).into())
+    }
+}
+
+// Merge all the valid replicas of the Register at `address` out of the responses to a
+// `GetReplicatedData` query, for the result to be at least as complete as any of them.
+fn merge_register_replicas(
+    responses: Vec<std::result::Result<Response, sn_networking::Error>>,
+    address: RegisterAddress,
+) -> Option<SignedRegister> {
+    let mut merged: Option<SignedRegister> = None;
+    for response in responses {
+        let replica = match response {
+            Ok(Response::Query(QueryResponse::GetReplicatedData(Ok((
+                _holder,
+                ReplicatedData::Register(replica),
+            )))))
+                if replica.verify_with_address(address).is_ok() =>
+            {
+                replica
+            }
+            other => {
+                trace!("No valid Register replica of {address:?} from a peer: {other:?}");
+                continue;
+            }
+        };
+        match merged.as_mut() {
+            Some(register) => {
+                if let Err(err) = register.merge(replica) {
+                    warn!("Could not merge a replica of Register {address:?}: {err:?}");
+                }
+            }
+            None => merged = Some(replica),
+        }
+    }
+    merged
+}
+
+// The dbcs whose spends are the parents of the `signed_spend`, i.e. the inputs of the tx the
+// spent dbc was created in, the genesis dbc having none.
+fn parent_dbc_ids(signed_spend: &SignedSpend) -> Vec<DbcId> {
+    let creation_tx = &signed_spend.spend.dbc_creation_tx;
+    if is_genesis_parent_tx(creation_tx) && signed_spend.dbc_id() == &GENESIS_DBC.id {
+        return vec![];
+    }
+    creation_tx
+        .inputs
+        .iter()
+        .map(|input| input.dbc_id())
+        .collect()
+}
+
+// Fetch the parent spends of all the `spends` using the provided `fetch` fn, each parent being
+// fetched once however many of the spends share it. The parents not found are left out.
+async fn fetch_parent_spends<'a, F, Fut>(
+    spends: impl IntoIterator<Item = &'a SignedSpend>,
+    fetch: F,
+) -> BTreeMap<DbcId, SignedSpend>
+where
+    F: Fn(DbcId) -> Fut,
+    Fut: Future<Output = Option<SignedSpend>>,
+{
+    let parent_dbc_ids: BTreeSet<DbcId> = spends.into_iter().flat_map(parent_dbc_ids).collect();
+
+    stream::iter(parent_dbc_ids)
+        .map(|dbc_id| {
+            let fetched = fetch(dbc_id);
+            async move { fetched.await.map(|spend| (dbc_id, spend)) }
+        })
+        .buffer_unordered(SPENDS_BATCH_MAX_SIZE)
+        .filter_map(|parent| async move { parent })
+        .collect()
+        .await
+}
+
+// Validate the `signed_spend` with the same rules nodes apply before storing it, its parents
+// being looked up in the already fetched `parent_spends`.
+fn validate_with_parents(
+    signed_spend: &SignedSpend,
+    source_tx: &DbcTransaction,
+    parent_spends: &BTreeMap<DbcId, SignedSpend>,
+    network_spends: Vec<SignedSpend>,
+) -> Result<()> {
+    let dbc_id = signed_spend.dbc_id();
+    if source_tx != &signed_spend.spend.dbc_creation_tx {
+        return Err(ProtocolError::SpendParentTxInvalid(format!(
+            "The provided source tx is not the one the dbc {dbc_id:?} was created in"
+        ))
+        .into());
+    }
+
+    let mut parents = BTreeSet::new();
+    for parent_dbc_id in parent_dbc_ids(signed_spend) {
+        match parent_spends.get(&parent_dbc_id) {
+            Some(parent) => {
+                let _ = parents.insert(parent.clone());
+            }
+            None => {
+                return Err(
+                    ProtocolError::SpendNotFound(DbcAddress::from_dbc_id(&parent_dbc_id)).into(),
+                )
+            }
+        }
+    }
+
+    validate_spend(signed_spend, &parents, network_spends)?;
+    Ok(())
+}
+
+// Poll the spends at `address` held by each peer until `quorum` of them hold the same single
+// spend, ignoring the spends which aren't of the address or signed by their owner.
+// Peers holding more than one spend for the address hold a double spend, which is returned as such.
+async fn wait_for_spend_quorum<F, Fut>(
+    mut poll: F,
+    address: DbcAddress,
+    quorum: usize,
+    interval: Duration,
+    timeout: Duration,
+) -> Result<()>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Vec<Vec<SignedSpend>>>,
+{
+    if !(1..=CLOSE_GROUP_SIZE).contains(&quorum) {
+        return Err(Error::InvalidSpendQuorum {
+            quorum,
+            max: CLOSE_GROUP_SIZE,
+        });
+    }
+    let is_valid = |spend: &SignedSpend| {
+        let valid = DbcAddress::from_dbc_id(spend.dbc_id()) == address
+            && spend.verify(spend.spent_tx_hash()).is_ok();
+        if !valid {
+            warn!("Ignoring an invalid spend held for {address:?}: {spend:?}");
+        }
+        valid
+    };
+
+    let wait = async {
+        loop {
+            let mut holders: BTreeMap<SignedSpend, usize> = BTreeMap::new();
+            for mut spends in poll().await {
+                spends.retain(is_valid);
+                match spends.as_slice() {
+                    [spend] => *holders.entry(spend.clone()).or_default() += 1,
+                    [one, two, ..] => {
+                        return Err(ProtocolError::DoubleSpendAttempt(
+                            Box::new(one.clone()),
+                            Box::new(two.clone()),
+                        )
+                        .into())
+                    }
+                    [] => {}
+                }
+            }
+
+            if holders.values().any(|count| *count >= quorum) {
+                return Ok(());
+            }
+            trace!("Spend not held by {quorum} peers yet: {holders:?}");
+            tokio::time::sleep(interval).await;
+        }
+    };
+
+    tokio::time::timeout(timeout, wait).await?
+}
+
+// The `CLOSE_GROUP_SIZE` peers closest to the `address` among the `peers`, by increasing distance.
+fn close_group_among(peers: Vec<PeerId>, address: &NetworkAddress) -> Result<Vec<PeerId>> {
+    Ok(sort_peers_by_address(peers, address, CLOSE_GROUP_SIZE)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bls::SecretKey;
+    use sn_protocol::test_utils::random_signed_spend;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    #[test]
+    fn data_map_and_chunks_are_reproducible() -> Result<()> {
+        use crate::chunks::encrypt_large;
+        use rand::RngCore;
+
+        let client = crate::register::tests::offline_client()?;
+        let mut content = vec![0u8; 10 * self_encryption::MAX_CHUNK_SIZE + 7];
+        rand::thread_rng().fill_bytes(&mut content);
+        let content = Bytes::from(content);
+
+        let hash_of = |data_map: &DataMap| -> Result<XorName> {
+            Ok(XorName::from_content(&bincode::serialize(data_map)?))
+        };
+        let first = client.compute_data_map(content.clone())?;
+        let second = client.compute_data_map(content.clone())?;
+        assert_eq!(hash_of(&first)?, hash_of(&second)?);
+        assert!(first == second);
+
+        // as are the chunks stored, in the same order
+        let names = |chunks: Vec<Chunk>| -> Vec<XorName> {
+            chunks.iter().map(|chunk| *chunk.name()).collect()
+        };
+        let (first_root, first_chunks) = encrypt_large(content.clone())?;
+        let (second_root, second_chunks) = encrypt_large(content)?;
+        assert_eq!(first_root, second_root);
+        assert_eq!(names(first_chunks), names(second_chunks));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn registers_are_created_concurrently_with_per_register_results() -> Result<()> {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+
+        // the fourth Register was already claimed by someone else
+        let results = create_in_batches((0..20).collect(), |index: usize| {
+            let in_flight = in_flight.clone();
+            let max_in_flight = max_in_flight.clone();
+            async move {
+                let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                let _ = max_in_flight.fetch_max(current, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                let _ = in_flight.fetch_sub(1, Ordering::SeqCst);
+
+                if index == 3 {
+                    let owner = SecretKey::random().public_key();
+                    Err(ProtocolError::RegisterAlreadyClaimed(owner).into())
+                } else {
+                    Ok(index)
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(results.len(), 20);
+        for (index, result) in results.into_iter().enumerate() {
+            match result {
+                Ok(created) => assert_eq!(created, index),
+                Err(_) => assert_eq!(index, 3),
+            }
+        }
+        assert_eq!(
+            max_in_flight.load(Ordering::SeqCst),
+            REGISTERS_BATCH_MAX_SIZE
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn registers_sharing_a_close_group_are_batched() {
+        let shared: Vec<PeerId> = (0..CLOSE_GROUP_SIZE).map(|_| PeerId::random()).collect();
+        let mut shuffled = shared.clone();
+        shuffled.reverse();
+        let other: Vec<PeerId> = (0..CLOSE_GROUP_SIZE).map(|_| PeerId::random()).collect();
+        let alone: Vec<PeerId> = (0..CLOSE_GROUP_SIZE).map(|_| PeerId::random()).collect();
+
+        // ten Registers share a close group, whichever order its peers are listed in, one of them
+        // failing to be looked up, two share another close group and one is alone in its own
+        let mut items: Vec<Result<(Vec<PeerId>, usize)>> = (0..10)
+            .map(|index| {
+                let close_group = if index % 2 == 0 { &shared } else { &shuffled };
+                Ok((close_group.clone(), index))
+            })
+            .collect();
+        items[5] = Err(sn_networking::Error::NotEnoughPeers {
+            found: 0,
+            required: CLOSE_GROUP_SIZE,
+        }
+        .into());
+        items.push(Ok((other.clone(), 10)));
+        items.push(Ok((other.clone(), 11)));
+        items.push(Ok((alone.clone(), 12)));
+
+        let (failed, batches) = batches_by_close_group(items);
+        assert_eq!(
+            failed.iter().map(|(index, _)| *index).collect::<Vec<_>>(),
+            vec![5]
+        );
+
+        let mut batched: Vec<Vec<usize>> = batches
+            .iter()
+            .map(|(close_group, batch)| {
+                for (index, item) in batch {
+                    assert_eq!(index, item);
+                    let expected = match index {
+                        10 | 11 => &other,
+                        12 => &alone,
+                        _ => &shared,
+                    };
+                    let mut expected = expected.clone();
+                    expected.sort();
+                    assert_eq!(close_group, &expected);
+                }
+                batch.iter().map(|(index, _)| *index).collect()
+            })
+            .collect();
+        batched.sort();
+        let mut shared_indexes: Vec<usize> = (0..10).filter(|index| *index != 5).collect();
+        let overflow = shared_indexes.split_off(REGISTERS_BATCH_MAX_SIZE);
+        let mut expected = vec![shared_indexes, overflow, vec![10, 11], vec![12]];
+        expected.sort();
+        assert_eq!(batched, expected);
+    }
+
+    #[test]
+    fn registers_of_a_batch_are_stored_by_a_majority() {
+        let owner = SecretKey::random().public_key();
+        let addresses: Vec<RegisterAddress> = (0..3)
+            .map(|_| RegisterAddress::new(XorName::random(&mut rand::thread_rng()), owner))
+            .collect();
+        let quota_exceeded = ProtocolError::KindQuotaExceeded {
+            kind: sn_protocol::AddressKind::RegisterAddress,
+        };
+        let batch_response = |results| Ok(Response::Cmd(CmdResponse::RegisterBatch(results)));
+
+        // the first Register is stored by a majority, the second by one peer less, and the
+        // third by none, as the peers' quota of Registers is reached
+        let mut responses: Vec<_> = (1..close_group_majority())
+            .map(|_| {
+                batch_response(Ok(vec![
+                    Ok(CmdOk::StoredSuccessfully),
+                    Ok(CmdOk::StoredSuccessfully),
+                    Err(quota_exceeded.clone()),
+                ]))
+            })
+            .collect();
+        responses.push(batch_response(Ok(vec![Ok(CmdOk::DataAlreadyPresent)])));
+        responses.push(batch_response(Err(ProtocolError::Overloaded)));
+        responses.push(Err(sn_networking::Error::RecordNotFound));
+
+        let results = register_batch_results(responses, &addresses);
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok());
+        assert!(matches!(
+            &results[1],
+            Err(Error::Protocol(ProtocolError::RegisterNotStored(address))) if **address == addresses[1]
+        ));
+        assert!(matches!(
+            &results[2],
+            Err(Error::Protocol(err)) if *err == quota_exceeded
+        ));
+    }
+
+    #[tokio::test]
+    async fn open_or_create_opens_existing_items() -> Result<()> {
+        let creations = AtomicUsize::new(0);
+        let opened = open_or_create(
+            || async { Ok(7) },
+            || async {
+                let _ = creations.fetch_add(1, Ordering::SeqCst);
+                Ok(0)
+            },
+        )
+        .await?;
+
+        assert_eq!(opened, 7);
+        assert_eq!(creations.load(Ordering::SeqCst), 0);
+
+        // errors other than the item missing aren't papered over by a creation
+        let result = open_or_create(
+            || async { Err::<usize, _>(sn_networking::Error::NoSwarmCmdChannelCapacity.into()) },
+            || async {
+                let _ = creations.fetch_add(1, Ordering::SeqCst);
+                Ok(0)
+            },
+        )
+        .await;
+        assert!(result.is_err());
+        assert_eq!(creations.load(Ordering::SeqCst), 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn open_or_create_creates_missing_items() -> Result<()> {
+        let opens = AtomicUsize::new(0);
+        let created = open_or_create(
+            || async {
+                let _ = opens.fetch_add(1, Ordering::SeqCst);
+                Err(sn_networking::Error::RecordNotFound.into())
+            },
+            || async { Ok(3) },
+        )
+        .await?;
+
+        assert_eq!(created, 3);
+        assert_eq!(opens.load(Ordering::SeqCst), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn open_or_create_opens_items_created_concurrently() -> Result<()> {
+        // another client creates the item right after our first attempt at opening it
+        let created_by_other = AtomicUsize::new(0);
+        let opened = open_or_create(
+            || async {
+                match created_by_other.load(Ordering::SeqCst) {
+                    0 => Err(sn_networking::Error::RecordNotFound.into()),
+                    item => Ok(item),
+                }
+            },
+            || async {
+                created_by_other.store(42, Ordering::SeqCst);
+                let key = PrettyPrintRecordKey::from(libp2p::kad::RecordKey::new(b"reg"));
+                Err(sn_networking::Error::ReturnedRecordDoesNotMatch(key).into())
+            },
+        )
+        .await?;
+
+        assert_eq!(opened, 42);
+
+        // other creation failures are reported
+        let result = open_or_create(
+            || async { Err::<usize, _>(sn_networking::Error::RecordNotFound.into()) },
+            || async { Err(sn_networking::Error::NoSwarmCmdChannelCapacity.into()) },
+        )
+        .await;
+        assert!(matches!(
+            result,
+            Err(Error::Network(
+                sn_networking::Error::NoSwarmCmdChannelCapacity
+            ))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn write_reports_reflect_partial_acks() -> Result<()> {
+        let holds = |held: bool| Ok(Response::Query(QueryResponse::HasRecord(Ok(held))));
+        let mut responses: Vec<_> = (0..5).map(|_| holds(true)).collect();
+        responses.push(holds(false));
+        responses.push(Err(sn_networking::Error::RecordNotFound));
+        responses.push(Ok(Response::Cmd(
+            sn_protocol::messages::CmdResponse::Replicate(Ok(())),
+        )));
+        assert_eq!(
+            chunk_write_report(responses),
+            WriteReport {
+                replicas_acked: 5,
+                replicas_targeted: 8,
+                divergent: false,
+            }
+        );
+
+        let owner_sk = SecretKey::random();
+        let mut writer = sn_registers::Register::new(
+            owner_sk.public_key(),
+            xor_name::rand::random(),
+            sn_registers::Permissions::new_owner_only(),
+            None,
+        );
+        let mut stale = writer.clone().into_signed(&owner_sk)?;
+        let (first, mut op) = writer.write(b"first".to_vec(), BTreeSet::new())?;
+        op.sign_with(&owner_sk);
+        stale.add_op(op)?;
+        let mut written = stale.clone();
+        let (_second, mut op) = writer.write(b"second".to_vec(), [first].into())?;
+        op.sign_with(&owner_sk);
+        written.add_op(op)?;
+
+        let version = |register: &SignedRegister| {
+            let holder = NetworkAddress::from_peer(libp2p::PeerId::random());
+            Ok(Response::Query(QueryResponse::RegisterVersion(Ok((
+                holder,
+                register.version(),
+            )))))
+        };
+        // 6 of the 8 peers got the write, one lags behind and one didn't respond
+        let responses = |lagging: &SignedRegister| {
+            let mut responses: Vec<_> = (0..6).map(|_| version(&written)).collect();
+            responses.push(version(lagging));
+            responses.push(Err(sn_networking::Error::RecordNotFound));
+            responses
+        };
+        assert_eq!(
+            register_write_report(responses(&stale), 2),
+            WriteReport {
+                replicas_acked: 6,
+                replicas_targeted: 8,
+                divergent: true,
+            }
+        );
+
+        // once all the replicas caught up, they agree
+        let report = register_write_report(responses(&written), 2);
+        assert_eq!(report.replicas_acked, 7);
+        assert!(!report.divergent);
+
+        Ok(())
+    }
+
+    #[test]
+    fn register_replicas_are_merged() -> Result<()> {
+        let owner_sk = SecretKey::random();
+        let base = sn_registers::Register::new(
+            owner_sk.public_key(),
+            xor_name::rand::random(),
+            sn_registers::Permissions::new_owner_only(),
+            None,
+        );
+        let address = *base.address();
+        let mut writer = base.clone();
+        let signed_base = base.clone().into_signed(&owner_sk)?;
+        let mut replica_with =
+            |entry: &[u8], mut replica: SignedRegister| -> Result<SignedRegister> {
+                let (_hash, mut op) = writer.write(entry.to_vec(), BTreeSet::new())?;
+                op.sign_with(&owner_sk);
+                replica.add_op(op)?;
+                Ok(replica)
+            };
+
+        // each replica holds an entry the other lacks
+        let left = replica_with(b"left", signed_base.clone())?;
+        let right = replica_with(b"right", signed_base)?;
+        // a replica not signed by the owner is ignored
+        let forged_sig = SecretKey::random().sign(base.bytes()?);
+        let forged = replica_with(b"forged", SignedRegister::new(base, forged_sig))?;
+
+        let replica_response = |register: SignedRegister| {
+            Ok(Response::Query(QueryResponse::GetReplicatedData(Ok((
+                NetworkAddress::from_peer(libp2p::PeerId::random()),
+                ReplicatedData::Register(register),
+            )))))
+        };
+        let responses = vec![
+            replica_response(left),
+            replica_response(forged),
+            Err(sn_networking::Error::RecordNotFound),
+            replica_response(right),
+        ];
+
+        let merged = match merge_register_replicas(responses, address) {
+            Some(merged) => merged.register()?,
+            None => panic!("No replica of the Register was merged"),
+        };
+        let entries: BTreeSet<Vec<u8>> =
+            merged.read().into_iter().map(|(_, entry)| entry).collect();
+        assert_eq!(
+            entries,
+            [b"left".to_vec(), b"right".to_vec()].into_iter().collect()
+        );
+
+        assert!(merge_register_replicas(vec![], address).is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn chunk_already_held_by_quorum_is_not_reuploaded() -> Result<()> {
+        let has_record = |held| Ok(Response::Query(QueryResponse::HasRecord(Ok(held))));
+        let responses_of = |holders: usize| {
+            let mut responses: Vec<_> = (0..holders).map(|_| has_record(true)).collect();
+            responses.extend((holders..CLOSE_GROUP_SIZE - 1).map(|_| has_record(false)));
+            responses.push(Err(sn_networking::Error::RecordNotFound));
+            responses
+        };
+        let checks = AtomicUsize::new(0);
+        let uploads = AtomicUsize::new(0);
+        // the first upload times out, though it may have landed
+        let upload = || async {
+            if uploads.fetch_add(1, Ordering::SeqCst) == 0 {
+                Err(sn_networking::Error::RecordNotFound.into())
+            } else {
+                Ok(())
+            }
+        };
+
+        // a chunk already held by a majority isn't uploaded at all
+        let held = || async {
+            let _ = checks.fetch_add(1, Ordering::SeqCst);
+            is_held_by_quorum(responses_of(close_group_majority()))
+        };
+        store_with_retries_unless_held(2, held, upload).await?;
+        assert_eq!(uploads.load(Ordering::SeqCst), 0);
+        assert_eq!(checks.load(Ordering::SeqCst), 1);
+
+        // a chunk held by less than a majority is uploaded, and uploaded again on failure
+        let held = || async { is_held_by_quorum(responses_of(close_group_majority() - 1)) };
+        store_with_retries_unless_held(2, held, upload).await?;
+        assert_eq!(uploads.load(Ordering::SeqCst), 2);
+
+        // the chunk turning out to be held after a failed upload isn't uploaded again
+        checks.store(0, Ordering::SeqCst);
+        uploads.store(0, Ordering::SeqCst);
+        let held = || async { checks.fetch_add(1, Ordering::SeqCst) > 0 };
+        store_with_retries_unless_held(2, held, upload).await?;
+        assert_eq!(uploads.load(Ordering::SeqCst), 1);
+        assert_eq!(checks.load(Ordering::SeqCst), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn stores_are_retried_up_to_the_configured_retries() {
+        let attempts = AtomicUsize::new(0);
+        let failing_store = || async {
+            let _ = attempts.fetch_add(1, Ordering::SeqCst);
+            Err(sn_networking::Error::RecordNotFound.into())
+        };
+
+        let result: Result<()> = with_retries(2, failing_store).await;
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+        // terminal errors aren't retried
+        attempts.store(0, Ordering::SeqCst);
+        let rejected_store = || async {
+            let _ = attempts.fetch_add(1, Ordering::SeqCst);
+            Err(sn_networking::Error::InvalidCloseGroupSize.into())
+        };
+        let result: Result<()> = with_retries(2, rejected_store).await;
+        assert!(result.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn queries_outlasting_the_timeout_time_out_and_are_retried() -> Result<()> {
+        let attempts = AtomicUsize::new(0);
+        let hanging_query = || async {
+            let _ = attempts.fetch_add(1, Ordering::SeqCst);
+            futures::future::pending::<Result<()>>().await
+        };
+
+        let result = with_retries(2, || {
+            with_timeout(Duration::from_millis(20), hanging_query())
+        })
+        .await;
+        assert!(matches!(result, Err(Error::ResponseTimeout(_))));
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+        // a query answering in time after a timeout succeeds
+        attempts.store(0, Ordering::SeqCst);
+        let slow_once_query = || async {
+            if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+            }
+            Ok(7)
+        };
+        let answer = with_retries(2, || {
+            with_timeout(Duration::from_millis(20), slow_once_query())
+        })
+        .await?;
+        assert_eq!(answer, 7);
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn wait_for_spend_resolves_once_quorum_holds_the_spend() -> Result<()> {
+        let spend = random_signed_spend();
+        let polls = Arc::new(AtomicUsize::new(0));
+
+        // the spend reaches one more peer on each poll
+        let poll = || {
+            let holders = polls.fetch_add(1, Ordering::SeqCst);
+            let spend = spend.clone();
+            async move { vec![vec![spend]; holders] }
+        };
+
+        let address = DbcAddress::from_dbc_id(spend.dbc_id());
+        wait_for_spend_quorum(
+            poll,
+            address,
+            3,
+            Duration::from_millis(10),
+            Duration::from_secs(5),
+        )
+        .await?;
+        assert_eq!(polls.load(Ordering::SeqCst), 4);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn wait_for_spend_ignores_spends_not_signed_by_their_owner() {
+        let mut forged = random_signed_spend();
+        forged.derived_key_sig = SecretKey::random().sign(forged.spend.to_bytes());
+        let address = DbcAddress::from_dbc_id(forged.dbc_id());
+        let poll = || {
+            let holders = vec![vec![forged.clone()]; CLOSE_GROUP_SIZE];
+            async move { holders }
+        };
+
+        let result = wait_for_spend_quorum(
+            poll,
+            address,
+            1,
+            Duration::from_millis(10),
+            Duration::from_millis(100),
+        )
+        .await;
+        assert!(matches!(result, Err(Error::ResponseTimeout(_))));
+    }
+
+    #[tokio::test]
+    async fn wait_for_spend_rejects_a_quorum_out_of_the_close_group() {
+        let address = DbcAddress::from_dbc_id(random_signed_spend().dbc_id());
+        for quorum in [0, CLOSE_GROUP_SIZE + 1] {
+            let poll = || async { vec![] };
+            let result = wait_for_spend_quorum(
+                poll,
+                address,
+                quorum,
+                Duration::from_millis(10),
+                Duration::from_secs(5),
+            )
+            .await;
+            assert!(matches!(
+                result,
+                Err(Error::InvalidSpendQuorum { quorum: q, .. }) if q == quorum
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn wait_for_spend_times_out_without_quorum() {
+        let spend = random_signed_spend();
+        let other_spend = random_signed_spend();
+        let poll = || {
+            let holders = vec![vec![spend.clone()], vec![other_spend.clone()], vec![]];
+            async move { holders }
+        };
+
+        let result = wait_for_spend_quorum(
+            poll,
+            DbcAddress::from_dbc_id(spend.dbc_id()),
+            2,
+            Duration::from_millis(10),
+            Duration::from_millis(100),
+        )
+        .await;
+        assert!(matches!(result, Err(Error::ResponseTimeout(_))));
+    }
+
+    // A spend of a dbc created by a tx spending the `parents`.
+    fn spend_with_parents(parents: &[DbcId]) -> SignedSpend {
+        let mut signed_spend = random_signed_spend();
+        signed_spend.spend.dbc_creation_tx.inputs = parents
+            .iter()
+            .map(|parent| sn_dbc::Input::new(*parent, 0))
+            .collect();
+        signed_spend
+    }
+
+    #[tokio::test]
+    async fn parents_shared_by_a_batch_of_spends_are_fetched_once() {
+        let common_parent = random_signed_spend();
+        let own_parents: Vec<SignedSpend> = (0..3).map(|_| random_signed_spend()).collect();
+        let spends: Vec<SignedSpend> = own_parents
+            .iter()
+            .map(|own_parent| spend_with_parents(&[*common_parent.dbc_id(), *own_parent.dbc_id()]))
+            .collect();
+
+        let network: BTreeMap<DbcId, SignedSpend> = own_parents
+            .iter()
+            .chain([&common_parent])
+            .map(|parent| (*parent.dbc_id(), parent.clone()))
+            .collect();
+        let fetches = std::sync::Mutex::new(Vec::new());
+        let fetch = |dbc_id: DbcId| {
+            if let Ok(mut fetches) = fetches.lock() {
+                fetches.push(dbc_id);
+            }
+            let parent = network.get(&dbc_id).cloned();
+            async move { parent }
+        };
+
+        let parents = fetch_parent_spends(&spends, fetch).await;
+        assert_eq!(parents, network);
+
+        let fetches = fetches.into_inner().unwrap_or_default();
+        assert_eq!(fetches.len(), 4);
+        assert_eq!(
+            fetches
+                .iter()
+                .filter(|dbc_id| *dbc_id == common_parent.dbc_id())
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn spends_with_a_missing_parent_are_rejected() {
+        let parent = random_signed_spend();
+        let spend = spend_with_parents(&[*parent.dbc_id()]);
+        let source_tx = spend.spend.dbc_creation_tx.clone();
+
+        let result = validate_with_parents(&spend, &source_tx, &BTreeMap::new(), vec![]);
+        assert!(matches!(
+            result,
+            Err(Error::Protocol(ProtocolError::SpendNotFound(address)))
+                if address == DbcAddress::from_dbc_id(parent.dbc_id())
+        ));
+
+        let result = validate_with_parents(&spend, &Default::default(), &BTreeMap::new(), vec![]);
+        assert!(matches!(
+            result,
+            Err(Error::Protocol(ProtocolError::SpendParentTxInvalid(_)))
+        ));
+    }
+
+    fn signed_data_record(data: &SignedData, address: SignedDataAddress) -> Result<Record> {
+        Ok(Record::new(
+            NetworkAddress::from_signed_data_address(address).to_record_key(),
+            try_serialize_record(data, RecordKind::SignedData)?,
+        ))
+    }
+
+    #[test]
+    fn record_of_another_kind_is_not_taken_for_signed_data() -> Result<()> {
+        let owner_sk = SecretKey::random();
+        let name = XorName::random(&mut rand::thread_rng());
+        let address = SignedDataAddress::from_owner_and_name(&owner_sk.public_key(), &name);
+
+        let spend_record = Record::new(
+            NetworkAddress::from_signed_data_address(address).to_record_key(),
+            try_serialize_record(&random_signed_spend(), RecordKind::DbcSpend)?,
+        );
+        assert!(matches!(
+            signed_data_from_record(&spend_record, address),
+            Err(Error::Protocol(ProtocolError::RecordKindMismatch(
+                RecordKind::SignedData
+            )))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn responsible_peers_are_the_closest_of_the_routing_table() -> Result<()> {
+        let address = NetworkAddress::from_chunk_address(ChunkAddress::new(XorName::random(
+            &mut rand::thread_rng(),
+        )));
+        let routing_table: Vec<PeerId> = (0..3 * CLOSE_GROUP_SIZE)
+            .map(|_| PeerId::random())
+            .collect();
+
+        let mut expected = routing_table.clone();
+        expected.sort_by_key(|peer| address.distance(&NetworkAddress::from_peer(*peer)));
+        expected.truncate(CLOSE_GROUP_SIZE);
+        assert_eq!(close_group_among(routing_table, &address)?, expected);
+
+        // a routing table too sparse to hold a full close group
+        let sparse = (1..CLOSE_GROUP_SIZE).map(|_| PeerId::random()).collect();
+        assert!(matches!(
+            close_group_among(sparse, &address),
+            Err(Error::Network(sn_networking::Error::NotEnoughPeers { .. }))
+        ));
+
+        Ok(())
+    }
+}
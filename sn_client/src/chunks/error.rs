@@ -55,6 +55,12 @@ pub enum Error {
         missing_chunks: Vec<XorName>,
     },
 
+    #[error("Chunk {0:?} could not be retrieved.")]
+    ChunkNotRetrieved(XorName),
+
+    #[error("Chunk {0:?} retrieved does not match its address.")]
+    ChunkCorrupted(XorName),
+
     #[error("Not all data was chunked, expected {expected}, but we have {chunked}.)")]
     NotAllDataWasChunked {
         /// Number of Chunks expected to be generated
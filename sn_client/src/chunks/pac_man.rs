@@ -38,6 +38,12 @@ pub(crate) fn encrypt_large(data: Bytes) -> Result<(XorName, Vec<Chunk>)> {
     pack(data_map, encrypted_chunks)
 }
 
+/// Returns the `DataMap` of the data, without keeping its encrypted chunks.
+pub(crate) fn compute_data_map(data: Bytes) -> Result<DataMap> {
+    let (data_map, _) = encrypt_data(data)?;
+    Ok(data_map)
+}
+
 /// Returns the top-most chunk address through which the entire
 /// data tree can be accessed, and all the other encrypted chunks.
 /// If encryption is provided, the additional `DataMapLevel` chunks are encrypted with it.
@@ -66,7 +72,7 @@ pub(crate) fn pack(
             break (name, chunks);
         } else {
             let serialized_chunk = Bytes::from(serialize(&chunk)?);
-            let (data_map, next_encrypted_chunks) = self_encryption::encrypt(serialized_chunk)?;
+            let (data_map, next_encrypted_chunks) = self_encrypt(serialized_chunk)?;
             chunks = next_encrypted_chunks
                 .par_iter()
                 .map(|c| to_chunk(c.content.clone())) // no need to encrypt what is self-encrypted
@@ -104,11 +110,17 @@ fn pack_data_map(data_map: DataMapLevel) -> Result<Bytes> {
 
 fn encrypt_file(file: &Path) -> Result<(DataMap, Vec<EncryptedChunk>)> {
     let bytes = Bytes::from(std::fs::read(file)?);
-    let encrypted_chunk = self_encryption::encrypt(bytes)?;
-    Ok(encrypted_chunk)
+    self_encrypt(bytes)
 }
 
 fn encrypt_data(bytes: Bytes) -> Result<(DataMap, Vec<EncryptedChunk>)> {
-    let encrypted_chunk = self_encryption::encrypt(bytes)?;
-    Ok(encrypted_chunk)
+    self_encrypt(bytes)
+}
+
+// Self-encrypt the bytes, the encrypted chunks being in the order of the source chunks, as
+// self_encryption encrypts them in parallel and returns them in the order they were done in.
+fn self_encrypt(bytes: Bytes) -> Result<(DataMap, Vec<EncryptedChunk>)> {
+    let (data_map, mut encrypted_chunks) = self_encryption::encrypt(bytes)?;
+    encrypted_chunks.sort_by_key(|chunk| chunk.index);
+    Ok((data_map, encrypted_chunks))
 }
@@ -0,0 +1,185 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use sn_networking::{close_group_majority, CLOSE_GROUP_SIZE};
+use std::time::Duration;
+
+/// Presets of the retry and timeout settings of a `Client`, see `ClientConfig::profile`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Profile {
+    /// Fail fast, for apps where a user is waiting on the outcome.
+    Interactive,
+    /// A middle ground between latency and durability.
+    #[default]
+    Balanced,
+    /// Wait and retry for as long as it takes for the data to be durably stored.
+    Durable,
+}
+
+/// The retry and timeout settings of a `Client`.
+///
+/// Most users pick a `Profile`, while individual fields can still be set afterwards.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClientConfig {
+    /// Timeout of each request sent to a peer.
+    pub request_timeout: Duration,
+    /// The number of times a store failing with a retryable error is retried.
+    pub store_retries: usize,
+    /// The number of attempts at sending a request to each peer, the request being re-sent to
+    /// the peers it failed with a retryable error.
+    pub request_attempts: usize,
+    /// The number of peers of the close group which must hold a spend for it to be
+    /// deemed recorded by the network.
+    pub spend_quorum: usize,
+    /// How long to wait for `spend_quorum` peers to hold a spend.
+    pub spend_confirmation_timeout: Duration,
+    /// Timeout of each attempt at querying the close group of an item, the lookup of the close
+    /// group included.
+    pub query_timeout: Duration,
+    /// The number of times a query of the close group of an item failing with a retryable
+    /// error, e.g. timing out, is retried.
+    pub query_retries: usize,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self::profile(Profile::default())
+    }
+}
+
+impl ClientConfig {
+    /// The settings of the given `Profile`.
+    pub fn profile(profile: Profile) -> Self {
+        match profile {
+            Profile::Interactive => Self {
+                request_timeout: Duration::from_secs(10),
+                store_retries: 0,
+                request_attempts: 1,
+                spend_quorum: 1,
+                spend_confirmation_timeout: Duration::from_secs(30),
+                query_timeout: Duration::from_secs(20),
+                query_retries: 0,
+            },
+            Profile::Balanced => Self {
+                request_timeout: Duration::from_secs(30),
+                store_retries: 0,
+                request_attempts: 2,
+                spend_quorum: close_group_majority(),
+                spend_confirmation_timeout: Duration::from_secs(120),
+                query_timeout: Duration::from_secs(60),
+                query_retries: 1,
+            },
+            Profile::Durable => Self {
+                request_timeout: Duration::from_secs(60),
+                store_retries: 5,
+                request_attempts: 3,
+                spend_quorum: CLOSE_GROUP_SIZE,
+                spend_confirmation_timeout: Duration::from_secs(600),
+                query_timeout: Duration::from_secs(120),
+                query_retries: 3,
+            },
+        }
+    }
+
+    /// These settings, with any of the `overrides` set taking precedence.
+    pub fn with_overrides(&self, overrides: &ConfigOverrides) -> Self {
+        Self {
+            request_timeout: self.request_timeout,
+            store_retries: self.store_retries,
+            request_attempts: self.request_attempts,
+            spend_quorum: overrides.spend_quorum.unwrap_or(self.spend_quorum),
+            spend_confirmation_timeout: overrides
+                .spend_confirmation_timeout
+                .unwrap_or(self.spend_confirmation_timeout),
+            query_timeout: overrides.query_timeout.unwrap_or(self.query_timeout),
+            query_retries: overrides.query_retries.unwrap_or(self.query_retries),
+        }
+    }
+}
+
+/// Per-operation overrides of the `ClientConfig` of a `Client`.
+///
+/// Only the settings an operation can change on its own are overridable: the request timeout
+/// is that of the client's network, and stores always use the client's `store_retries`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConfigOverrides {
+    /// Overrides `ClientConfig::spend_quorum`.
+    pub spend_quorum: Option<usize>,
+    /// Overrides `ClientConfig::spend_confirmation_timeout`.
+    pub spend_confirmation_timeout: Option<Duration>,
+    /// Overrides `ClientConfig::query_timeout`.
+    pub query_timeout: Option<Duration>,
+    /// Overrides `ClientConfig::query_retries`.
+    pub query_retries: Option<usize>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profiles_set_the_expected_values() {
+        let interactive = ClientConfig::profile(Profile::Interactive);
+        assert_eq!(interactive.request_timeout, Duration::from_secs(10));
+        assert_eq!(interactive.store_retries, 0);
+        assert_eq!(interactive.request_attempts, 1);
+        assert_eq!(interactive.spend_quorum, 1);
+        assert_eq!(
+            interactive.spend_confirmation_timeout,
+            Duration::from_secs(30)
+        );
+        assert_eq!(interactive.query_timeout, Duration::from_secs(20));
+        assert_eq!(interactive.query_retries, 0);
+
+        let balanced = ClientConfig::profile(Profile::Balanced);
+        assert_eq!(balanced.request_timeout, Duration::from_secs(30));
+        assert_eq!(balanced.store_retries, 0);
+        assert_eq!(balanced.request_attempts, 2);
+        assert_eq!(balanced.spend_quorum, close_group_majority());
+        assert_eq!(
+            balanced.spend_confirmation_timeout,
+            Duration::from_secs(120)
+        );
+        assert_eq!(balanced.query_timeout, Duration::from_secs(60));
+        assert_eq!(balanced.query_retries, 1);
+        assert_eq!(ClientConfig::default(), balanced);
+
+        let durable = ClientConfig::profile(Profile::Durable);
+        assert_eq!(durable.request_timeout, Duration::from_secs(60));
+        assert_eq!(durable.store_retries, 5);
+        assert_eq!(durable.request_attempts, 3);
+        assert_eq!(durable.spend_quorum, CLOSE_GROUP_SIZE);
+        assert_eq!(durable.spend_confirmation_timeout, Duration::from_secs(600));
+        assert_eq!(durable.query_timeout, Duration::from_secs(120));
+        assert_eq!(durable.query_retries, 3);
+    }
+
+    #[test]
+    fn overrides_take_precedence_over_the_profile() {
+        let durable = ClientConfig::profile(Profile::Durable);
+        let overrides = ConfigOverrides {
+            spend_quorum: Some(2),
+            query_retries: Some(0),
+            ..Default::default()
+        };
+
+        let config = durable.with_overrides(&overrides);
+        assert_eq!(config.spend_quorum, 2);
+        assert_eq!(config.query_retries, 0);
+        // the fields not overridden are the profile's
+        assert_eq!(config.request_timeout, durable.request_timeout);
+        assert_eq!(config.store_retries, durable.store_retries);
+        assert_eq!(config.query_timeout, durable.query_timeout);
+        assert_eq!(
+            config.spend_confirmation_timeout,
+            durable.spend_confirmation_timeout
+        );
+
+        assert_eq!(durable.with_overrides(&ConfigOverrides::default()), durable);
+    }
+}
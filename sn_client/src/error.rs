@@ -8,12 +8,11 @@
 
 pub(crate) type Result<T> = std::result::Result<T, Error>;
 
-use super::ClientEvent;
+use super::{BranchResolver, ClientEvent};
 
-use sn_protocol::storage::ChunkAddress;
-use sn_registers::{Entry, EntryHash};
+use sn_protocol::{storage::ChunkAddress, NetworkAddress};
+use sn_registers::EntryHash;
 
-use std::collections::BTreeSet;
 use thiserror::Error;
 
 /// Internal error.
@@ -45,6 +44,9 @@ pub enum Error {
     #[error("Failed to verify transfer validity in the network {0}")]
     CouldNotVerifyTransfer(String),
 
+    #[error("The spend quorum must be within 1..={max}, got {quorum}")]
+    InvalidSpendQuorum { quorum: usize, max: usize },
+
     #[error("Chunks error {0}.")]
     Chunks(#[from] super::chunks::Error),
 
@@ -53,14 +55,78 @@ pub enum Error {
 
     #[error(
         "Content branches detected in the Register which need to be merged/resolved by user. \
-        Entries hashes of branches are: {0:?}"
+        Entries hashes of branches are: {:?}", .0.hashes()
     )]
-    ContentBranchDetected(BTreeSet<(EntryHash, Entry)>),
+    ContentBranchDetected(Box<BranchResolver>),
+
+    #[error("No branches to resolve in the Register")]
+    NoBranchesToResolve,
+
+    #[error("Entry {0:?} not found in the Register, nor in its replicas on the network")]
+    EntryNotFound(EntryHash),
+
+    #[error("Failed to compress the Register entry: {0}")]
+    EntryCompressionFailed(std::io::Error),
+
+    #[error("Failed to decrypt the Register entry {0:?} with the key provided")]
+    EntryDecryptionFailed(EntryHash),
+
+    #[error("The Register of the ledger has branches, its records are no longer in sequence")]
+    LedgerForked,
+
+    #[error(
+        "Entry {0:?} of the ledger is not chained to the previous one or not signed by its owner"
+    )]
+    LedgerTampered(EntryHash),
+
+    #[error("Failed to access the Register write-ahead log: {0}")]
+    WriteAheadLog(std::io::Error),
 
     #[error("Missing a payment proof for address {0:?}")]
     MissingPaymentProof(ChunkAddress),
 
+    #[error("The root of a file must be the address of a chunk, not {0:?}")]
+    RootNotAChunk(Box<NetworkAddress>),
+
+    #[error("The operation was cancelled, having completed {completed} of its steps")]
+    OperationCancelled { completed: usize },
+
+    #[error("The operation was aborted before completing")]
+    OperationAborted,
+
     /// A general error when a transfer fails.
     #[error("Failed to send tokens due to {0}")]
     CouldNotSendTokens(String),
 }
+
+impl Error {
+    /// Returns `true` if the error is transient and the operation is worth retrying.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::Network(error) => error.is_retryable(),
+            Error::Protocol(error) => error.is_retryable(),
+            Error::ResponseTimeout(_) => true,
+            Error::Transfers(_)
+            | Error::Register(_)
+            | Error::EventsReceiver(_)
+            | Error::EventsSender(_)
+            | Error::CouldNotVerifyTransfer(_)
+            | Error::InvalidSpendQuorum { .. }
+            | Error::Chunks(_)
+            | Error::BincodeError(_)
+            | Error::ContentBranchDetected(_)
+            | Error::NoBranchesToResolve
+            | Error::EntryNotFound(_)
+            | Error::EntryCompressionFailed(_)
+            | Error::EntryDecryptionFailed(_)
+            | Error::LedgerForked
+            | Error::LedgerTampered(_)
+            | Error::WriteAheadLog(_)
+            | Error::MissingPaymentProof(_)
+            | Error::RootNotAChunk(_)
+            | Error::OperationCancelled { .. }
+            | Error::OperationAborted
+            | Error::CouldNotSendTokens(_) => false,
+        }
+    }
+}
@@ -9,17 +9,26 @@
 use super::{
     chunks::{to_chunk, DataMapLevel, Error, SmallFile},
     error::Result,
+    operation::{OperationHandle, OperationProgress},
     Client,
 };
 
-use sn_protocol::storage::{Chunk, ChunkAddress};
+use sn_protocol::{
+    storage::{Chunk, ChunkAddress},
+    NetworkAddress,
+};
 use sn_transfers::wallet::PaymentProofsMap;
 
 use bincode::deserialize;
 use bytes::Bytes;
-use futures::future::join_all;
+use futures::{
+    future::join_all,
+    stream::{self, Stream, StreamExt, TryStreamExt},
+    Future,
+};
 use itertools::Itertools;
 use self_encryption::{self, ChunkInfo, DataMap, EncryptedChunk, MIN_ENCRYPTABLE_BYTES};
+use std::sync::Arc;
 use tokio::task::{self, JoinHandle};
 use tracing::trace;
 use xor_name::XorName;
@@ -30,12 +39,23 @@ const CHUNKS_BATCH_MAX_SIZE: usize = 4;
 /// File APIs.
 pub struct Files {
     client: Client,
+    download_concurrency: usize,
 }
 
 impl Files {
     /// Create file apis instance.
     pub fn new(client: Client) -> Self {
-        Self { client }
+        Self {
+            client,
+            download_concurrency: CHUNKS_BATCH_MAX_SIZE,
+        }
+    }
+
+    /// Set the maximum number of chunks fetched concurrently when reading a file,
+    /// which defaults to `CHUNKS_BATCH_MAX_SIZE`.
+    pub fn with_download_concurrency(mut self, concurrency: usize) -> Self {
+        self.download_concurrency = concurrency;
+        self
     }
 
     #[instrument(skip(self), level = "debug")]
@@ -90,6 +110,27 @@ impl Files {
         Ok(bytes)
     }
 
+    /// Returns a stream of the decrypted contents of the file described by the `DataMap`,
+    /// yielding the contents of each chunk in order.
+    ///
+    /// Chunks are only fetched as the stream is polled, with up to `CHUNKS_BATCH_MAX_SIZE`
+    /// chunks being fetched ahead, hence memory usage stays bounded regardless of the file size.
+    pub fn get_file_stream(&self, data_map: DataMap) -> impl Stream<Item = Result<Bytes>> {
+        let client = self.client.clone();
+        decrypt_stream(data_map, move |chunk_info| {
+            let client = client.clone();
+            async move {
+                let chunk = client
+                    .get_chunk(ChunkAddress::new(chunk_info.dst_hash))
+                    .await?;
+                Ok(EncryptedChunk {
+                    index: chunk_info.index,
+                    content: chunk.value().clone(),
+                })
+            }
+        })
+    }
+
     /// Directly writes [`Bytes`] to the network in the
     /// form of immutable chunks, without any batching.
     #[instrument(skip(self, bytes), level = "debug")]
@@ -115,6 +156,34 @@ impl Files {
         self.upload_bytes(bytes, payment_proofs, true).await
     }
 
+    /// Stores the file, along with its `DataMap`, returning the single root address the whole file
+    /// can be retrieved from with `get_file_rooted`.
+    /// The root chunk holds the `DataMap` of the file, itself self-encrypted into further chunks,
+    /// level after level, should it be too large for a single chunk. Small files are stored in the
+    /// root chunk as is.
+    /// Payment proofs are expected for all the chunks `chunk_bytes` produces for the file.
+    #[instrument(skip(self, bytes, payment_proofs), level = "debug")]
+    pub async fn store_file_rooted(
+        &self,
+        bytes: Bytes,
+        payment_proofs: &PaymentProofsMap,
+        verify_store: bool,
+    ) -> Result<NetworkAddress> {
+        let root = self
+            .upload_bytes(bytes, payment_proofs, verify_store)
+            .await?;
+        Ok(NetworkAddress::from_chunk_address(root))
+    }
+
+    /// Retrieves the file stored with `store_file_rooted` from its root address.
+    #[instrument(skip(self), level = "debug")]
+    pub async fn get_file_rooted(&self, root: &NetworkAddress) -> Result<Bytes> {
+        match root {
+            NetworkAddress::ChunkAddress(address) => self.read_bytes(*address).await,
+            other => Err(super::Error::RootNotAChunk(Box::new(other.clone()))),
+        }
+    }
+
     /// Calculates a LargeFile's/SmallFile's address from self encrypted chunks,
     /// without storing them onto the network.
     #[instrument(skip_all, level = "debug")]
@@ -144,42 +213,30 @@ impl Files {
         payment_proofs: &PaymentProofsMap,
         verify_store: bool,
     ) -> Result<()> {
-        trace!("Client upload in batches started");
-        let mut tasks = vec![];
-        let mut next_batch_size = 0;
-        for chunk in chunks {
-            next_batch_size += 1;
-            let client = self.client.clone();
-            let chunk_addr = *chunk.address();
-            let payment = payment_proofs
-                .get(chunk_addr.xorname())
-                .cloned()
-                .ok_or(super::Error::MissingPaymentProof(chunk_addr))?;
-
-            tasks.push(task::spawn(async move {
-                client.store_chunk(chunk, payment, verify_store).await?;
-
-                Ok::<(), super::error::Error>(())
-            }));
-
-            if next_batch_size == CHUNKS_BATCH_MAX_SIZE {
-                let tasks_to_poll = tasks;
-                join_all_tasks(tasks_to_poll).await?;
-                // In case of not verifying, sleep for a little bit to let the network settle down.
-                if !verify_store {
-                    tokio::time::sleep(std::time::Duration::from_millis(500)).await;
-                }
-                tasks = vec![];
-                next_batch_size = 0;
-            }
-        }
-
-        if next_batch_size > 0 {
-            join_all_tasks(tasks).await?;
-        }
+        let progress = Arc::new(OperationProgress::default());
+        self.upload_chunks_with_progress(chunks, payment_proofs, verify_store, &progress)
+            .await
+    }
 
-        trace!("Client upload in batches completed");
-        Ok(())
+    /// Writes [`Bytes`] to the network in the form of immutable chunks, in the background,
+    /// returning the handle to await the address of the data, or to cancel the upload.
+    /// Once cancelled, no further chunk is stored, the upload failing with
+    /// `Error::OperationCancelled` carrying the number of chunks stored.
+    pub fn upload_cancellable(
+        &self,
+        bytes: Bytes,
+        payment_proofs: PaymentProofsMap,
+        verify_store: bool,
+    ) -> OperationHandle<ChunkAddress> {
+        let files = Self {
+            client: self.client.clone(),
+            download_concurrency: self.download_concurrency,
+        };
+        OperationHandle::spawn(move |progress| async move {
+            files
+                .upload_bytes_with_progress(bytes, &payment_proofs, verify_store, &progress)
+                .await
+        })
     }
 
     // --------------------------------------------
@@ -192,18 +249,58 @@ impl Files {
         bytes: Bytes,
         payment_proofs: &PaymentProofsMap,
         verify: bool,
+    ) -> Result<ChunkAddress> {
+        let progress = Arc::new(OperationProgress::default());
+        self.upload_bytes_with_progress(bytes, payment_proofs, verify, &progress)
+            .await
+    }
+
+    // Upload the `bytes`, reporting each chunk stored to the `progress`, and storing no further
+    // chunk once it's cancelled.
+    async fn upload_bytes_with_progress(
+        &self,
+        bytes: Bytes,
+        payment_proofs: &PaymentProofsMap,
+        verify: bool,
+        progress: &Arc<OperationProgress>,
     ) -> Result<ChunkAddress> {
         if bytes.len() < MIN_ENCRYPTABLE_BYTES {
             let file = SmallFile::new(bytes)?;
-            self.upload_small(file, payment_proofs, verify).await
+            progress.check_cancelled()?;
+            let address = self.upload_small(file, payment_proofs, verify).await?;
+            progress.step_completed();
+            Ok(address)
         } else {
             let (head_address, chunks) = encrypt_large(bytes)?;
-            self.upload_chunks_in_batches(chunks.into_iter(), payment_proofs, verify)
+            self.upload_chunks_with_progress(chunks.into_iter(), payment_proofs, verify, progress)
                 .await?;
             Ok(ChunkAddress::new(head_address))
         }
     }
 
+    // Store the `chunks` in batches, reporting each chunk stored to the `progress`.
+    async fn upload_chunks_with_progress(
+        &self,
+        chunks: impl Iterator<Item = Chunk>,
+        payment_proofs: &PaymentProofsMap,
+        verify_store: bool,
+        progress: &Arc<OperationProgress>,
+    ) -> Result<()> {
+        trace!("Client upload in batches started");
+        upload_in_batches(chunks, verify_store, progress, |chunk| {
+            let client = self.client.clone();
+            let chunk_addr = *chunk.address();
+            let payment = payment_proofs
+                .get(chunk_addr.xorname())
+                .cloned()
+                .ok_or(super::Error::MissingPaymentProof(chunk_addr))?;
+            Ok(async move { client.store_chunk(chunk, payment, verify_store).await })
+        })
+        .await?;
+        trace!("Client upload in batches completed");
+        Ok(())
+    }
+
     /// Directly writes a [`SmallFile`] to the network in the
     /// form of a single chunk, without any batching.
     #[instrument(skip_all, level = "trace")]
@@ -280,65 +377,32 @@ impl Files {
     #[instrument(skip_all, level = "trace")]
     async fn try_get_chunks(&self, chunks_info: Vec<ChunkInfo>) -> Result<Vec<EncryptedChunk>> {
         let expected_count = chunks_info.len();
-        let mut retrieved_chunks = vec![];
-        for next_batch in chunks_info.chunks(CHUNKS_BATCH_MAX_SIZE) {
-            let tasks = next_batch.iter().cloned().map(|chunk_info| {
-                let client = self.client.clone();
-                task::spawn(async move {
-                    match client
-                        .get_chunk(ChunkAddress::new(chunk_info.dst_hash))
-                        .await
-                    {
-                        Ok(chunk) => Ok(EncryptedChunk {
-                            index: chunk_info.index,
-                            content: chunk.value().clone(),
-                        }),
-                        Err(err) => {
-                            warn!(
-                                "Reading chunk {} from network, resulted in error {err:?}.",
-                                chunk_info.dst_hash
-                            );
-                            Err(err)
-                        }
-                    }
-                })
-            });
-
-            // This swallowing of errors is basically a compaction into a single
-            // error saying "didn't get all chunks".
-            retrieved_chunks.extend(join_all(tasks).await.into_iter().flatten().flatten());
-
-            info!(
-                "Client download progress {:?}/{expected_count:?}",
-                retrieved_chunks.len()
-            );
-            println!(
-                "Client download progress {:?}/{expected_count:?}",
-                retrieved_chunks.len()
-            );
-        }
-
-        if expected_count > retrieved_chunks.len() {
-            let missing_chunks: Vec<XorName> = chunks_info
-                .iter()
-                .filter_map(|expected_info| {
-                    if retrieved_chunks.iter().any(|retrieved_chunk| {
-                        XorName::from_content(&retrieved_chunk.content) == expected_info.dst_hash
-                    }) {
-                        None
-                    } else {
-                        Some(expected_info.dst_hash)
-                    }
-                })
-                .collect();
-            Err(Error::NotEnoughChunksRetrieved {
-                expected: expected_count,
-                retrieved: retrieved_chunks.len(),
-                missing_chunks,
-            })?
-        } else {
-            Ok(retrieved_chunks)
-        }
+        let mut retrieved_count = 0;
+        fetch_verified_chunks(chunks_info, self.download_concurrency, |chunk_info| {
+            let client = self.client.clone();
+            async move {
+                let chunk = client
+                    .get_chunk(ChunkAddress::new(chunk_info.dst_hash))
+                    .await
+                    .map_err(|err| {
+                        warn!(
+                            "Reading chunk {} from network, resulted in error {err:?}.",
+                            chunk_info.dst_hash
+                        );
+                        Error::ChunkNotRetrieved(chunk_info.dst_hash)
+                    })?;
+                Ok(chunk.value().clone())
+            }
+        })
+        .inspect(|result| {
+            if result.is_ok() {
+                retrieved_count += 1;
+                info!("Client download progress {retrieved_count:?}/{expected_count:?}");
+                println!("Client download progress {retrieved_count:?}/{expected_count:?}");
+            }
+        })
+        .try_collect()
+        .await
     }
 }
 
@@ -359,6 +423,99 @@ fn package_small(file: SmallFile) -> Result<Chunk> {
     Ok(chunk)
 }
 
+// Fetches the chunks of the `DataMap` in order using the provided `fetch` fn, keeping up to
+// `CHUNKS_BATCH_MAX_SIZE` fetches in flight, and decrypts each one of them as it's yielded.
+fn decrypt_stream<F, Fut>(data_map: DataMap, fetch: F) -> impl Stream<Item = Result<Bytes>>
+where
+    F: FnMut(ChunkInfo) -> Fut,
+    Fut: Future<Output = Result<EncryptedChunk>>,
+{
+    stream::iter(data_map.infos())
+        .map(fetch)
+        .buffered(CHUNKS_BATCH_MAX_SIZE)
+        .map(move |result| {
+            let encrypted_chunk = result?;
+            let bytes =
+                self_encryption::decrypt_range(&data_map, &[encrypted_chunk], 0, usize::MAX)
+                    .map_err(Error::SelfEncryption)?;
+            Ok(bytes)
+        })
+}
+
+// Fetches the chunks described by `chunks_info` using the provided `fetch` fn, keeping up to
+// `concurrency` fetches in flight, and yields them in the order of `chunks_info`.
+// Each chunk is checked against its address as it arrives, so that a corrupt chunk errors
+// rather than yielding wrong contents. Fetching stops at the first error once polled.
+fn fetch_verified_chunks<F, Fut>(
+    chunks_info: Vec<ChunkInfo>,
+    concurrency: usize,
+    mut fetch: F,
+) -> impl Stream<Item = Result<EncryptedChunk>>
+where
+    F: FnMut(ChunkInfo) -> Fut,
+    Fut: Future<Output = Result<Bytes>>,
+{
+    stream::iter(chunks_info)
+        .map(move |chunk_info| {
+            let fetched = fetch(chunk_info.clone());
+            async move {
+                let content = fetched.await?;
+                if XorName::from_content(&content) != chunk_info.dst_hash {
+                    warn!(
+                        "Chunk {} retrieved does not match its address",
+                        chunk_info.dst_hash
+                    );
+                    return Err(Error::ChunkCorrupted(chunk_info.dst_hash).into());
+                }
+                Ok(EncryptedChunk {
+                    index: chunk_info.index,
+                    content,
+                })
+            }
+        })
+        .buffered(concurrency.max(1))
+}
+
+// Store the `chunks` with `store`, `CHUNKS_BATCH_MAX_SIZE` at a time, reporting each chunk
+// stored to the `progress`. Once it's cancelled, no further chunk is stored, the chunks being
+// stored are awaited, and the upload fails with the number of chunks stored.
+async fn upload_in_batches<F, Fut>(
+    chunks: impl Iterator<Item = Chunk>,
+    verify_store: bool,
+    progress: &Arc<OperationProgress>,
+    store: F,
+) -> Result<()>
+where
+    F: Fn(Chunk) -> Result<Fut>,
+    Fut: Future<Output = Result<()>> + Send + 'static,
+{
+    let mut tasks = vec![];
+    for chunk in chunks {
+        if progress.check_cancelled().is_err() {
+            join_all_tasks(tasks).await?;
+            return progress.check_cancelled();
+        }
+
+        let stored = store(chunk)?;
+        let task_progress = progress.clone();
+        tasks.push(task::spawn(async move {
+            stored.await?;
+            task_progress.step_completed();
+            Ok::<(), super::error::Error>(())
+        }));
+
+        if tasks.len() == CHUNKS_BATCH_MAX_SIZE {
+            join_all_tasks(std::mem::take(&mut tasks)).await?;
+            // In case of not verifying, sleep for a little bit to let the network settle down.
+            if !verify_store {
+                tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+            }
+        }
+    }
+
+    join_all_tasks(tasks).await
+}
+
 // Helper to join a provided set of spawned tasks
 async fn join_all_tasks(tasks: Vec<JoinHandle<Result<()>>>) -> Result<()> {
     let responses = join_all(tasks)
@@ -375,3 +532,163 @@ async fn join_all_tasks(tasks: Vec<JoinHandle<Result<()>>>) -> Result<()> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::RngCore;
+    use self_encryption::MAX_CHUNK_SIZE;
+    use std::{
+        collections::BTreeMap,
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    #[tokio::test]
+    async fn file_stream_yields_the_original_bytes_in_order() -> Result<()> {
+        let mut content = vec![0u8; 5 * MAX_CHUNK_SIZE + 123];
+        rand::thread_rng().fill_bytes(&mut content);
+        let content = Bytes::from(content);
+
+        let (data_map, encrypted_chunks) =
+            self_encryption::encrypt(content.clone()).map_err(Error::SelfEncryption)?;
+        assert!(encrypted_chunks.len() > CHUNKS_BATCH_MAX_SIZE);
+        let stored: BTreeMap<XorName, Bytes> = encrypted_chunks
+            .into_iter()
+            .map(|chunk| (XorName::from_content(&chunk.content), chunk.content))
+            .collect();
+
+        let segments: Vec<Bytes> = decrypt_stream(data_map, |chunk_info| {
+            let content = stored.get(&chunk_info.dst_hash).cloned();
+            async move {
+                let content = content.ok_or(Error::NotEnoughChunksRetrieved {
+                    expected: 1,
+                    retrieved: 0,
+                    missing_chunks: vec![chunk_info.dst_hash],
+                })?;
+                Ok(EncryptedChunk {
+                    index: chunk_info.index,
+                    content,
+                })
+            }
+        })
+        .collect::<Vec<_>>()
+        .await
+        .into_iter()
+        .collect::<Result<_>>()?;
+
+        assert!(segments.len() > 1);
+        assert_eq!(content, segments.concat());
+
+        Ok(())
+    }
+
+    fn encrypt_random(size: usize) -> Result<(Bytes, DataMap, BTreeMap<XorName, Bytes>)> {
+        let mut content = vec![0u8; size];
+        rand::thread_rng().fill_bytes(&mut content);
+        let content = Bytes::from(content);
+
+        let (data_map, encrypted_chunks) =
+            self_encryption::encrypt(content.clone()).map_err(Error::SelfEncryption)?;
+        let stored = encrypted_chunks
+            .into_iter()
+            .map(|chunk| (XorName::from_content(&chunk.content), chunk.content))
+            .collect();
+        Ok((content, data_map, stored))
+    }
+
+    #[tokio::test]
+    async fn chunks_are_fetched_in_parallel_and_reassembled_in_order() -> Result<()> {
+        let (content, data_map, stored) = encrypt_random(8 * MAX_CHUNK_SIZE + 45)?;
+        let in_flight = AtomicUsize::new(0);
+        let max_in_flight = AtomicUsize::new(0);
+
+        let fetched: Vec<EncryptedChunk> = fetch_verified_chunks(data_map.infos(), 3, |info| {
+            let content = stored[&info.dst_hash].clone();
+            let in_flight = &in_flight;
+            let max_in_flight = &max_in_flight;
+            async move {
+                let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                let _ = max_in_flight.fetch_max(now, Ordering::SeqCst);
+                // later chunks of a batch arrive first
+                tokio::time::sleep(Duration::from_millis(30 - 5 * (info.index as u64 % 3))).await;
+                let _ = in_flight.fetch_sub(1, Ordering::SeqCst);
+                Ok(content)
+            }
+        })
+        .try_collect()
+        .await?;
+
+        assert_eq!(max_in_flight.load(Ordering::SeqCst), 3);
+        let indexes: Vec<_> = fetched.iter().map(|chunk| chunk.index).collect();
+        let expected_indexes: Vec<_> = data_map.infos().iter().map(|info| info.index).collect();
+        assert_eq!(indexes, expected_indexes);
+
+        let bytes = self_encryption::decrypt_full_set(&data_map, &fetched)
+            .map_err(Error::SelfEncryption)?;
+        assert_eq!(bytes, content);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn corrupt_chunk_is_detected() -> Result<()> {
+        let (_, data_map, stored) = encrypt_random(6 * MAX_CHUNK_SIZE)?;
+        let corrupt = data_map.infos()[2].dst_hash;
+
+        let result: Result<Vec<EncryptedChunk>> =
+            fetch_verified_chunks(data_map.infos(), 4, |info| {
+                let mut content = stored[&info.dst_hash].to_vec();
+                if info.dst_hash == corrupt {
+                    content[0] ^= 0xff;
+                }
+                async move { Ok(Bytes::from(content)) }
+            })
+            .try_collect()
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(crate::Error::Chunks(Error::ChunkCorrupted(name))) if name == corrupt
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn cancelled_upload_stores_no_further_chunk() -> Result<()> {
+        let chunks: Vec<Chunk> = (0..3 * CHUNKS_BATCH_MAX_SIZE)
+            .map(|i| Chunk::new(Bytes::from(format!("chunk {i}"))))
+            .collect();
+        let stored = Arc::new(AtomicUsize::new(0));
+
+        let stored_by_upload = stored.clone();
+        let upload = OperationHandle::spawn(move |progress| async move {
+            upload_in_batches(chunks.into_iter(), false, &progress, |_chunk| {
+                let stored = stored_by_upload.clone();
+                Ok(async move {
+                    tokio::time::sleep(Duration::from_millis(20)).await;
+                    let _ = stored.fetch_add(1, Ordering::SeqCst);
+                    Ok(())
+                })
+            })
+            .await
+        });
+
+        // cancel while the upload lets the network settle after its first batch
+        while upload.completed() < CHUNKS_BATCH_MAX_SIZE {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        upload.cancel();
+
+        let outcome = upload.outcome().await;
+        let stored = stored.load(Ordering::SeqCst);
+        assert_eq!(stored, CHUNKS_BATCH_MAX_SIZE);
+        assert!(matches!(
+            outcome,
+            Err(crate::Error::OperationCancelled { completed }) if completed == stored
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,198 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{Client, ClientRegister, Error, Result};
+
+use bls::{PublicKey, Signature};
+use serde::{Deserialize, Serialize};
+use sn_registers::{EntryHash, RegisterAddress};
+use xor_name::XorName;
+
+/// An append-only ledger over a linear Register, each record being chained to the previous one.
+///
+/// Each entry of the Register holds a record along with the hash of the previous entry, both
+/// signed by the writer, so that altering, dropping or reordering records is detected by
+/// `verify_chain`, on top of the Register only accepting entries signed by its owner.
+pub struct ClientLedger {
+    register: ClientRegister,
+}
+
+// An entry of the Register backing a ledger.
+#[derive(Serialize, Deserialize)]
+struct LedgerEntry {
+    // Hash of the content of the previous entry, none for the first record.
+    prev: Option<XorName>,
+    record: Vec<u8>,
+    // Signature of the `prev` hash and the `record`.
+    signature: Signature,
+}
+
+impl ClientLedger {
+    /// Use the provided Register as a ledger.
+    pub fn new(register: ClientRegister) -> Self {
+        Self { register }
+    }
+
+    /// Create a new ledger on the Network, backed by the Register with the given `meta`.
+    pub async fn create_online(client: Client, meta: XorName, verify_store: bool) -> Result<Self> {
+        let register = ClientRegister::create_online(client, meta, verify_store).await?;
+        Ok(Self::new(register))
+    }
+
+    /// Return the address of the Register backing the ledger.
+    pub fn address(&self) -> &RegisterAddress {
+        self.register.address()
+    }
+
+    /// Append a record locally, chained to the last one, to be sent to the network on the next
+    /// `push`. Errors with `Error::LedgerForked` if the ledger isn't linear.
+    pub fn append(&mut self, record: &[u8]) -> Result<()> {
+        let prev = self.last_entry_digest()?;
+        let signature = self.register.client().sign(signed_bytes(prev, record)?);
+        let entry = LedgerEntry {
+            prev,
+            record: record.to_vec(),
+            signature,
+        };
+        self.register.write(&bincode::serialize(&entry)?)
+    }
+
+    /// Push the records appended locally to the network.
+    pub async fn push(&mut self, verify_store: bool) -> Result<()> {
+        self.register.push(verify_store).await
+    }
+
+    /// Walk the whole ledger, from its first record, checking each entry is chained to the
+    /// previous one and signed by the owner of the ledger, and return the records in order.
+    /// Errors with `Error::LedgerTampered` carrying the first entry failing the checks.
+    pub fn verify_chain(&self) -> Result<Vec<Vec<u8>>> {
+        let mut hashes = vec![];
+        let mut next = self.head()?;
+        while let Some(hash) = next {
+            hashes.push(hash);
+            let children = self.register.replica().children(hash)?;
+            if children.len() > 1 {
+                return Err(Error::LedgerForked);
+            }
+            next = children.into_iter().next();
+        }
+
+        let owner = self.register.owner();
+        let mut prev = None;
+        let mut records = vec![];
+        for hash in hashes.into_iter().rev() {
+            let bytes = self.register.get(hash)?;
+            let entry = verified_entry(&bytes, prev, &owner).ok_or(Error::LedgerTampered(hash))?;
+            prev = Some(XorName::from_content(&bytes));
+            records.push(entry.record);
+        }
+        Ok(records)
+    }
+
+    // The last entry of the ledger, if any.
+    fn head(&self) -> Result<Option<EntryHash>> {
+        let heads = self.register.read();
+        if heads.len() > 1 {
+            return Err(Error::LedgerForked);
+        }
+        Ok(heads.into_iter().next().map(|(hash, _)| hash))
+    }
+
+    // Hash of the content of the last entry of the ledger, for the next one to be chained to it.
+    fn last_entry_digest(&self) -> Result<Option<XorName>> {
+        match self.head()? {
+            Some(hash) => Ok(Some(XorName::from_content(&self.register.get(hash)?))),
+            None => Ok(None),
+        }
+    }
+}
+
+// The bytes an entry chained to `prev` and holding the `record` is signed over.
+fn signed_bytes(prev: Option<XorName>, record: &[u8]) -> Result<Vec<u8>> {
+    Ok(bincode::serialize(&(prev, record))?)
+}
+
+// The entry out of its `bytes`, if it's chained to `prev` and signed by the `owner`.
+fn verified_entry(bytes: &[u8], prev: Option<XorName>, owner: &PublicKey) -> Option<LedgerEntry> {
+    let entry: LedgerEntry = bincode::deserialize(bytes).ok()?;
+    let signed = signed_bytes(entry.prev, &entry.record).ok()?;
+    (entry.prev == prev && owner.verify(&entry.signature, signed)).then_some(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::register::tests::offline_client;
+
+    fn offline_ledger() -> Result<ClientLedger> {
+        let register = ClientRegister::create(offline_client()?, xor_name::rand::random())?;
+        Ok(ClientLedger::new(register))
+    }
+
+    #[test]
+    fn appended_records_form_a_valid_chain() -> Result<()> {
+        let mut ledger = offline_ledger()?;
+        assert!(ledger.verify_chain()?.is_empty());
+
+        for record in ["opened", "credited 10", "debited 3"] {
+            ledger.append(record.as_bytes())?;
+        }
+        let expected: Vec<Vec<u8>> = ["opened", "credited 10", "debited 3"]
+            .iter()
+            .map(|record| record.as_bytes().to_vec())
+            .collect();
+        assert_eq!(ledger.verify_chain()?, expected);
+
+        Ok(())
+    }
+
+    // Write an entry holding the `record`, chained to `prev` and signed over `signed_record`,
+    // returning its hash.
+    fn write_entry(
+        ledger: &mut ClientLedger,
+        prev: Option<XorName>,
+        record: &[u8],
+        signed_record: &[u8],
+    ) -> Result<Option<EntryHash>> {
+        let signature = ledger
+            .register
+            .client()
+            .sign(signed_bytes(prev, signed_record)?);
+        let entry = LedgerEntry {
+            prev,
+            record: record.to_vec(),
+            signature,
+        };
+        ledger.register.write(&bincode::serialize(&entry)?)?;
+        ledger.head()
+    }
+
+    #[test]
+    fn tampered_entries_are_detected() -> Result<()> {
+        // an altered record no longer matches its signature
+        let mut ledger = offline_ledger()?;
+        ledger.append(b"opened")?;
+        let prev = ledger.last_entry_digest()?;
+        let altered = write_entry(&mut ledger, prev, b"credited 1000", b"credited 10")?;
+        assert!(matches!(
+            ledger.verify_chain(),
+            Err(Error::LedgerTampered(hash)) if Some(hash) == altered
+        ));
+
+        // a validly signed record which isn't chained to the previous one is detected too
+        let mut ledger = offline_ledger()?;
+        ledger.append(b"opened")?;
+        let unchained = write_entry(&mut ledger, None, b"credited 10", b"credited 10")?;
+        assert!(matches!(
+            ledger.verify_chain(),
+            Err(Error::LedgerTampered(hash)) if Some(hash) == unchained
+        ));
+
+        Ok(())
+    }
+}
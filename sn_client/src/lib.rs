@@ -11,21 +11,33 @@ extern crate tracing;
 
 mod api;
 mod chunks;
+mod config;
 mod error;
 mod event;
 mod faucet;
 mod file_apis;
+mod ledger;
+mod operation;
 mod register;
+mod topic;
 mod wallet;
 
 pub(crate) use error::Result;
 
 pub use self::{
+    api::WriteReport,
+    config::{ClientConfig, ConfigOverrides, Profile},
     error::Error,
     event::{ClientEvent, ClientEventsReceiver},
     faucet::{get_tokens_from_faucet, load_faucet_wallet_from_genesis_wallet},
     file_apis::Files,
-    register::ClientRegister,
+    ledger::ClientLedger,
+    operation::OperationHandle,
+    register::{
+        tagged_register_meta, BranchResolver, ChangelogEntry, ClientRegister, OwnershipProof,
+        PushEstimate, RegisterDelta, RegisterSpec,
+    },
+    topic::{ClientTopic, DEFAULT_TOPIC_POLL_INTERVAL},
     wallet::{send, WalletClient},
 };
 
@@ -42,4 +54,5 @@ pub struct Client {
     peers_added: usize,
     progress: Option<ProgressBar>,
     network_store_cost: u64,
+    config: ClientConfig,
 }
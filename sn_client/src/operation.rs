@@ -0,0 +1,85 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{Error, Result};
+
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+use tokio::task::JoinHandle;
+
+/// Handle to a long-running operation, e.g. a file upload, running in the background.
+///
+/// Cancelling the operation stops it from starting any further step, the steps in progress
+/// being completed, after which it fails with `Error::OperationCancelled` reporting how many
+/// steps it completed. Dropping the handle doesn't cancel the operation.
+pub struct OperationHandle<T> {
+    progress: Arc<OperationProgress>,
+    task: JoinHandle<Result<T>>,
+}
+
+impl<T: Send + 'static> OperationHandle<T> {
+    // Run the operation returned by `operation` in the background, it being provided the
+    // progress to report its steps to and check for cancellation.
+    pub(crate) fn spawn<F, Fut>(operation: F) -> Self
+    where
+        F: FnOnce(Arc<OperationProgress>) -> Fut,
+        Fut: Future<Output = Result<T>> + Send + 'static,
+    {
+        let progress = Arc::new(OperationProgress::default());
+        let task = tokio::spawn(operation(progress.clone()));
+        Self { progress, task }
+    }
+
+    /// Cancel the operation, no further step being started.
+    pub fn cancel(&self) {
+        self.progress.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// The number of steps of the operation completed so far.
+    pub fn completed(&self) -> usize {
+        self.progress.completed()
+    }
+
+    /// Await the outcome of the operation.
+    pub async fn outcome(self) -> Result<T> {
+        self.task.await.map_err(|_| Error::OperationAborted)?
+    }
+}
+
+/// Progress of an operation, shared with its handle.
+#[derive(Default)]
+pub(crate) struct OperationProgress {
+    cancelled: AtomicBool,
+    completed: AtomicUsize,
+}
+
+impl OperationProgress {
+    // Fail with `Error::OperationCancelled` should the operation have been cancelled.
+    pub(crate) fn check_cancelled(&self) -> Result<()> {
+        if self.cancelled.load(Ordering::SeqCst) {
+            return Err(Error::OperationCancelled {
+                completed: self.completed(),
+            });
+        }
+        Ok(())
+    }
+
+    // Record a step of the operation as completed.
+    pub(crate) fn step_completed(&self) {
+        let _ = self.completed.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn completed(&self) -> usize {
+        self.completed.load(Ordering::SeqCst)
+    }
+}
@@ -6,10 +6,12 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::{Client, Error, Result};
+use crate::{Client, Error, Result, WriteReport};
 
-use bls::PublicKey;
+use bls::{Ciphertext, PublicKey, SecretKey, Signature};
+use futures::{future::join_all, Future};
 use libp2p::kad::Record;
+use serde::{Deserialize, Serialize};
 use sn_protocol::{
     error::Error as ProtocolError,
     messages::RegisterCmd,
@@ -20,16 +22,203 @@ use sn_registers::{
     Entry, EntryHash, Permissions, Register, RegisterAddress, SignedRegister, User,
 };
 
-use std::collections::{BTreeSet, LinkedList};
+use std::{
+    borrow::Cow,
+    cmp::Ordering,
+    collections::{BTreeSet, LinkedList},
+    fs,
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
 use xor_name::XorName;
 
+/// Header flagging an entry written compressed, see `ClientRegister::write_compressed`,
+/// followed by the brotli compressed value.
+const COMPRESSED_ENTRY_HEADER: &[u8] = b"\x00snz\x01";
+/// Brotli quality entries are compressed with, trading some size for speed.
+const ENTRY_COMPRESSION_QUALITY: i32 = 6;
+/// Maximum size of a decompressed entry, for a small entry not to be inflated without bound.
+const MAX_DECOMPRESSED_ENTRY_SIZE: usize = 64 * 1024;
+/// Header of the entries written with `ClientRegister::write_encrypted`, followed by the
+/// ciphertext of the value.
+const ENCRYPTED_ENTRY_HEADER: &[u8] = b"\x00sne\x01";
+/// Prefix of the bytes an `OwnershipProof` is signed over, so that it can't be mistaken for
+/// another signature made with the owner key.
+const OWNERSHIP_PROOF_DOMAIN: &[u8] = b"sn_register_ownership_proof";
+
+/// The state of a `ClientRegister` persisted to its write-ahead log, see
+/// `ClientRegister::with_write_ahead_log`.
+#[derive(Serialize, Deserialize)]
+struct WriteAheadLog<'a> {
+    register: Cow<'a, Register>,
+    ops: Cow<'a, LinkedList<RegisterCmd>>,
+    /// Logs written before compression was persisted decode with compression disabled
+    #[serde(default)]
+    compression: bool,
+}
+
+/// Specification of a Register to be created along with others with `Client::create_registers`.
+#[derive(Clone, Debug)]
+pub struct RegisterSpec {
+    /// Metadata the address of the Register is derived from
+    pub meta: XorName,
+    /// Permissions of the Register
+    pub permissions: Permissions,
+}
+
+/// An entry of the history of a Register, see `ClientRegister::changelog`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChangelogEntry {
+    /// Hash of the entry
+    pub hash: EntryHash,
+    /// The user who wrote the entry, if known, see `Register::read_with_meta`
+    pub author: Option<User>,
+    /// Lamport timestamp of the entry
+    pub lamport: u64,
+    /// The value written
+    pub value: Entry,
+    /// Hashes of the entries this one was written atop of
+    pub atop: BTreeSet<EntryHash>,
+}
+
+impl ChangelogEntry {
+    /// Returns `true` if the entry merged several branches of the Register.
+    pub fn is_merge(&self) -> bool {
+        self.atop.len() > 1
+    }
+}
+
+/// What the next `push` of a Register would send, see `ClientRegister::estimate_push`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PushEstimate {
+    /// Number of ops yet to be pushed
+    pub op_count: usize,
+    /// Number of peers of the close group of the Register each op is sent to
+    pub peer_count: usize,
+}
+
+/// The changes a `ClientRegister::sync` pulled in from the replicas on the network.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RegisterDelta {
+    /// Hashes of the entries added from the remote replica
+    pub added: BTreeSet<EntryHash>,
+    /// Whether any of our latest entries was written atop of remotely, i.e. is no longer one of
+    /// the latest entries of the Register
+    pub superseded: bool,
+}
+
+impl RegisterDelta {
+    /// Returns `true` if the sync didn't pull in any new entry.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+    }
+}
+
+/// Proof of control of the owner key of a Register, signed over a challenge of an app, see
+/// `ClientRegister::prove_ownership`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OwnershipProof {
+    /// Address of the Register whose ownership is proven
+    pub address: RegisterAddress,
+    /// Signature of the owner over the address and the challenge
+    pub signature: Signature,
+}
+
+/// The branches `ClientRegister::write` found in the Register, returned with
+/// `Error::ContentBranchDetected` along with the value which couldn't be written, so the
+/// conflict can be resolved in one step, e.g. `resolver.merge_all(&mut register)`.
+/// Each method writes a single entry atop all the branches, and returns its hash. The resolver
+/// only applies to the Register the branches were found in.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BranchResolver {
+    address: RegisterAddress,
+    branches: BTreeSet<(EntryHash, Entry)>,
+    entry: Vec<u8>,
+    compress: bool,
+}
+
+impl BranchResolver {
+    /// The branches found, with the bytes of the entries as stored in the Register.
+    pub fn branches(&self) -> &BTreeSet<(EntryHash, Entry)> {
+        &self.branches
+    }
+
+    /// The value which was to be written.
+    pub fn entry(&self) -> &[u8] {
+        &self.entry
+    }
+
+    /// Write the value which was to be written atop all the branches, compressed if it was to be.
+    pub fn merge_all(self, register: &mut ClientRegister) -> Result<EntryHash> {
+        self.check_register(register)?;
+        let children = self.hashes();
+        let entry = register.encode(&self.entry, self.compress)?;
+        register.stage_atop(&entry, children)
+    }
+
+    /// Discard the value which was to be written, and resolve the branches down to the branch
+    /// with the provided `hash`, which is written again atop all of them.
+    pub fn keep(self, register: &mut ClientRegister, hash: EntryHash) -> Result<EntryHash> {
+        self.check_register(register)?;
+        let children = self.hashes();
+        let kept = self
+            .branches
+            .into_iter()
+            .find_map(|(branch, entry)| (branch == hash).then_some(entry))
+            .ok_or(Error::EntryNotFound(hash))?;
+        register.stage_atop(&kept, children)
+    }
+
+    /// Discard the value which was to be written, and resolve the branches down to the greatest
+    /// of them as per `cmp`, see `ClientRegister::resolve_branches_with`.
+    pub fn keep_by(
+        self,
+        register: &mut ClientRegister,
+        cmp: impl Fn(&Entry, &Entry) -> Ordering,
+    ) -> Result<EntryHash> {
+        self.check_register(register)?;
+        let children = self.hashes();
+        match register.greatest_branch(self.branches, cmp) {
+            // the entry is written as stored, i.e. compressed if it was
+            Some(entry) => register.stage_atop(&entry, children),
+            None => Err(Error::NoBranchesToResolve),
+        }
+    }
+
+    pub(crate) fn hashes(&self) -> BTreeSet<EntryHash> {
+        self.branches.iter().map(|(hash, _)| *hash).collect()
+    }
+
+    // Check the `register` is the one the branches were found in.
+    fn check_register(&self, register: &ClientRegister) -> Result<()> {
+        if register.address() != &self.address {
+            return Err(sn_registers::Error::RegisterAddrMismatch {
+                dst_addr: Box::new(self.address),
+                reg_addr: Box::new(*register.address()),
+            }
+            .into());
+        }
+        Ok(())
+    }
+}
+
 /// Ops made to an offline Register instance are applied locally only,
 /// and accumulated till the user explicitly calls 'sync'. The user can
 /// switch back to sync with the network for every op by invoking `online` API.
+/// Reads always reflect the ops made locally, even before they are pushed to the network.
+///
+/// Should a write-ahead log be enabled with `with_write_ahead_log`, the Register and the ops yet
+/// to be pushed are persisted to disk on each change, so that a client restarted after a crash
+/// can `recover` them and push the pending ops.
+///
+/// Entries written compressed, with `write_compressed` or with compression enabled with
+/// `with_compression`, are decompressed on every read path, whatever the local setting.
 pub struct ClientRegister {
     client: Client,
     register: Register,
     ops: LinkedList<RegisterCmd>, // Cached operations.
+    wal: Option<PathBuf>,
+    compression: bool,
 }
 
 impl ClientRegister {
@@ -37,11 +226,13 @@ impl ClientRegister {
     fn create_register(client: Client, meta: XorName, perms: Permissions) -> Result<Self> {
         let public_key = client.signer_pk();
 
-        let register = Register::new(public_key, meta, perms);
+        let register = Register::new(public_key, meta, perms, None);
         let reg = Self {
             client,
             register,
             ops: LinkedList::new(),
+            wal: None,
+            compression: false,
         };
 
         Ok(reg)
@@ -71,6 +262,54 @@ impl ClientRegister {
         Ok(reg)
     }
 
+    /// Create a Register locally from an existing snapshot, e.g. a pre-built Register shipped
+    /// with an app. The snapshot is verified before being adopted, and it is created on the
+    /// network along with its content on the next `sync` or `push`.
+    /// The snapshot must be owned by the client's key.
+    pub fn from_snapshot(client: Client, register: Register) -> Result<Self> {
+        register.verify()?;
+        if register.owner() != client.signer_pk() {
+            return Err(Error::Register(sn_registers::Error::InvalidSecretKey));
+        }
+
+        let cmd = RegisterCmd::Create {
+            register: register.clone(),
+            signature: client.sign(register.bytes()?),
+        };
+        let mut ops = LinkedList::new();
+        ops.push_front(cmd);
+
+        Ok(Self {
+            client,
+            register,
+            ops,
+            wal: None,
+            compression: false,
+        })
+    }
+
+    /// Create a new Register as per the provided `RegisterSpec` and send it to the Network.
+    pub(super) async fn create_online_with_spec(
+        client: Client,
+        spec: RegisterSpec,
+        verify_store: bool,
+    ) -> Result<Self> {
+        let mut reg = Self::create_register(client, spec.meta, spec.permissions)?;
+        reg.sync(verify_store).await?;
+        Ok(reg)
+    }
+
+    /// Create a new Register locally as per the provided `RegisterSpec`.
+    pub(super) fn create_with_spec(client: Client, spec: RegisterSpec) -> Result<Self> {
+        Self::create_register(client, spec.meta, spec.permissions)
+    }
+
+    /// The Register signed by us, as it's created on the network.
+    pub(super) fn signed_register(&self) -> Result<SignedRegister> {
+        let signature = self.client.sign(self.register.bytes()?);
+        Ok(SignedRegister::new(self.register.clone(), signature))
+    }
+
     /// Retrieve a Register from the network to work on it offline.
     pub(super) async fn retrieve(client: Client, address: RegisterAddress) -> Result<Self> {
         let register = Self::get_register_from_network(&client, address).await?;
@@ -79,6 +318,52 @@ impl ClientRegister {
             client,
             register,
             ops: LinkedList::new(),
+            wal: None,
+            compression: false,
+        })
+    }
+
+    /// Persist the Register and the ops yet to be pushed to the write-ahead log at `wal_path`,
+    /// and keep it up to date on each write and push.
+    pub fn with_write_ahead_log(mut self, wal_path: PathBuf) -> Result<Self> {
+        self.wal = Some(wal_path);
+        self.persist_wal()?;
+        Ok(self)
+    }
+
+    /// Compress all the values written from then on, as `write_compressed` does.
+    /// The setting is persisted to the write-ahead log, if any, for `recover` to restore it.
+    pub fn with_compression(mut self) -> Result<Self> {
+        self.compression = true;
+        self.persist_wal()?;
+        Ok(self)
+    }
+
+    /// Rebuild a Register from the write-ahead log at `wal_path`, e.g. after a crash, along
+    /// with the ops which weren't pushed yet, for the next `push` to flush them, and whether
+    /// compression was enabled, see `with_compression`.
+    /// The write-ahead log is kept up to date from then on.
+    pub fn recover(client: Client, wal_path: PathBuf) -> Result<Self> {
+        let bytes = fs::read(&wal_path).map_err(Error::WriteAheadLog)?;
+        let wal: WriteAheadLog = rmp_serde::from_slice(&bytes)
+            .map_err(|err| Error::WriteAheadLog(io::Error::new(io::ErrorKind::InvalidData, err)))?;
+        let register = wal.register.into_owned();
+        let ops = wal.ops.into_owned();
+        if register.owner() != client.signer_pk() {
+            return Err(Error::Register(sn_registers::Error::InvalidSecretKey));
+        }
+        debug!(
+            "Recovered Register at {:?} with {} pending cmds from {wal_path:?}",
+            register.address(),
+            ops.len()
+        );
+
+        Ok(Self {
+            client,
+            register,
+            ops,
+            wal: Some(wal_path),
+            compression: wal.compression,
         })
     }
 
@@ -96,32 +381,166 @@ impl ClientRegister {
         self.register.permissions()
     }
 
+    /// Prove control of the owner key of the Register to an app, by signing the `challenge` it
+    /// provided, e.g. a random nonce, with the `owner_sk`, without revealing the key.
+    /// The proof is bound to the Register, and is checked with `verify_ownership_proof`.
+    pub fn prove_ownership(&self, challenge: &[u8], owner_sk: &SecretKey) -> OwnershipProof {
+        let address = *self.address();
+        OwnershipProof {
+            address,
+            signature: owner_sk.sign(ownership_proof_bytes(&address, challenge)),
+        }
+    }
+
+    /// Returns `true` if the `proof` was signed over the `challenge` by the owner of the Register
+    /// it was made for, which must be `owner_pk`.
+    pub fn verify_ownership_proof(
+        proof: &OwnershipProof,
+        owner_pk: &PublicKey,
+        challenge: &[u8],
+    ) -> bool {
+        proof.address.owner() == *owner_pk
+            && owner_pk.verify(
+                &proof.signature,
+                ownership_proof_bytes(&proof.address, challenge),
+            )
+    }
+
     /// Return the number of items held in the register
     pub fn size(&self) -> u64 {
         self.register.size()
     }
 
     /// Return a value corresponding to the provided 'hash', if present.
-    pub fn get(&self, hash: EntryHash) -> Result<&Entry> {
-        let entry = self.register.get(hash)?;
-        Ok(entry)
+    pub fn get(&self, hash: EntryHash) -> Result<Entry> {
+        let entry = self.register.get_cloned(hash)?;
+        Ok(self.decode(entry))
+    }
+
+    /// Return a value corresponding to the provided 'hash', fetching the Register from the
+    /// network should the entry not be held locally, e.g. it was written to another replica we
+    /// haven't synced with yet. Our replica is left untouched, `sync` to merge the fetched one.
+    pub async fn get_online(&self, hash: EntryHash) -> Result<Entry> {
+        if let Ok(entry) = self.get(hash) {
+            return Ok(entry);
+        }
+        debug!(
+            "Entry {hash:?} not held locally, fetching Register {} from the network",
+            self.address()
+        );
+        let register = Self::get_register_from_network(&self.client, *self.address()).await?;
+        entry_of(&register, hash).map(|entry| self.decode(entry))
+    }
+
+    /// Returns `true` if the register holds an entry with the provided 'hash',
+    /// e.g. to check the children of a `write_atop` are known before writing.
+    pub fn contains(&self, hash: &EntryHash) -> bool {
+        self.register.contains(hash)
     }
 
     /// Read the last entry, or entries when there are branches, if the register is not empty.
+    /// Entries written compressed are returned decompressed.
     pub fn read(&self) -> BTreeSet<(EntryHash, Entry)> {
-        self.register.read()
+        self.register
+            .read()
+            .into_iter()
+            .map(|(hash, entry)| (hash, self.decode(entry)))
+            .collect()
+    }
+
+    /// The current branches of the Register, i.e. its latest entries when concurrent writes left
+    /// more than one, for them to be inspected before being merged, e.g. with
+    /// `write_merging_branches` or `resolve_branches_with`. Empty when there are no branches.
+    /// Entries are returned as `read` returns them.
+    pub fn branches(&self) -> BTreeSet<(EntryHash, Entry)> {
+        let latest = self.read();
+        if latest.len() > 1 {
+            latest
+        } else {
+            BTreeSet::new()
+        }
+    }
+
+    /// Same as `branches`, but with the bytes of the entries as stored in the Register, i.e.
+    /// compressed for those written compressed, ordered by entry hash.
+    pub fn branch_values(&self) -> Vec<(EntryHash, Vec<u8>)> {
+        let latest = self.register.read();
+        if latest.len() > 1 {
+            latest.into_iter().collect()
+        } else {
+            vec![]
+        }
+    }
+
+    /// The history of the Register: every entry it holds, including those overwritten, each
+    /// listed after the entries it was written atop of. Values are returned as `read` returns
+    /// them.
+    pub fn changelog(&self) -> Vec<ChangelogEntry> {
+        self.register
+            .entries_topological()
+            .into_iter()
+            .map(|(hash, value, lamport)| ChangelogEntry {
+                hash,
+                author: self.register.author(hash),
+                lamport,
+                value: self.decode(value),
+                atop: self.register.children(hash).unwrap_or_default(),
+            })
+            .collect()
     }
 
     /// Write a new value onto the Register atop latest value.
-    /// It returns an error if it finds branches in the content/entries; if it is
-    /// required to merge/resolve the branches, invoke the `write_merging_branches` API.
+    /// It returns an error if it finds branches in the content/entries, carrying a
+    /// `BranchResolver` to merge/resolve them with, or invoke the `write_merging_branches` API.
     pub fn write(&mut self, entry: &[u8]) -> Result<()> {
+        self.write_checking_branches(entry, self.compression)
+    }
+
+    /// Same as `write`, but compressing the value, which shrinks compressible values such as
+    /// text or JSON. The entry is flagged as compressed and signed in its compressed form, and
+    /// every read path decompresses it, so that compressed and plain entries can be mixed.
+    pub fn write_compressed(&mut self, entry: &[u8]) -> Result<()> {
+        self.write_checking_branches(entry, true)
+    }
+
+    // Write the value atop the latest one, compressed if `compress` is set, see `write`.
+    fn write_checking_branches(&mut self, entry: &[u8], compress: bool) -> Result<()> {
         let children = self.register.read();
         if children.len() > 1 {
-            return Err(Error::ContentBranchDetected(children));
+            return Err(Error::ContentBranchDetected(Box::new(BranchResolver {
+                address: *self.address(),
+                branches: children,
+                entry: entry.to_vec(),
+                compress,
+            })));
         }
 
-        self.write_atop(entry, children.into_iter().map(|(hash, _)| hash).collect())
+        let entry = self.encode(entry, compress)?;
+        let children = children.into_iter().map(|(hash, _)| hash).collect();
+        self.stage_atop(&entry, children).map(|_| ())
+    }
+
+    /// Same as `write`, but encrypting the value to the `encryption_key`, for it to be read with
+    /// `read_decrypted` by the holder of the corresponding secret key only.
+    /// This is client-side encryption: only the value is encrypted, the structure of the
+    /// Register, i.e. its entries hashes and the entries they were written atop of, remains
+    /// public for replicas to merge, while nodes store and see the ciphertext only.
+    pub fn write_encrypted(&mut self, entry: &[u8], encryption_key: &PublicKey) -> Result<()> {
+        self.write(&encrypt_entry(entry, encryption_key))
+    }
+
+    /// Same as `read`, but decrypting the entries, which must all have been written with
+    /// `write_encrypted`, with the `decryption_key`.
+    /// Returns `Error::EntryDecryptionFailed` should an entry not decrypt with the key, including
+    /// entries which weren't encrypted, whatever bytes they start with.
+    pub fn read_decrypted(
+        &self,
+        decryption_key: &SecretKey,
+    ) -> Result<BTreeSet<(EntryHash, Entry)>> {
+        self.read()
+            .into_iter()
+            .map(|(hash, entry)| Ok((hash, decrypt_entry(hash, entry, decryption_key)?)))
+            .collect()
     }
 
     /// Write a new value onto the Register atop latest value.
@@ -140,31 +559,85 @@ impl ClientRegister {
         self.write_atop(entry, children)
     }
 
+    /// Resolve the branches of the Register, if any, down to a single entry: the greatest of the
+    /// current entries as per `cmp`, which is written again atop all of them.
+    /// Entries are compared as `read` returns them.
+    pub fn resolve_branches_with(
+        &mut self,
+        cmp: impl Fn(&Entry, &Entry) -> Ordering,
+    ) -> Result<()> {
+        let branches = self.register.read();
+        if branches.len() < 2 {
+            return Ok(());
+        }
+
+        let children = branches.iter().map(|(hash, _)| *hash).collect();
+        match self.greatest_branch(branches, cmp) {
+            // the entry is written as stored, i.e. compressed if it was
+            Some(entry) => self.stage_atop(&entry, children).map(|_| ()),
+            None => Ok(()),
+        }
+    }
+
+    // The entry, as stored, of the greatest of the `branches` as per `cmp`, comparing the entries
+    // as `read` returns them. The first of equal branches wins.
+    fn greatest_branch(
+        &self,
+        branches: BTreeSet<(EntryHash, Entry)>,
+        cmp: impl Fn(&Entry, &Entry) -> Ordering,
+    ) -> Option<Entry> {
+        branches
+            .into_iter()
+            .map(|(_, entry)| (self.decode(entry.clone()), entry))
+            .reduce(|greatest, candidate| match cmp(&candidate.0, &greatest.0) {
+                Ordering::Greater => candidate,
+                Ordering::Less | Ordering::Equal => greatest,
+            })
+            .map(|(_, entry)| entry)
+    }
+
     /// Write a new value onto the Register atop the set of braches/entries
     /// referenced by the provided list of their corresponding entry hash.
     /// Note you can use `write_merging_branches` API instead if you
     /// want to write atop all exiting branches/entries.
     pub fn write_atop(&mut self, entry: &[u8], children: BTreeSet<EntryHash>) -> Result<()> {
+        let entry = self.encode(entry, self.compression)?;
+        self.stage_atop(&entry, children).map(|_| ())
+    }
+
+    // Same as `write_atop`, returning the hash of the entry written.
+    fn stage_atop(&mut self, entry: &[u8], children: BTreeSet<EntryHash>) -> Result<EntryHash> {
         // check permissions first
         let public_key = self.client.signer_pk();
         self.register
             .check_user_permissions(User::Key(public_key))?;
 
-        let (_hash, mut op) = self.register.write(entry.into(), children)?;
+        let (hash, mut op) = self.register.write(entry.into(), children)?;
         let signature = self.client.sign(op.bytes_for_signing());
         op.add_signature(public_key, signature)?;
         let cmd = RegisterCmd::Edit(op);
 
         self.ops.push_front(cmd);
+        self.persist_wal()?;
+        Ok(hash)
+    }
 
-        Ok(())
+    /// The client used to sync the Register.
+    pub(crate) fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// The local replica of the Register.
+    pub(crate) fn replica(&self) -> &Register {
+        &self.register
     }
 
     // ********* Online methods  *********
 
-    /// Sync this Register with the replicas on the network.
+    /// Sync this Register with the replicas on the network, returning the `RegisterDelta` of the
+    /// entries pulled in from them, e.g. for an app to re-render on remote changes.
     /// This will optionally verify the stored Register on the network is the same as the local one.
-    pub async fn sync(&mut self, verify_store: bool) -> Result<()> {
+    pub async fn sync(&mut self, verify_store: bool) -> Result<RegisterDelta> {
         debug!("Syncing Register at {:?}!", self.address());
         let remote_replica =
             match Self::get_register_from_network(&self.client, *self.address()).await {
@@ -179,32 +652,146 @@ impl ClientRegister {
                         register: self.register.clone(),
                         signature: self.client.sign(self.register.bytes()?),
                     };
-                    self.publish_register(cmd, verify_store).await?;
+                    self.publish_register(cmd, verify_store, None).await?;
                     self.register.clone()
                 }
             };
-        self.register.merge(remote_replica);
+        let delta = self.merge_remote(remote_replica)?;
+        self.push(verify_store).await?;
+        Ok(delta)
+    }
+
+    // Merge the `remote` replica into ours, returning what it added to it.
+    fn merge_remote(&mut self, remote: Register) -> Result<RegisterDelta> {
+        let added = remote
+            .entries_topological()
+            .into_iter()
+            .map(|(hash, _, _)| hash)
+            .filter(|hash| !self.register.contains(hash))
+            .collect();
+        let local_heads: Vec<EntryHash> = self
+            .register
+            .read()
+            .into_iter()
+            .map(|(hash, _)| hash)
+            .collect();
+
+        self.register.merge(remote);
+
+        let heads: BTreeSet<EntryHash> = self
+            .register
+            .read()
+            .into_iter()
+            .map(|(hash, _)| hash)
+            .collect();
+        let superseded = local_heads.iter().any(|hash| !heads.contains(hash));
+        self.persist_wal()?;
+        Ok(RegisterDelta { added, superseded })
+    }
+
+    /// Sync this Register with the replicas on the network, only fetching the ops we are missing
+    /// rather than the whole Register.
+    /// Should the Register not be found on the network, this falls back to a full `sync`.
+    /// This will optionally verify the stored Register on the network is the same as the local one.
+    pub async fn sync_delta(&mut self, verify_store: bool) -> Result<()> {
+        debug!("Syncing Register delta at {:?}!", self.address());
+        let known_heads = self
+            .register
+            .read()
+            .into_iter()
+            .map(|(hash, _)| hash)
+            .collect();
+        let ops = match self
+            .client
+            .get_register_delta(*self.address(), known_heads)
+            .await
+        {
+            Ok(ops) => ops,
+            Err(err) => {
+                debug!("Failed to fetch register delta, syncing the full Register: {err:?}");
+                return self.sync(verify_store).await.map(|_| ());
+            }
+        };
+
+        for op in ops {
+            self.register.apply_op(op)?;
+        }
+        self.persist_wal()?;
         self.push(verify_store).await
     }
 
+    /// Return the number of ops made locally which are yet to be pushed to the network.
+    pub fn pending_ops(&self) -> usize {
+        self.ops.len()
+    }
+
+    /// Estimate what the next `push` would send: the number of ops yet to be pushed, and the
+    /// number of peers of the Register's close group, as currently resolved, they'd be sent to.
+    /// Nothing is sent, and the ops are left untouched.
+    pub async fn estimate_push(&self) -> Result<PushEstimate> {
+        let address = NetworkAddress::from_register_address(*self.address());
+        let peers = self
+            .client
+            .network
+            .client_get_closest_peers(&address)
+            .await?;
+        Ok(PushEstimate {
+            op_count: self.pending_ops(),
+            peer_count: peers.len(),
+        })
+    }
+
     /// Push all operations made locally to the replicas of this Register on the network.
     /// This optionally verifies that the stored Register is the same as our local register
     pub async fn push(&mut self, verify_store: bool) -> Result<()> {
+        self.push_to_replicas(verify_store, None).await
+    }
+
+    /// Same as `push`, but returning a `WriteReport` of the replicas of the Register's close
+    /// group holding all our ops right after the push.
+    pub async fn push_with_report(&mut self, verify_store: bool) -> Result<WriteReport> {
+        self.push(verify_store).await?;
+        let op_count = self.register.version().op_count;
+        self.client
+            .register_write_report(*self.address(), op_count)
+            .await
+    }
+
+    /// Push all operations made locally to the `replicas` closest peers to this Register,
+    /// rather than to its close group only, for durability-critical writes.
+    /// Peers beyond the close group aren't responsible for the Register, hence may reject it
+    /// or not keep it.
+    /// This optionally verifies that the stored Register is the same as our local register
+    pub async fn push_with_redundancy(
+        &mut self,
+        verify_store: bool,
+        replicas: usize,
+    ) -> Result<()> {
+        self.push_to_replicas(verify_store, Some(replicas)).await
+    }
+
+    async fn push_to_replicas(
+        &mut self,
+        verify_store: bool,
+        replicas: Option<usize>,
+    ) -> Result<()> {
         let ops_len = self.ops.len();
         if ops_len > 0 {
             let address = *self.address();
             debug!("Pushing {ops_len} cached Register cmds at {address}!");
 
-            // TODO: send them all concurrently
-            while let Some(cmd) = self.ops.pop_back() {
-                let result = self.publish_register(cmd.clone(), verify_store).await;
-
-                if let Err(err) = result {
-                    warn!("Did not push Register cmd on all nodes in the close group!: {err}");
-                    // We keep the cmd for next sync to retry
-                    self.ops.push_back(cmd);
-                    return Err(err);
-                }
+            // the ops are cached newest first
+            let cmds: Vec<RegisterCmd> = std::mem::take(&mut self.ops).into_iter().rev().collect();
+            let (failed, error) = push_concurrently(cmds, |cmd| {
+                self.publish_register(cmd, verify_store, replicas)
+            })
+            .await;
+            for cmd in failed {
+                self.ops.push_front(cmd);
+            }
+            self.persist_wal()?;
+            if let Some(err) = error {
+                return Err(err);
             }
 
             debug!("Successfully pushed {ops_len} Register cmds at {address}!");
@@ -251,9 +838,14 @@ impl ClientRegister {
 
     // ********* Private helpers  *********
 
-    /// Publish a `Register` command on the network.
+    /// Publish a `Register` command on the network, to the `replicas` closest peers if provided.
     /// If `verify_store` is true, it will verify the Register was stored on the network.
-    async fn publish_register(&self, cmd: RegisterCmd, verify_store: bool) -> Result<()> {
+    async fn publish_register(
+        &self,
+        cmd: RegisterCmd,
+        verify_store: bool,
+        replicas: Option<usize>,
+    ) -> Result<()> {
         let cmd_dst = cmd.dst();
         debug!("Querying existing Register for cmd: {cmd_dst:?}");
         let network_reg = self
@@ -290,7 +882,48 @@ impl ClientRegister {
             publisher: None,
             expires: None,
         };
-        Ok(self.client.network.put_record(record, verify_store).await?)
+        match replicas {
+            Some(replicas) => Ok(self
+                .client
+                .network
+                .put_record_with_redundancy(record, replicas, verify_store)
+                .await?),
+            None => Ok(self.client.network.put_record(record, verify_store).await?),
+        }
+    }
+
+    // The entry to write for the value, compressed if `compress` is set. A plain value starting
+    // with the `COMPRESSED_ENTRY_HEADER` is compressed nonetheless, for reads not to alter it.
+    fn encode(&self, value: &[u8], compress: bool) -> Result<Entry> {
+        if compress || value.starts_with(COMPRESSED_ENTRY_HEADER) {
+            compress_entry(value)
+        } else {
+            Ok(value.to_vec())
+        }
+    }
+
+    // The value of the entry, decompressed if it was written compressed.
+    fn decode(&self, entry: Entry) -> Entry {
+        decompress_entry(entry)
+    }
+
+    // Overwrite the write-ahead log, if any, with the Register, the ops yet to be pushed and
+    // whether compression is enabled.
+    // The log is written aside first, so that a crash while writing it leaves the previous one.
+    // It's encoded as the records are, for the fields only serialised when set to be decoded.
+    fn persist_wal(&self) -> Result<()> {
+        let wal_path = match &self.wal {
+            Some(wal_path) => wal_path,
+            None => return Ok(()),
+        };
+        let wal = WriteAheadLog {
+            register: Cow::Borrowed(&self.register),
+            ops: Cow::Borrowed(&self.ops),
+            compression: self.compression,
+        };
+        let bytes = rmp_serde::to_vec(&wal)
+            .map_err(|err| Error::WriteAheadLog(io::Error::new(io::ErrorKind::InvalidData, err)))?;
+        write_atomically(wal_path, &bytes).map_err(Error::WriteAheadLog)
     }
 
     // Retrieve a `Register` from the Network.
@@ -299,8 +932,920 @@ impl ClientRegister {
         address: RegisterAddress,
     ) -> Result<Register> {
         debug!("Retrieving Register from: {address}");
-        let reg = client.get_signed_register_from_network(address).await?;
+        let reg = client
+            .get_most_complete_signed_register_from_network(address)
+            .await?;
         reg.verify_with_address(address)?;
         Ok(reg.register()?)
     }
 }
+
+/// The meta of the Register named `name` and tagged `tag`, e.g. with the type of data it holds,
+/// for an app to address several Registers by the same name, see `Client::get_register_history`.
+pub fn tagged_register_meta(name: XorName, tag: u64) -> XorName {
+    XorName::from_content_parts(&[&name.0, &tag.to_be_bytes()])
+}
+
+// The value of the entry of the `register` with the provided `hash`.
+fn entry_of(register: &Register, hash: EntryHash) -> Result<Entry> {
+    match register.get_cloned(hash) {
+        Ok(entry) => Ok(entry),
+        Err(sn_registers::Error::NoSuchEntry(_)) => Err(Error::EntryNotFound(hash)),
+        Err(err) => Err(err.into()),
+    }
+}
+
+// Write the `bytes` to a temporary file next to `path`, then move it to `path`. Both the file and
+// the rename are flushed to disk before returning, so that the write survives a crash.
+fn write_atomically(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    let mut file = fs::File::create(&tmp_path)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    fs::rename(tmp_path, path)?;
+    sync_parent_dir(path)
+}
+
+// Flush the directory holding `path` to disk, persisting the entries renamed into it.
+#[cfg(unix)]
+fn sync_parent_dir(path: &Path) -> std::io::Result<()> {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => fs::File::open(dir)?.sync_all(),
+        _ => fs::File::open(".")?.sync_all(),
+    }
+}
+
+// Directories can't be opened to be flushed on other platforms.
+#[cfg(not(unix))]
+fn sync_parent_dir(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
+
+// Publish the `cmds`, oldest first, concurrently: the creations of the Register first, then,
+// once it exists, the edits. Returns the cmds which failed, oldest first, for them to be queued
+// again, along with the first error, if any. Should a creation fail, the edits aren't sent but returned.
+async fn push_concurrently<F, Fut>(
+    cmds: Vec<RegisterCmd>,
+    publish: F,
+) -> (Vec<RegisterCmd>, Option<Error>)
+where
+    F: Fn(RegisterCmd) -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    let (creates, edits): (Vec<_>, Vec<_>) = cmds
+        .into_iter()
+        .partition(|cmd| matches!(cmd, RegisterCmd::Create { .. }));
+
+    let (mut failed, error) = publish_all(creates, &publish).await;
+    if error.is_some() {
+        failed.extend(edits);
+        return (failed, error);
+    }
+    publish_all(edits, &publish).await
+}
+
+// Publish all the `cmds` at once, see `push_concurrently`.
+async fn publish_all<F, Fut>(
+    cmds: Vec<RegisterCmd>,
+    publish: &F,
+) -> (Vec<RegisterCmd>, Option<Error>)
+where
+    F: Fn(RegisterCmd) -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    let results = join_all(cmds.iter().cloned().map(publish)).await;
+
+    let mut failed = vec![];
+    let mut first_error = None;
+    for (cmd, result) in cmds.into_iter().zip(results) {
+        if let Err(err) = result {
+            warn!("Did not push Register cmd on all nodes in the close group!: {err}");
+            // We keep the cmd for next push to retry
+            failed.push(cmd);
+            if first_error.is_none() {
+                first_error = Some(err);
+            }
+        }
+    }
+    (failed, first_error)
+}
+
+// Compress the entry, prefixing it with the `COMPRESSED_ENTRY_HEADER`.
+fn compress_entry(entry: &[u8]) -> Result<Entry> {
+    let params = brotli::enc::BrotliEncoderParams {
+        quality: ENTRY_COMPRESSION_QUALITY,
+        ..Default::default()
+    };
+    let mut compressed = COMPRESSED_ENTRY_HEADER.to_vec();
+    let _ = brotli::BrotliCompress(&mut &entry[..], &mut compressed, &params)
+        .map_err(Error::EntryCompressionFailed)?;
+    Ok(compressed)
+}
+
+// The bytes an `OwnershipProof` of the Register at `address` is signed over.
+fn ownership_proof_bytes(address: &RegisterAddress, challenge: &[u8]) -> Vec<u8> {
+    [OWNERSHIP_PROOF_DOMAIN, &address.xorname().0, challenge].concat()
+}
+
+// Encrypt the entry to the `key`, flagging it as such. The digest of the entry is encrypted
+// along with it, for a decryption with another key to be detected.
+fn encrypt_entry(entry: &[u8], key: &PublicKey) -> Entry {
+    let plaintext = [&XorName::from_content(entry).0[..], entry].concat();
+    let mut encrypted = ENCRYPTED_ENTRY_HEADER.to_vec();
+    encrypted.extend(key.encrypt(plaintext).to_bytes());
+    encrypted
+}
+
+// Decrypt the entry at `hash` with the `key`, which must have been written encrypted.
+fn decrypt_entry(hash: EntryHash, entry: Entry, key: &SecretKey) -> Result<Entry> {
+    let ciphertext = entry
+        .strip_prefix(ENCRYPTED_ENTRY_HEADER)
+        .ok_or(Error::EntryDecryptionFailed(hash))?;
+    let plaintext = Ciphertext::from_bytes(ciphertext)
+        .ok()
+        .and_then(|ciphertext| key.decrypt(&ciphertext))
+        .ok_or(Error::EntryDecryptionFailed(hash))?;
+    if plaintext.len() < xor_name::XOR_NAME_LEN {
+        return Err(Error::EntryDecryptionFailed(hash));
+    }
+    let (digest, entry) = plaintext.split_at(xor_name::XOR_NAME_LEN);
+    if digest != XorName::from_content(entry).0 {
+        return Err(Error::EntryDecryptionFailed(hash));
+    }
+    Ok(entry.to_vec())
+}
+
+// Decompress the entry, up to `MAX_DECOMPRESSED_ENTRY_SIZE`, returning it as is should it not
+// have been written compressed.
+fn decompress_entry(entry: Entry) -> Entry {
+    let compressed = match entry.strip_prefix(COMPRESSED_ENTRY_HEADER) {
+        Some(compressed) => compressed,
+        None => return entry,
+    };
+    let mut decompressed = vec![];
+    let mut decompressor =
+        brotli::Decompressor::new(compressed, 4096).take(MAX_DECOMPRESSED_ENTRY_SIZE as u64 + 1);
+    match decompressor.read_to_end(&mut decompressed) {
+        Ok(size) if size <= MAX_DECOMPRESSED_ENTRY_SIZE => decompressed,
+        Ok(_) => {
+            warn!("Register entry decompresses beyond {MAX_DECOMPRESSED_ENTRY_SIZE} bytes");
+            entry
+        }
+        Err(err) => {
+            warn!("Failed to decompress a Register entry: {err:?}");
+            entry
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{ClientConfig, ClientEventsChannel};
+    use bls::SecretKey;
+    use sn_networking::SwarmDriver;
+    use std::{
+        sync::atomic::{AtomicBool, Ordering as AtomicOrdering},
+        time::Duration,
+    };
+    use tokio::sync::Barrier;
+
+    // Merge another replica of the Register into the one of `reg`, as a sync would.
+    pub(crate) fn merge_replica(
+        reg: &mut ClientRegister,
+        replica: Register,
+    ) -> Result<RegisterDelta> {
+        reg.merge_remote(replica)
+    }
+
+    // A client which isn't connected to any network.
+    pub(crate) fn offline_client() -> Result<Client> {
+        let (network, _events_receiver, _swarm_driver) = SwarmDriver::new_client(true, None)?;
+        Ok(Client {
+            network,
+            events_channel: ClientEventsChannel::default(),
+            signer: SecretKey::random(),
+            peers_added: 0,
+            progress: None,
+            network_store_cost: 0,
+            config: ClientConfig::default(),
+        })
+    }
+
+    #[tokio::test]
+    async fn register_reads_reflect_local_writes_before_push() -> Result<()> {
+        let mut reg = ClientRegister::create(offline_client()?, xor_name::rand::random())?;
+
+        reg.write(b"first")?;
+        let read: Vec<(EntryHash, Entry)> = reg.read().into_iter().collect();
+        assert_eq!(read.len(), 1);
+        let (first, entry) = read[0].clone();
+        assert_eq!(entry, b"first".to_vec());
+
+        reg.write_atop(b"second", [first].into())?;
+        let read: Vec<Entry> = reg.read().into_iter().map(|(_, entry)| entry).collect();
+        assert_eq!(read, vec![b"second".to_vec()]);
+        assert_eq!(reg.get(first)?, b"first".to_vec());
+        assert_eq!(reg.size(), 2);
+        assert_eq!(reg.ops.len(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn compressed_entries_are_read_back_decompressed() -> Result<()> {
+        let mut reg = ClientRegister::create(offline_client()?, xor_name::rand::random())?
+            .with_compression()?;
+
+        let json = format!(
+            "[{}]",
+            (0..200)
+                .map(|i| format!(r#"{{"id":{i},"name":"item","tags":["a","b","c"]}}"#))
+                .collect::<Vec<_>>()
+                .join(",")
+        );
+        reg.write(json.as_bytes())?;
+        let read: Vec<(EntryHash, Entry)> = reg.read().into_iter().collect();
+        assert_eq!(read.len(), 1);
+        let (compressed_hash, entry) = read[0].clone();
+        assert_eq!(entry, json.as_bytes().to_vec());
+
+        // the Register holds, and the op signs, the compressed bytes
+        let stored = reg.register.get(compressed_hash)?;
+        assert!(stored.starts_with(COMPRESSED_ENTRY_HEADER));
+        assert!(stored.len() < json.len() / 10);
+        for cmd in &reg.ops {
+            if let RegisterCmd::Edit(op) = cmd {
+                reg.register.check_register_op(op)?;
+            }
+        }
+
+        // every read path decompresses
+        assert_eq!(reg.get(compressed_hash)?, json.as_bytes().to_vec());
+        let changelog = reg.changelog();
+        assert_eq!(changelog.len(), 1);
+        assert_eq!(changelog[0].value, json.as_bytes().to_vec());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn compressed_and_plain_entries_are_mixed() -> Result<()> {
+        let client = offline_client()?;
+        let mut reg = ClientRegister::create(client.clone(), xor_name::rand::random())?;
+
+        let json = r#"{"name":"item","tags":["a","b","c"]}"#.repeat(20);
+        reg.write(b"plain")?;
+        let (plain, _) = reg.read().into_iter().next().expect("an entry to be read");
+        reg.write_compressed(json.as_bytes())?;
+        let (compressed, entry) = reg.read().into_iter().next().expect("an entry to be read");
+        assert_eq!(entry, json.as_bytes().to_vec());
+        assert!(reg
+            .register
+            .get(compressed)?
+            .starts_with(COMPRESSED_ENTRY_HEADER));
+        assert_eq!(reg.register.get(plain)?, &b"plain".to_vec());
+
+        // a plain entry starting like a compressed one reads back as written
+        let mut lookalike = COMPRESSED_ENTRY_HEADER.to_vec();
+        lookalike.extend(compress_entry(b"not meant to be decompressed")?);
+        reg.write(&lookalike)?;
+        let read: Vec<Entry> = reg.read().into_iter().map(|(_, entry)| entry).collect();
+        assert_eq!(read, vec![lookalike.clone()]);
+
+        // entries written compressed are decompressed without compression enabled locally
+        let mut replica = ClientRegister::create(client, reg.address().meta())?;
+        merge_replica(&mut replica, reg.register.clone())?;
+        let values: Vec<Entry> = replica
+            .changelog()
+            .into_iter()
+            .map(|entry| entry.value)
+            .collect();
+        assert_eq!(
+            values,
+            vec![b"plain".to_vec(), json.as_bytes().to_vec(), lookalike]
+        );
+
+        // an entry inflating beyond the maximum size is returned as stored
+        let bomb = compress_entry(&vec![0; MAX_DECOMPRESSED_ENTRY_SIZE + 1])?;
+        assert!(bomb.len() < MAX_DECOMPRESSED_ENTRY_SIZE);
+        assert_eq!(decompress_entry(bomb.clone()), bomb);
+        let fitting = compress_entry(&vec![0; MAX_DECOMPRESSED_ENTRY_SIZE])?;
+        assert_eq!(
+            decompress_entry(fitting),
+            vec![0; MAX_DECOMPRESSED_ENTRY_SIZE]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn changelog_lists_entries_in_order_with_their_authors() -> Result<()> {
+        let client = offline_client()?;
+        let owner = User::Key(client.signer_pk());
+        let writer_sk = SecretKey::random();
+        let writer = User::Key(writer_sk.public_key());
+        let mut register = Register::new(
+            client.signer_pk(),
+            xor_name::rand::random(),
+            Permissions::new_with([owner, writer]),
+            None,
+        );
+
+        // the owner writes the first entry, then the writer and the owner write concurrently
+        // atop of it, and the owner finally merges both branches
+        let mut write = |entry: &[u8], atop: BTreeSet<EntryHash>, sk: &SecretKey| {
+            let (hash, mut op) = register.clone().write(entry.to_vec(), atop)?;
+            op.sign_with(sk);
+            register.apply_op(op)?;
+            Ok::<_, Error>(hash)
+        };
+        let owner_sk = client.signer.clone();
+        let first = write(b"first", BTreeSet::new(), &owner_sk)?;
+        let by_writer = write(b"by writer", [first].into(), &writer_sk)?;
+        let by_owner = write(b"by owner", [first].into(), &owner_sk)?;
+        let merge = write(b"merge", [by_writer, by_owner].into(), &owner_sk)?;
+
+        let reg = ClientRegister::from_snapshot(client, register)?;
+        let changelog = reg.changelog();
+        let (first_branch, second_branch) = if by_writer < by_owner {
+            (
+                (by_writer, writer, "by writer"),
+                (by_owner, owner, "by owner"),
+            )
+        } else {
+            (
+                (by_owner, owner, "by owner"),
+                (by_writer, writer, "by writer"),
+            )
+        };
+        let expected = vec![
+            ChangelogEntry {
+                hash: first,
+                author: Some(owner),
+                lamport: 1,
+                value: b"first".to_vec(),
+                atop: BTreeSet::new(),
+            },
+            ChangelogEntry {
+                hash: first_branch.0,
+                author: Some(first_branch.1),
+                lamport: 2,
+                value: first_branch.2.as_bytes().to_vec(),
+                atop: [first].into(),
+            },
+            ChangelogEntry {
+                hash: second_branch.0,
+                author: Some(second_branch.1),
+                lamport: 2,
+                value: second_branch.2.as_bytes().to_vec(),
+                atop: [first].into(),
+            },
+            ChangelogEntry {
+                hash: merge,
+                author: Some(owner),
+                lamport: 3,
+                value: b"merge".to_vec(),
+                atop: [by_writer, by_owner].into(),
+            },
+        ];
+        assert_eq!(changelog, expected);
+        let merges: Vec<_> = changelog.iter().map(ChangelogEntry::is_merge).collect();
+        assert_eq!(merges, vec![false, false, false, true]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn pending_ops_track_local_writes() -> Result<()> {
+        let mut reg = ClientRegister::create(offline_client()?, xor_name::rand::random())?;
+        assert_eq!(reg.pending_ops(), 0);
+
+        reg.write(b"first")?;
+        reg.write(b"second")?;
+        assert_eq!(reg.pending_ops(), 2);
+
+        // a write rejected for the branches isn't pending
+        let root: BTreeSet<EntryHash> = reg.read().into_iter().map(|(hash, _)| hash).collect();
+        reg.write_atop(b"left", root.clone())?;
+        reg.write_atop(b"right", root)?;
+        assert_eq!(reg.pending_ops(), 4);
+        assert!(reg.write(b"conflicting").is_err());
+        assert_eq!(reg.pending_ops(), 4);
+
+        reg.write_merging_branches(b"merged")?;
+        assert_eq!(reg.pending_ops(), 5);
+
+        // a snapshot is pending creation on the network
+        let client = offline_client()?;
+        let snapshot = Register::new(
+            client.signer_pk(),
+            xor_name::rand::random(),
+            Permissions::new_owner_only(),
+            None,
+        );
+        assert_eq!(
+            ClientRegister::from_snapshot(client, snapshot)?.pending_ops(),
+            1
+        );
+
+        Ok(())
+    }
+
+    // The cmds of a new Register, created from a snapshot, and written to `edits` times.
+    fn queued_cmds(edits: usize) -> Result<Vec<RegisterCmd>> {
+        let client = offline_client()?;
+        let register = Register::new(
+            client.signer_pk(),
+            xor_name::rand::random(),
+            Permissions::new_owner_only(),
+            None,
+        );
+        let mut reg = ClientRegister::from_snapshot(client, register)?;
+        for i in 0..edits {
+            reg.write(format!("edit {i}").as_bytes())?;
+        }
+        Ok(reg.ops.into_iter().rev().collect())
+    }
+
+    #[tokio::test]
+    async fn queued_edits_are_pushed_concurrently_after_the_creation() -> Result<()> {
+        let cmds = queued_cmds(10)?;
+        let created = AtomicBool::new(false);
+        // every edit waits for all the others to be sent
+        let all_edits_sent = Barrier::new(10);
+
+        let (created, all_edits_sent) = (&created, &all_edits_sent);
+        let push = push_concurrently(cmds, |cmd| async move {
+            match cmd {
+                RegisterCmd::Create { .. } => created.store(true, AtomicOrdering::SeqCst),
+                RegisterCmd::Edit(_) => {
+                    assert!(created.load(AtomicOrdering::SeqCst));
+                    let _ = all_edits_sent.wait().await;
+                }
+            }
+            Ok(())
+        });
+        let (failed, error) = tokio::time::timeout(Duration::from_secs(5), push)
+            .await
+            .expect("the edits were not sent in one batch");
+        assert!(failed.is_empty());
+        assert!(error.is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn only_the_failed_ops_are_queued_again() -> Result<()> {
+        let cmds = queued_cmds(10)?;
+        let failing = cmds[4].clone();
+        // ops failing with a terminal error are queued again too, rather than dropped
+        let rejected = cmds[7].clone();
+        assert!(!Error::Protocol(ProtocolError::RegisterAlreadyClaimed(
+            SecretKey::random().public_key()
+        ))
+        .is_retryable());
+
+        let (failed, error) = push_concurrently(cmds, |cmd| {
+            let result = if cmd == failing {
+                Err(Error::Protocol(ProtocolError::NotReady))
+            } else if cmd == rejected {
+                Err(Error::Protocol(ProtocolError::RegisterAlreadyClaimed(
+                    SecretKey::random().public_key(),
+                )))
+            } else {
+                Ok(())
+            };
+            async { result }
+        })
+        .await;
+        assert_eq!(failed, vec![failing, rejected]);
+        assert!(matches!(
+            error,
+            Some(Error::Protocol(ProtocolError::NotReady))
+        ));
+
+        // while no edit is sent should the creation fail
+        let cmds = queued_cmds(3)?;
+        let (failed, error) = push_concurrently(cmds.clone(), |cmd| {
+            let result = match cmd {
+                RegisterCmd::Create { .. } => Err(Error::Protocol(ProtocolError::NotReady)),
+                RegisterCmd::Edit(_) => panic!("edit sent before the creation succeeded"),
+            };
+            async { result }
+        })
+        .await;
+        assert_eq!(failed, cmds);
+        assert!(error.is_some());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn entry_missing_locally_is_found_in_a_synced_replica() -> Result<()> {
+        let client = offline_client()?;
+        let register = Register::new(
+            client.signer_pk(),
+            xor_name::rand::random(),
+            Permissions::new_owner_only(),
+            None,
+        );
+        let mut local = ClientRegister::from_snapshot(client.clone(), register.clone())?;
+        let mut remote = ClientRegister::from_snapshot(client, register)?;
+        remote.write(b"written remotely")?;
+        let written: Vec<EntryHash> = remote.read().into_iter().map(|(hash, _)| hash).collect();
+        assert_eq!(written.len(), 1);
+        let hash = written[0];
+
+        assert!(local.get(hash).is_err());
+        assert!(matches!(
+            entry_of(local.replica(), hash),
+            Err(Error::EntryNotFound(missing)) if missing == hash
+        ));
+
+        // the replica fetched from the network holds it
+        merge_replica(&mut local, remote.replica().clone())?;
+        assert_eq!(
+            entry_of(local.replica(), hash)?,
+            b"written remotely".to_vec()
+        );
+        assert_eq!(local.get_online(hash).await?, b"written remotely".to_vec());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn encrypted_entries_are_only_read_by_the_key_holder() -> Result<()> {
+        let mut reg = ClientRegister::create(offline_client()?, xor_name::rand::random())?;
+        let key = SecretKey::random();
+        reg.write(b"public")?;
+        let root: BTreeSet<EntryHash> = reg.read().into_iter().map(|(hash, _)| hash).collect();
+        reg.write_encrypted(b"secret", &key.public_key())?;
+        let hash = reg.read().into_iter().map(|(hash, _)| hash).next();
+        let hash = hash.expect("the encrypted entry");
+        assert_eq!(reg.register.children(hash)?, root);
+
+        // the replica sent to nodes holds the ciphertext only
+        let stored = reg.replica().get(hash)?;
+        assert!(stored.starts_with(ENCRYPTED_ENTRY_HEADER));
+        assert!(!stored
+            .windows(b"secret".len())
+            .any(|bytes| bytes == b"secret"));
+        assert_ne!(reg.read(), [(hash, b"secret".to_vec())].into());
+
+        assert_eq!(
+            reg.read_decrypted(&key)?,
+            [(hash, b"secret".to_vec())].into()
+        );
+        assert!(matches!(
+            reg.read_decrypted(&SecretKey::random()),
+            Err(Error::EntryDecryptionFailed(failed)) if failed == hash
+        ));
+
+        // entries which weren't encrypted fail to decrypt, even starting like encrypted ones
+        let mut reg = ClientRegister::create(offline_client()?, xor_name::rand::random())?;
+        let mut lookalike = ENCRYPTED_ENTRY_HEADER.to_vec();
+        lookalike.extend_from_slice(b"public");
+        reg.write(&lookalike)?;
+        let (plain, _) = reg.read().into_iter().next().expect("the plain entry");
+        assert!(matches!(
+            reg.read_decrypted(&key),
+            Err(Error::EntryDecryptionFailed(failed)) if failed == plain
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn ownership_is_proven_with_the_owner_key_only() -> Result<()> {
+        let client = offline_client()?;
+        let owner_sk = client.signer.clone();
+        let reg = ClientRegister::create(client, xor_name::rand::random())?;
+        let owner_pk = reg.owner();
+        assert_eq!(owner_pk, owner_sk.public_key());
+
+        let proof = reg.prove_ownership(b"challenge", &owner_sk);
+        assert!(ClientRegister::verify_ownership_proof(
+            &proof,
+            &owner_pk,
+            b"challenge"
+        ));
+        // the proof doesn't answer another challenge
+        assert!(!ClientRegister::verify_ownership_proof(
+            &proof,
+            &owner_pk,
+            b"another challenge"
+        ));
+
+        // nor can it be made with another key, whichever owner it's checked against
+        let other_sk = SecretKey::random();
+        let forged = reg.prove_ownership(b"challenge", &other_sk);
+        assert!(!ClientRegister::verify_ownership_proof(
+            &forged,
+            &owner_pk,
+            b"challenge"
+        ));
+        assert!(!ClientRegister::verify_ownership_proof(
+            &forged,
+            &other_sk.public_key(),
+            b"challenge"
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn sync_reports_the_entries_added_remotely() -> Result<()> {
+        let client = offline_client()?;
+        let mut local = ClientRegister::create(client.clone(), xor_name::rand::random())?;
+        local.write(b"shared")?;
+        let mut remote = ClientRegister::from_snapshot(client, local.replica().clone())?;
+        remote.write(b"remote")?;
+        let added: BTreeSet<EntryHash> = remote.read().into_iter().map(|(hash, _)| hash).collect();
+
+        let delta = merge_replica(&mut local, remote.replica().clone())?;
+        assert_eq!(delta.added, added);
+        // the remote entry was written atop our latest one
+        assert!(delta.superseded);
+
+        // nothing new the second time around
+        let delta = merge_replica(&mut local, remote.replica().clone())?;
+        assert!(delta.is_empty());
+        assert!(!delta.superseded);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn divergent_writes_are_listed_as_branches() -> Result<()> {
+        let mut reg = ClientRegister::create(offline_client()?, xor_name::rand::random())?
+            .with_compression()?;
+        reg.write(b"root")?;
+        assert!(reg.branches().is_empty());
+        assert!(reg.branch_values().is_empty());
+
+        let root: BTreeSet<EntryHash> = reg.read().into_iter().map(|(hash, _)| hash).collect();
+        reg.write_atop(b"left", root.clone())?;
+        reg.write_atop(b"right", root)?;
+
+        let branches = reg.branches();
+        let values: BTreeSet<Entry> = branches.iter().map(|(_, entry)| entry.clone()).collect();
+        assert_eq!(values, [b"left".to_vec(), b"right".to_vec()].into());
+        assert!(matches!(
+            reg.write(b"conflicting"),
+            Err(Error::ContentBranchDetected(detected)) if detected.branches().len() == 2
+        ));
+
+        // the raw values hold the entries as stored
+        let raw = reg.branch_values();
+        let hashes: Vec<EntryHash> = raw.iter().map(|(hash, _)| *hash).collect();
+        let expected_hashes: Vec<EntryHash> = branches.iter().map(|(hash, _)| *hash).collect();
+        assert_eq!(hashes, expected_hashes);
+        assert!(raw
+            .iter()
+            .any(|(_, value)| value.starts_with(COMPRESSED_ENTRY_HEADER)));
+
+        reg.write_merging_branches(b"merged")?;
+        assert!(reg.branches().is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn branches_are_resolved_to_the_greatest_entry() -> Result<()> {
+        let mut reg = ClientRegister::create(offline_client()?, xor_name::rand::random())?
+            .with_compression()?;
+        reg.write(b"1")?;
+        let root: BTreeSet<EntryHash> = reg.read().into_iter().map(|(hash, _)| hash).collect();
+
+        // concurrent writes of numeric values atop the same entry, all of them compressed
+        reg.write_atop(b"5", root.clone())?;
+        reg.write_atop(b"42", root.clone())?;
+        reg.write_atop(b"17", root)?;
+        let branches: BTreeSet<EntryHash> = reg.read().into_iter().map(|(hash, _)| hash).collect();
+        assert_eq!(branches.len(), 3);
+
+        let numeric =
+            |entry: &Entry| -> u64 { String::from_utf8_lossy(entry).parse().unwrap_or_default() };
+        reg.resolve_branches_with(|a, b| numeric(a).cmp(&numeric(b)))?;
+
+        let read: Vec<(EntryHash, Entry)> = reg.read().into_iter().collect();
+        assert_eq!(read.len(), 1);
+        let (head, value) = read[0].clone();
+        assert_eq!(value, b"42".to_vec());
+        assert_eq!(reg.register.children(head)?, branches);
+
+        // resolving a single head is a no-op
+        let size = reg.size();
+        reg.resolve_branches_with(|a, b| numeric(a).cmp(&numeric(b)))?;
+        assert_eq!(reg.size(), size);
+
+        Ok(())
+    }
+
+    // A Register with compression enabled and two branches, "left" and "right", and the resolver
+    // of a conflicting write.
+    fn branched_register() -> Result<(ClientRegister, BranchResolver)> {
+        let mut reg = ClientRegister::create(offline_client()?, xor_name::rand::random())?
+            .with_compression()?;
+        reg.write(b"root")?;
+        let root: BTreeSet<EntryHash> = reg.read().into_iter().map(|(hash, _)| hash).collect();
+        reg.write_atop(b"left", root.clone())?;
+        reg.write_atop(b"right", root)?;
+
+        match reg.write(b"conflicting") {
+            Err(Error::ContentBranchDetected(resolver)) => Ok((reg, *resolver)),
+            other => panic!("Expected branches to be detected, got {other:?}"),
+        }
+    }
+
+    // The single latest entry of the Register, asserting it was written atop the `branches`.
+    fn resolved_head(
+        reg: &ClientRegister,
+        branches: &BTreeSet<EntryHash>,
+    ) -> Result<(EntryHash, Entry)> {
+        let read: Vec<(EntryHash, Entry)> = reg.read().into_iter().collect();
+        assert_eq!(read.len(), 1);
+        assert_eq!(&reg.register.children(read[0].0)?, branches);
+        Ok(read[0].clone())
+    }
+
+    #[tokio::test]
+    async fn resolver_merges_all_branches_with_the_conflicting_entry() -> Result<()> {
+        let (mut reg, resolver) = branched_register()?;
+        assert_eq!(resolver.entry(), b"conflicting");
+        let branches: BTreeSet<EntryHash> =
+            resolver.branches().iter().map(|(hash, _)| *hash).collect();
+        assert_eq!(branches.len(), 2);
+
+        let hash = resolver.merge_all(&mut reg)?;
+        assert_eq!(
+            resolved_head(&reg, &branches)?,
+            (hash, b"conflicting".to_vec())
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn resolver_keeps_the_chosen_branch() -> Result<()> {
+        let (mut reg, resolver) = branched_register()?;
+        let branches: BTreeSet<EntryHash> =
+            resolver.branches().iter().map(|(hash, _)| *hash).collect();
+        let (right, _) = reg
+            .branches()
+            .into_iter()
+            .find(|(_, entry)| entry == b"right")
+            .expect("the right branch");
+
+        // an unknown branch can't be kept
+        let unknown = EntryHash(rand::random());
+        assert!(matches!(
+            resolver.clone().keep(&mut reg, unknown),
+            Err(Error::EntryNotFound(missing)) if missing == unknown
+        ));
+
+        let hash = resolver.keep(&mut reg, right)?;
+        assert_eq!(resolved_head(&reg, &branches)?, (hash, b"right".to_vec()));
+        // the entry is written as stored, i.e. still compressed
+        assert!(reg.register.get(hash)?.starts_with(COMPRESSED_ENTRY_HEADER));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn resolver_keeps_the_greatest_branch() -> Result<()> {
+        let (mut reg, resolver) = branched_register()?;
+        let branches: BTreeSet<EntryHash> =
+            resolver.branches().iter().map(|(hash, _)| *hash).collect();
+
+        // the decompressed values are compared, "left" < "right"
+        let hash = resolver.keep_by(&mut reg, |a, b| b.cmp(a))?;
+        assert_eq!(resolved_head(&reg, &branches)?, (hash, b"left".to_vec()));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn resolver_only_applies_to_the_register_the_branches_were_found_in() -> Result<()> {
+        let (reg, resolver) = branched_register()?;
+        let mut other = ClientRegister::create(reg.client.clone(), xor_name::rand::random())?;
+
+        assert!(matches!(
+            resolver.clone().merge_all(&mut other),
+            Err(Error::Register(
+                sn_registers::Error::RegisterAddrMismatch { .. }
+            ))
+        ));
+        assert!(matches!(
+            resolver.keep_by(&mut other, |a, b| a.cmp(b)),
+            Err(Error::Register(
+                sn_registers::Error::RegisterAddrMismatch { .. }
+            ))
+        ));
+        assert_eq!(other.size(), 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn staged_ops_are_recovered_from_the_write_ahead_log() -> Result<()> {
+        let client = offline_client()?;
+        let wal_path = std::env::temp_dir().join(format!("register-wal-{}", rand::random::<u64>()));
+
+        let mut reg = ClientRegister::create(client.clone(), xor_name::rand::random())?
+            .with_write_ahead_log(wal_path.clone())?;
+        reg.write(b"first")?;
+        reg.write(b"second")?;
+        let address = *reg.address();
+        let expected_read = reg.read();
+        let expected_ops = reg.ops.clone();
+
+        // the client crashes before pushing the ops
+        drop(reg);
+
+        let mut recovered = ClientRegister::recover(client.clone(), wal_path.clone())?;
+        assert_eq!(recovered.address(), &address);
+        assert_eq!(recovered.read(), expected_read);
+        assert_eq!(recovered.ops, expected_ops);
+
+        // a push failing to reach the network leaves the ops in the log, to be flushed later
+        assert!(recovered.push(false).await.is_err());
+        let mut recovered = ClientRegister::recover(client.clone(), wal_path.clone())?;
+        assert_eq!(recovered.ops, expected_ops);
+
+        // the entries merged in from a replica are logged too
+        let mut replica = ClientRegister::create(client.clone(), address.meta())?;
+        replica.write(b"remote")?;
+        let _delta = merge_replica(&mut recovered, replica.register.clone())?;
+        let merged_read = recovered.read();
+        let recovered = ClientRegister::recover(client, wal_path.clone())?;
+        assert_eq!(recovered.read(), merged_read);
+        assert_eq!(recovered.ops, expected_ops);
+
+        // only the client owning the Register may recover it
+        assert!(matches!(
+            ClientRegister::recover(offline_client()?, wal_path.clone()),
+            Err(Error::Register(sn_registers::Error::InvalidSecretKey))
+        ));
+
+        std::fs::remove_file(wal_path).map_err(Error::WriteAheadLog)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn compressed_register_is_recovered_compressed() -> Result<()> {
+        let client = offline_client()?;
+        let wal_path = std::env::temp_dir().join(format!("register-wal-{}", rand::random::<u64>()));
+
+        let json = r#"{"name":"item","tags":["a","b","c"]}"#.repeat(20);
+        let mut reg = ClientRegister::create(client.clone(), xor_name::rand::random())?
+            .with_write_ahead_log(wal_path.clone())?
+            .with_compression()?;
+        reg.write(json.as_bytes())?;
+        let expected_read = reg.read();
+
+        // the client crashes before pushing the ops
+        drop(reg);
+
+        let mut recovered = ClientRegister::recover(client, wal_path.clone())?;
+        assert!(recovered.compression);
+        assert_eq!(recovered.read(), expected_read);
+
+        // the entries written after the recovery are compressed too
+        recovered.write(json.as_bytes())?;
+        let (hash, entry) = recovered
+            .read()
+            .into_iter()
+            .next()
+            .expect("an entry to be read");
+        assert_eq!(entry, json.as_bytes().to_vec());
+        assert!(recovered
+            .register
+            .get(hash)?
+            .starts_with(COMPRESSED_ENTRY_HEADER));
+
+        std::fs::remove_file(wal_path).map_err(Error::WriteAheadLog)?;
+        Ok(())
+    }
+
+    #[test]
+    fn write_ahead_logs_without_compression_are_recovered() -> Result<()> {
+        let register = Register::new(
+            SecretKey::random().public_key(),
+            xor_name::rand::random(),
+            Permissions::new_owner_only(),
+            None,
+        );
+        let ops = LinkedList::<RegisterCmd>::new();
+        let bytes = rmp_serde::to_vec(&(&register, &ops))
+            .map_err(|err| Error::WriteAheadLog(io::Error::new(io::ErrorKind::InvalidData, err)))?;
+
+        let wal: WriteAheadLog = rmp_serde::from_slice(&bytes)
+            .map_err(|err| Error::WriteAheadLog(io::Error::new(io::ErrorKind::InvalidData, err)))?;
+        assert_eq!(wal.register.into_owned(), register);
+        assert!(!wal.compression);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,199 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{Client, ClientRegister, Result};
+
+use futures::{stream, Stream};
+use sn_registers::{Entry, EntryHash, Register, RegisterAddress};
+use std::{
+    collections::{BTreeSet, VecDeque},
+    time::Duration,
+};
+use xor_name::XorName;
+
+/// How often subscribers poll the Register of a topic for new messages, by default.
+pub const DEFAULT_TOPIC_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// A publish/subscribe channel over a Register, each message published being an entry of it.
+///
+/// Publishers write atop all the current entries of the Register, merging the branches left by
+/// concurrent publishers. Subscribers poll the Register and receive the messages they haven't
+/// seen yet ordered by lamport timestamp, then by entry hash, so that concurrent messages are
+/// received in the same order by all the subscribers polling after they were published.
+/// Identical messages published concurrently atop the same entries are the same entry, hence
+/// received once.
+pub struct ClientTopic {
+    register: ClientRegister,
+    poll_interval: Duration,
+}
+
+impl ClientTopic {
+    /// Use the provided Register as a topic.
+    pub fn new(register: ClientRegister) -> Self {
+        Self {
+            register,
+            poll_interval: DEFAULT_TOPIC_POLL_INTERVAL,
+        }
+    }
+
+    /// Create a new topic on the Network, backed by the Register with the given `meta`.
+    pub async fn create_online(client: Client, meta: XorName, verify_store: bool) -> Result<Self> {
+        let register = ClientRegister::create_online(client, meta, verify_store).await?;
+        Ok(Self::new(register))
+    }
+
+    /// Set how often the subscribers of this topic poll for new messages.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Return the address of the Register backing the topic.
+    pub fn address(&self) -> &RegisterAddress {
+        self.register.address()
+    }
+
+    /// Publish a message locally, to be sent to the network on the next `push`.
+    pub fn publish(&mut self, message: &[u8]) -> Result<()> {
+        self.register.write_merging_branches(message)
+    }
+
+    /// Push the messages published locally to the network.
+    pub async fn push(&mut self, verify_store: bool) -> Result<()> {
+        self.register.push(verify_store).await
+    }
+
+    /// Publish a message on the network, after fetching the messages published by others
+    /// for ours to follow them.
+    pub async fn publish_online(&mut self, message: &[u8], verify_store: bool) -> Result<()> {
+        self.register.sync_delta(verify_store).await?;
+        self.publish(message)?;
+        self.push(verify_store).await
+    }
+
+    /// Return a stream of the messages of the topic, from the first one, each message being
+    /// received once. The topic is polled for new messages every poll interval, and a failure to
+    /// fetch it is yielded as an error, the stream carrying on polling afterwards.
+    pub fn subscribe(&self) -> impl Stream<Item = Result<Entry>> {
+        let subscription = Subscription {
+            client: self.register.client().clone(),
+            address: *self.address(),
+            poll_interval: self.poll_interval,
+            cursor: TopicCursor::default(),
+            pending: VecDeque::new(),
+            polled: false,
+        };
+        stream::unfold(subscription, |mut subscription| async move {
+            loop {
+                if let Some(message) = subscription.pending.pop_front() {
+                    return Some((Ok(message), subscription));
+                }
+                if subscription.polled {
+                    tokio::time::sleep(subscription.poll_interval).await;
+                }
+                subscription.polled = true;
+                match subscription.client.get_register(subscription.address).await {
+                    Ok(register) => {
+                        let messages = subscription.cursor.next_messages(register.replica());
+                        subscription.pending.extend(messages);
+                    }
+                    Err(err) => return Some((Err(err), subscription)),
+                }
+            }
+        })
+    }
+}
+
+// State of a subscriber to a topic.
+struct Subscription {
+    client: Client,
+    address: RegisterAddress,
+    poll_interval: Duration,
+    cursor: TopicCursor,
+    pending: VecDeque<Entry>,
+    polled: bool,
+}
+
+// The messages of a topic seen by a subscriber.
+#[derive(Debug, Default)]
+struct TopicCursor {
+    seen: BTreeSet<EntryHash>,
+}
+
+impl TopicCursor {
+    // The messages of the `register` we haven't seen yet, in lamport then hash order.
+    fn next_messages(&mut self, register: &Register) -> Vec<Entry> {
+        register
+            .entries_topological()
+            .into_iter()
+            .filter(|(hash, _, _)| self.seen.insert(*hash))
+            .map(|(_, message, _)| message)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::register::tests::{merge_replica, offline_client};
+    use sn_registers::Permissions;
+
+    #[tokio::test]
+    async fn subscriber_receives_the_messages_of_concurrent_publishers_once() -> Result<()> {
+        let client = offline_client()?;
+        let register = Register::new(
+            client.signer_pk(),
+            xor_name::rand::random(),
+            Permissions::new_owner_only(),
+            None,
+        );
+        let mut alice = ClientTopic::new(ClientRegister::from_snapshot(
+            client.clone(),
+            register.clone(),
+        )?);
+        let mut bob = ClientTopic::new(ClientRegister::from_snapshot(client, register.clone())?);
+
+        let mut subscriber = TopicCursor::default();
+        let mut replica = register.clone();
+        let mut received = vec![];
+
+        // both publish concurrently, then the subscriber polls
+        alice.publish(b"alice 1")?;
+        bob.publish(b"bob 1")?;
+        replica.merge(alice.register.replica().clone());
+        replica.merge(bob.register.replica().clone());
+        let first_round = subscriber.next_messages(&replica);
+        assert_eq!(first_round.len(), 2);
+        received.extend(first_round);
+
+        // both catch up with each other, publish concurrently again, and the subscriber polls
+        merge_replica(&mut alice.register, bob.register.replica().clone())?;
+        merge_replica(&mut bob.register, alice.register.replica().clone())?;
+        alice.publish(b"alice 2")?;
+        bob.publish(b"bob 2")?;
+        replica.merge(alice.register.replica().clone());
+        replica.merge(bob.register.replica().clone());
+        received.extend(subscriber.next_messages(&replica));
+
+        // nothing new
+        assert!(subscriber.next_messages(&replica).is_empty());
+
+        let mut sorted = received.clone();
+        sorted.sort();
+        let expected: Vec<Entry> = ["alice 1", "alice 2", "bob 1", "bob 2"]
+            .iter()
+            .map(|message| message.as_bytes().to_vec())
+            .collect();
+        assert_eq!(sorted, expected);
+
+        // a late subscriber receives them in the same order
+        assert_eq!(TopicCursor::default().next_messages(&replica), received);
+
+        Ok(())
+    }
+}
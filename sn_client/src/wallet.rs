@@ -9,8 +9,12 @@
 use super::Client;
 
 use rand::rngs::OsRng;
-use sn_dbc::{Dbc, PublicAddress, Token};
-use sn_protocol::{messages::PaymentProof, storage::ChunkAddress, NetworkAddress};
+use sn_dbc::{Dbc, DbcId, PublicAddress, Token};
+use sn_protocol::{
+    messages::PaymentProof,
+    storage::{ChunkAddress, DbcAddress},
+    NetworkAddress,
+};
 use sn_transfers::{
     client_transfers::TransferOutputs,
     payment_proof::build_payment_proofs,
@@ -221,19 +225,56 @@ impl Client {
     }
 
     /// Send a spend request to the network.
-    /// This can optionally verify the spend has been correctly stored before returning
-    pub async fn send(&self, transfer: TransferOutputs, verify_store: bool) -> Result<()> {
+    /// This can optionally verify the spend has been correctly stored before returning,
+    /// in which case the spends of the newly created dbcs are verified as well.
+    ///
+    /// Returns the ids and addresses of the dbcs created by the transaction (including any change dbc),
+    /// so that they can be tracked straight away.
+    pub async fn send(
+        &self,
+        transfer: TransferOutputs,
+        verify_store: bool,
+    ) -> Result<Vec<(DbcId, DbcAddress)>> {
+        self.send_to_replicas(transfer, verify_store, None).await
+    }
+
+    /// Send a spend request to the `replicas` closest peers to each spend, rather than to their
+    /// close group only, for durability-critical transfers.
+    /// Peers beyond the close group of a spend aren't responsible for it, hence may reject it
+    /// or not keep it.
+    ///
+    /// Returns the ids and addresses of the dbcs created by the transaction, as `send` does.
+    pub async fn send_with_redundancy(
+        &self,
+        transfer: TransferOutputs,
+        verify_store: bool,
+        replicas: usize,
+    ) -> Result<Vec<(DbcId, DbcAddress)>> {
+        self.send_to_replicas(transfer, verify_store, Some(replicas))
+            .await
+    }
+
+    async fn send_to_replicas(
+        &self,
+        transfer: TransferOutputs,
+        verify_store: bool,
+        replicas: Option<usize>,
+    ) -> Result<Vec<(DbcId, DbcAddress)>> {
         let mut tasks = Vec::new();
         for spend_request in &transfer.all_spend_requests {
             trace!("sending spend request to the network: {spend_request:#?}");
-            tasks.push(self.network_store_spend(spend_request.clone(), verify_store));
+            tasks.push(self.network_store_spend(spend_request.clone(), verify_store, replicas));
         }
 
         for spend_attempt_result in join_all(tasks).await {
             spend_attempt_result.map_err(|err| Error::CouldNotSendTokens(err.to_string()))?;
         }
 
-        Ok(())
+        if verify_store {
+            self.verify_stored(&transfer).await?;
+        }
+
+        Ok(transfer.outputs())
     }
 
     /// Send a spend request to the network.
@@ -242,7 +283,7 @@ impl Client {
         let mut tasks = Vec::new();
         for spend_request in &transfer.all_spend_requests {
             trace!("sending spend request to the network: {spend_request:#?}");
-            tasks.push(self.network_store_spend(spend_request.clone(), false));
+            tasks.push(self.network_store_spend(spend_request.clone(), false, None));
         }
 
         for spend_attempt_result in join_all(tasks).await {
@@ -252,6 +293,22 @@ impl Client {
         Ok(())
     }
 
+    /// Verify that the spends which created each of the output dbcs of the transfer
+    /// (the dbcs sent to recipients as well as the change dbc) are recorded in the network.
+    pub async fn verify_stored(&self, transfer: &TransferOutputs) -> Result<()> {
+        let tasks = transfer
+            .created_dbcs
+            .iter()
+            .chain(transfer.change_dbc.iter())
+            .map(|dbc| self.verify(dbc));
+
+        for result in join_all(tasks).await {
+            result?;
+        }
+
+        Ok(())
+    }
+
     pub async fn verify(&self, dbc: &Dbc) -> Result<()> {
         // We need to get all the spends in the dbc from the network,
         // and compare them to the spends in the dbc, to know if the
@@ -7,7 +7,10 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::{error::Error, MsgResponder, NetworkEvent, SwarmDriver};
-use crate::{error::Result, multiaddr_pop_p2p, sort_peers_by_address, CLOSE_GROUP_SIZE};
+use crate::{
+    error::Result, multiaddr_pop_p2p, request_watchdog::PendingRequest, sort_peers_by_address,
+    RequestInfo, RoutingTableSnapshot, CLOSE_GROUP_SIZE,
+};
 use libp2p::{
     kad::{store::RecordStore, Quorum, Record, RecordKey},
     swarm::{
@@ -18,10 +21,11 @@ use libp2p::{
 };
 use sn_dbc::Token;
 use sn_protocol::{
-    messages::{Request, Response},
+    error::Error as ProtocolError,
+    messages::{Request, RequestMsg, Response, ResponseMsg, SignedResponse},
     NetworkAddress, PrettyPrintRecordKey,
 };
-use std::collections::HashSet;
+use std::{collections::HashSet, time::Instant};
 use tokio::sync::oneshot;
 
 /// Commands to send to the Swarm
@@ -56,6 +60,14 @@ pub enum SwarmCmd {
         sender: oneshot::Sender<Vec<PeerId>>,
     },
     GetSwarmLocalState(oneshot::Sender<SwarmLocalState>),
+    // Returns the requests awaiting their response for longer than the request timeout.
+    GetStuckRequests {
+        sender: oneshot::Sender<Vec<RequestInfo>>,
+    },
+    // Returns a snapshot of the local RoutingTable, per k-bucket.
+    GetRoutingTable {
+        sender: oneshot::Sender<RoutingTableSnapshot>,
+    },
     // Send Request to the PeerId.
     SendRequest {
         req: Request,
@@ -78,6 +90,11 @@ pub enum SwarmCmd {
         key: RecordKey,
         sender: oneshot::Sender<bool>,
     },
+    /// Remove a Record from the local RecordStore, returning whether it was held
+    RemoveLocalRecord {
+        key: RecordKey,
+        sender: oneshot::Sender<bool>,
+    },
     /// Get the Addresses of all the Records held locally
     GetAllLocalRecordAddresses {
         sender: oneshot::Sender<HashSet<NetworkAddress>>,
@@ -85,6 +102,9 @@ pub enum SwarmCmd {
     /// Get Record from the Kad network
     GetNetworkRecord {
         key: RecordKey,
+        /// When set, the number of peers which must return identical copies of the Record,
+        /// any diverging copy failing the get. The majority of the close group otherwise.
+        quorum: Option<usize>,
         sender: oneshot::Sender<Result<Record>>,
     },
     /// GetLocalStoreCost for this node
@@ -96,20 +116,43 @@ pub enum SwarmCmd {
         key: RecordKey,
         sender: oneshot::Sender<Option<Record>>,
     },
+    /// Get the peer which published a locally stored Record, if tracked
+    GetLocalRecordOrigin {
+        key: RecordKey,
+        sender: oneshot::Sender<Option<PeerId>>,
+    },
     /// Put record to network
     PutRecord {
         record: Record,
         sender: oneshot::Sender<Result<()>>,
     },
-    /// Put record to the local RecordStore
+    /// Put record to the provided peers, rather than to the close group of the record
+    PutRecordTo {
+        record: Record,
+        peers: Vec<PeerId>,
+        sender: oneshot::Sender<Result<()>>,
+    },
+    /// Put record to the specified peers, the `sender` being sent the peers which acknowledged
+    /// storing it once the put completes
+    PutRecordToAcked {
+        record: Record,
+        peers: Vec<PeerId>,
+        sender: oneshot::Sender<HashSet<PeerId>>,
+    },
+    /// Put record to the local RecordStore, the `sender` being sent whether it's been stored
     PutLocalRecord {
         record: Record,
+        sender: oneshot::Sender<Result<()>>,
     },
     /// The keys added to the replication fetcher are later used to fetch the Record from the peer/network
     AddKeysToReplicationFetcher {
         peer: PeerId,
         keys: Vec<NetworkAddress>,
     },
+    /// Stop fetching the Record for replication, as we decline to store it
+    DeclineReplicatedRecord {
+        key: RecordKey,
+    },
 }
 
 /// Snapshot of information kept in the Swarm's local state
@@ -146,11 +189,21 @@ impl SwarmDriver {
                     self.send_event(NetworkEvent::KeysForReplication(keys_to_fetch));
                 }
             }
-            SwarmCmd::GetNetworkRecord { key, sender } => {
+            SwarmCmd::DeclineReplicatedRecord { key } => {
+                let keys_to_fetch = self.replication_fetcher.notify_about_rejected_put(key);
+                if !keys_to_fetch.is_empty() {
+                    self.send_event(NetworkEvent::KeysForReplication(keys_to_fetch));
+                }
+            }
+            SwarmCmd::GetNetworkRecord {
+                key,
+                quorum,
+                sender,
+            } => {
                 let query_id = self.swarm.behaviour_mut().kademlia.get_record(key);
                 if self
                     .pending_get_record
-                    .insert(query_id, (sender, Default::default()))
+                    .insert(query_id, (sender, Default::default(), quorum))
                     .is_some()
                 {
                     warn!("An existing get_record task {query_id:?} got replaced");
@@ -171,6 +224,58 @@ impl SwarmDriver {
                     .map(|rec| rec.into_owned());
                 let _ = sender.send(record);
             }
+            SwarmCmd::GetLocalRecordOrigin { key, sender } => {
+                let origin = self
+                    .swarm
+                    .behaviour_mut()
+                    .kademlia
+                    .store_mut()
+                    .record_origin(&key);
+                let _ = sender.send(origin);
+            }
+            SwarmCmd::PutRecordTo {
+                record,
+                peers,
+                sender,
+            } => {
+                let record_key = PrettyPrintRecordKey::from(record.key.clone());
+                trace!(
+                    "Putting record sized: {:?} to {} peers {:?}",
+                    record.value.len(),
+                    peers.len(),
+                    record_key
+                );
+                let request_id = self.swarm.behaviour_mut().kademlia.put_record_to(
+                    record,
+                    peers.into_iter(),
+                    Quorum::All,
+                );
+                trace!("Sent record {record_key:?} to peers. Request id: {request_id:?}");
+
+                if let Err(err) = sender.send(Ok(())) {
+                    error!("Could not send response to PutRecordTo cmd: {:?}", err);
+                }
+            }
+            SwarmCmd::PutRecordToAcked {
+                record,
+                peers,
+                sender,
+            } => {
+                let record_key = PrettyPrintRecordKey::from(record.key.clone());
+                trace!(
+                    "Putting record {record_key:?} to {} peers, awaiting their acks",
+                    peers.len()
+                );
+                let targeted: HashSet<PeerId> = peers.iter().copied().collect();
+                let query_id = self.swarm.behaviour_mut().kademlia.put_record_to(
+                    record,
+                    peers.into_iter(),
+                    Quorum::All,
+                );
+                let _ = self
+                    .pending_put_record_to
+                    .insert(query_id, (sender, targeted));
+            }
             SwarmCmd::PutRecord { record, sender } => {
                 let record_key = PrettyPrintRecordKey::from(record.key.clone());
                 trace!(
@@ -198,23 +303,36 @@ impl SwarmDriver {
                     error!("Could not send response to PutRecord cmd: {:?}", err);
                 }
             }
-            SwarmCmd::PutLocalRecord { record } => {
+            SwarmCmd::PutLocalRecord { record, sender } => {
                 let key = record.key.clone();
-                match self
+                let res = self
                     .swarm
                     .behaviour_mut()
                     .kademlia
                     .store_mut()
-                    .put_verified(record)
-                {
-                    Ok(_) => {
-                        let new_keys_to_fetch = self.replication_fetcher.notify_about_new_put(key);
-                        if !new_keys_to_fetch.is_empty() {
-                            self.send_event(NetworkEvent::KeysForReplication(new_keys_to_fetch));
-                        }
+                    .put_verified(record);
+                let new_keys_to_fetch = match &res {
+                    Ok(_) => self.replication_fetcher.notify_about_new_put(key),
+                    // we won't store it however many times it's replicated to us
+                    Err(Error::Protocol(ProtocolError::KindQuotaExceeded { .. })) => {
+                        self.replication_fetcher.notify_about_rejected_put(key)
                     }
-                    Err(err) => return Err(err.into()),
+                    Err(_) => vec![],
                 };
+                if !new_keys_to_fetch.is_empty() {
+                    self.send_event(NetworkEvent::KeysForReplication(new_keys_to_fetch));
+                }
+                let _ = sender.send(res);
+            }
+            SwarmCmd::RemoveLocalRecord { key, sender } => {
+                let store = self.swarm.behaviour_mut().kademlia.store_mut();
+                let was_held = store.contains(&key);
+                if was_held {
+                    store.remove(&key);
+                    // having made space, we may now store the records we declined
+                    self.replication_fetcher.forget_rejected_keys();
+                }
+                let _ = sender.send(was_held);
             }
             SwarmCmd::RecordStoreHasKey { key, sender } => {
                 let has_key = self
@@ -287,17 +405,28 @@ impl SwarmDriver {
                     trace!("Sending request to self");
 
                     self.send_event(NetworkEvent::RequestReceived {
+                        peer,
                         req,
                         channel: MsgResponder::FromSelf(sender),
                     });
                 } else {
+                    let msg = RequestMsg::new(rand::random(), req);
+                    let msg_id = msg.request_id;
                     let request_id = self
                         .swarm
                         .behaviour_mut()
                         .request_response
-                        .send_request(&peer, req);
-                    trace!("Sending request {request_id:?} to peer {peer:?}");
-                    let _ = self.pending_requests.insert(request_id, sender);
+                        .send_request(&peer, msg);
+                    trace!("Sending request {request_id:?} (msg id {msg_id}) to peer {peer:?}");
+                    let _ = self.pending_requests.insert(
+                        request_id,
+                        PendingRequest {
+                            sender,
+                            peer,
+                            msg_id,
+                            sent_at: Instant::now(),
+                        },
+                    );
                 }
             }
             SwarmCmd::SendResponse { resp, channel } => match channel {
@@ -317,12 +446,33 @@ impl SwarmDriver {
                         }
                     }
                 }
-                MsgResponder::FromPeer(channel) => {
+                MsgResponder::FromPeer {
+                    peer,
+                    channel,
+                    msg_id,
+                } => {
+                    let mut response = match &self.response_signing_key {
+                        Some(keypair) => {
+                            Response::Signed(SignedResponse::new(&resp, msg_id, keypair)?)
+                        }
+                        None => resp,
+                    };
+                    if let Some(threshold) = self.response_compression_threshold {
+                        if self.peers_accepting_compressed_responses.contains(&peer) {
+                            response = response.compressed_above(threshold)?;
+                        }
+                    }
                     self.swarm
                         .behaviour_mut()
                         .request_response
-                        .send_response(channel, resp)
-                        .map_err(Error::OutgoingResponseDropped)?;
+                        .send_response(
+                            channel,
+                            ResponseMsg {
+                                request_id: msg_id,
+                                response,
+                            },
+                        )
+                        .map_err(|msg| Error::OutgoingResponseDropped(msg.response))?;
                 }
             },
             SwarmCmd::GetSwarmLocalState(sender) => {
@@ -335,6 +485,12 @@ impl SwarmDriver {
                     .send(current_state)
                     .map_err(|_| Error::InternalMsgChannelDropped)?;
             }
+            SwarmCmd::GetStuckRequests { sender } => {
+                let _ = sender.send(self.stuck_requests());
+            }
+            SwarmCmd::GetRoutingTable { sender } => {
+                let _ = sender.send(self.routing_table_snapshot());
+            }
         }
         Ok(())
     }
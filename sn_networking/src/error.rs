@@ -12,10 +12,10 @@ use libp2p::{
     kad::{self, Record},
     request_response::{OutboundFailure, RequestId},
     swarm::DialError,
-    TransportError,
+    PeerId, TransportError,
 };
 use sn_protocol::{messages::Response, PrettyPrintRecordKey};
-use std::{io, path::PathBuf};
+use std::{collections::HashSet, io, path::PathBuf};
 use thiserror::Error;
 use tokio::sync::{mpsc, oneshot};
 
@@ -66,7 +66,7 @@ pub enum Error {
     DialError(#[from] DialError),
 
     #[error("This peer is already being dialed: {0}")]
-    AlreadyDialingPeer(libp2p::PeerId),
+    AlreadyDialingPeer(PeerId),
 
     #[error("Outbound Error")]
     OutboundError(#[from] OutboundFailure),
@@ -95,6 +95,9 @@ pub enum Error {
     #[error("Get Record completed with non enough copies")]
     RecordNotEnoughCopies(Record),
 
+    #[error("Get Record returned diverging copies")]
+    SplitRecord(Vec<(Record, HashSet<PeerId>)>),
+
     #[error("Error putting record")]
     PutRecordError(#[from] kad::PutRecordError),
 
@@ -104,10 +107,56 @@ pub enum Error {
     #[error("Failed to sign the message with the PeerId keypair")]
     SigningFailed(#[from] libp2p::identity::SigningError),
 
+    #[error("Request {0} was cancelled, having awaited its response for too long")]
+    RequestCancelled(RequestId),
+
+    #[error("Protocol error {0}")]
+    Protocol(#[from] sn_protocol::error::Error),
+
     #[error("Failed to pop from front of CircularVec")]
     CircularVecPopFrontError,
 }
 
+impl Error {
+    /// Returns `true` if the error is transient, e.g. a timeout, an unreachable peer or not enough
+    /// responses from the network, and the operation is worth retrying.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::NotEnoughCostQuotes
+            | Error::NoStoreCostReturned
+            | Error::ReceivedResponseDropped(_)
+            | Error::OutgoingResponseDropped(_)
+            | Error::FailedToVerifyRecordWasStored(_)
+            | Error::Io(_)
+            | Error::TransportError(_)
+            | Error::DialError(_)
+            | Error::AlreadyDialingPeer(_)
+            | Error::NotEnoughPeers { .. }
+            | Error::RequestCancelled(_)
+            | Error::RecordNotFound
+            | Error::RecordNotEnoughCopies(_)
+            | Error::PutRecordError(_)
+            | Error::NoSwarmCmdChannelCapacity => true,
+            Error::OutboundError(failure) => {
+                !matches!(failure, OutboundFailure::UnsupportedProtocols)
+            }
+            Error::Protocol(error) => error.is_retryable(),
+            Error::InvalidCloseGroupSize
+            | Error::InternalMsgChannelDropped
+            | Error::ReturnedRecordDoesNotMatch(_)
+            | Error::SplitRecord(_)
+            | Error::FailedToCreateRecordStoreDir { .. }
+            | Error::KademliaStoreError(_)
+            | Error::NetworkEventReceiverDropped(_)
+            | Error::ReceivedKademliaEventDropped(_)
+            | Error::SwarmCmdReceiverDropped(_)
+            | Error::SenderDropped(_)
+            | Error::SigningFailed(_)
+            | Error::CircularVecPopFrontError => false,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use sn_protocol::{storage::ChunkAddress, NetworkAddress};
@@ -128,4 +177,57 @@ mod tests {
         println!("xor_name_str: {}", xor_name_str);
         assert_eq!(record_str, xor_name_str);
     }
+
+    #[test]
+    fn retryable_classification() {
+        let key = PrettyPrintRecordKey::from(kad::RecordKey::new(&[1, 2, 3]));
+        let record = Record::new(vec![1, 2, 3], vec![]);
+        let peer_id = PeerId::random();
+
+        let retryable = vec![
+            Error::NotEnoughCostQuotes,
+            Error::NoStoreCostReturned,
+            Error::FailedToVerifyRecordWasStored(key.clone()),
+            Error::Io(io::Error::from(io::ErrorKind::TimedOut)),
+            Error::AlreadyDialingPeer(peer_id),
+            Error::OutboundError(OutboundFailure::Timeout),
+            Error::OutboundError(OutboundFailure::ConnectionClosed),
+            Error::OutboundError(OutboundFailure::DialFailure),
+            Error::NotEnoughPeers {
+                found: 1,
+                required: 8,
+            },
+            Error::RecordNotFound,
+            Error::RecordNotEnoughCopies(record.clone()),
+            Error::PutRecordError(kad::PutRecordError::Timeout {
+                key: record.key.clone(),
+                success: vec![],
+                quorum: std::num::NonZeroUsize::MIN,
+            }),
+            Error::NoSwarmCmdChannelCapacity,
+        ];
+        for error in retryable {
+            assert!(error.is_retryable(), "{error:?} should be retryable");
+        }
+
+        let terminal = vec![
+            Error::InvalidCloseGroupSize,
+            Error::InternalMsgChannelDropped,
+            Error::ReturnedRecordDoesNotMatch(key),
+            Error::FailedToCreateRecordStoreDir {
+                path: PathBuf::from("/"),
+                source: io::Error::from(io::ErrorKind::PermissionDenied),
+            },
+            Error::OutboundError(OutboundFailure::UnsupportedProtocols),
+            Error::KademliaStoreError(kad::store::Error::MaxRecords),
+            Error::CircularVecPopFrontError,
+            Error::SplitRecord(vec![(record.clone(), HashSet::from([peer_id]))]),
+            Error::Protocol(sn_protocol::error::Error::ResponseSignatureInvalid(
+                Box::new(NetworkAddress::from_peer(peer_id)),
+            )),
+        ];
+        for error in terminal {
+            assert!(!error.is_retryable(), "{error:?} should be terminal");
+        }
+    }
 }
@@ -14,7 +14,7 @@ use super::{
 
 use crate::{
     close_group_majority, multiaddr_is_global, multiaddr_strip_p2p, sort_peers_by_address,
-    CLOSE_GROUP_SIZE, IDENTIFY_AGENT_STR,
+    RequestInfo, CLOSE_GROUP_SIZE, IDENTIFY_AGENT_STR,
 };
 
 use core::fmt;
@@ -25,8 +25,8 @@ use libp2p::mdns;
 use libp2p::{
     autonat::{self, NatStatus},
     kad::{
-        GetRecordError, GetRecordOk, InboundRequest, Kademlia, KademliaEvent, PeerRecord, QueryId,
-        QueryResult, Record, RecordKey, K_VALUE,
+        GetRecordError, GetRecordOk, InboundRequest, Kademlia, KademliaEvent, PeerRecord,
+        PutRecordError, PutRecordOk, QueryId, QueryResult, Record, RecordKey, K_VALUE,
     },
     multiaddr::Protocol,
     request_response::{self, ResponseChannel as PeerResponseChannel},
@@ -34,13 +34,17 @@ use libp2p::{
     Multiaddr, PeerId,
 };
 use sn_protocol::{
-    messages::{Request, Response},
+    messages::{
+        accepts_compressed_responses, advertised_max_payload, Request, RequestMsg, Response,
+        ResponseMsg,
+    },
     NetworkAddress, PrettyPrintRecordKey,
 };
 use std::{
     collections::{BTreeMap, HashMap, HashSet},
     fmt::{Debug, Formatter},
     num::NonZeroUsize,
+    time::SystemTime,
 };
 
 use tokio::sync::oneshot;
@@ -54,7 +58,7 @@ pub(super) type GetRecordResultMap = HashMap<XorName, (Record, HashSet<PeerId>)>
 #[derive(NetworkBehaviour)]
 #[behaviour(to_swarm = "NodeEvent")]
 pub(super) struct NodeBehaviour {
-    pub(super) request_response: request_response::cbor::Behaviour<Request, Response>,
+    pub(super) request_response: request_response::cbor::Behaviour<RequestMsg, ResponseMsg>,
     pub(super) kademlia: Kademlia<DiskBackedRecordStore>,
     #[cfg(feature = "local-discovery")]
     pub(super) mdns: mdns::tokio::Behaviour,
@@ -65,7 +69,7 @@ pub(super) struct NodeBehaviour {
 /// NodeEvent enum
 #[derive(CustomDebug)]
 pub(super) enum NodeEvent {
-    MsgReceived(request_response::Event<Request, Response>),
+    MsgReceived(request_response::Event<RequestMsg, ResponseMsg>),
     Kademlia(KademliaEvent),
     #[cfg(feature = "local-discovery")]
     Mdns(Box<mdns::Event>),
@@ -73,8 +77,8 @@ pub(super) enum NodeEvent {
     Autonat(autonat::Event),
 }
 
-impl From<request_response::Event<Request, Response>> for NodeEvent {
-    fn from(event: request_response::Event<Request, Response>) -> Self {
+impl From<request_response::Event<RequestMsg, ResponseMsg>> for NodeEvent {
+    fn from(event: request_response::Event<RequestMsg, ResponseMsg>) -> Self {
         NodeEvent::MsgReceived(event)
     }
 }
@@ -110,7 +114,13 @@ pub enum MsgResponder {
     /// Respond to a request from `self` through a simple one-shot channel.
     FromSelf(Option<oneshot::Sender<Result<Response>>>),
     /// Respond to a request from a peer in the network.
-    FromPeer(PeerResponseChannel<Response>),
+    FromPeer {
+        /// The peer the request comes from.
+        peer: PeerId,
+        channel: PeerResponseChannel<ResponseMsg>,
+        /// The id of the `RequestMsg`, echoed in the `ResponseMsg`.
+        msg_id: u64,
+    },
 }
 
 #[allow(clippy::large_enum_variant)]
@@ -118,6 +128,8 @@ pub enum MsgResponder {
 pub enum NetworkEvent {
     /// Incoming `Request` from a peer
     RequestReceived {
+        /// The peer which sent the request, which is ourselves for requests sent to self
+        peer: PeerId,
         /// Request
         req: Request,
         /// The channel to send the `Response` through
@@ -140,14 +152,16 @@ pub enum NetworkEvent {
     NatStatusChanged(NatStatus),
     /// Report unverified record
     UnverifiedRecord(Record),
+    /// A request we sent has been cancelled, having awaited its response for too long
+    RequestCancelled(RequestInfo),
 }
 
 // Manually implement Debug as `#[debug(with = "unverified_record_fmt")]` not working as expected.
 impl Debug for NetworkEvent {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         match self {
-            NetworkEvent::RequestReceived { req, .. } => {
-                write!(f, "NetworkEvent::RequestReceived({req:?})")
+            NetworkEvent::RequestReceived { peer, req, .. } => {
+                write!(f, "NetworkEvent::RequestReceived({req:?} from {peer:?})")
             }
             NetworkEvent::ResponseReceived { res, .. } => {
                 write!(f, "NetworkEvent::ResponseReceived({res:?})")
@@ -178,6 +192,9 @@ impl Debug for NetworkEvent {
                 let pretty_key = PrettyPrintRecordKey::from(record.key.clone());
                 write!(f, "NetworkEvent::UnverifiedRecord({pretty_key:?})")
             }
+            NetworkEvent::RequestCancelled(info) => {
+                write!(f, "NetworkEvent::RequestCancelled({info:?})")
+            }
         }
     }
 }
@@ -201,6 +218,18 @@ impl SwarmDriver {
                 match *iden {
                     libp2p::identify::Event::Received { peer_id, info } => {
                         debug!(%peer_id, ?info, "identify: received info");
+                        let _ = self.peers_last_seen.insert(peer_id, SystemTime::now());
+                        if accepts_compressed_responses(&info.agent_version) {
+                            let _ = self.peers_accepting_compressed_responses.insert(peer_id);
+                        }
+                        if let Some(max_payload) = advertised_max_payload(&info.agent_version) {
+                            match self.peer_max_payloads.write() {
+                                Ok(mut peer_max_payloads) => {
+                                    let _ = peer_max_payloads.insert(peer_id, max_payload);
+                                }
+                                Err(_) => error!("The peer max payloads lock is poisoned"),
+                            }
+                        }
 
                         // If we are not local, we care only for peers that we dialed and thus are reachable.
                         if (self.local || self.dialed_peers.contains(&peer_id))
@@ -301,11 +330,15 @@ impl SwarmDriver {
                 ..
             } => {
                 debug!(%peer_id, num_established, "ConnectionEstablished: {}", endpoint_str(&endpoint));
+                let _ = self.peers_last_seen.insert(peer_id, SystemTime::now());
 
                 if endpoint.is_dialer() {
                     self.dialed_peers
                         .push(peer_id)
                         .map_err(|_| Error::CircularVecPopFrontError)?;
+                } else if !self.is_peer_allowed(&peer_id) {
+                    info!(%peer_id, "Refusing the inbound connection of a peer not on our allowlist");
+                    let _ = self.swarm.disconnect_peer_id(peer_id);
                 }
             }
             SwarmEvent::ConnectionClosed {
@@ -316,6 +349,9 @@ impl SwarmDriver {
                 connection_id,
             } => {
                 debug!(%peer_id, ?connection_id, ?cause, num_established, "ConnectionClosed: {}", endpoint_str(&endpoint));
+                if num_established == 0 {
+                    self.forget_disconnected_peer(&peer_id);
+                }
             }
             SwarmEvent::OutgoingConnectionError {
                 peer_id: Some(failed_peer_id),
@@ -329,6 +365,7 @@ impl SwarmDriver {
                     .kademlia
                     .remove_peer(&failed_peer_id)
                 {
+                    let _ = self.peers_last_seen.remove(dead_peer.node.key.preimage());
                     self.send_event(NetworkEvent::PeerRemoved(*dead_peer.node.key.preimage()));
                     self.log_kbuckets(&failed_peer_id);
                     let _ = self.check_for_change_in_our_close_group();
@@ -442,8 +479,15 @@ impl SwarmDriver {
                 step,
             } => {
                 trace!("Query task {id:?} of get_record completed with {stats:?} - {step:?}");
-                if let Some((sender, result_map)) = self.pending_get_record.remove(&id) {
-                    if let Some((record, _)) = result_map.values().next() {
+                if let Some((sender, result_map, quorum)) = self.pending_get_record.remove(&id) {
+                    if let Some(quorum) = quorum {
+                        // The peers which didn't return a copy won't, e.g. being offline
+                        let result = quorum_outcome(&result_map, quorum, true)
+                            .unwrap_or(Err(Error::RecordNotFound));
+                        sender
+                            .send(result)
+                            .map_err(|_| Error::InternalMsgChannelDropped)?;
+                    } else if let Some((record, _)) = result_map.values().next() {
                         info!(
                             "Getting record {:?} early completed with {:?} copies received",
                             PrettyPrintRecordKey::from(record.key.clone()),
@@ -493,12 +537,25 @@ impl SwarmDriver {
                     }
                 }
 
-                if let Some((sender, _)) = self.pending_get_record.remove(&id) {
+                if let Some((sender, _, _)) = self.pending_get_record.remove(&id) {
                     sender
                         .send(Err(Error::RecordNotFound))
                         .map_err(|_| Error::InternalMsgChannelDropped)?;
                 }
             }
+            KademliaEvent::OutboundQueryProgressed {
+                id,
+                result: QueryResult::PutRecord(result),
+                stats,
+                ..
+            } => {
+                trace!("Query task {id:?} put record with {result:?}, {stats:?}");
+                if let Some((sender, targeted)) = self.pending_put_record_to.remove(&id) {
+                    sender
+                        .send(acked_peers(result, targeted))
+                        .map_err(|_| Error::InternalMsgChannelDropped)?;
+                }
+            }
             KademliaEvent::OutboundQueryProgressed {
                 id,
                 result: QueryResult::Bootstrap(bootstrap_result),
@@ -529,8 +586,9 @@ impl SwarmDriver {
                     }
                 }
 
-                if old_peer.is_some() {
+                if let Some(old_peer) = old_peer {
                     info!("Evicted old peer on new peer join: {old_peer:?}");
+                    let _ = self.peers_last_seen.remove(&old_peer);
                     self.send_event(NetworkEvent::PeerRemoved(peer));
                     self.log_kbuckets(&peer);
                 }
@@ -573,6 +631,32 @@ impl SwarmDriver {
         all_peers
     }
 
+    // Drop what we hold about a peer we're no longer connected to. Only the peers of our
+    // RoutingTable are remembered as last seen, for the peers merely connecting to us not to
+    // accumulate.
+    pub(crate) fn forget_disconnected_peer(&mut self, peer_id: &PeerId) {
+        let _ = self.peers_accepting_compressed_responses.remove(peer_id);
+        match self.peer_max_payloads.write() {
+            Ok(mut peer_max_payloads) => {
+                let _ = peer_max_payloads.remove(peer_id);
+            }
+            Err(_) => error!("The peer max payloads lock is poisoned"),
+        }
+        if !self.is_in_routing_table(peer_id) {
+            let _ = self.peers_last_seen.remove(peer_id);
+        }
+    }
+
+    // Whether the peer is one of our RoutingTable.
+    fn is_in_routing_table(&mut self, peer_id: &PeerId) -> bool {
+        match self.swarm.behaviour_mut().kademlia.kbucket(*peer_id) {
+            Some(kbucket) => kbucket
+                .iter()
+                .any(|entry| entry.node.key.preimage() == peer_id),
+            None => false,
+        }
+    }
+
     // Check for changes in our close group
     fn check_for_change_in_our_close_group(&mut self) -> Option<Vec<PeerId>> {
         let new_closest_peers = {
@@ -643,9 +727,10 @@ impl SwarmDriver {
     }
 
     // Completes when any of the following condition reaches first:
-    // 1, Return whenever reached majority of CLOSE_GROUP_SIZE
+    // 1, Return whenever reached majority of CLOSE_GROUP_SIZE, or the quorum if any
     // 2, In case of split, return with NotFound,
     //    whenever `ProgressStep::count` hits CLOSE_GROUP_SIZE
+    //    or with SplitRecord if there's a quorum, once any copy reaches it
     fn accumulate_get_record_ok(
         &mut self,
         query_id: QueryId,
@@ -659,7 +744,7 @@ impl SwarmDriver {
         };
         let record_content_hash = XorName::from_content(&peer_record.record.value);
 
-        if let Some((sender, mut result_map)) = self.pending_get_record.remove(&query_id) {
+        if let Some((sender, mut result_map, quorum)) = self.pending_get_record.remove(&query_id) {
             let peer_list =
                 if let Some((_, mut peer_list)) = result_map.remove(&record_content_hash) {
                     let _ = peer_list.insert(peer_id);
@@ -669,24 +754,24 @@ impl SwarmDriver {
                     let _ = peer_list.insert(peer_id);
                     peer_list
                 };
-
-            let result = if peer_list.len() >= close_group_majority() {
-                Some(Ok(peer_record.record.clone()))
-            } else if usize::from(count) >= CLOSE_GROUP_SIZE {
-                Some(Err(Error::RecordNotFound))
-            } else {
-                None
+            let agreeing = peer_list.len();
+            let _ = result_map.insert(record_content_hash, (peer_record.record.clone(), peer_list));
+
+            let completed = usize::from(count) >= CLOSE_GROUP_SIZE;
+            let result = match quorum {
+                Some(quorum) => quorum_outcome(&result_map, quorum, completed),
+                None if agreeing >= close_group_majority() => Some(Ok(peer_record.record)),
+                None if completed => Some(Err(Error::RecordNotFound)),
+                None => None,
             };
 
-            let _ = result_map.insert(record_content_hash, (peer_record.record, peer_list));
-
             if let Some(result) = result {
                 let _ = sender.send(result);
                 self.try_update_self_for_split_record(result_map);
             } else {
                 let _ = self
                     .pending_get_record
-                    .insert(query_id, (sender, result_map));
+                    .insert(query_id, (sender, result_map, quorum));
             }
         }
     }
@@ -728,6 +813,50 @@ impl SwarmDriver {
             }
         }
     }
+
+    // Any peer is allowed unless we've been given an allowlist.
+    fn is_peer_allowed(&self, peer_id: &PeerId) -> bool {
+        match &self.peer_allowlist {
+            Some(allowlist) => allowlist.contains(peer_id),
+            None => true,
+        }
+    }
+}
+
+// The outcome of getting a record which `quorum` peers must return identical copies of, the
+// copies returned so far being those of the `result_map`, or `None` if more are to be awaited.
+// A copy reaching the quorum is returned when it's the only one, otherwise the diverging copies
+// are, for the caller to tell the genuine one, as they are when the get `completed` without one.
+fn quorum_outcome(
+    result_map: &GetRecordResultMap,
+    quorum: usize,
+    completed: bool,
+) -> Option<Result<Record>> {
+    let quorum_reached = result_map.values().any(|(_, peers)| peers.len() >= quorum);
+    match result_map.values().next() {
+        Some((record, _)) if quorum_reached && result_map.len() == 1 => Some(Ok(record.clone())),
+        Some(_) if result_map.len() > 1 && (quorum_reached || completed) => Some(Err(
+            Error::SplitRecord(result_map.values().cloned().collect()),
+        )),
+        _ if completed => Some(Err(Error::RecordNotFound)),
+        _ => None,
+    }
+}
+
+// The peers which acknowledged storing the record put to the `targeted` peers, out of the
+// `result` of the put. All of them did when the put succeeded, its quorum being all of them.
+fn acked_peers(
+    result: std::result::Result<PutRecordOk, PutRecordError>,
+    targeted: HashSet<PeerId>,
+) -> HashSet<PeerId> {
+    match result {
+        Ok(_) => targeted,
+        Err(PutRecordError::QuorumFailed { success, .. })
+        | Err(PutRecordError::Timeout { success, .. }) => success
+            .into_iter()
+            .filter(|peer| targeted.contains(peer))
+            .collect(),
+    }
 }
 
 /// Helper function to print formatted connection role info.
@@ -741,3 +870,103 @@ fn endpoint_str(endpoint: &libp2p::core::ConnectedPoint) -> String {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The copies of a record returned by `peers` peers each, one map entry per distinct copy.
+    fn copies(values: &[(&[u8], usize)]) -> GetRecordResultMap {
+        values
+            .iter()
+            .map(|(value, peers)| {
+                let record = Record::new(b"key".to_vec(), value.to_vec());
+                let peers = (0..*peers).map(|_| PeerId::random()).collect();
+                (XorName::from_content(value), (record, peers))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn record_agreed_on_by_exactly_the_quorum_is_returned() {
+        let quorum = CLOSE_GROUP_SIZE - 1;
+        let result_map = copies(&[(b"spend", quorum)]);
+        assert!(matches!(
+            quorum_outcome(&result_map, quorum, false),
+            Some(Ok(record)) if record.value == b"spend"
+        ));
+    }
+
+    #[test]
+    fn record_agreed_on_below_the_quorum_fails_once_completed() {
+        let quorum = CLOSE_GROUP_SIZE - 1;
+        let result_map = copies(&[(b"spend", quorum - 1)]);
+        assert!(quorum_outcome(&result_map, quorum, false).is_none());
+        assert!(matches!(
+            quorum_outcome(&result_map, quorum, true),
+            Some(Err(Error::RecordNotFound))
+        ));
+        assert!(matches!(
+            quorum_outcome(&GetRecordResultMap::new(), quorum, true),
+            Some(Err(Error::RecordNotFound))
+        ));
+    }
+
+    #[test]
+    fn diverging_copies_are_awaited_until_one_reaches_the_quorum() {
+        let quorum = CLOSE_GROUP_SIZE - 1;
+        let result_map = copies(&[(b"spend", quorum - 1), (b"double spend", 1)]);
+        assert!(quorum_outcome(&result_map, quorum, false).is_none());
+        assert!(matches!(
+            quorum_outcome(&result_map, quorum, true),
+            Some(Err(Error::SplitRecord(split))) if split.len() == 2
+        ));
+    }
+
+    #[test]
+    fn diverging_copies_are_returned_once_one_reaches_the_quorum() {
+        let quorum = CLOSE_GROUP_SIZE - 1;
+        let result_map = copies(&[(b"spend", quorum), (b"double spend", 1)]);
+        match quorum_outcome(&result_map, quorum, false) {
+            Some(Err(Error::SplitRecord(mut split))) => {
+                split.sort_by(|(a, _), (b, _)| a.value.cmp(&b.value));
+                let values: Vec<_> = split
+                    .into_iter()
+                    .map(|(record, peers)| (record.value, peers.len()))
+                    .collect();
+                assert_eq!(
+                    values,
+                    vec![(b"double spend".to_vec(), 1), (b"spend".to_vec(), quorum)]
+                );
+            }
+            other => panic!("expected a split record, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn only_the_peers_which_stored_the_record_are_acked() {
+        let key = RecordKey::new(b"key");
+        let targeted: HashSet<PeerId> = (0..CLOSE_GROUP_SIZE).map(|_| PeerId::random()).collect();
+        let some: Vec<PeerId> = targeted.iter().take(3).copied().collect();
+
+        let ok = Ok(PutRecordOk { key: key.clone() });
+        assert_eq!(acked_peers(ok, targeted.clone()), targeted);
+
+        let quorum_failed = Err(PutRecordError::QuorumFailed {
+            key: key.clone(),
+            success: some.clone(),
+            quorum: NonZeroUsize::MIN,
+        });
+        assert_eq!(
+            acked_peers(quorum_failed, targeted.clone()),
+            some.iter().copied().collect()
+        );
+
+        let timeout = Err(PutRecordError::Timeout {
+            key,
+            success: vec![],
+            quorum: NonZeroUsize::MIN,
+        });
+        assert!(acked_peers(timeout, targeted).is_empty());
+    }
+}
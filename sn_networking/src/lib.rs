@@ -16,11 +16,16 @@ mod event;
 mod msg;
 mod record_store;
 mod replication_fetcher;
+mod request_watchdog;
+mod routing_table;
 
 pub use self::{
     cmd::SwarmLocalState,
     error::Error,
     event::{MsgResponder, NetworkEvent},
+    record_store::EvictionPolicy,
+    request_watchdog::RequestInfo,
+    routing_table::{BucketSnapshot, PeerSnapshot, RoutingTableSnapshot},
 };
 
 use self::{
@@ -32,9 +37,10 @@ use self::{
         DiskBackedRecordStore, DiskBackedRecordStoreConfig, REPLICATION_INTERVAL_LOWER_BOUND,
         REPLICATION_INTERVAL_UPPER_BOUND,
     },
-    replication_fetcher::ReplicationFetcher,
+    replication_fetcher::{ReplicationFetcher, FETCH_TIMEOUT},
+    request_watchdog::{PendingRequest, REQUEST_WATCHDOG_INTERVAL},
 };
-use futures::{future::select_all, StreamExt};
+use futures::{future::select_all, Future, StreamExt};
 use itertools::Itertools;
 #[cfg(feature = "local-discovery")]
 use libp2p::mdns;
@@ -49,18 +55,26 @@ use libp2p::{
 use rand::Rng;
 use sn_dbc::Token;
 use sn_protocol::{
-    messages::{Query, QueryResponse, Request, Response},
-    NetworkAddress, PrettyPrintRecordKey,
+    messages::{
+        advertise_max_payload, advertise_response_compression, check_payload_fits, request_size,
+        Query, QueryResponse, Request, Response, MAX_REQUEST_SIZE,
+    },
+    AddressKind, NetworkAddress, PrettyPrintRecordKey,
 };
 use std::{
     collections::{HashMap, HashSet},
     net::SocketAddr,
     num::NonZeroUsize,
     path::PathBuf,
-    time::Duration,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::{Duration, SystemTime},
 };
 use tokio::sync::{mpsc, oneshot};
 use tracing::warn;
+use xor_name::XorName;
 
 /// The maximum number of peers to return in a `GetClosestPeers` response.
 /// This is the group size used in safe network protocol to be responsible for
@@ -73,7 +87,10 @@ pub const CLOSE_GROUP_SIZE: usize = 8;
 // TODO: revisit once utxo is in
 pub const MAX_PACKET_SIZE: usize = 1024 * 1024 * 2; // the chunk size is 1mb, so should be higher than that to prevent failures
 
-// Timeout for requests sent/received through the request_response behaviour.
+// Default timeout for requests sent/received through the request_response behaviour.
+// Any request which takes longer fails at the libp2p layer, hence the timeouts the application
+// layer puts on requests, e.g. `FETCH_TIMEOUT` for replication fetches, only take effect if
+// they're shorter than it.
 const REQUEST_TIMEOUT_DEFAULT_S: Duration = Duration::from_secs(30);
 // Sets the keep-alive timeout of idle connections.
 const CONNECTION_KEEP_ALIVE_TIMEOUT: Duration = Duration::from_secs(30);
@@ -83,8 +100,10 @@ pub const IDENTIFY_AGENT_STR: &str = "safe/node/";
 
 /// The suffix is the version of the node.
 const SN_NODE_VERSION_STR: &str = concat!("safe/node/", env!("CARGO_PKG_VERSION"));
-/// / first version for the req/response protocol
-const REQ_RESPONSE_VERSION_STR: &str = concat!("/safe/node/", env!("CARGO_PKG_VERSION"));
+/// The req/response protocol, versioned with the `RequestMsg`/`ResponseMsg` envelopes the
+/// requests and responses are sent in, for peers sending them bare not to negotiate it.
+const REQ_RESPONSE_VERSION_STR: &str =
+    concat!("/safe/node/", env!("CARGO_PKG_VERSION"), "/msg-envelope/1");
 
 /// The suffix is the version of the client.
 const IDENTIFY_CLIENT_VERSION_STR: &str = concat!("safe/client/", env!("CARGO_PKG_VERSION"));
@@ -98,6 +117,13 @@ const VERIFICATION_ATTEMPTS: usize = 30;
 /// Number of attempts to re-put a record
 const PUT_RECORD_RETRIES: usize = 3;
 
+/// Default number of attempts to send a request to a peer of `send_and_get_responses`,
+/// should it fail to reach the peer.
+const REQUEST_ATTEMPTS_DEFAULT: usize = 2;
+
+/// Backoff before re-sending a request to a peer, multiplied by the attempts already made.
+const REQUEST_RETRY_BACKOFF: Duration = Duration::from_millis(250);
+
 const NETWORKING_CHANNEL_SIZE: usize = 10_000;
 /// Majority of a given group (i.e. > 1/2).
 #[inline]
@@ -106,7 +132,15 @@ pub const fn close_group_majority() -> usize {
 }
 
 type PendingGetClosest = HashMap<QueryId, (oneshot::Sender<HashSet<PeerId>>, HashSet<PeerId>)>;
-type PendingGetRecord = HashMap<QueryId, (oneshot::Sender<Result<Record>>, GetRecordResultMap)>;
+type PendingPutRecordTo = HashMap<QueryId, (oneshot::Sender<HashSet<PeerId>>, HashSet<PeerId>)>;
+type PendingGetRecord = HashMap<
+    QueryId,
+    (
+        oneshot::Sender<Result<Record>>,
+        GetRecordResultMap,
+        Option<usize>,
+    ),
+>;
 
 /// `SwarmDriver` is responsible for managing the swarm of peers, handling
 /// swarm events, processing commands, and maintaining the state of pending
@@ -119,8 +153,10 @@ pub struct SwarmDriver {
     // This wraps the call and pushes it off thread so as to be non-blocking
     event_sender: mpsc::Sender<NetworkEvent>,
     pending_get_closest_peers: PendingGetClosest,
-    pending_requests: HashMap<RequestId, Option<oneshot::Sender<Result<Response>>>>,
+    pending_requests: HashMap<RequestId, PendingRequest>,
     pending_get_record: PendingGetRecord,
+    /// The puts to specific peers awaiting their acks, along with the peers targeted.
+    pending_put_record_to: PendingPutRecordTo,
     replication_fetcher: ReplicationFetcher,
     local: bool,
     /// A list of the most recent peers we have dialed ourselves.
@@ -130,6 +166,60 @@ pub struct SwarmDriver {
     /// Perform initial kad bootstrap process on adding the first peer
     bootstrap_done: bool,
     is_client: bool,
+    /// When set, only these peers may connect to us.
+    peer_allowlist: Option<HashSet<PeerId>>,
+    /// The last time we heard from each of the peers in our routing table.
+    peers_last_seen: HashMap<PeerId, SystemTime>,
+    /// When set, the responses we send to peers are signed with this key.
+    response_signing_key: Option<Keypair>,
+    /// The timeout of the requests we send, past which they are considered stuck.
+    request_timeout: Duration,
+    /// The maximum size of the payloads each peer accepts, as advertised on identify.
+    peer_max_payloads: Arc<RwLock<HashMap<PeerId, usize>>>,
+    /// When set, the responses larger than this many bytes are compressed for the peers
+    /// accepting it.
+    response_compression_threshold: Option<usize>,
+    /// The peers which advertised on identify they accept compressed responses.
+    peers_accepting_compressed_responses: HashSet<PeerId>,
+}
+
+/// The settings of the `SwarmDriver` of a node, see `SwarmDriver::new`.
+#[derive(Debug, Clone)]
+pub struct NetworkConfig {
+    /// Whether the node is on a local network, in which case local addresses may be dialled.
+    pub local: bool,
+    /// Whether to keep track of the peer which published each stored record, for auditing.
+    /// Disabled by default for privacy.
+    pub record_origins: bool,
+    /// The timeout of the requests sent to and received from peers, 30 seconds if not set.
+    pub request_timeout: Option<Duration>,
+    /// What to do with an incoming record once the record store is full.
+    pub eviction: EvictionPolicy,
+    /// When set, only these peers may connect to the node.
+    pub peer_allowlist: Option<HashSet<PeerId>>,
+    /// Whether to sign the responses sent to peers with the node's keypair.
+    pub sign_responses: bool,
+    /// When set, the responses larger than this many bytes are compressed for the peers
+    /// accepting it.
+    pub response_compression_threshold: Option<usize>,
+    /// The number of attempts at sending a request to each peer, see
+    /// `Network::set_request_attempts`.
+    pub request_attempts: usize,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            local: false,
+            record_origins: false,
+            request_timeout: None,
+            eviction: EvictionPolicy::default(),
+            peer_allowlist: None,
+            sign_responses: false,
+            response_compression_threshold: None,
+            request_attempts: REQUEST_ATTEMPTS_DEFAULT,
+        }
+    }
 }
 
 impl SwarmDriver {
@@ -149,8 +239,8 @@ impl SwarmDriver {
     pub fn new(
         keypair: Keypair,
         addr: SocketAddr,
-        local: bool,
         root_dir: PathBuf,
+        config: NetworkConfig,
     ) -> Result<(Network, mpsc::Receiver<NetworkEvent>, Self)> {
         // get a random integer between REPLICATION_INTERVAL_LOWER_BOUND and REPLICATION_INTERVAL_UPPER_BOUND
         let replication_interval = rand::thread_rng()
@@ -187,12 +277,11 @@ impl SwarmDriver {
             root_dir,
             keypair,
             kad_cfg,
-            local,
             false,
             replication_interval,
-            None,
             ProtocolSupport::Full,
             SN_NODE_VERSION_STR.to_string(),
+            config,
         )?;
 
         // Listen on the provided address
@@ -228,13 +317,16 @@ impl SwarmDriver {
             std::env::temp_dir(),
             Keypair::generate_ed25519(),
             kad_cfg,
-            local,
             true,
             // Nonsense interval for the client which never replicates
             Duration::from_secs(1000),
-            request_timeout,
             ProtocolSupport::Outbound,
             IDENTIFY_CLIENT_VERSION_STR.to_string(),
+            NetworkConfig {
+                local,
+                request_timeout,
+                ..Default::default()
+            },
         )
     }
 
@@ -268,32 +360,34 @@ impl SwarmDriver {
         root_dir_path: PathBuf,
         keypair: Keypair,
         kad_cfg: KademliaConfig,
-        local: bool,
         is_client: bool,
         replication_interval: Duration,
-        request_response_timeout: Option<Duration>,
         req_res_protocol: ProtocolSupport,
         identify_version: String,
+        config: NetworkConfig,
     ) -> Result<(Network, mpsc::Receiver<NetworkEvent>, Self)> {
+        let NetworkConfig {
+            local,
+            record_origins,
+            request_timeout: request_response_timeout,
+            eviction,
+            peer_allowlist,
+            sign_responses,
+            response_compression_threshold,
+            request_attempts,
+        } = config;
         let peer_id = PeerId::from(keypair.public());
         info!("Node (PID: {}) with PeerId: {peer_id}", std::process::id());
         info!("PeerId: {peer_id} has replication interval of {replication_interval:?}");
 
         // RequestResponse Behaviour
-        let request_response = {
-            let mut cfg = RequestResponseConfig::default();
-            let _ = cfg
-                .set_request_timeout(request_response_timeout.unwrap_or(REQUEST_TIMEOUT_DEFAULT_S))
-                .set_connection_keep_alive(CONNECTION_KEEP_ALIVE_TIMEOUT);
-
-            request_response::cbor::Behaviour::new(
-                [(
-                    StreamProtocol::new(REQ_RESPONSE_VERSION_STR),
-                    req_res_protocol,
-                )],
-                cfg,
-            )
-        };
+        let request_response = request_response::cbor::Behaviour::new(
+            [(
+                StreamProtocol::new(REQ_RESPONSE_VERSION_STR),
+                req_res_protocol,
+            )],
+            request_response_config(request_response_timeout),
+        );
 
         let (network_event_sender, network_event_receiver) = mpsc::channel(NETWORKING_CHANNEL_SIZE);
 
@@ -312,6 +406,8 @@ impl SwarmDriver {
                 max_value_bytes: MAX_PACKET_SIZE, // TODO, does this need to be _less_ than MAX_PACKET_SIZE
                 storage_dir: storage_dir_path,
                 replication_interval,
+                record_origins,
+                eviction,
                 ..Default::default()
             };
 
@@ -342,7 +438,10 @@ impl SwarmDriver {
         let identify = {
             let cfg =
                 libp2p::identify::Config::new(IDENTIFY_PROTOCOL_STR.to_string(), keypair.public())
-                    .with_agent_version(identify_version);
+                    .with_agent_version(advertise_max_payload(
+                        &advertise_response_compression(&identify_version),
+                        MAX_REQUEST_SIZE,
+                    ));
             libp2p::identify::Behaviour::new(cfg)
         };
 
@@ -393,6 +492,7 @@ impl SwarmDriver {
         let swarm = SwarmBuilder::with_tokio_executor(transport, behaviour, peer_id).build();
 
         let (swarm_cmd_sender, swarm_cmd_receiver) = mpsc::channel(NETWORKING_CHANNEL_SIZE);
+        let peer_max_payloads: Arc<RwLock<HashMap<PeerId, usize>>> = Default::default();
         let swarm_driver = Self {
             self_peer_id: peer_id,
             swarm,
@@ -401,6 +501,7 @@ impl SwarmDriver {
             pending_get_closest_peers: Default::default(),
             pending_requests: Default::default(),
             pending_get_record: Default::default(),
+            pending_put_record_to: Default::default(),
             replication_fetcher: Default::default(),
             local,
             // We use 63 here, as in practice the capacity will be rounded to the nearest 2^n-1.
@@ -411,6 +512,13 @@ impl SwarmDriver {
             close_group: Default::default(),
             bootstrap_done: false,
             is_client,
+            peer_allowlist,
+            peers_last_seen: Default::default(),
+            response_signing_key: sign_responses.then(|| keypair.clone()),
+            request_timeout: request_response_timeout.unwrap_or(REQUEST_TIMEOUT_DEFAULT_S),
+            peer_max_payloads: peer_max_payloads.clone(),
+            response_compression_threshold,
+            peers_accepting_compressed_responses: Default::default(),
         };
 
         Ok((
@@ -419,12 +527,26 @@ impl SwarmDriver {
                 peer_id,
                 root_dir_path,
                 keypair,
+                preferred_peers: Default::default(),
+                peer_max_payloads,
+                in_flight_requests: Default::default(),
+                request_attempts: Arc::new(AtomicUsize::new(request_attempts.max(1))),
             },
             network_event_receiver,
             swarm_driver,
         ))
     }
 
+    /// Sets the maximum number of records the node stores at each of these kinds of address, see
+    /// `DiskBackedRecordStoreConfig::kind_quotas`. No quota applies to the kinds not listed.
+    pub fn set_kind_quotas(&mut self, kind_quotas: HashMap<AddressKind, u64>) {
+        self.swarm
+            .behaviour_mut()
+            .kademlia
+            .store_mut()
+            .set_kind_quotas(kind_quotas);
+    }
+
     /// Asynchronously drives the swarm event loop, handling events from both
     /// the swarm and command receiver. This function will run indefinitely,
     /// until the command channel is closed.
@@ -433,8 +555,10 @@ impl SwarmDriver {
     /// and command receiver messages, ensuring efficient handling of multiple
     /// asynchronous tasks.
     pub async fn run(mut self) {
+        let mut request_watchdog = tokio::time::interval(REQUEST_WATCHDOG_INTERVAL);
         loop {
             tokio::select! {
+                _ = request_watchdog.tick() => self.cancel_overdue_requests(),
                 swarm_event = self.swarm.select_next_some() => {
                     if let Err(err) = self.handle_swarm_events(swarm_event) {
                         warn!("Error while handling swarm event: {err}");
@@ -495,9 +619,55 @@ pub struct Network {
     pub peer_id: PeerId,
     pub root_dir_path: PathBuf,
     keypair: Keypair,
+    /// Peers contacted ahead of the others when among the closest peers, shared by all clones.
+    preferred_peers: Arc<RwLock<HashSet<PeerId>>>,
+    /// The maximum size of the payloads each peer accepts, as advertised on identify.
+    peer_max_payloads: Arc<RwLock<HashMap<PeerId, usize>>>,
+    /// The requests awaiting their response, keyed by peer and request content, along with the
+    /// callers of identical concurrent requests awaiting the same response.
+    in_flight_requests: InFlightRequests,
+    /// The number of attempts to send a request to a peer of `send_and_get_responses`, shared by
+    /// all clones.
+    request_attempts: Arc<AtomicUsize>,
 }
 
 impl Network {
+    /// Set the peers to contact first, or only, when they're among the closest peers of a client
+    /// operation. Peers outside of the close group of an item are never contacted for it.
+    pub fn set_preferred_peers(&self, peers: impl IntoIterator<Item = PeerId>) {
+        match self.preferred_peers.write() {
+            Ok(mut preferred_peers) => *preferred_peers = peers.into_iter().collect(),
+            Err(_) => error!("The preferred peers lock is poisoned"),
+        }
+    }
+
+    /// Set the number of attempts to send a request to each peer of `send_and_get_responses`,
+    /// the request being re-sent to a peer, after a short backoff, only should it have failed
+    /// to reach the peer. At least one attempt is always made.
+    pub fn set_request_attempts(&self, attempts: usize) {
+        self.request_attempts
+            .store(attempts.max(1), Ordering::SeqCst);
+    }
+
+    /// The maximum size of the `RequestMsg`s the `peer` accepts: the one it advertised, within
+    /// the limit of the codec requests are sent with, see `request_size`.
+    pub fn peer_max_payload(&self, peer: &PeerId) -> usize {
+        let advertised = match self.peer_max_payloads.read() {
+            Ok(peer_max_payloads) => peer_max_payloads.get(peer).copied(),
+            Err(_) => {
+                error!("The peer max payloads lock is poisoned");
+                None
+            }
+        };
+        advertised.map_or(MAX_REQUEST_SIZE, |limit| limit.min(MAX_REQUEST_SIZE))
+    }
+
+    // Check the `req` fits within the maximum payload of the `peer`.
+    fn check_fits_peer_max_payload(&self, req: &Request, peer: &PeerId) -> Result<()> {
+        check_payload_fits(request_size(req)?, self.peer_max_payload(peer))?;
+        Ok(())
+    }
+
     /// Signs the given data with the node's keypair.
     pub fn sign(&self, msg: &[u8]) -> Result<Vec<u8>> {
         self.keypair.sign(msg).map_err(Error::from)
@@ -517,10 +687,23 @@ impl Network {
         receiver.await?
     }
 
-    /// Returns the closest peers to the given `XorName`, sorted by their distance to the xor_name.
+    /// Returns the closest peers to the given `XorName`, sorted by their distance to the xor_name,
+    /// the preferred peers among them first.
     /// Excludes the client's `PeerId` while calculating the closest peers.
     pub async fn client_get_closest_peers(&self, key: &NetworkAddress) -> Result<Vec<PeerId>> {
-        self.get_closest_peers(key, true).await
+        let closest_peers = self.get_closest_peers(key, true).await?;
+        Ok(self.preferred_first(closest_peers))
+    }
+
+    // Move the preferred peers ahead of the others, see `preferred_first`.
+    fn preferred_first(&self, peers: Vec<PeerId>) -> Vec<PeerId> {
+        match self.preferred_peers.read() {
+            Ok(preferred_peers) => preferred_first(peers, &preferred_peers),
+            Err(_) => {
+                error!("The preferred peers lock is poisoned");
+                peers
+            }
+        }
     }
 
     /// Returns the closest peers to the given `NetworkAddress`, sorted by their distance to the key.
@@ -621,7 +804,31 @@ impl Network {
             "Sending {request:?} with dst {:?} to the closest peers.",
             request.dst()
         );
-        let closest_peers = self.client_get_closest_peers(&request.dst()).await?;
+        let mut closest_peers = self.client_get_closest_peers(&request.dst()).await?;
+
+        // a single response will do, which the preferred peers are given a chance to provide
+        // before the others are contacted
+        if !expect_all_responses {
+            let preferred_count = match self.preferred_peers.read() {
+                Ok(preferred_peers) => closest_peers
+                    .iter()
+                    .filter(|peer| preferred_peers.contains(peer))
+                    .count(),
+                Err(_) => 0,
+            };
+            if preferred_count > 0 {
+                let others = closest_peers.split_off(preferred_count);
+                let responses = self
+                    .send_and_get_responses(closest_peers, request, false)
+                    .await;
+                if responses.iter().any(|response| response.is_ok()) || others.is_empty() {
+                    return Ok(responses);
+                }
+                debug!("No response from the preferred peers, contacting the others");
+                closest_peers = others;
+            }
+        }
+
         Ok(self
             .send_and_get_responses(closest_peers, request, expect_all_responses)
             .await)
@@ -673,6 +880,31 @@ impl Network {
         key: RecordKey,
         target_record: Option<Record>,
         re_attempt: bool,
+    ) -> Result<Record> {
+        self.get_record(key, target_record, None, re_attempt).await
+    }
+
+    /// Get the Record from the network, once `quorum` of the peers holding it returned identical
+    /// copies, carrying out re-attempts if required.
+    /// Should peers return diverging copies, the get fails with `Error::SplitRecord` carrying
+    /// them all once any reaches the quorum, or all peers answered, for the caller to tell the
+    /// genuine one, rather than settling on one.
+    pub async fn get_record_with_quorum(
+        &self,
+        key: RecordKey,
+        quorum: usize,
+        re_attempt: bool,
+    ) -> Result<Record> {
+        self.get_record(key, None, Some(quorum), re_attempt).await
+    }
+
+    // See `get_record_from_network` and `get_record_with_quorum`.
+    async fn get_record(
+        &self,
+        key: RecordKey,
+        target_record: Option<Record>,
+        quorum: Option<usize>,
+        re_attempt: bool,
     ) -> Result<Record> {
         let total_attempts = if re_attempt { VERIFICATION_ATTEMPTS } else { 1 };
 
@@ -688,6 +920,7 @@ impl Network {
             let (sender, receiver) = oneshot::channel();
             self.send_swarm_cmd(SwarmCmd::GetNetworkRecord {
                 key: key.clone(),
+                quorum,
                 sender,
             })?;
 
@@ -727,9 +960,10 @@ impl Network {
                         }
                     }
                 }
+                Err(Error::SplitRecord(copies)) => return Err(Error::SplitRecord(copies)),
                 Err(error) => {
                     error!("{error:?}");
-                    if verification_attempts >= total_attempts {
+                    if verification_attempts >= total_attempts || !error.is_retryable() {
                         break;
                     }
                     warn!(
@@ -769,23 +1003,92 @@ impl Network {
             .map_err(|_e| Error::InternalMsgChannelDropped)
     }
 
+    /// Get the peer which published the locally stored `Record`, if record origins are tracked.
+    pub async fn get_local_record_origin(&self, key: &RecordKey) -> Result<Option<PeerId>> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_swarm_cmd(SwarmCmd::GetLocalRecordOrigin {
+            key: key.clone(),
+            sender,
+        })?;
+
+        receiver
+            .await
+            .map_err(|_e| Error::InternalMsgChannelDropped)
+    }
+
     /// Put `Record` to network
     /// optionally verify the record is stored after putting it to network
     pub async fn put_record(&self, record: Record, verify_store: bool) -> Result<()> {
         if verify_store {
-            self.put_record_with_retries(record).await
+            self.put_record_with_retries(record, None).await
+        } else {
+            self.put_record_once(record, false, None).await
+        }
+    }
+
+    /// Put `Record` to the `replicas` closest peers to its key, rather than to its close group
+    /// only, for extra redundancy. `replicas` is at least `CLOSE_GROUP_SIZE`.
+    /// Peers beyond the close group aren't responsible for the record, hence may reject it or
+    /// not keep it.
+    /// optionally verify the record is stored after putting it to network
+    pub async fn put_record_with_redundancy(
+        &self,
+        record: Record,
+        replicas: usize,
+        verify_store: bool,
+    ) -> Result<()> {
+        let key = NetworkAddress::from_record_key(record.key.clone());
+        let (sender, receiver) = oneshot::channel();
+        self.send_swarm_cmd(SwarmCmd::GetClosestPeers {
+            key: key.clone(),
+            sender,
+        })?;
+        let k_bucket_peers = receiver.await?;
+        let peers = redundant_put_targets(k_bucket_peers, &key, replicas)?;
+        debug!("Putting record to {} peers for redundancy", peers.len());
+
+        if verify_store {
+            self.put_record_with_retries(record, Some(peers)).await
         } else {
-            self.put_record_once(record, false).await
+            self.put_record_once(record, false, Some(peers)).await
         }
     }
 
+    /// Put `Record` to the given `peers`, whether or not they're responsible for it, without
+    /// verifying it got stored.
+    pub async fn put_record_to(&self, record: Record, peers: Vec<PeerId>) -> Result<()> {
+        self.put_record_once(record, false, Some(peers)).await
+    }
+
+    /// Put `Record` to the given `peers`, whether or not they're responsible for it, returning
+    /// the peers which acknowledged storing it.
+    pub async fn put_record_to_acked(
+        &self,
+        record: Record,
+        peers: Vec<PeerId>,
+    ) -> Result<HashSet<PeerId>> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_swarm_cmd(SwarmCmd::PutRecordToAcked {
+            record,
+            peers,
+            sender,
+        })?;
+        Ok(receiver.await?)
+    }
+
     /// Put `Record` to network
     /// Verify the record is stored after putting it to network
     /// Retry up to `PUT_RECORD_RETRIES` times if we can't verify the record is stored
-    async fn put_record_with_retries(&self, record: Record) -> Result<()> {
+    async fn put_record_with_retries(
+        &self,
+        record: Record,
+        peers: Option<Vec<PeerId>>,
+    ) -> Result<()> {
         let mut retries = 0;
         while retries < PUT_RECORD_RETRIES {
-            let res = self.put_record_once(record.clone(), true).await;
+            let res = self
+                .put_record_once(record.clone(), true, peers.clone())
+                .await;
             if !matches!(res, Err(Error::FailedToVerifyRecordWasStored(_))) {
                 return res;
             }
@@ -794,7 +1097,13 @@ impl Network {
         Err(Error::FailedToVerifyRecordWasStored(record.key.into()))
     }
 
-    async fn put_record_once(&self, record: Record, verify_store: bool) -> Result<()> {
+    // Put the record to the provided peers if any, otherwise to its close group.
+    async fn put_record_once(
+        &self,
+        record: Record,
+        verify_store: bool,
+        peers: Option<Vec<PeerId>>,
+    ) -> Result<()> {
         debug!(
             "Putting record of {} - length {:?} to network",
             PrettyPrintRecordKey::from(record.key.clone()),
@@ -803,10 +1112,17 @@ impl Network {
         let the_record = record.clone();
         // Waiting for a response to avoid flushing to network too quick that causing choke
         let (sender, receiver) = oneshot::channel();
-        self.send_swarm_cmd(SwarmCmd::PutRecord {
-            record: record.clone(),
-            sender,
-        })?;
+        match peers {
+            Some(peers) => self.send_swarm_cmd(SwarmCmd::PutRecordTo {
+                record: record.clone(),
+                peers,
+                sender,
+            })?,
+            None => self.send_swarm_cmd(SwarmCmd::PutRecord {
+                record: record.clone(),
+                sender,
+            })?,
+        }
         let response = receiver.await?;
 
         if verify_store {
@@ -828,13 +1144,34 @@ impl Network {
 
     /// Put `Record` to the local RecordStore
     /// Must be called after the validations are performed on the Record
-    pub fn put_local_record(&self, record: Record) -> Result<()> {
+    ///
+    /// Fails with `ProtocolError::KindQuotaExceeded` when the kind of the Record is at its quota.
+    pub async fn put_local_record(&self, record: Record) -> Result<()> {
         debug!(
             "Writing Record locally, for {:?} - length {:?}",
             PrettyPrintRecordKey::from(record.key.clone()),
             record.value.len()
         );
-        self.send_swarm_cmd(SwarmCmd::PutLocalRecord { record })
+        let (sender, receiver) = oneshot::channel();
+        self.send_swarm_cmd(SwarmCmd::PutLocalRecord { record, sender })?;
+
+        receiver
+            .await
+            .map_err(|_e| Error::InternalMsgChannelDropped)?
+    }
+
+    /// Remove a Record from the local RecordStore.
+    /// Returns false if the Record wasn't held locally.
+    pub async fn remove_local_record(&self, key: &RecordKey) -> Result<bool> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_swarm_cmd(SwarmCmd::RemoveLocalRecord {
+            key: key.clone(),
+            sender,
+        })?;
+
+        receiver
+            .await
+            .map_err(|_e| Error::InternalMsgChannelDropped)
     }
 
     /// Returns true if a RecordKey is present locally in the RecordStore
@@ -869,11 +1206,64 @@ impl Network {
         self.send_swarm_cmd(SwarmCmd::AddKeysToReplicationFetcher { peer, keys })
     }
 
+    /// Stop fetching the Record at `key` for replication, as we decline to store it, e.g. as we
+    /// don't store records of its kind. It's not fetched again when peers replicate it to us.
+    pub fn decline_replicated_record(&self, key: RecordKey) -> Result<()> {
+        self.send_swarm_cmd(SwarmCmd::DeclineReplicatedRecord { key })
+    }
+
     /// Send `Request` to the given `PeerId` and await for the response. If `self` is the recipient,
     /// then the `Request` is forwarded to itself and handled, and a corresponding `Response` is created
     /// and returned to itself. Hence the flow remains the same and there is no branching at the upper
     /// layers.
+    /// Fails with a `PayloadExceedsPeerLimit` error should the `Request` be larger than the `peer`
+    /// accepts.
+    ///
+    /// A request identical to one already awaiting its response from the same peer isn't sent
+    /// again, but shares the response of the former. Should the former fail, it's sent anyway.
     pub async fn send_request(&self, req: Request, peer: PeerId) -> Result<Response> {
+        self.check_fits_peer_max_payload(&req, &peer)?;
+        let key = (peer, req.dedup_key()?);
+        let in_flight = match self.in_flight_requests.lock() {
+            Ok(mut in_flight) => match in_flight.get_mut(&key) {
+                Some(waiters) => {
+                    let (sender, receiver) = oneshot::channel();
+                    waiters.push(sender);
+                    Some(receiver)
+                }
+                None => {
+                    let _ = in_flight.insert(key, vec![]);
+                    None
+                }
+            },
+            Err(_) => {
+                error!("The in-flight requests lock is poisoned");
+                None
+            }
+        };
+        if let Some(receiver) = in_flight {
+            trace!("Awaiting the response to the identical request in flight: {req:?}");
+            return match receiver.await {
+                Ok(response) => Ok(response),
+                Err(_) => self.send_request_to_peer(req, peer).await,
+            };
+        }
+
+        let in_flight = InFlightRequest {
+            in_flight_requests: self.in_flight_requests.clone(),
+            key: Some(key),
+        };
+        let result = self.send_request_to_peer(req, peer).await;
+        if let Ok(response) = &result {
+            for waiter in in_flight.complete() {
+                let _ = waiter.send(response.clone());
+            }
+        }
+        result
+    }
+
+    // Send the `req` to the `peer` and await its response.
+    async fn send_request_to_peer(&self, req: Request, peer: PeerId) -> Result<Response> {
         let (sender, receiver) = oneshot::channel();
         self.send_swarm_cmd(SwarmCmd::SendRequest {
             req,
@@ -886,6 +1276,7 @@ impl Network {
     /// Send `Request` to the given `PeerId` and do _not_ await a response here.
     /// Instead the Response will be handled by the common `response_handler`
     pub fn send_req_ignore_reply(&self, req: Request, peer: PeerId) -> Result<()> {
+        self.check_fits_peer_max_payload(&req, &peer)?;
         let swarm_cmd = SwarmCmd::SendRequest {
             req,
             peer,
@@ -907,6 +1298,23 @@ impl Network {
         Ok(state)
     }
 
+    /// Return the requests we sent which have been awaiting their response for longer than
+    /// the request timeout, oldest first.
+    pub async fn get_stuck_requests(&self) -> Result<Vec<RequestInfo>> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_swarm_cmd(SwarmCmd::GetStuckRequests { sender })?;
+        let stuck = receiver.await?;
+        Ok(stuck)
+    }
+
+    /// Return a `RoutingTableSnapshot` of the peers in our k-buckets.
+    pub async fn get_routing_table(&self) -> Result<RoutingTableSnapshot> {
+        let (sender, receiver) = oneshot::channel();
+        self.send_swarm_cmd(SwarmCmd::GetRoutingTable { sender })?;
+        let snapshot = receiver.await?;
+        Ok(snapshot)
+    }
+
     // Helper to send SwarmCmd
     fn send_swarm_cmd(&self, cmd: SwarmCmd) -> Result<()> {
         let capacity = self.swarm_cmd_sender.capacity();
@@ -952,6 +1360,8 @@ impl Network {
     /// If `get_all_responses` is true, we wait for the responses from all the peers.
     /// NB TODO: Will return an error if the request timeouts.
     /// If `get_all_responses` is false, we return the first successful response that we get
+    /// The request is re-sent to the peers it failed to reach, up to the number of attempts set
+    /// with `set_request_attempts`, each peer being retried independently of the others.
     pub async fn send_and_get_responses(
         &self,
         peers: Vec<PeerId>,
@@ -959,9 +1369,14 @@ impl Network {
         get_all_responses: bool,
     ) -> Vec<Result<Response>> {
         trace!("send_and_get_responses for {req:?}");
+        let attempts = self.request_attempts.load(Ordering::SeqCst);
         let mut list_of_futures = peers
             .iter()
-            .map(|peer| Box::pin(self.send_request(req.clone(), *peer)))
+            .map(|peer| {
+                Box::pin(send_with_retries(attempts, REQUEST_RETRY_BACKOFF, || {
+                    self.send_request(req.clone(), *peer)
+                }))
+            })
             .collect::<Vec<_>>();
 
         let mut responses = Vec::new();
@@ -984,6 +1399,77 @@ impl Network {
     }
 }
 
+/// Make up to `attempts` attempts to `send` a request, re-sending it after a `backoff`
+/// multiplied by the attempts already made, only should it have failed with a retryable error
+/// other than a protocol error, see `retry_on`.
+async fn send_with_retries<F, Fut>(
+    attempts: usize,
+    backoff: Duration,
+    mut send: F,
+) -> Result<Response>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<Response>>,
+{
+    let mut attempt = 1;
+    loop {
+        match send().await {
+            Err(err) if attempt < attempts && retry_on(&err) => {
+                trace!("Request failed on attempt {attempt}, retrying: {err:?}");
+                tokio::time::sleep(backoff * attempt as u32).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Whether a request to a peer is worth re-sending: the error is retryable, and isn't a protocol
+/// error, which the peer would return again.
+fn retry_on(err: &Error) -> bool {
+    err.is_retryable() && !matches!(err, Error::Protocol(_))
+}
+
+/// Move the `preferred` peers ahead of the others, keeping the order of both otherwise.
+/// Only the provided `peers` are returned, whether or not all the `preferred` peers are among them.
+fn preferred_first(peers: Vec<PeerId>, preferred: &HashSet<PeerId>) -> Vec<PeerId> {
+    let (mut first, others): (Vec<_>, Vec<_>) =
+        peers.into_iter().partition(|peer| preferred.contains(peer));
+    first.extend(others);
+    first
+}
+
+/// Select the `replicas` closest peers to the `key`, out of the peers of our k-buckets,
+/// or its close group should `replicas` be smaller than `CLOSE_GROUP_SIZE`.
+fn redundant_put_targets(
+    k_bucket_peers: impl IntoIterator<Item = PeerId>,
+    key: &NetworkAddress,
+    replicas: usize,
+) -> Result<Vec<PeerId>> {
+    sort_peers_by_address(
+        k_bucket_peers.into_iter().collect(),
+        key,
+        replicas.max(CLOSE_GROUP_SIZE),
+    )
+}
+
+/// The config of the request_response behaviour, with the provided request timeout if any.
+fn request_response_config(request_timeout: Option<Duration>) -> RequestResponseConfig {
+    let request_timeout = request_timeout.unwrap_or(REQUEST_TIMEOUT_DEFAULT_S);
+    if request_timeout < FETCH_TIMEOUT {
+        warn!(
+            "The request timeout of {request_timeout:?} is shorter than the replication fetch \
+             timeout of {FETCH_TIMEOUT:?}, fetches will fail before being retried"
+        );
+    }
+
+    let mut cfg = RequestResponseConfig::default();
+    let _ = cfg
+        .set_request_timeout(request_timeout)
+        .set_connection_keep_alive(CONNECTION_KEEP_ALIVE_TIMEOUT);
+    cfg
+}
+
 /// Given `all_costs` it will return the CLOSE_GROUP majority cost.
 fn get_fee_from_store_cost_quotes(
     all_costs: &mut Vec<Token>,
@@ -1055,11 +1541,214 @@ pub(crate) fn multiaddr_strip_p2p(multiaddr: &Multiaddr) -> Multiaddr {
         .collect()
 }
 
+type InFlightRequests = Arc<Mutex<HashMap<(PeerId, XorName), Vec<oneshot::Sender<Response>>>>>;
+
+// A request in flight, no longer tracked once dropped, which in turn drops its waiters'
+// senders, e.g. should the request fail or be cancelled.
+struct InFlightRequest {
+    in_flight_requests: InFlightRequests,
+    // None once completed, not to untrack an identical request sent since.
+    key: Option<(PeerId, XorName)>,
+}
+
+impl InFlightRequest {
+    // Stop tracking the request, returning the callers awaiting its response.
+    fn complete(mut self) -> Vec<oneshot::Sender<Response>> {
+        match (self.key.take(), self.in_flight_requests.lock()) {
+            (Some(key), Ok(mut in_flight)) => in_flight.remove(&key).unwrap_or_default(),
+            _ => vec![],
+        }
+    }
+}
+
+impl Drop for InFlightRequest {
+    fn drop(&mut self) {
+        if let (Some(key), Ok(mut in_flight)) = (self.key.take(), self.in_flight_requests.lock()) {
+            let _ = in_flight.remove(&key);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use eyre::bail;
 
     use super::*;
+    use libp2p::request_response::OutboundFailure;
+
+    #[test]
+    fn redundant_puts_target_peers_beyond_the_close_group() -> Result<()> {
+        let key = NetworkAddress::from_peer(PeerId::random());
+        let peers: Vec<PeerId> = (0..30).map(|_| PeerId::random()).collect();
+        let close_group = sort_peers_by_address(peers.clone(), &key, CLOSE_GROUP_SIZE)?;
+
+        let targets = redundant_put_targets(peers.clone(), &key, CLOSE_GROUP_SIZE + 3)?;
+        assert_eq!(targets.len(), CLOSE_GROUP_SIZE + 3);
+        assert_eq!(targets[..CLOSE_GROUP_SIZE], close_group[..]);
+
+        // the close group is always targeted
+        assert_eq!(redundant_put_targets(peers, &key, 1)?, close_group);
+
+        Ok(())
+    }
+
+    #[test]
+    fn preferred_peers_come_first_among_the_closest_only() -> Result<()> {
+        let key = NetworkAddress::from_peer(PeerId::random());
+        let peers: Vec<PeerId> = (0..30).map(|_| PeerId::random()).collect();
+        let close_group = sort_peers_by_address(peers.clone(), &key, CLOSE_GROUP_SIZE)?;
+
+        // two peers of the close group, and one beyond it, are preferred
+        let beyond = peers
+            .iter()
+            .find(|peer| !close_group.contains(peer))
+            .copied()
+            .ok_or(Error::NotEnoughPeers {
+                found: peers.len(),
+                required: CLOSE_GROUP_SIZE + 1,
+            })?;
+        let preferred: HashSet<PeerId> = [close_group[5], close_group[2], beyond].into();
+
+        let ranked = preferred_first(close_group.clone(), &preferred);
+        assert_eq!(ranked[..2], [close_group[2], close_group[5]]);
+        let others: Vec<PeerId> = close_group
+            .iter()
+            .filter(|peer| !preferred.contains(peer))
+            .copied()
+            .collect();
+        assert_eq!(ranked[2..], others[..]);
+
+        // the peer beyond the close group isn't responsible, thus not contacted
+        assert!(!ranked.contains(&beyond));
+
+        assert_eq!(
+            preferred_first(close_group.clone(), &HashSet::new()),
+            close_group
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn configured_request_timeout_is_applied() {
+        // the libp2p config has no getters, hence we check its debug output
+        let default_cfg = format!("{:?}", request_response_config(None));
+        assert!(default_cfg.contains(&format!("request_timeout: {REQUEST_TIMEOUT_DEFAULT_S:?}")));
+
+        let timeout = Duration::from_secs(42);
+        let cfg = format!("{:?}", request_response_config(Some(timeout)));
+        assert!(cfg.contains(&format!("request_timeout: {timeout:?}")));
+    }
+
+    // A local node, storing its records in a fresh temp dir.
+    fn local_node(
+        request_timeout: Option<Duration>,
+    ) -> Result<(Network, mpsc::Receiver<NetworkEvent>)> {
+        let keypair = Keypair::generate_ed25519();
+        let root_dir =
+            std::env::temp_dir().join(format!("safenode-{}", PeerId::from(keypair.public())));
+        let (network, events_receiver, swarm_driver) = SwarmDriver::new(
+            keypair,
+            "127.0.0.1:0".parse().expect("valid socket address"),
+            root_dir,
+            NetworkConfig {
+                local: true,
+                request_timeout,
+                ..Default::default()
+            },
+        )?;
+        let _handle = tokio::spawn(swarm_driver.run());
+        Ok((network, events_receiver))
+    }
+
+    #[tokio::test]
+    async fn requests_to_slow_peers_time_out_as_configured() -> eyre::Result<()> {
+        // the requests to the slow peer are received but never responded to
+        let (slow_peer, _unanswered_requests) = local_node(None)?;
+        let timeout = Duration::from_secs(1);
+        let (requester, _events) = local_node(Some(timeout))?;
+
+        let mut listeners = vec![];
+        while listeners.is_empty() {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            listeners = slow_peer.get_swarm_local_state().await?.listeners;
+        }
+        let addr = listeners[0].clone().with(Protocol::P2p(slow_peer.peer_id));
+        requester.dial(addr).await?;
+        while !requester
+            .get_swarm_local_state()
+            .await?
+            .connected_peers
+            .contains(&slow_peer.peer_id)
+        {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+
+        let query = Request::Query(Query::HasRecord(
+            NetworkAddress::from_peer(PeerId::random()),
+        ));
+        let started = std::time::Instant::now();
+        let response = requester.send_request(query, slow_peer.peer_id).await;
+        assert!(matches!(
+            response,
+            Err(Error::OutboundError(
+                request_response::OutboundFailure::Timeout
+            ))
+        ));
+        assert!(started.elapsed() < REQUEST_TIMEOUT_DEFAULT_S);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn identical_concurrent_requests_share_one_wire_request() -> eyre::Result<()> {
+        let (responder, mut requests) = local_node(None)?;
+        let (requester, _events) = local_node(None)?;
+
+        let mut listeners = vec![];
+        while listeners.is_empty() {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            listeners = responder.get_swarm_local_state().await?.listeners;
+        }
+        let addr = listeners[0].clone().with(Protocol::P2p(responder.peer_id));
+        requester.dial(addr).await?;
+        while !requester
+            .get_swarm_local_state()
+            .await?
+            .connected_peers
+            .contains(&responder.peer_id)
+        {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+
+        // count the requests received, answering each once both requests have been issued
+        let (received_sender, mut received) = mpsc::unbounded_channel();
+        let responding = responder.clone();
+        let _handle = tokio::spawn(async move {
+            while let Some(event) = requests.recv().await {
+                if let NetworkEvent::RequestReceived { req, channel, .. } = event {
+                    let _ = received_sender.send(req);
+                    tokio::time::sleep(Duration::from_millis(200)).await;
+                    let response = Response::Query(QueryResponse::HasRecord(Ok(true)));
+                    let _ = responding.send_response(response, channel);
+                }
+            }
+        });
+
+        let query = Request::Query(Query::HasRecord(
+            NetworkAddress::from_peer(PeerId::random()),
+        ));
+        let (first, second) = tokio::join!(
+            requester.send_request(query.clone(), responder.peer_id),
+            requester.send_request(query.clone(), responder.peer_id)
+        );
+        assert_eq!(first?, second?);
+
+        assert_eq!(received.recv().await, Some(query));
+        assert!(received.try_recv().is_err());
+
+        Ok(())
+    }
 
     #[test]
     fn test_get_fee_from_store_cost_quotes() -> Result<()> {
@@ -1108,4 +1797,61 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn requests_failing_to_reach_the_peer_are_retried() -> Result<()> {
+        let response = Response::Query(QueryResponse::HasRecord(Ok(true)));
+        let sent = AtomicUsize::new(0);
+        let fails_once = || {
+            let attempt = sent.fetch_add(1, Ordering::SeqCst);
+            let response = response.clone();
+            async move {
+                if attempt == 0 {
+                    Err(Error::OutboundError(OutboundFailure::ConnectionClosed))
+                } else {
+                    Ok(response)
+                }
+            }
+        };
+        assert_eq!(
+            send_with_retries(
+                REQUEST_ATTEMPTS_DEFAULT,
+                Duration::from_millis(1),
+                fails_once
+            )
+            .await?,
+            response
+        );
+        assert_eq!(sent.load(Ordering::SeqCst), 2);
+
+        // the attempts are bounded
+        sent.store(0, Ordering::SeqCst);
+        let always_fails = || {
+            let _ = sent.fetch_add(1, Ordering::SeqCst);
+            async { Err(Error::OutboundError(OutboundFailure::DialFailure)) }
+        };
+        assert!(send_with_retries(3, Duration::from_millis(1), always_fails)
+            .await
+            .is_err());
+        assert_eq!(sent.load(Ordering::SeqCst), 3);
+
+        // requests refused by the peer, or failing with protocol errors, aren't retried
+        let errors: [fn() -> Error; 2] = [
+            || Error::OutboundError(OutboundFailure::UnsupportedProtocols),
+            || Error::Protocol(sn_protocol::error::Error::Overloaded),
+        ];
+        for error in errors {
+            sent.store(0, Ordering::SeqCst);
+            let refused = || {
+                let _ = sent.fetch_add(1, Ordering::SeqCst);
+                async move { Err(error()) }
+            };
+            assert!(send_with_retries(3, Duration::from_millis(1), refused)
+                .await
+                .is_err());
+            assert_eq!(sent.load(Ordering::SeqCst), 1);
+        }
+
+        Ok(())
+    }
 }
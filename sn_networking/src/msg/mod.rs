@@ -6,17 +6,19 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::{error::Error, MsgResponder, NetworkEvent, SwarmDriver};
+use crate::{
+    error::Error, request_watchdog::PendingRequest, MsgResponder, NetworkEvent, SwarmDriver,
+};
 
 use libp2p::request_response::{self, Message};
-use sn_protocol::messages::{Request, Response};
+use sn_protocol::messages::{RequestMsg, Response, ResponseMsg};
 use tracing::{trace, warn};
 
 impl SwarmDriver {
     /// Forwards `Request` to the upper layers using `Sender<NetworkEvent>`. Sends `Response` to the peers
     pub fn handle_msg(
         &mut self,
-        event: request_response::Event<Request, Response>,
+        event: request_response::Event<RequestMsg, ResponseMsg>,
     ) -> Result<(), Error> {
         match event {
             request_response::Event::Message { message, peer } => match message {
@@ -26,31 +28,69 @@ impl SwarmDriver {
                     request_id,
                     ..
                 } => {
-                    trace!("Received request {request_id:?} from peer {peer:?}, req: {request:?}");
+                    let RequestMsg {
+                        request_id: msg_id,
+                        request,
+                    } = request;
+                    trace!("Received request {request_id:?} (msg id {msg_id}) from peer {peer:?}, req: {request:?}");
                     self.send_event(NetworkEvent::RequestReceived {
+                        peer,
                         req: request,
-                        channel: MsgResponder::FromPeer(channel),
+                        channel: MsgResponder::FromPeer {
+                            peer,
+                            channel,
+                            msg_id,
+                        },
                     })
                 }
                 Message::Response {
                     request_id,
                     response,
                 } => {
-                    trace!("Got response {request_id:?} from peer {peer:?}, res: {response}.");
-                    if let Some(sender) = self.pending_requests.remove(&request_id) {
+                    let ResponseMsg {
+                        request_id: msg_id,
+                        response,
+                    } = response;
+                    trace!("Got response {request_id:?} (msg id {msg_id}) from peer {peer:?}, res: {response}.");
+                    let pending = self.pending_requests.remove(&request_id);
+                    // the response is signed for the msg id we sent, rather than the one echoed
+                    let expected_msg_id = match &pending {
+                        Some(pending) => {
+                            if pending.msg_id != msg_id {
+                                warn!("Response {request_id:?} from peer {peer:?} echoes msg id {msg_id} rather than {}", pending.msg_id);
+                            }
+                            pending.msg_id
+                        }
+                        None => msg_id,
+                    };
+                    // Signed responses are only handed over once verified as coming from the peer,
+                    // in response to our request, after being decompressed if they were compressed
+                    // on top of being signed
+                    let response = response
+                        .decompressed()
+                        .and_then(|response| match response {
+                            Response::Signed(signed) => signed.verify(&peer, expected_msg_id),
+                            response => Ok(response),
+                        })
+                        .map_err(Error::from);
+                    if let Some(pending) = pending {
+                        let sender = pending.sender;
                         // The sender will be provided if the caller (Requester) is awaiting for a response
                         // at the call site.
                         // Else the Request was just sent to the peer and the Response was
                         // meant to be handled in another way and is not awaited.
                         match sender {
                             Some(sender) => sender
-                                .send(Ok(response))
+                                .send(response)
                                 .map_err(|_| Error::InternalMsgChannelDropped)?,
-                            None => {
+                            None => match response {
                                 // responses that are not awaited at the call site must be handled
                                 // separately
-                                self.send_event(NetworkEvent::ResponseReceived { res: response });
-                            }
+                                Ok(res) => self.send_event(NetworkEvent::ResponseReceived { res }),
+                                Err(err) => {
+                                    warn!("Dropping response {request_id:?} from peer {peer:?}: {err:?}")
+                                }
+                            },
                         }
                     } else {
                         warn!("Tried to remove a RequestId from pending_requests which was not inserted in the first place.
@@ -63,7 +103,9 @@ impl SwarmDriver {
                 error,
                 peer,
             } => {
-                if let Some(sender) = self.pending_requests.remove(&request_id) {
+                if let Some(PendingRequest { sender, .. }) =
+                    self.pending_requests.remove(&request_id)
+                {
                     match sender {
                         Some(sender) => {
                             sender
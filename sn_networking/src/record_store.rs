@@ -16,13 +16,19 @@ use libp2p::{
 };
 use rand::Rng;
 use sn_dbc::Token;
-use sn_protocol::{NetworkAddress, PrettyPrintRecordKey};
+use sn_protocol::{
+    error::Error as ProtocolError,
+    storage::{RecordHeader, RecordKind},
+    AddressKind, NetworkAddress, PrettyPrintRecordKey,
+};
 use sn_transfers::dbc_genesis::TOTAL_SUPPLY;
 use std::{
     borrow::Cow,
-    collections::{hash_set, HashSet},
-    fs,
+    collections::{hash_set, HashMap, HashSet},
+    fmt, fs,
     path::{Path, PathBuf},
+    str::FromStr,
+    sync::atomic::{AtomicU64, Ordering},
     time::Duration,
     vec,
 };
@@ -48,6 +54,14 @@ pub struct DiskBackedRecordStore {
     config: DiskBackedRecordStoreConfig,
     /// A set of keys, each corresponding to a data `Record` stored on disk.
     records: HashSet<Key>,
+    /// The peer which published each stored record, if `record_origins` is enabled.
+    origins: HashMap<Key, PeerId>,
+    /// The number of times each stored record has been read.
+    access_counts: HashMap<Key, AtomicU64>,
+    /// The kind of each stored record, as per its `RecordHeader`.
+    kinds: HashMap<Key, RecordKind>,
+    /// The number of stored records of each kind.
+    kind_counts: HashMap<RecordKind, usize>,
     /// Currently only used to notify the record received via network put to be validated.
     event_sender: Option<mpsc::Sender<NetworkEvent>>,
     /// Distance range specify the acceptable range of record entry.
@@ -67,6 +81,55 @@ pub struct DiskBackedRecordStoreConfig {
     /// This node's replication interval
     /// Which should be between REPLICATION_INTERVAL_LOWER_BOUND and REPLICATION_INTERVAL_UPPER_BOUND
     pub replication_interval: Duration,
+    /// Whether to keep track of the peer which published each stored record, for auditing.
+    /// Disabled by default for privacy.
+    pub record_origins: bool,
+    /// What to do with an incoming record when `max_records` are already stored.
+    pub eviction: EvictionPolicy,
+    /// The maximum number of records stored at each of these kinds of address. Once a quota is
+    /// reached, an incoming record of its kind is handled as per the `eviction` policy, among the
+    /// records of the kind. Quotas count records, not bytes, each record being of at most
+    /// `max_value_bytes`.
+    pub kind_quotas: HashMap<AddressKind, u64>,
+}
+
+/// What a `DiskBackedRecordStore` does with an incoming record once it's full.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EvictionPolicy {
+    /// Reject the incoming record.
+    #[default]
+    RejectNew,
+    /// Evict the record farthest from our own address, i.e. the least likely to be our
+    /// responsibility, should the incoming record be closer than it. Otherwise reject it.
+    EvictFarthest,
+    /// Evict the record which has been read the least times, the farthest one among those.
+    EvictLeastAccessed,
+}
+
+impl FromStr for EvictionPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "reject-new" => Ok(Self::RejectNew),
+            "evict-farthest" => Ok(Self::EvictFarthest),
+            "evict-least-accessed" => Ok(Self::EvictLeastAccessed),
+            other => Err(format!(
+                "Unknown eviction policy {other:?}, expected one of reject-new, evict-farthest or evict-least-accessed"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for EvictionPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let policy = match self {
+            Self::RejectNew => "reject-new",
+            Self::EvictFarthest => "evict-farthest",
+            Self::EvictLeastAccessed => "evict-least-accessed",
+        };
+        write!(f, "{policy}")
+    }
 }
 
 impl Default for DiskBackedRecordStoreConfig {
@@ -80,6 +143,9 @@ impl Default for DiskBackedRecordStoreConfig {
             max_records: MAX_RECORDS_COUNT,
             max_value_bytes: 65 * 1024,
             replication_interval,
+            record_origins: false,
+            eviction: EvictionPolicy::default(),
+            kind_quotas: HashMap::new(),
         }
     }
 }
@@ -95,6 +161,10 @@ impl DiskBackedRecordStore {
             local_key: KBucketKey::from(local_id),
             config,
             records: Default::default(),
+            origins: Default::default(),
+            access_counts: Default::default(),
+            kinds: Default::default(),
+            kind_counts: Default::default(),
             event_sender,
             distance_range: None,
         }
@@ -122,6 +192,24 @@ impl DiskBackedRecordStore {
             .collect()
     }
 
+    /// Returns the peer which published the stored record, if known.
+    /// This is only tracked when `record_origins` is enabled in the config.
+    pub fn record_origin(&self, key: &Key) -> Option<PeerId> {
+        self.origins.get(key).copied()
+    }
+
+    /// Returns the number of stored records of the given `kind`.
+    pub fn record_count_of_kind(&self, kind: &RecordKind) -> usize {
+        self.kind_counts.get(kind).copied().unwrap_or_default()
+    }
+
+    /// Returns the number of times the stored record has been read.
+    pub fn access_count(&self, key: &Key) -> u64 {
+        self.access_counts
+            .get(key)
+            .map_or(0, |count| count.load(Ordering::Relaxed))
+    }
+
     #[allow(clippy::mutable_key_type)]
     pub fn record_addresses_ref(&self) -> &HashSet<Key> {
         &self.records
@@ -149,15 +237,36 @@ impl DiskBackedRecordStore {
         }
     }
 
-    pub fn put_verified(&mut self, r: Record) -> Result<()> {
+    /// Store a record which has been validated.
+    ///
+    /// It's rejected with `ProtocolError::KindQuotaExceeded` should its kind be at its quota,
+    /// and the eviction policy not let us make space among the records of the kind.
+    pub fn put_verified(&mut self, r: Record) -> crate::error::Result<()> {
         let content_hash = XorName::from_content(&r.value);
         let record_key = PrettyPrintRecordKey::from(r.key.clone());
         trace!("PUT a verified Record: {record_key:?} (content_hash {content_hash:?})");
 
+        let kind = match RecordHeader::from_record(&r) {
+            Ok(header) => Some(header.kind),
+            Err(err) => {
+                warn!("Storing record {record_key:?} of unknown kind: {err:?}");
+                None
+            }
+        };
+        if let Some(kind) = &kind {
+            self.prune_kind_if_needed_for_record(&r.key, kind)?;
+        }
         self.prune_storage_if_needed_for_record(&r.key)?;
 
         let filename = Self::key_to_hex(&r.key);
         let file_path = self.config.storage_dir.join(&filename);
+        if self.config.record_origins {
+            if let Some(publisher) = r.publisher {
+                let _ = self.origins.insert(r.key.clone(), publisher);
+            }
+        }
+        let _ = self.access_counts.entry(r.key.clone()).or_default();
+        self.set_kind(&r.key, kind);
         let _ = self.records.insert(r.key);
 
         // TODO: How could we clean up records if we fail to insert?
@@ -177,55 +286,115 @@ impl DiskBackedRecordStore {
         Ok(())
     }
 
-    /// Prune the records in the store to ensure that we free up space
-    /// for the incoming record.
-    ///
-    /// An error is returned if we are full and the new record is not closer than
-    /// the furthest record
-    fn prune_storage_if_needed_for_record(&mut self, r: &Key) -> Result<()> {
-        let num_records = self.records.len();
+    // Record the `kind` of the stored record at `key`, if known, keeping count of the records of
+    // each kind.
+    fn set_kind(&mut self, key: &Key, kind: Option<RecordKind>) {
+        let previous = match kind {
+            Some(kind) => {
+                *self.kind_counts.entry(kind.clone()).or_default() += 1;
+                self.kinds.insert(key.clone(), kind)
+            }
+            None => self.kinds.remove(key),
+        };
+        if let Some(count) = previous.and_then(|previous| self.kind_counts.get_mut(&previous)) {
+            *count = count.saturating_sub(1);
+        }
+    }
 
-        // we're not full, so we don't need to prune
-        if num_records < self.config.max_records {
-            return Ok(());
+    // The stored record to evict for the incoming record at `r`, among those which `may_evict`,
+    // as allowed by the `EvictionPolicy` of the config.
+    fn eviction_candidate(&self, r: &Key, may_evict: impl Fn(&Key) -> bool) -> Option<Key> {
+        let distance_to = |key: &Key| self.local_key.distance(&KBucketKey::from(key.to_vec()));
+        let candidates = self.records.iter().filter(|key| may_evict(key));
+
+        match self.config.eviction {
+            EvictionPolicy::RejectNew => None,
+            // now check if the incoming record is closer than our furthest
+            // if it is, we can prune
+            EvictionPolicy::EvictFarthest => candidates
+                .max_by_key(|key| distance_to(key))
+                .filter(|furthest| distance_to(r) < distance_to(furthest))
+                .cloned(),
+            EvictionPolicy::EvictLeastAccessed => candidates
+                .min_by_key(|key| (self.access_count(key), std::cmp::Reverse(distance_to(key))))
+                .cloned(),
         }
+    }
 
-        // sort records by distance to our local key
-        let mut records = self.records.iter().cloned().collect::<Vec<_>>();
-        records.sort_unstable_by_key(|k| {
-            let kbucket_key = KBucketKey::from(k.to_vec());
-            self.local_key.distance(&kbucket_key)
-        });
+    /// Prune the records of the given `kind` to make space for the incoming record at `r`, should
+    /// the kind be at its quota, as allowed by the `EvictionPolicy` of the config.
+    ///
+    /// An error is returned if the quota is reached and the policy doesn't let us make space.
+    fn prune_kind_if_needed_for_record(
+        &mut self,
+        r: &Key,
+        kind: &RecordKind,
+    ) -> crate::error::Result<()> {
+        let address_kind = kind.address_kind();
+        let quota = match self.config.kind_quotas.get(&address_kind) {
+            Some(quota) => *quota,
+            None => return Ok(()),
+        };
+        let stored = self.record_count_of_kind(kind) as u64;
 
-        // now check if the incoming record is closer than our furthest
-        // if it is, we can prune
-        if let Some(furthest_record) = records.last() {
-            let furthest_record_key = KBucketKey::from(furthest_record.to_vec());
-            let incoming_record_key = KBucketKey::from(r.to_vec());
+        // the quota isn't reached, or the record is updating one of ours of the kind
+        if stored < quota || self.kinds.get(r) == Some(kind) {
+            return Ok(());
+        }
 
-            if incoming_record_key.distance(&self.local_key)
-                < furthest_record_key.distance(&self.local_key)
-            {
+        match self.eviction_candidate(r, |key| self.kinds.get(key) == Some(kind)) {
+            Some(evicted) => {
                 trace!(
-                    "{:?} will be pruned to make space for new record: {:?}",
-                    PrettyPrintRecordKey::from(furthest_record.clone()),
+                    "{:?} will be pruned to make space for new record of kind {kind}: {:?}",
+                    PrettyPrintRecordKey::from(evicted.clone()),
                     PrettyPrintRecordKey::from(r.clone())
                 );
-                // we should prune and make space
-                self.remove(furthest_record);
+                self.remove(&evicted);
+                Ok(())
+            }
+            None => {
+                warn!("Record not stored. Quota of {quota} records of kind {kind} reached.");
+                Err(ProtocolError::KindQuotaExceeded { kind: address_kind }.into())
+            }
+        }
+    }
 
-                // Warn if the furthest record was within our distance range
-                if let Some(distance_range) = self.distance_range {
-                    if furthest_record_key.distance(&self.local_key) < distance_range {
-                        warn!("Pruned record would also be within our distance range.");
-                    }
-                }
-            } else {
+    /// Prune the records in the store to ensure that we free up space
+    /// for the incoming record, as allowed by the `EvictionPolicy` of the config.
+    ///
+    /// An error is returned if we are full and the policy doesn't let us make space.
+    fn prune_storage_if_needed_for_record(&mut self, r: &Key) -> Result<()> {
+        let num_records = self.records.len();
+
+        // we're not full, or the record is replacing one of ours, so we don't need to prune
+        if num_records < self.config.max_records || self.records.contains(r) {
+            return Ok(());
+        }
+
+        let distance_to = |key: &Key| self.local_key.distance(&KBucketKey::from(key.to_vec()));
+
+        let evicted = match self.eviction_candidate(r, |_| true) {
+            Some(evicted) => evicted,
+            None => {
                 // we should not prune, but warn as we're at max capcaity
                 warn!("Record not stored. Maximum number of records reached. Current num_records: {num_records}");
                 return Err(Error::MaxRecords);
             }
+        };
+
+        trace!(
+            "{:?} will be pruned to make space for new record: {:?}",
+            PrettyPrintRecordKey::from(evicted.clone()),
+            PrettyPrintRecordKey::from(r.clone())
+        );
+        // Warn if the pruned record was within our distance range
+        if let Some(distance_range) = self.distance_range {
+            if distance_to(&evicted) < distance_range {
+                warn!("Pruned record would also be within our distance range.");
+            }
         }
+        // we should prune and make space
+        self.remove(&evicted);
 
         Ok(())
     }
@@ -275,6 +444,13 @@ impl DiskBackedRecordStore {
     }
 
     /// Setup the distance range.
+    /// Sets the maximum number of records stored at each of these kinds of address, see
+    /// `DiskBackedRecordStoreConfig::kind_quotas`. Records already stored above a new quota are
+    /// kept, only incoming records of their kind being rejected or evicting one of them.
+    pub fn set_kind_quotas(&mut self, kind_quotas: HashMap<AddressKind, u64>) {
+        self.config.kind_quotas = kind_quotas;
+    }
+
     pub fn set_distance_range(&mut self, distance_range: Distance) {
         self.distance_range = Some(distance_range);
     }
@@ -296,6 +472,9 @@ impl RecordStore for DiskBackedRecordStore {
             trace!("Record not found locally");
             return None;
         }
+        if let Some(count) = self.access_counts.get(k) {
+            let _ = count.fetch_add(1, Ordering::Relaxed);
+        }
 
         Self::read_from_disk(k, &self.config.storage_dir)
     }
@@ -339,6 +518,9 @@ impl RecordStore for DiskBackedRecordStore {
 
     fn remove(&mut self, k: &Key) {
         let _ = self.records.remove(k);
+        let _ = self.origins.remove(k);
+        let _ = self.access_counts.remove(k);
+        self.set_kind(k, None);
 
         let filename = Self::key_to_hex(k);
         let file_path = self.config.storage_dir.join(&filename);
@@ -409,6 +591,7 @@ impl<'a> Iterator for RecordsIterator<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::{Error as NetworkError, Result};
     use libp2p::{
         core::multihash::Multihash,
         kad::{KBucketKey, RecordKey},
@@ -554,6 +737,119 @@ mod tests {
         assert!(store.get(&r.key).is_none());
     }
 
+    #[tokio::test]
+    async fn record_origin_is_tracked_when_enabled() -> Result<()> {
+        let publisher = PeerId::random();
+        let record = Record {
+            key: NetworkAddress::from_peer(PeerId::random()).to_record_key(),
+            value: vec![1, 2, 3],
+            publisher: Some(publisher),
+            expires: None,
+        };
+
+        let mut store =
+            DiskBackedRecordStore::with_config(PeerId::random(), Default::default(), None);
+        store.put_verified(record.clone())?;
+        assert_eq!(store.record_origin(&record.key), None);
+
+        let store_config = DiskBackedRecordStoreConfig {
+            record_origins: true,
+            ..Default::default()
+        };
+        let mut store = DiskBackedRecordStore::with_config(PeerId::random(), store_config, None);
+        store.put_verified(record.clone())?;
+        assert_eq!(store.record_origin(&record.key), Some(publisher));
+
+        store.remove(&record.key);
+        assert_eq!(store.record_origin(&record.key), None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn records_are_counted_per_kind() -> eyre::Result<()> {
+        let mut store =
+            DiskBackedRecordStore::with_config(PeerId::random(), Default::default(), None);
+        let record_of_kind = |kind| -> eyre::Result<Record> {
+            Ok(Record::new(
+                NetworkAddress::from_peer(PeerId::random()).to_record_key(),
+                sn_protocol::storage::try_serialize_record(&vec![1u8, 2, 3], kind)?,
+            ))
+        };
+
+        let register = record_of_kind(RecordKind::Register)?;
+        store.put_verified(register.clone())?;
+        store.put_verified(record_of_kind(RecordKind::Register)?)?;
+        store.put_verified(record_of_kind(RecordKind::Chunk)?)?;
+        // records of unknown kind are stored, but not counted
+        store.put_verified(Record::new(
+            NetworkAddress::from_peer(PeerId::random()).to_record_key(),
+            vec![1],
+        ))?;
+
+        assert_eq!(store.record_count_of_kind(&RecordKind::Register), 2);
+        assert_eq!(store.record_count_of_kind(&RecordKind::Chunk), 1);
+        assert_eq!(store.record_count_of_kind(&RecordKind::DbcSpend), 0);
+
+        store.remove(&register.key);
+        assert_eq!(store.record_count_of_kind(&RecordKind::Register), 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn kind_quota_is_enforced_on_put() -> eyre::Result<()> {
+        let quota_of = |eviction| {
+            let store_config = DiskBackedRecordStoreConfig {
+                eviction,
+                kind_quotas: [(AddressKind::RegisterAddress, 2)].into_iter().collect(),
+                ..Default::default()
+            };
+            DiskBackedRecordStore::with_config(PeerId::random(), store_config, None)
+        };
+        let record_of_kind = |kind| -> eyre::Result<Record> {
+            Ok(Record::new(
+                NetworkAddress::from_peer(PeerId::random()).to_record_key(),
+                sn_protocol::storage::try_serialize_record(&vec![1u8, 2, 3], kind)?,
+            ))
+        };
+
+        let mut store = quota_of(EvictionPolicy::RejectNew);
+        let register = record_of_kind(RecordKind::Register)?;
+        store.put_verified(register.clone())?;
+        store.put_verified(record_of_kind(RecordKind::Register)?)?;
+        assert!(matches!(
+            store.put_verified(record_of_kind(RecordKind::Register)?),
+            Err(NetworkError::Protocol(ProtocolError::KindQuotaExceeded {
+                kind: AddressKind::RegisterAddress,
+            }))
+        ));
+        assert_eq!(store.record_count_of_kind(&RecordKind::Register), 2);
+
+        // registers already held can still be updated, and other kinds are still stored
+        store.put_verified(register.clone())?;
+        store.put_verified(record_of_kind(RecordKind::Chunk)?)?;
+        assert_eq!(store.record_count_of_kind(&RecordKind::Register), 2);
+
+        // the quota is freed up by removing a register
+        store.remove(&register.key);
+        store.put_verified(record_of_kind(RecordKind::Register)?)?;
+        assert_eq!(store.record_count_of_kind(&RecordKind::Register), 2);
+
+        // when evicting, a register takes the place of another, the chunk being kept
+        let mut store = quota_of(EvictionPolicy::EvictLeastAccessed);
+        let chunk = record_of_kind(RecordKind::Chunk)?;
+        store.put_verified(chunk.clone())?;
+        store.put_verified(register.clone())?;
+        store.put_verified(record_of_kind(RecordKind::Register)?)?;
+        store.put_verified(record_of_kind(RecordKind::Register)?)?;
+        assert_eq!(store.record_count_of_kind(&RecordKind::Register), 2);
+        assert!(store.contains(&chunk.key));
+        assert_eq!(store.records.len(), 3);
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn pruning_on_full() -> Result<()> {
         let max_iterations = 10;
@@ -564,6 +860,7 @@ mod tests {
         // check there is an expected pruning behaviour got carried out.
         let store_config = DiskBackedRecordStoreConfig {
             max_records,
+            eviction: EvictionPolicy::EvictFarthest,
             ..Default::default()
         };
         let self_id = PeerId::random();
@@ -640,4 +937,108 @@ mod tests {
 
         Ok(())
     }
+
+    fn random_record() -> Record {
+        Record {
+            key: NetworkAddress::from_peer(PeerId::random()).to_record_key(),
+            value: vec![1, 2, 3],
+            publisher: None,
+            expires: None,
+        }
+    }
+
+    fn full_store(eviction: EvictionPolicy, max_records: usize) -> Result<DiskBackedRecordStore> {
+        let store_config = DiskBackedRecordStoreConfig {
+            max_records,
+            eviction,
+            ..Default::default()
+        };
+        let mut store = DiskBackedRecordStore::with_config(PeerId::random(), store_config, None);
+        for _ in 0..max_records {
+            store.put_verified(random_record())?;
+        }
+        Ok(store)
+    }
+
+    #[tokio::test]
+    async fn reject_new_policy_rejects_records_at_capacity() -> Result<()> {
+        let mut store = full_store(EvictionPolicy::RejectNew, 5)?;
+        let stored = store.records.clone();
+
+        assert!(matches!(
+            store.put_verified(random_record()),
+            Err(NetworkError::KademliaStoreError(Error::MaxRecords))
+        ));
+        assert_eq!(store.records, stored);
+
+        // records already held can still be updated
+        let mut update = random_record();
+        update.key = stored.iter().next().cloned().expect("store is full");
+        store.put_verified(update)?;
+        assert_eq!(store.records, stored);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn evict_farthest_policy_makes_room_for_closer_records() -> Result<()> {
+        let mut store = full_store(EvictionPolicy::EvictFarthest, 5)?;
+        let local_key = store.local_key.clone();
+        let distance_to = |key: &Key| local_key.distance(&KBucketKey::from(key.to_vec()));
+        let farthest = store
+            .records
+            .iter()
+            .max_by_key(|key| distance_to(key))
+            .cloned()
+            .expect("store is full");
+        let closest = store
+            .records
+            .iter()
+            .min_by_key(|key| distance_to(key))
+            .cloned()
+            .expect("store is full");
+
+        // a record farther than all of ours is rejected
+        let farther = std::iter::repeat_with(random_record)
+            .find(|record| distance_to(&record.key) > distance_to(&farthest))
+            .expect("infinite iterator");
+        assert!(matches!(
+            store.put_verified(farther),
+            Err(NetworkError::KademliaStoreError(Error::MaxRecords))
+        ));
+        assert!(store.contains(&farthest));
+
+        // while a closer one takes the place of the farthest
+        let closer = std::iter::repeat_with(random_record)
+            .find(|record| distance_to(&record.key) < distance_to(&closest))
+            .expect("infinite iterator");
+        store.put_verified(closer.clone())?;
+        assert!(store.contains(&closer.key));
+        assert!(!store.contains(&farthest));
+        assert_eq!(store.records.len(), 5);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn evict_least_accessed_policy_evicts_the_least_read_record() -> Result<()> {
+        let mut store = full_store(EvictionPolicy::EvictLeastAccessed, 3)?;
+        let keys: Vec<_> = store.records.iter().cloned().collect();
+
+        // all records but the last one are read
+        for key in &keys[..2] {
+            let _ = store.get(key);
+            let _ = store.get(key);
+        }
+        assert_eq!(store.access_count(&keys[0]), 2);
+        assert_eq!(store.access_count(&keys[2]), 0);
+
+        let incoming = random_record();
+        store.put_verified(incoming.clone())?;
+        assert!(store.contains(&incoming.key));
+        assert!(!store.contains(&keys[2]));
+        assert!(store.contains(&keys[0]) && store.contains(&keys[1]));
+
+        Ok(())
+    }
 }
@@ -20,7 +20,7 @@ const MAX_PARALLEL_FETCH: usize = 8;
 
 // The duration after which a peer will be considered failed to fetch data from,
 // if no response got from that peer.
-const FETCH_TIMEOUT: Duration = Duration::from_secs(15);
+pub(crate) const FETCH_TIMEOUT: Duration = Duration::from_secs(15);
 
 // The maximum number of retries that is performed per peer.
 // Else the key is fetched from the Network
@@ -49,6 +49,9 @@ pub(crate) struct ReplicationFetcher {
         BTreeMap<PeerId, (ReplicationRequestSentTime, HolderStatus, FailedAttempts)>,
     >,
     on_going_fetches: usize,
+    // The keys of the records we declined to store, which aren't fetched again till we may
+    // store them, see `forget_rejected_keys`.
+    rejected: HashSet<RecordKey>,
 }
 
 impl ReplicationFetcher {
@@ -62,11 +65,16 @@ impl ReplicationFetcher {
     ) -> Vec<(RecordKey, Option<PeerId>)> {
         self.retain_keys(locally_stored_keys);
 
-        // add non existing keys to the fetcher
-        incoming_keys
+        // add non existing keys, which we didn't decline to store, to the fetcher
+        let new_keys = incoming_keys
             .into_iter()
             .filter_map(|incoming| incoming.as_record_key())
-            .filter(|incoming| !locally_stored_keys.contains(incoming))
+            .filter(|incoming| {
+                !locally_stored_keys.contains(incoming) && !self.rejected.contains(incoming)
+            })
+            .collect::<Vec<_>>();
+        new_keys
+            .into_iter()
             .for_each(|incoming| self.add_holder_pey_key(incoming, peer_id));
 
         self.next_keys_to_fetch()
@@ -79,8 +87,32 @@ impl ReplicationFetcher {
         &mut self,
         new_put: RecordKey,
     ) -> Vec<(RecordKey, Option<PeerId>)> {
+        self.stop_fetching(&new_put);
+        self.next_keys_to_fetch()
+    }
+
+    // Notify the replication fetcher that we declined to store the Record at `key`, e.g. as its
+    // kind is at its quota. The key is no longer fetched, nor added back when peers replicate it.
+    // Also returns the next set of keys that has to be fetched from the peer/network.
+    pub(crate) fn notify_about_rejected_put(
+        &mut self,
+        key: RecordKey,
+    ) -> Vec<(RecordKey, Option<PeerId>)> {
+        self.stop_fetching(&key);
+        let _ = self.rejected.insert(key);
+        self.next_keys_to_fetch()
+    }
+
+    // Forget the keys of the records we declined to store, as we may store them now, having
+    // made space.
+    pub(crate) fn forget_rejected_keys(&mut self) {
+        self.rejected.clear();
+    }
+
+    // Remove the key from the fetcher.
+    fn stop_fetching(&mut self, key: &RecordKey) {
         // if we're actively fetching for the key, reduce the on_going_fetches before removing the key
-        if let Some(holders) = self.to_be_fetched.get(&new_put) {
+        if let Some(holders) = self.to_be_fetched.get(key) {
             if holders
                 .values()
                 .any(|(_, status, _)| *status == HolderStatus::OnGoing)
@@ -88,8 +120,7 @@ impl ReplicationFetcher {
                 self.on_going_fetches = self.on_going_fetches.saturating_sub(1);
             }
         }
-        self.to_be_fetched.remove(&new_put);
-        self.next_keys_to_fetch()
+        self.to_be_fetched.remove(key);
     }
 
     // Returns the set of keys that has to be fetched from the peer/network.
@@ -345,4 +376,35 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn rejected_keys_are_not_fetched_again_till_forgotten() {
+        let mut replication_fetcher = ReplicationFetcher::default();
+        let locally_stored_keys = HashSet::new();
+
+        let random_data: Vec<u8> = (0..50).map(|_| rand::random::<u8>()).collect();
+        let key = NetworkAddress::from_record_key(RecordKey::from(random_data));
+        let record_key = key.as_record_key().expect("a record key");
+        let peer = PeerId::random();
+
+        let keys_to_fetch =
+            replication_fetcher.add_keys(peer, vec![key.clone()], &locally_stored_keys);
+        assert_eq!(keys_to_fetch.len(), 1);
+
+        // once rejected, the key isn't fetched, and the fetch slot is freed
+        assert!(replication_fetcher
+            .notify_about_rejected_put(record_key.clone())
+            .is_empty());
+        assert_eq!(replication_fetcher.on_going_fetches, 0);
+
+        // nor is it fetched again when replicated to us by other peers
+        let keys_to_fetch =
+            replication_fetcher.add_keys(PeerId::random(), vec![key.clone()], &locally_stored_keys);
+        assert!(keys_to_fetch.is_empty());
+
+        // till we may store it
+        replication_fetcher.forget_rejected_keys();
+        let keys_to_fetch = replication_fetcher.add_keys(peer, vec![key], &locally_stored_keys);
+        assert_eq!(keys_to_fetch, vec![(record_key, Some(peer))]);
+    }
 }
@@ -0,0 +1,156 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{error::Result, Error, NetworkEvent, SwarmDriver};
+use libp2p::{request_response::RequestId, PeerId};
+use sn_protocol::messages::Response;
+use std::time::{Duration, Instant};
+use tokio::sync::oneshot;
+
+/// How often in-flight requests are checked for being overdue.
+pub(crate) const REQUEST_WATCHDOG_INTERVAL: Duration = Duration::from_secs(10);
+
+/// A request to a peer awaiting its response.
+#[derive(Debug)]
+pub(crate) struct PendingRequest {
+    /// Where to send the response to, if it's awaited at the call site.
+    pub(crate) sender: Option<oneshot::Sender<Result<Response>>>,
+    pub(crate) peer: PeerId,
+    /// The id of the `RequestMsg`, to be echoed in its `ResponseMsg`.
+    pub(crate) msg_id: u64,
+    pub(crate) sent_at: Instant,
+}
+
+/// Information about a request awaiting its response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestInfo {
+    /// The id of the request
+    pub request_id: RequestId,
+    /// The peer the request was sent to
+    pub peer: PeerId,
+    /// How long the request has been in flight
+    pub age: Duration,
+}
+
+impl SwarmDriver {
+    /// The requests in flight for longer than the request timeout, by which libp2p should have
+    /// failed them, oldest first.
+    pub(crate) fn stuck_requests(&self) -> Vec<RequestInfo> {
+        let mut stuck: Vec<RequestInfo> = self
+            .pending_requests
+            .iter()
+            .filter_map(|(request_id, pending)| {
+                let age = pending.sent_at.elapsed();
+                (age > self.request_timeout).then_some(RequestInfo {
+                    request_id: *request_id,
+                    peer: pending.peer,
+                    age,
+                })
+            })
+            .collect();
+        stuck.sort_by_key(|info| std::cmp::Reverse(info.age));
+        stuck
+    }
+
+    /// Cancels the requests in flight for longer than twice the request timeout, failing them at
+    /// their call site and emitting a `NetworkEvent::RequestCancelled` for each.
+    pub(crate) fn cancel_overdue_requests(&mut self) {
+        let deadline = self.request_timeout * 2;
+        let overdue: Vec<RequestId> = self
+            .pending_requests
+            .iter()
+            .filter(|(_, pending)| pending.sent_at.elapsed() > deadline)
+            .map(|(request_id, _)| *request_id)
+            .collect();
+
+        for request_id in overdue {
+            let pending = match self.pending_requests.remove(&request_id) {
+                Some(pending) => pending,
+                None => continue,
+            };
+            let info = RequestInfo {
+                request_id,
+                peer: pending.peer,
+                age: pending.sent_at.elapsed(),
+            };
+            warn!(
+                "Cancelling request {request_id:?} to {:?}, in flight for {:?}",
+                info.peer, info.age
+            );
+            if let Some(sender) = pending.sender {
+                let _ = sender.send(Err(Error::RequestCancelled(request_id)));
+            }
+            self.send_event(NetworkEvent::RequestCancelled(info));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::SwarmCmd;
+    use sn_protocol::{
+        messages::{Query, Request},
+        NetworkAddress,
+    };
+
+    #[tokio::test]
+    async fn watchdog_reports_and_cancels_hung_requests() -> eyre::Result<()> {
+        let timeout = Duration::from_secs(5);
+        let (_network, mut events, mut driver) = SwarmDriver::new_client(true, Some(timeout))?;
+
+        // the peers are unreachable, and the swarm isn't even run, hence no response ever comes
+        let send_request = |driver: &mut SwarmDriver| -> eyre::Result<_> {
+            let peer = PeerId::random();
+            let (sender, receiver) = oneshot::channel();
+            driver.handle_cmd(SwarmCmd::SendRequest {
+                req: Request::Query(Query::HasRecord(NetworkAddress::from_peer(peer))),
+                peer,
+                sender: Some(sender),
+            })?;
+            Ok((peer, receiver))
+        };
+        let (hung_peer, mut hung_receiver) = send_request(&mut driver)?;
+        let (slow_peer, mut slow_receiver) = send_request(&mut driver)?;
+        let (_fresh_peer, mut fresh_receiver) = send_request(&mut driver)?;
+
+        // age the requests, as if they'd been hanging
+        for pending in driver.pending_requests.values_mut() {
+            if pending.peer == hung_peer {
+                pending.sent_at -= timeout * 3;
+            } else if pending.peer == slow_peer {
+                pending.sent_at -= timeout + Duration::from_secs(1);
+            }
+        }
+
+        let stuck = driver.stuck_requests();
+        assert_eq!(
+            stuck.iter().map(|info| info.peer).collect::<Vec<_>>(),
+            vec![hung_peer, slow_peer]
+        );
+
+        driver.cancel_overdue_requests();
+        assert!(matches!(
+            hung_receiver.try_recv()?,
+            Err(Error::RequestCancelled(_))
+        ));
+        assert!(slow_receiver.try_recv().is_err());
+        assert!(fresh_receiver.try_recv().is_err());
+        assert_eq!(driver.pending_requests.len(), 2);
+
+        match events.recv().await {
+            Some(NetworkEvent::RequestCancelled(info)) => {
+                assert_eq!(info.peer, hung_peer);
+                assert!(info.age > timeout * 2);
+            }
+            other => eyre::bail!("Unexpected event {other:?}"),
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,182 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::SwarmDriver;
+use libp2p::{Multiaddr, PeerId};
+use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+
+/// Snapshot of the local Kademlia routing table, bucket by bucket.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RoutingTableSnapshot {
+    /// The non-empty buckets, ordered by increasing distance to us.
+    pub buckets: Vec<BucketSnapshot>,
+}
+
+/// The peers held in a single k-bucket.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BucketSnapshot {
+    /// The `ilog2` of the distances covered by the bucket, i.e. it holds
+    /// the peers at a distance within `[2^index, 2^(index + 1))` from us.
+    pub index: u32,
+    /// The peers in the bucket.
+    pub peers: Vec<PeerSnapshot>,
+}
+
+/// What we know about a peer in our routing table.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerSnapshot {
+    /// The peer's id.
+    pub peer_id: PeerId,
+    /// The addresses we hold for the peer.
+    pub addresses: Vec<Multiaddr>,
+    /// Whether we are currently connected to the peer.
+    pub connected: bool,
+    /// The last time we established a connection with, or got identified by, the peer.
+    /// `None` if we have not heard from it since we started.
+    pub last_seen: Option<SystemTime>,
+}
+
+impl RoutingTableSnapshot {
+    /// Total number of peers across all the buckets.
+    pub fn num_peers(&self) -> usize {
+        self.buckets.iter().map(|bucket| bucket.peers.len()).sum()
+    }
+
+    /// Returns the index of the bucket holding the given peer, if any.
+    pub fn bucket_index_of(&self, peer_id: &PeerId) -> Option<u32> {
+        self.buckets
+            .iter()
+            .find(|bucket| bucket.peers.iter().any(|peer| &peer.peer_id == peer_id))
+            .map(|bucket| bucket.index)
+    }
+}
+
+impl SwarmDriver {
+    /// Builds a `RoutingTableSnapshot` out of our current k-buckets.
+    pub(crate) fn routing_table_snapshot(&mut self) -> RoutingTableSnapshot {
+        let mut buckets = vec![];
+        for kbucket in self.swarm.behaviour_mut().kademlia.kbuckets() {
+            let index = match kbucket.range().0.ilog2() {
+                Some(index) => index,
+                None => {
+                    // This shall never happen.
+                    error!("A kbucket covers the distance to ourself");
+                    continue;
+                }
+            };
+            let peers = kbucket
+                .iter()
+                .map(|entry| {
+                    let peer_id = entry.node.key.clone().into_preimage();
+                    PeerSnapshot {
+                        peer_id,
+                        addresses: entry.node.value.iter().cloned().collect(),
+                        connected: false,
+                        last_seen: None,
+                    }
+                })
+                .collect();
+            buckets.push(BucketSnapshot { index, peers });
+        }
+
+        for peer in buckets
+            .iter_mut()
+            .flat_map(|bucket| bucket.peers.iter_mut())
+        {
+            peer.connected = self.swarm.is_connected(&peer.peer_id);
+            peer.last_seen = self.peers_last_seen.get(&peer.peer_id).copied();
+        }
+
+        RoutingTableSnapshot { buckets }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libp2p::kad::KBucketKey;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn snapshot_places_known_peers_in_their_buckets() -> eyre::Result<()> {
+        let (_network, _events, mut driver) = SwarmDriver::new_client(true, None)?;
+        let our_key = KBucketKey::from(driver.self_peer_id);
+
+        let seen_at = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let mut known_peers = vec![];
+        for i in 0..10u16 {
+            let peer = PeerId::random();
+            let addr: Multiaddr = format!("/ip4/10.0.0.1/tcp/{}", 12000 + i).parse()?;
+            let _ = driver
+                .swarm
+                .behaviour_mut()
+                .kademlia
+                .add_address(&peer, addr.clone());
+            if i % 2 == 0 {
+                let _ = driver.peers_last_seen.insert(peer, seen_at);
+            }
+            known_peers.push((peer, addr));
+        }
+
+        let snapshot = driver.routing_table_snapshot();
+        assert_eq!(snapshot.num_peers(), known_peers.len());
+
+        for (i, (peer, addr)) in known_peers.iter().enumerate() {
+            let expected_index = our_key.distance(&KBucketKey::from(*peer)).ilog2();
+            assert_eq!(snapshot.bucket_index_of(peer), expected_index);
+
+            let entry = snapshot
+                .buckets
+                .iter()
+                .flat_map(|bucket| bucket.peers.iter())
+                .find(|entry| &entry.peer_id == peer)
+                .ok_or_else(|| eyre::eyre!("peer missing from the snapshot"))?;
+            assert_eq!(entry.addresses, vec![addr.clone()]);
+            assert!(!entry.connected);
+            let expected_last_seen = (i % 2 == 0).then_some(seen_at);
+            assert_eq!(entry.last_seen, expected_last_seen);
+        }
+
+        // the buckets are ordered by distance
+        assert!(snapshot
+            .buckets
+            .windows(2)
+            .all(|pair| pair[0].index < pair[1].index));
+
+        // and the snapshot can be shipped around
+        let bytes = rmp_serde::to_vec(&snapshot)?;
+        let decoded: RoutingTableSnapshot = rmp_serde::from_slice(&bytes)?;
+        assert_eq!(decoded, snapshot);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn disconnected_peers_are_only_remembered_in_the_routing_table() -> eyre::Result<()> {
+        let (_network, _events, mut driver) = SwarmDriver::new_client(true, None)?;
+        let seen_at = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+        let known = PeerId::random();
+        let _ = driver
+            .swarm
+            .behaviour_mut()
+            .kademlia
+            .add_address(&known, "/ip4/10.0.0.1/tcp/12000".parse()?);
+        let stranger = PeerId::random();
+        for peer in [known, stranger] {
+            let _ = driver.peers_last_seen.insert(peer, seen_at);
+            driver.forget_disconnected_peer(&peer);
+        }
+
+        assert_eq!(driver.peers_last_seen.get(&known), Some(&seen_at));
+        assert!(!driver.peers_last_seen.contains_key(&stranger));
+
+        Ok(())
+    }
+}
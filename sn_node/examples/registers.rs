@@ -87,12 +87,12 @@ async fn main() -> Result<()> {
         let msg = format!("[{user}]: {input_text}");
         match reg_replica.write(msg.as_bytes()) {
             Ok(()) => {}
-            Err(Error::ContentBranchDetected(branches)) => {
+            Err(Error::ContentBranchDetected(resolver)) => {
                 println!(
                     "Branches ({}) detected in Register, let's merge them all...",
-                    branches.len()
+                    resolver.branches().len()
                 );
-                reg_replica.write_merging_branches(msg.as_bytes())?;
+                resolver.merge_all(&mut reg_replica)?;
             }
             Err(err) => return Err(err.into()),
         }
@@ -6,18 +6,44 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::{error::Result, event::NodeEventsChannel, Marker, Network, Node, NodeEvent};
+use super::{
+    error::{Error, Result},
+    event::NodeEventsChannel,
+    fair_queue::FairRequestQueue,
+    merge_stats::MergeStats,
+    peer_allowlist::unauthorized_response,
+    probe_target::ProbeTargets,
+    put_validation::verify_kind_accepted,
+    readiness::{not_ready_response, Readiness},
+    replication::{
+        record_key_filter_of, replication_report_of, replication_status_of, ReplicationReport,
+        ReplicationStatus,
+    },
+    Marker, MergeLog, Network, Node, NodeConfig, NodeEvent, SpendValidationPermits,
+};
+use bytes::Bytes;
+use futures::{future::join_all, Future};
 use libp2p::{autonat::NatStatus, identity::Keypair, Multiaddr, PeerId};
 use rand::{rngs::StdRng, Rng, SeedableRng};
-use sn_networking::{MsgResponder, NetworkEvent, SwarmDriver, SwarmLocalState};
+use sn_networking::{
+    MsgResponder, NetworkEvent, RequestInfo, RoutingTableSnapshot, SwarmDriver, SwarmLocalState,
+    CLOSE_GROUP_SIZE,
+};
 use sn_protocol::{
     error::Error as ProtocolError,
     messages::{Cmd, CmdResponse, Query, QueryResponse, ReplicatedData, Request, Response},
     storage::DbcAddress,
     NetworkAddress, PrettyPrintRecordKey,
 };
-use std::{collections::HashSet, net::SocketAddr, path::PathBuf, time::Duration};
-use tokio::task::spawn;
+use sn_registers::RegisterAddress;
+use std::{
+    collections::HashSet,
+    net::SocketAddr,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tokio::task::{spawn, JoinHandle};
 
 /// Once a node is started and running, the user obtains
 /// a `NodeRunning` object which can be used to interact with it.
@@ -25,6 +51,8 @@ use tokio::task::spawn;
 pub struct RunningNode {
     network: Network,
     node_events_channel: NodeEventsChannel,
+    merge_log: MergeLog,
+    tasks: Arc<Mutex<Vec<JoinHandle<()>>>>,
 }
 
 impl RunningNode {
@@ -51,16 +79,147 @@ impl RunningNode {
         Ok(state)
     }
 
+    /// Returns the requests sent by the node which have been awaiting their response for longer
+    /// than the request timeout, oldest first. Requests still in flight after twice the timeout
+    /// are cancelled, emitting a `NodeEvent::RequestCancelled`.
+    pub async fn stuck_requests(&self) -> Result<Vec<RequestInfo>> {
+        let stuck = self.network.get_stuck_requests().await?;
+        Ok(stuck)
+    }
+
+    /// Returns a `RoutingTableSnapshot` of the peers currently in the node's k-buckets.
+    pub async fn routing_table(&self) -> Result<RoutingTableSnapshot> {
+        let snapshot = self.network.get_routing_table().await?;
+        Ok(snapshot)
+    }
+
     /// Returns the node events channel where to subscribe to receive `NodeEvent`s
     pub fn node_events_channel(&self) -> &NodeEventsChannel {
         &self.node_events_channel
     }
 
+    /// Stops the node, aborting the swarm driver, which closes its connections, and the tasks
+    /// handling the node's events and requests, returning once they have terminated.
+    /// All the clones of the `RunningNode` are stopped alike.
+    pub async fn shutdown(self) {
+        let tasks = match self.tasks.lock() {
+            Ok(mut tasks) => std::mem::take(&mut *tasks),
+            Err(_) => {
+                error!("The node tasks lock is poisoned, cannot shut the node down");
+                return;
+            }
+        };
+        for task in &tasks {
+            task.abort();
+        }
+        for task in tasks {
+            let _ = task.await;
+        }
+        info!("Node {:?} has been shut down", self.network.peer_id);
+    }
+
     /// Returns the list of all the RecordKeys held by the node
     pub async fn get_all_record_addresses(&self) -> Result<HashSet<NetworkAddress>> {
         let addresses = self.network.get_all_local_record_addresses().await?;
         Ok(addresses)
     }
+
+    /// Returns the record held locally at `address`, if any, as stored, i.e. serialized along with
+    /// its `RecordHeader`. Only the node's storage is read, no request being sent to the network,
+    /// for the records held by the node to be served by a gateway to non-libp2p clients.
+    pub async fn read_record(&self, address: &NetworkAddress) -> Result<Option<Bytes>> {
+        let record = self
+            .network
+            .get_local_record(&address.to_record_key())
+            .await?;
+        Ok(record.map(|record| Bytes::from(record.value)))
+    }
+
+    /// Returns the addresses of all the records held locally, ordered, to be read with
+    /// `read_record`.
+    pub async fn list_records(&self) -> Result<Vec<NetworkAddress>> {
+        let mut addresses: Vec<_> = self.get_all_record_addresses().await?.into_iter().collect();
+        addresses.sort();
+        Ok(addresses)
+    }
+
+    /// Returns whether the record at `address` is held locally. Only the node's storage is
+    /// checked, whether or not the rest of the close group holds the record.
+    pub async fn has_local_record(&self, address: &NetworkAddress) -> Result<bool> {
+        let present = self
+            .network
+            .is_key_present_locally(&address.to_record_key())
+            .await?;
+        Ok(present)
+    }
+
+    /// Returns the peer which published the record held locally at `address`.
+    /// Only available when the node was started with record origin tracking enabled.
+    pub async fn record_origin(&self, address: &NetworkAddress) -> Result<Option<PeerId>> {
+        let origin = self
+            .network
+            .get_local_record_origin(&address.to_record_key())
+            .await?;
+        Ok(origin)
+    }
+
+    /// Queries the close group responsible for the record at `address`, ourselves included,
+    /// for whether they hold it, to report how far behind its replication is.
+    pub async fn replication_status(&self, address: &NetworkAddress) -> Result<ReplicationStatus> {
+        let responsible = self.network.node_get_closest_peers(address).await?;
+        let request = Request::Query(Query::HasRecord(address.clone()));
+        let responses = join_all(responsible.into_iter().map(|peer| {
+            let request = request.clone();
+            async move { (peer, self.network.send_request(request, peer).await) }
+        }))
+        .await;
+        Ok(replication_status_of(responses))
+    }
+
+    /// Compares the records held by the node with those held by its close group, as per the
+    /// node's view of the network: the records it's responsible for but doesn't hold, which
+    /// are to be re-replicated to it, and those it holds but is no longer responsible for.
+    /// The peers of the close group failing to tell which records they hold are left out, and
+    /// each peer tells of a page of the records we're missing only, so that the records missing
+    /// from a node far behind are reported over several calls.
+    pub async fn replication_report(&self) -> Result<ReplicationReport> {
+        let our_peer_id = self.network.peer_id;
+        let held = self.network.get_all_local_record_addresses().await?;
+        let filter = record_key_filter_of(&held);
+        let close_group = self.network.get_our_close_group().await?;
+
+        let responses = join_all(
+            close_group
+                .into_iter()
+                .filter(|peer| *peer != our_peer_id)
+                .map(|peer| {
+                    let request = Request::Query(Query::RecordKeysMissingFrom {
+                        address: NetworkAddress::from_peer(peer),
+                        filter: filter.clone(),
+                    });
+                    async move { (peer, self.network.send_request(request, peer).await) }
+                }),
+        )
+        .await;
+        let mut held_by_peers = vec![];
+        for (peer, response) in responses {
+            match response {
+                Ok(Response::Query(QueryResponse::RecordKeysMissingFrom(Ok(keys)))) => {
+                    held_by_peers.extend(keys)
+                }
+                other => warn!("Failed to get the records held by {peer:?}: {other:?}"),
+            }
+        }
+
+        let all_peers = self.network.get_all_local_peers().await?;
+        replication_report_of(our_peer_id, &held, held_by_peers, all_peers)
+    }
+
+    /// Returns the `n` Registers whose merges took the node the longest, slowest first, along
+    /// with the `MergeStats` of their most expensive merge, to spot abusive or buggy Registers.
+    pub fn expensive_merges(&self, n: usize) -> Vec<(RegisterAddress, MergeStats)> {
+        self.merge_log.most_expensive(n)
+    }
 }
 
 impl Node {
@@ -73,6 +232,31 @@ impl Node {
     ///
     /// A tuple containing a `Node` instance and a `NodeEventsChannel`.
     ///
+    /// The node is set up as per its `config`, see `NodeConfig`. Notably:
+    /// - Requests of peers beyond `max_concurrent_requests` are queued, and handled in turn for
+    /// each of the peers they come from.
+    /// - Validating a spend is aborted with `ProtocolError::ValidationBudgetExceeded` should it
+    /// take longer than the `spend_validation_budget`, which can't be zero, or
+    /// `Error::InvalidSpendValidationBudget` is returned.
+    /// - The spends fetched from the network are accepted once `spend_quorum` of the peers
+    /// holding them returned them identically, diverging spends being a double spend attempt.
+    /// The `spend_quorum` must be within `1..=CLOSE_GROUP_SIZE`, or `Error::InvalidSpendQuorum`
+    /// is returned.
+    /// - Should a `peer_allowlist` be provided, only the peers on it may connect to the node and
+    /// have their requests served.
+    /// - Should `accepted_kinds` be provided, only records of those kinds are stored by the node,
+    /// which declines to fetch the records of other kinds replicated to it. Those are held by one
+    /// peer less of their close group, which the operators of the network should account for.
+    /// Queries aren't routed by kind: the node, not holding records of other kinds, lets the
+    /// other peers answer the queries for them.
+    /// - The `per_kind_quota` caps the number of records the node stores at each of its kinds of
+    /// address, even if it has space left for records of other kinds, records beyond a quota
+    /// being rejected with
+    /// `ProtocolError::KindQuotaExceeded` unless the eviction policy makes room for them.
+    /// - Requests of peers are rejected with `ProtocolError::NotReady` till the node is connected
+    /// to `min_peers_before_serving` of the peers in its routing table, a
+    /// `NodeEvent::ReadyToServe` being broadcast once it is.
+    ///
     /// # Errors
     ///
     /// Returns an error if there is a problem initializing the `SwarmDriver`.
@@ -80,25 +264,82 @@ impl Node {
         keypair: Keypair,
         addr: SocketAddr,
         initial_peers: Vec<Multiaddr>,
-        local: bool,
         root_dir: PathBuf,
+        mut config: NodeConfig,
     ) -> Result<RunningNode> {
-        let (network, mut network_event_receiver, swarm_driver) =
-            SwarmDriver::new(keypair, addr, local, root_dir)?;
+        if !(1..=CLOSE_GROUP_SIZE).contains(&config.spend_quorum) {
+            return Err(Error::InvalidSpendQuorum(config.spend_quorum));
+        }
+        if config.spend_validation_budget.is_zero() {
+            return Err(Error::InvalidSpendValidationBudget);
+        }
+        // we always serve the requests we send to ourselves
+        if let Some(allowlist) = config.network.peer_allowlist.as_mut() {
+            let _ = allowlist.insert(PeerId::from(keypair.public()));
+        }
+        let NodeConfig {
+            network: network_config,
+            max_concurrent_spend_validations,
+            spend_validation_budget,
+            spend_quorum,
+            max_concurrent_requests,
+            anti_entropy_interval,
+            delete_operators,
+            accepted_kinds,
+            per_kind_quota,
+            probe_target,
+            double_spend_propagation,
+            min_peers_before_serving,
+        } = config;
+        let peer_allowlist = network_config.peer_allowlist.clone();
+        let mut probe_targets = ProbeTargets::new(probe_target, PeerId::from(keypair.public()));
+        let (network, mut network_event_receiver, mut swarm_driver) =
+            SwarmDriver::new(keypair, addr, root_dir, network_config)?;
+        swarm_driver.set_kind_quotas(per_kind_quota);
         let node_events_channel = NodeEventsChannel::default();
 
         let node = Self {
             network: network.clone(),
             events_channel: node_events_channel.clone(),
             initial_peers,
+            spend_validation_permits: SpendValidationPermits::new(
+                max_concurrent_spend_validations,
+                spend_validation_budget,
+            ),
+            spend_quorum,
+            delete_operators: Arc::new(delete_operators),
+            peer_allowlist: Arc::new(peer_allowlist),
+            accepted_kinds: Arc::new(accepted_kinds),
+            request_queue: FairRequestQueue::new(max_concurrent_requests),
+            double_spend_propagation,
+            readiness: Readiness::new(min_peers_before_serving),
+            merge_log: MergeLog::default(),
         };
+        let merge_log = node.merge_log.clone();
 
         let network_clone = network.clone();
         let node_event_sender = node_events_channel.clone();
         let mut rng = StdRng::from_entropy();
 
-        let _handle = spawn(swarm_driver.run());
-        let _handle = spawn(async move {
+        let mut tasks = vec![
+            spawn(swarm_driver.run()),
+            spawn(node.clone().handle_queued_requests()),
+        ];
+        if let Some(interval) = anti_entropy_interval {
+            let node = node.clone();
+            tasks.push(spawn(async move {
+                let mut interval = tokio::time::interval(interval);
+                // the first tick completes immediately, while we're yet to join the network
+                let _ = interval.tick().await;
+                loop {
+                    let _ = interval.tick().await;
+                    if let Err(err) = node.anti_entropy_round().await {
+                        warn!("Anti-entropy round failed: {err:?}");
+                    }
+                }
+            }));
+        }
+        tasks.push(spawn(async move {
             // use a random inactivity timeout to ensure that the nodes do not sync when messages
             // are being transmitted.
             let inactivity_timeout: i32 = rng.gen_range(20..40);
@@ -126,12 +367,14 @@ impl Node {
                         trace!("NetworkEvent inactivity timeout hit");
 
                         let network_clone = network_clone.clone();
+                        let node_event_sender = node_event_sender.clone();
+                        let probe_target = probe_targets.next_target();
 
                         Marker::NoNetworkActivity( inactivity_timeout ).log();
                         let _handle = spawn ( async move {
-                            let random_target = NetworkAddress::from_peer(PeerId::random());
-                            debug!("No network activity in the past {inactivity_timeout:?}, performing a random get_closest query to target: {random_target:?}");
-                            match network_clone.node_get_closest_peers(&random_target).await {
+                            debug!("No network activity in the past {inactivity_timeout:?}, performing a get_closest query to target: {probe_target:?}");
+                            let lookup = network_clone.node_get_closest_peers(&probe_target);
+                            match timed_closest_peers_lookup(&probe_target, &node_event_sender, lookup).await {
                                 Ok(closest) => debug!("Network inactivity: get_closest returned {closest:?}"),
                                 Err(e) => {
                                     warn!("get_closest query failed after network inactivity timeout - check your connection: {}", e);
@@ -142,11 +385,13 @@ impl Node {
                     }
                 }
             }
-        });
+        }));
 
         Ok(RunningNode {
             network,
             node_events_channel,
+            merge_log,
+            tasks: Arc::new(Mutex::new(tasks)),
         })
     }
 
@@ -154,9 +399,12 @@ impl Node {
 
     async fn handle_network_event(&self, event: NetworkEvent) {
         match event {
-            NetworkEvent::RequestReceived { req, channel } => {
-                trace!("RequestReceived: {req:?}");
-                self.handle_request(req, channel).await;
+            NetworkEvent::RequestReceived { peer, req, channel } => {
+                trace!("RequestReceived: {req:?} from {peer:?}");
+                if let Err((req, channel)) = self.request_queue.push(peer, (req, channel)) {
+                    warn!("Too many requests queued for {peer:?}, rejecting {req:?}");
+                    self.send_response(req.error_response(ProtocolError::Overloaded), channel);
+                }
             }
             NetworkEvent::ResponseReceived { res } => {
                 trace!("NetworkEvent::ResponseReceived {res:?}");
@@ -167,6 +415,10 @@ impl Node {
             NetworkEvent::PeerAdded(peer_id) => {
                 Marker::PeerAddedToRoutingTable(peer_id).log();
 
+                if !self.readiness.is_ready() {
+                    self.check_readiness().await;
+                }
+
                 if let Err(err) = self.try_trigger_replication(peer_id, false).await {
                     error!("During CloseGroupUpdate, error while triggering replication {err:?}");
                 }
@@ -208,6 +460,12 @@ impl Node {
                     self.events_channel.broadcast(NodeEvent::BehindNat);
                 }
             }
+            NetworkEvent::RequestCancelled(info) => {
+                self.events_channel.broadcast(NodeEvent::RequestCancelled {
+                    peer: NetworkAddress::from_peer(info.peer),
+                    age: info.age,
+                });
+            }
             NetworkEvent::UnverifiedRecord(record) => {
                 let key = PrettyPrintRecordKey::from(record.key.clone());
                 match self.validate_and_store_record(record, true).await {
@@ -221,16 +479,25 @@ impl Node {
     }
 
     // Handle the response that was not awaited at the call site
-    async fn handle_response(&self, response: Response) -> Result<()> {
+    pub(crate) async fn handle_response(&self, response: Response) -> Result<()> {
         match response {
             Response::Query(QueryResponse::GetReplicatedData(Ok((_holder, replicated_data)))) => {
+                if let Err(err) = verify_kind_accepted(
+                    &replicated_data.record_kind(),
+                    self.accepted_kinds.as_ref().as_ref(),
+                ) {
+                    // or we'd fetch it again whenever it's replicated to us
+                    let key = replicated_data.dst()?.to_record_key();
+                    self.network.decline_replicated_record(key)?;
+                    return Err(err.into());
+                }
                 match replicated_data {
                     ReplicatedData::Chunk(chunk_with_payment) => {
                         let chunk_addr = *chunk_with_payment.chunk.address();
                         debug!("Chunk received for replication: {:?}", chunk_addr.xorname());
 
                         let success = self
-                            .validate_and_store_chunk(chunk_with_payment, false)
+                            .validate_and_store_chunk(chunk_with_payment, false, None)
                             .await?;
                         trace!("ReplicatedData::Chunk with {chunk_addr:?} has been validated and stored. {success:?}");
                     }
@@ -243,8 +510,28 @@ impl Node {
                             );
                             let addr = NetworkAddress::from_dbc_address(dbc_addr);
 
-                            let success = self.validate_and_store_spends(signed_spend).await?;
-                            trace!("ReplicatedData::Dbc with {addr:?} has been validated and stored. {success:?}");
+                            match self.validate_and_store_spends(signed_spend, None).await {
+                                Ok(success) => {
+                                    trace!("ReplicatedData::Dbc with {addr:?} has been validated and stored. {success:?}");
+                                }
+                                Err(ProtocolError::DoubleSpendAttempt(..)) => {
+                                    // the replicated spends hold the proof of a double spend,
+                                    // which we now hold as well
+                                    warn!("Learnt of a double spend at {addr:?} via replication");
+                                    self.events_channel
+                                        .broadcast(NodeEvent::DoubleSpendLearned(dbc_addr));
+                                }
+                                Err(ProtocolError::SpendDivergence { address, variants }) => {
+                                    // a parent of the replicated spend is double spent
+                                    warn!("Not storing the replicated spend at {addr:?}, the peers disagreeing on its parent at {address:?}: {variants:?}");
+                                    return Err(ProtocolError::SpendDivergence {
+                                        address,
+                                        variants,
+                                    }
+                                    .into());
+                                }
+                                Err(err) => return Err(err.into()),
+                            }
                         } else {
                             // Put validations make sure that we have >= 1 spends and with the same
                             // dbc_id
@@ -259,9 +546,16 @@ impl Node {
                             register_addr.xorname()
                         );
 
-                        let success = self.validate_and_store_register(register).await?;
+                        let success = self.validate_and_store_register(register, None).await?;
                         trace!("ReplicatedData::Register with {register_addr:?} has been validated and stored. {success:?}");
                     }
+                    ReplicatedData::SignedData(data) => {
+                        let address = data.address();
+                        debug!("Signed data received for replication: {address:?}");
+
+                        let success = self.validate_and_store_signed_data(data, None).await?;
+                        trace!("ReplicatedData::SignedData with {address:?} has been validated and stored. {success:?}");
+                    }
                 }
             }
             Response::Query(QueryResponse::GetReplicatedData(Err(
@@ -283,16 +577,62 @@ impl Node {
         Ok(())
     }
 
-    async fn handle_request(&self, request: Request, response_channel: MsgResponder) {
+    // Check whether we're now connected to enough of the peers of our routing table to serve
+    // requests, announcing it if so.
+    async fn check_readiness(&self) {
+        let routing_table = match self.network.get_routing_table().await {
+            Ok(routing_table) => routing_table,
+            Err(err) => {
+                warn!("Failed to get the routing table to check our readiness: {err:?}");
+                return;
+            }
+        };
+        let connected_peers = routing_table
+            .buckets
+            .iter()
+            .flat_map(|bucket| &bucket.peers)
+            .filter(|peer| peer.connected)
+            .count();
+        if self.readiness.update(connected_peers) {
+            info!("Connected to {connected_peers} peers, ready to serve requests");
+            self.events_channel.broadcast(NodeEvent::ReadyToServe);
+        }
+    }
+
+    // Handle the queued requests as handling slots free up, for as long as the node runs.
+    async fn handle_queued_requests(self) {
+        while let Some((slot, peer, (request, channel))) = self.request_queue.next().await {
+            let node = self.clone();
+            let _handle = spawn(async move {
+                node.handle_request(peer, request, channel).await;
+                drop(slot);
+            });
+        }
+        error!("The request queue is closed, no longer handling requests");
+    }
+
+    async fn handle_request(&self, peer: PeerId, request: Request, response_channel: MsgResponder) {
         trace!("Handling request: {request:?}");
+        if let Some(response) =
+            unauthorized_response(&request, peer, self.peer_allowlist.as_ref().as_ref())
+        {
+            self.send_response(response, response_channel);
+            return;
+        }
+        if let Some(response) =
+            not_ready_response(&request, peer, self.network.peer_id, &self.readiness)
+        {
+            self.send_response(response, response_channel);
+            return;
+        }
         let response = match request {
-            Request::Cmd(cmd) => self.handle_node_cmd(cmd),
-            Request::Query(query) => self.handle_query(query).await,
+            Request::Cmd(cmd) => self.handle_node_cmd(peer, cmd).await,
+            Request::Query(query) => self.handle_query(peer, query).await,
         };
         self.send_response(response, response_channel);
     }
 
-    async fn handle_query(&self, query: Query) -> Response {
+    pub(crate) async fn handle_query(&self, peer: PeerId, query: Query) -> Response {
         let resp: QueryResponse = match query {
             Query::GetStoreCost(_address) => {
                 trace!("Got GetStoreCost");
@@ -315,11 +655,41 @@ impl Node {
                     });
                 QueryResponse::GetReplicatedData(result)
             }
+            Query::RecordKeyFilter(_address) => {
+                trace!("Got RecordKeyFilter query");
+                let result = self.get_record_key_filter().await;
+                QueryResponse::RecordKeyFilter(result)
+            }
+            Query::RecordKeysMissingFrom { filter, .. } => {
+                trace!("Got RecordKeysMissingFrom query");
+                let result = self.get_record_keys_missing_from(peer, &filter).await;
+                QueryResponse::RecordKeysMissingFrom(result)
+            }
+            Query::GetRegisterDelta {
+                address,
+                known_heads,
+            } => {
+                trace!("Got GetRegisterDelta query for {address:?}");
+                let result = self.get_register_delta(address, known_heads).await;
+                QueryResponse::RegisterDelta(result)
+            }
+            Query::GetRegisterVersion(address) => {
+                trace!("Got GetRegisterVersion query for {address:?}");
+                let result = self
+                    .get_register_version(address)
+                    .await
+                    .map(|version| (NetworkAddress::from_peer(self.network.peer_id), version));
+                QueryResponse::RegisterVersion(result)
+            }
+            Query::HasRecord(address) => {
+                trace!("Got HasRecord query for {address:?}");
+                QueryResponse::HasRecord(Ok(self.has_record(&address).await))
+            }
         };
         Response::Query(resp)
     }
 
-    fn handle_node_cmd(&self, cmd: Cmd) -> Response {
+    async fn handle_node_cmd(&self, peer: PeerId, cmd: Cmd) -> Response {
         Marker::NodeCmdReceived(&cmd).log();
         let resp = match cmd {
             Cmd::Replicate { holder, keys } => {
@@ -335,6 +705,26 @@ impl Node {
                 // if we do not send a response, we can cause connection failures.
                 CmdResponse::Replicate(Ok(()))
             }
+            Cmd::DeleteRecord {
+                address,
+                expires_at,
+                operator,
+                signature,
+            } => {
+                debug!("DeleteRecord received from operator {operator:?} for {address:?}");
+                let result = self
+                    .delete_record(address, expires_at, operator, &signature)
+                    .await;
+                CmdResponse::DeleteRecord(result)
+            }
+            Cmd::RegisterBatch(batch) => {
+                debug!(
+                    "RegisterBatch of {} registers received from {peer:?}",
+                    batch.registers.len()
+                );
+                let result = self.validate_and_store_register_batch(batch, peer).await;
+                CmdResponse::RegisterBatch(result)
+            }
         };
 
         Marker::NodeCmdResponded(&resp).log();
@@ -348,3 +738,149 @@ impl Node {
         }
     }
 }
+
+// Await the `lookup` of the peers closest to the `target`, broadcasting how many were found and
+// how long it took once it completes.
+async fn timed_closest_peers_lookup<E>(
+    target: &NetworkAddress,
+    events_channel: &NodeEventsChannel,
+    lookup: impl Future<Output = Result<Vec<PeerId>, E>>,
+) -> Result<Vec<PeerId>, E> {
+    let started = Instant::now();
+    let closest = lookup.await?;
+    events_channel.broadcast(NodeEvent::ClosestPeersFound {
+        target: target.clone(),
+        count: closest.len(),
+        elapsed_ms: u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX),
+    });
+    Ok(closest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::TempDir;
+    use libp2p::kad::Record;
+    use sn_networking::NetworkConfig;
+    use sn_protocol::storage::{try_serialize_record, Chunk, RecordKind};
+
+    // The `RunningNode` of a node which isn't connected to any network, storing records in
+    // `root_dir`, see `put_validation::tests::offline_node`.
+    fn offline_node(root_dir: PathBuf) -> eyre::Result<RunningNode> {
+        let node = crate::put_validation::tests::offline_node(root_dir)?;
+        Ok(RunningNode {
+            network: node.network,
+            node_events_channel: node.events_channel,
+            merge_log: node.merge_log,
+            tasks: Default::default(),
+        })
+    }
+
+    #[tokio::test]
+    async fn stored_records_are_listed_and_read_locally() -> eyre::Result<()> {
+        let root_dir = TempDir::new()?;
+        let node = offline_node(root_dir.path().to_path_buf())?;
+        assert!(node.list_records().await?.is_empty());
+
+        let mut stored = vec![];
+        for content in [&b"first"[..], b"second"] {
+            let chunk = Chunk::new(Bytes::copy_from_slice(content));
+            let key = NetworkAddress::from_chunk_address(*chunk.address()).to_record_key();
+            let value = try_serialize_record(&chunk, RecordKind::Chunk)?;
+            node.network
+                .put_local_record(Record::new(key.clone(), value.clone()))
+                .await?;
+            stored.push((NetworkAddress::from_record_key(key), value));
+        }
+        stored.sort();
+
+        // the listed addresses are those the records are read at
+        let listed = node.list_records().await?;
+        assert_eq!(listed.len(), stored.len());
+        for (address, (expected, value)) in listed.iter().zip(stored) {
+            assert_eq!(address, &expected);
+            assert!(node.has_local_record(address).await?);
+            assert_eq!(node.read_record(address).await?, Some(Bytes::from(value)));
+        }
+
+        let missing = NetworkAddress::from_chunk_address(*Chunk::new(Bytes::new()).address());
+        assert!(!node.has_local_record(&missing).await?);
+        assert_eq!(node.read_record(&missing).await?, None);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn completed_closest_peers_lookup_is_broadcast_with_its_timing() -> eyre::Result<()> {
+        let events_channel = NodeEventsChannel::default();
+        let mut events = events_channel.subscribe();
+        let target = NetworkAddress::from_peer(PeerId::random());
+        let closest: Vec<PeerId> = (0..CLOSE_GROUP_SIZE).map(|_| PeerId::random()).collect();
+
+        let lookup = async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Ok::<_, eyre::Report>(closest.clone())
+        };
+        assert_eq!(
+            timed_closest_peers_lookup(&target, &events_channel, lookup).await?,
+            closest
+        );
+
+        match events.try_recv()? {
+            NodeEvent::ClosestPeersFound {
+                target: found_target,
+                count,
+                elapsed_ms,
+            } => {
+                assert_eq!(found_target, target);
+                assert_eq!(count, CLOSE_GROUP_SIZE);
+                assert!(elapsed_ms >= 50, "lookup took {elapsed_ms}ms");
+            }
+            other => panic!("Unexpected event {other:?}"),
+        }
+
+        // a failed lookup isn't broadcast
+        let failed = async { Err::<Vec<PeerId>, _>(eyre::eyre!("lookup failed")) };
+        assert!(timed_closest_peers_lookup(&target, &events_channel, failed)
+            .await
+            .is_err());
+        assert!(events.try_recv().is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn shutdown_stops_the_node_tasks() -> eyre::Result<()> {
+        let root_dir = TempDir::new()?;
+        let node = Node::run(
+            Keypair::generate_ed25519(),
+            "127.0.0.1:0".parse()?,
+            vec![],
+            root_dir.path().to_path_buf(),
+            NodeConfig {
+                network: NetworkConfig {
+                    local: true,
+                    ..Default::default()
+                },
+                anti_entropy_interval: Some(Duration::from_secs(1)),
+                ..Default::default()
+            },
+        )
+        .await?;
+        let _ = node.get_swarm_local_state().await?;
+
+        let tasks = node.tasks.clone();
+        tokio::time::timeout(Duration::from_secs(5), node.clone().shutdown()).await?;
+        assert!(tasks
+            .lock()
+            .map_err(|_| eyre::eyre!("poisoned"))?
+            .is_empty());
+
+        // with the swarm driver stopped, the node no longer handles any command
+        let state =
+            tokio::time::timeout(Duration::from_secs(5), node.get_swarm_local_state()).await?;
+        assert!(state.is_err());
+
+        Ok(())
+    }
+}
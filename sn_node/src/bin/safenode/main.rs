@@ -11,14 +11,22 @@ extern crate tracing;
 
 mod rpc;
 
+use bls::PublicKey;
 use clap::Parser;
 use eyre::{eyre, Error, Result};
 use libp2p::{identity::Keypair, Multiaddr, PeerId};
 #[cfg(feature = "metrics")]
 use sn_logging::metrics::init_metrics;
 use sn_logging::{parse_log_format, LogFormat, LogOutputDest};
-use sn_node::{Marker, Node, NodeEvent, NodeEventsReceiver};
+use sn_networking::{EvictionPolicy, NetworkConfig, CLOSE_GROUP_SIZE};
+use sn_node::{
+    Marker, Node, NodeConfig, NodeEvent, NodeEventsReceiver, ProbeTargetStrategy,
+    PropagationStrategy, DEFAULT_ANTI_ENTROPY_INTERVAL, DEFAULT_MAX_CONCURRENT_REQUESTS,
+    DEFAULT_MAX_CONCURRENT_SPEND_VALIDATIONS, DEFAULT_SPEND_QUORUM,
+    DEFAULT_SPEND_VALIDATION_BUDGET,
+};
 use sn_peers_acquisition::{parse_peer_addr, PeersArgs};
+use sn_protocol::{storage::RecordKind, AddressKind};
 use std::{
     env,
     io::Write,
@@ -64,6 +72,46 @@ pub fn parse_log_output(val: &str) -> Result<LogOutputDestArg> {
     }
 }
 
+pub fn parse_record_kind(val: &str) -> Result<RecordKind> {
+    match val {
+        "chunk" => Ok(RecordKind::Chunk),
+        "spend" => Ok(RecordKind::DbcSpend),
+        "register" => Ok(RecordKind::Register),
+        "signed-data" => Ok(RecordKind::SignedData),
+        other => Err(eyre!("Unknown record kind {other:?}")),
+    }
+}
+
+/// Parse a "<kind>=<count>" quota of records of a kind, keyed by the kind of their address.
+pub fn parse_kind_quota(val: &str) -> Result<(AddressKind, u64)> {
+    let (kind, count) = val
+        .split_once('=')
+        .ok_or_else(|| eyre!("Expected a quota as <kind>=<count>, got {val:?}"))?;
+    Ok((parse_record_kind(kind)?.address_kind(), count.parse()?))
+}
+
+/// Parse a spend quorum, which can't exceed the number of the peers holding a spend.
+pub fn parse_spend_quorum(val: &str) -> Result<usize> {
+    let quorum = val.parse()?;
+    if !(1..=CLOSE_GROUP_SIZE).contains(&quorum) {
+        return Err(eyre!(
+            "The spend quorum must be between 1 and {CLOSE_GROUP_SIZE}, got {quorum}"
+        ));
+    }
+    Ok(quorum)
+}
+
+/// Parse a spend validation budget in seconds, which can't be zero.
+pub fn parse_spend_validation_budget(val: &str) -> Result<u64> {
+    let budget = val.parse()?;
+    if budget == 0 {
+        return Err(eyre!(
+            "The spend validation budget must be at least 1 second"
+        ));
+    }
+    Ok(budget)
+}
+
 // Please do not remove the blank lines in these doc comments.
 // They are used for inserting line breaks when the help menu is rendered in the UI.
 #[derive(Parser, Debug)]
@@ -99,6 +147,15 @@ struct Opt {
     #[clap(long, verbatim_doc_comment)]
     root_dir: Option<PathBuf>,
 
+    /// Specify a file holding the node's Ed25519 secret key.
+    ///
+    /// If the file exists, the key is loaded from it and the node keeps the same peer ID across
+    /// restarts. Otherwise a new key is generated and written to the file.
+    ///
+    /// If not provided, the key is stored as "secret-key" inside the node's data directory.
+    #[clap(long, verbatim_doc_comment)]
+    identity_path: Option<PathBuf>,
+
     /// Specify the port to listen on.
     ///
     /// The special value `0` will cause the OS to assign a random port.
@@ -125,6 +182,121 @@ struct Opt {
     /// When this flag is set, we will not filter out local addresses that we observe.
     #[clap(long)]
     local: bool,
+
+    /// Specify the maximum number of spends validated concurrently.
+    ///
+    /// Further spends wait for an ongoing validation to finish, and are rejected if they wait too long.
+    #[clap(long, default_value_t = DEFAULT_MAX_CONCURRENT_SPEND_VALIDATIONS)]
+    max_concurrent_spend_validations: usize,
+
+    /// Time budget in seconds for validating a spend, at least 1.
+    ///
+    /// Validations taking longer are aborted, and the spend rejected.
+    #[clap(long, default_value_t = DEFAULT_SPEND_VALIDATION_BUDGET.as_secs(), value_parser = parse_spend_validation_budget)]
+    spend_validation_budget: u64,
+
+    /// Number of the peers holding a spend which must return it identically for it to be accepted.
+    ///
+    /// Peers returning different spends are a double spend attempt, whatever their number.
+    /// Must be between 1 and the size of the close group.
+    #[clap(long, default_value_t = DEFAULT_SPEND_QUORUM, value_parser = parse_spend_quorum)]
+    spend_quorum: usize,
+
+    /// Specify the maximum number of requests of peers handled concurrently.
+    ///
+    /// Further requests are queued, and handled in turn for each of the peers they come from.
+    #[clap(long, default_value_t = DEFAULT_MAX_CONCURRENT_REQUESTS)]
+    max_concurrent_requests: usize,
+
+    /// Keep track of the peer which published each stored record, for auditing.
+    ///
+    /// Disabled by default for privacy.
+    #[clap(long)]
+    record_origins: bool,
+
+    /// Timeout in seconds for requests to and from other nodes, 30 seconds by default.
+    ///
+    /// It should be longer than the timeouts the node puts on its own requests,
+    /// e.g. the 15 seconds it waits for replicated data, as requests fail once it elapses.
+    #[clap(long, value_parser = |t: &str| -> Result<Duration> { Ok(t.parse().map(Duration::from_secs)?) })]
+    request_timeout: Option<Duration>,
+
+    /// Interval in seconds between rounds of anti-entropy, reconciling the records held with a
+    /// random peer of the close group.
+    ///
+    /// Set to 0 to disable anti-entropy.
+    #[clap(long, default_value_t = DEFAULT_ANTI_ENTROPY_INTERVAL.as_secs())]
+    anti_entropy_interval: u64,
+
+    /// Hex encoded public key of an operator allowed to delete records held by this node.
+    ///
+    /// This can be specified multiple times. Deletions are local to this node only.
+    #[clap(long = "delete-operator", value_parser = |pk: &str| -> Result<PublicKey> { Ok(PublicKey::from_hex(pk)?) })]
+    delete_operators: Vec<PublicKey>,
+
+    /// What to do with incoming records once the node's storage is full.
+    ///
+    /// One of "reject-new", "evict-farthest" (evicting the record farthest from the node's address,
+    /// should the incoming record be closer) or "evict-least-accessed".
+    #[clap(long, default_value_t = EvictionPolicy::RejectNew)]
+    eviction_policy: EvictionPolicy,
+
+    /// PeerId of a peer allowed to connect to this node and send it requests.
+    ///
+    /// This can be specified multiple times. When not specified, the node is open to any peer.
+    #[clap(long = "allow-peer")]
+    allowed_peers: Vec<PeerId>,
+
+    /// Kind of records this node stores, one of "chunk", "spend", "register" or "signed-data".
+    ///
+    /// This can be specified multiple times. When not specified, the node stores records of any kind.
+    #[clap(long = "accept-kind", value_parser = parse_record_kind)]
+    accepted_kinds: Vec<RecordKind>,
+
+    /// Maximum number of records of a kind this node stores, as "<kind>=<count>", e.g.
+    /// "register=400", the kind being one of those of "--accept-kind".
+    ///
+    /// This can be specified multiple times. Kinds without a quota are only limited by the node's
+    /// overall capacity. Once a quota is reached, records of the kind are evicted to make room for
+    /// new ones as per the "--eviction-policy".
+    #[clap(long = "kind-quota", value_parser = parse_kind_quota)]
+    per_kind_quota: Vec<(AddressKind, u64)>,
+
+    /// Sign the responses sent to peers with the node's identity key.
+    ///
+    /// This lets clients verify a response comes from this node, even when relayed, at the
+    /// cost of signing each response.
+    #[clap(long)]
+    sign_responses: bool,
+
+    /// Compress the responses larger than this many bytes, for the peers accepting it.
+    ///
+    /// Responses are compressed independently of the requests, trading some CPU for bandwidth
+    /// on the large responses such as those carrying records. Disabled by default.
+    #[clap(long)]
+    response_compression_threshold: Option<usize>,
+
+    /// How to pick the target of the queries probing the network when it's been inactive.
+    ///
+    /// One of "random", "near-self" (refreshing the peers around the node's own address) or
+    /// "mixed", alternating between both.
+    #[clap(long, default_value_t = ProbeTargetStrategy::Random)]
+    probe_target: ProbeTargetStrategy,
+
+    /// Which peers to propagate the double spends detected by the node to.
+    ///
+    /// One of "close-group" (the peers responsible for the spent dbc), "neighbourhood[:<peers>]"
+    /// (that many of the closest peers to the spent dbc, 20 by default) or "disabled".
+    #[clap(long, default_value_t = PropagationStrategy::CloseGroup)]
+    double_spend_propagation: PropagationStrategy,
+
+    /// Number of the peers in the node's routing table it must be connected to before serving
+    /// requests.
+    ///
+    /// Until then, requests are rejected for their senders to retry them later on, so that a
+    /// node which is still joining the network doesn't serve incomplete results.
+    #[clap(long, default_value_t = 0)]
+    min_peers_before_serving: usize,
 }
 
 #[derive(Debug)]
@@ -143,7 +315,7 @@ fn main() -> Result<()> {
     let mut opt = Opt::parse();
 
     let node_socket_addr = SocketAddr::new(opt.ip, opt.port);
-    let (root_dir, keypair) = get_root_dir_and_keypair(opt.root_dir)?;
+    let (root_dir, keypair) = get_root_dir_and_keypair(opt.root_dir, opt.identity_path)?;
 
     let (log_output_dest, _log_appender_guard) = init_logging(
         opt.log_output_dest,
@@ -200,14 +372,40 @@ fn main() -> Result<()> {
     let rt = Runtime::new()?;
     #[cfg(feature = "metrics")]
     rt.spawn(init_metrics(std::process::id()));
+    let config = NodeConfig {
+        network: NetworkConfig {
+            local: opt.local,
+            record_origins: opt.record_origins,
+            request_timeout: opt.request_timeout,
+            eviction: opt.eviction_policy,
+            peer_allowlist: (!opt.allowed_peers.is_empty())
+                .then(|| opt.allowed_peers.into_iter().collect()),
+            sign_responses: opt.sign_responses,
+            response_compression_threshold: opt.response_compression_threshold,
+            ..Default::default()
+        },
+        max_concurrent_spend_validations: opt.max_concurrent_spend_validations,
+        spend_validation_budget: Duration::from_secs(opt.spend_validation_budget),
+        spend_quorum: opt.spend_quorum,
+        max_concurrent_requests: opt.max_concurrent_requests,
+        anti_entropy_interval: (opt.anti_entropy_interval > 0)
+            .then(|| Duration::from_secs(opt.anti_entropy_interval)),
+        delete_operators: opt.delete_operators,
+        accepted_kinds: (!opt.accepted_kinds.is_empty())
+            .then(|| opt.accepted_kinds.into_iter().collect()),
+        per_kind_quota: opt.per_kind_quota.into_iter().collect(),
+        probe_target: opt.probe_target,
+        double_spend_propagation: opt.double_spend_propagation,
+        min_peers_before_serving: opt.min_peers_before_serving,
+    };
     rt.block_on(start_node(
         keypair,
         node_socket_addr,
         initial_peers,
         opt.rpc,
-        opt.local,
         &log_output_dest,
         root_dir,
+        config,
     ))?;
 
     // actively shut down the runtime
@@ -228,14 +426,14 @@ async fn start_node(
     node_socket_addr: SocketAddr,
     peers: Vec<Multiaddr>,
     rpc: Option<SocketAddr>,
-    local: bool,
     log_output_dest: &str,
     root_dir: PathBuf,
+    config: NodeConfig,
 ) -> Result<()> {
     let started_instant = std::time::Instant::now();
 
     info!("Starting node ...");
-    let running_node = Node::run(keypair, node_socket_addr, peers, local, root_dir).await?;
+    let running_node = Node::run(keypair, node_socket_addr, peers, root_dir, config).await?;
 
     // write the PID to the root dir
     let pid = std::process::id();
@@ -436,9 +634,27 @@ fn get_root_dir(peer_id: PeerId) -> Result<PathBuf> {
     Ok(dir)
 }
 
-/// The keypair is located inside the root directory. At the same time, when no dir is specified,
-/// the dir name is derived from the keypair used in the application: the peer ID is used as the directory name.
-fn get_root_dir_and_keypair(root_dir: Option<PathBuf>) -> Result<(PathBuf, Keypair)> {
+/// The keypair is located inside the root directory, unless an identity path is specified.
+/// At the same time, when no dir is specified, the dir name is derived from the keypair used in
+/// the application: the peer ID is used as the directory name.
+fn get_root_dir_and_keypair(
+    root_dir: Option<PathBuf>,
+    identity_path: Option<PathBuf>,
+) -> Result<(PathBuf, Keypair)> {
+    if let Some(identity_path) = identity_path {
+        if let Some(parent) = identity_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let keypair = keypair_from_path(identity_path)?;
+        let dir = match root_dir {
+            Some(dir) => dir,
+            None => get_root_dir(keypair.public().to_peer_id())?,
+        };
+        std::fs::create_dir_all(&dir)?;
+
+        return Ok((dir, keypair));
+    }
+
     match root_dir {
         Some(dir) => {
             std::fs::create_dir_all(&dir)?;
@@ -500,3 +716,28 @@ fn start_new_node_process() {
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::TempDir;
+
+    #[test]
+    fn identity_path_gives_the_same_peer_id_across_runs() -> Result<()> {
+        let temp_dir = TempDir::new()?;
+        let root_dir = temp_dir.path().join("root");
+        let identity_path = temp_dir.path().join("identity").join("secret-key");
+
+        let (_, first_keypair) =
+            get_root_dir_and_keypair(Some(root_dir.clone()), Some(identity_path.clone()))?;
+        assert!(identity_path.exists());
+
+        let (_, second_keypair) = get_root_dir_and_keypair(Some(root_dir), Some(identity_path))?;
+        assert_eq!(
+            first_keypair.public().to_peer_id(),
+            second_keypair.public().to_peer_id()
+        );
+
+        Ok(())
+    }
+}
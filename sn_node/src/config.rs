@@ -0,0 +1,74 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    ProbeTargetStrategy, PropagationStrategy, DEFAULT_MAX_CONCURRENT_REQUESTS,
+    DEFAULT_MAX_CONCURRENT_SPEND_VALIDATIONS, DEFAULT_SPEND_QUORUM,
+    DEFAULT_SPEND_VALIDATION_BUDGET,
+};
+use bls::PublicKey;
+use sn_networking::NetworkConfig;
+use sn_protocol::{storage::RecordKind, AddressKind};
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
+
+/// The default interval between rounds of anti-entropy.
+pub const DEFAULT_ANTI_ENTROPY_INTERVAL: Duration = Duration::from_secs(600);
+
+/// The settings of a node, see `Node::run`.
+#[derive(Debug, Clone)]
+pub struct NodeConfig {
+    /// The settings of the node's networking.
+    pub network: NetworkConfig,
+    /// The maximum number of spends validated at once.
+    pub max_concurrent_spend_validations: usize,
+    /// How long validating a spend may take before it's aborted. It can't be zero.
+    pub spend_validation_budget: Duration,
+    /// The number of the peers holding a spend which must return it identically for it to be
+    /// accepted, within `1..=CLOSE_GROUP_SIZE`.
+    pub spend_quorum: usize,
+    /// The maximum number of requests of peers handled at once.
+    pub max_concurrent_requests: usize,
+    /// The interval between rounds of anti-entropy, which is disabled if not set.
+    pub anti_entropy_interval: Option<Duration>,
+    /// Keys of the operators allowed to delete records from the node's storage.
+    pub delete_operators: Vec<PublicKey>,
+    /// When set, only records of these kinds are stored by the node, see `Node::run`.
+    pub accepted_kinds: Option<HashSet<RecordKind>>,
+    /// The maximum number of records stored by the node at each of these kinds of address, see
+    /// `Node::run`.
+    pub per_kind_quota: HashMap<AddressKind, u64>,
+    /// How to pick the targets of the queries probing the network when it's been inactive.
+    pub probe_target: ProbeTargetStrategy,
+    /// Which peers the double spends detected by the node are propagated to.
+    pub double_spend_propagation: PropagationStrategy,
+    /// The number of the peers in the routing table the node must be connected to before
+    /// serving requests.
+    pub min_peers_before_serving: usize,
+}
+
+impl Default for NodeConfig {
+    fn default() -> Self {
+        Self {
+            network: NetworkConfig::default(),
+            max_concurrent_spend_validations: DEFAULT_MAX_CONCURRENT_SPEND_VALIDATIONS,
+            spend_validation_budget: DEFAULT_SPEND_VALIDATION_BUDGET,
+            spend_quorum: DEFAULT_SPEND_QUORUM,
+            max_concurrent_requests: DEFAULT_MAX_CONCURRENT_REQUESTS,
+            anti_entropy_interval: Some(DEFAULT_ANTI_ENTROPY_INTERVAL),
+            delete_operators: vec![],
+            accepted_kinds: None,
+            per_kind_quota: HashMap::new(),
+            probe_target: ProbeTargetStrategy::default(),
+            double_spend_propagation: PropagationStrategy::default(),
+            min_peers_before_serving: 0,
+        }
+    }
+}
@@ -0,0 +1,195 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{Node, NodeEvent};
+use bls::{PublicKey, Signature};
+use sn_protocol::{error::Error as ProtocolError, messages::Cmd, NetworkAddress};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+impl Node {
+    /// Remove the record at `address` from our local storage, as requested by an operator.
+    /// The operator must be one of the operators we authorized, and have signed the deletion of
+    /// the address by us, which must not have expired.
+    pub(crate) async fn delete_record(
+        &self,
+        address: NetworkAddress,
+        expires_at: u64,
+        operator: PublicKey,
+        signature: &Signature,
+    ) -> Result<(), ProtocolError> {
+        verify_delete_record(
+            &address,
+            expires_at,
+            &operator,
+            signature,
+            &NetworkAddress::from_peer(self.network.peer_id),
+            &self.delete_operators,
+        )?;
+
+        let was_held = self
+            .network
+            .remove_local_record(&address.to_record_key())
+            .await
+            .map_err(|err| {
+                warn!("Error while deleting the record at {address:?}: {err}");
+                ProtocolError::RecordNotDeleted(Box::new(address.clone()))
+            })?;
+
+        if was_held {
+            info!("Deleted the record at {address:?} as requested by operator {operator:?}");
+            self.events_channel
+                .broadcast(NodeEvent::RecordDeleted(address));
+        } else {
+            debug!("No record held at {address:?} to be deleted");
+        }
+
+        Ok(())
+    }
+}
+
+// Check the operator is among the authorized `operators`, and signed the deletion of the `address`
+// by us, at `our_address`, which hasn't expired.
+fn verify_delete_record(
+    address: &NetworkAddress,
+    expires_at: u64,
+    operator: &PublicKey,
+    signature: &Signature,
+    our_address: &NetworkAddress,
+    operators: &[PublicKey],
+) -> Result<(), ProtocolError> {
+    let bytes = Cmd::delete_record_bytes_for_signing(address, our_address, expires_at);
+    if !operators.contains(operator) || !operator.verify(signature, bytes) {
+        warn!("Rejecting the deletion of {address:?} by unauthorized operator {operator:?}");
+        return Err(ProtocolError::DeleteNotAuthorized(*operator));
+    }
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    if now >= expires_at {
+        warn!(
+            "Rejecting the deletion of {address:?} by {operator:?}, which expired at {expires_at}"
+        );
+        return Err(ProtocolError::DeleteExpired);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bls::SecretKey;
+    use libp2p::PeerId;
+    use sn_protocol::storage::ChunkAddress;
+    use xor_name::XorName;
+
+    fn random_address() -> NetworkAddress {
+        NetworkAddress::from_chunk_address(ChunkAddress::new(XorName::random(
+            &mut rand::thread_rng(),
+        )))
+    }
+
+    // The time `secs` seconds from now, in seconds since the UNIX epoch.
+    fn in_secs(secs: i64) -> u64 {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("now is past the epoch")
+            .as_secs();
+        now.saturating_add_signed(secs)
+    }
+
+    fn verify(
+        cmd: &Cmd,
+        our_address: &NetworkAddress,
+        operators: &[PublicKey],
+    ) -> Result<(), ProtocolError> {
+        match cmd {
+            Cmd::DeleteRecord {
+                address,
+                expires_at,
+                operator,
+                signature,
+            } => verify_delete_record(
+                address,
+                *expires_at,
+                operator,
+                signature,
+                our_address,
+                operators,
+            ),
+            other => panic!("Unexpected cmd {other:?}"),
+        }
+    }
+
+    #[test]
+    fn authorized_operator_can_delete_records() {
+        let operator_sk = SecretKey::random();
+        let operators = vec![SecretKey::random().public_key(), operator_sk.public_key()];
+        let node = NetworkAddress::from_peer(PeerId::random());
+
+        let cmd = Cmd::new_delete_record(random_address(), &node, in_secs(60), &operator_sk);
+        assert_eq!(verify(&cmd, &node, &operators), Ok(()));
+    }
+
+    #[test]
+    fn unauthorized_deletions_are_rejected() {
+        let operator_sk = SecretKey::random();
+        let operators = vec![operator_sk.public_key()];
+        let node = NetworkAddress::from_peer(PeerId::random());
+        let unauthorized = Err(ProtocolError::DeleteNotAuthorized(operator_sk.public_key()));
+
+        // an operator we didn't authorize
+        let intruder_sk = SecretKey::random();
+        let cmd = Cmd::new_delete_record(random_address(), &node, in_secs(60), &intruder_sk);
+        assert_eq!(
+            verify(&cmd, &node, &operators),
+            Err(ProtocolError::DeleteNotAuthorized(intruder_sk.public_key()))
+        );
+
+        // an authorized operator's signature replayed for another address, or a later expiry
+        let signed = Cmd::new_delete_record(random_address(), &node, in_secs(60), &operator_sk);
+        let replay = |address, expires_at| match signed.clone() {
+            Cmd::DeleteRecord {
+                operator,
+                signature,
+                ..
+            } => Cmd::DeleteRecord {
+                address,
+                expires_at,
+                operator,
+                signature,
+            },
+            other => panic!("Unexpected cmd {other:?}"),
+        };
+        assert_eq!(
+            verify(&replay(random_address(), in_secs(60)), &node, &operators),
+            unauthorized
+        );
+        assert_eq!(
+            verify(&replay(signed.dst(), in_secs(3600)), &node, &operators),
+            unauthorized
+        );
+
+        // or sent to another node than the one it's signed for
+        let other_node = NetworkAddress::from_peer(PeerId::random());
+        assert_eq!(verify(&signed, &other_node, &operators), unauthorized);
+    }
+
+    #[test]
+    fn expired_deletions_are_rejected() {
+        let operator_sk = SecretKey::random();
+        let operators = vec![operator_sk.public_key()];
+        let node = NetworkAddress::from_peer(PeerId::random());
+
+        let cmd = Cmd::new_delete_record(random_address(), &node, in_secs(-1), &operator_sk);
+        assert_eq!(
+            verify(&cmd, &node, &operators),
+            Err(ProtocolError::DeleteExpired)
+        );
+    }
+}
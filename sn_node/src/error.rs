@@ -28,4 +28,13 @@ pub enum Error {
 
     #[error("Failed to parse NodeEvent")]
     NodeEventParsingFailed,
+
+    #[error(
+        "The spend quorum must be between 1 and {}, got {0}",
+        sn_networking::CLOSE_GROUP_SIZE
+    )]
+    InvalidSpendQuorum(usize),
+
+    #[error("The spend validation budget must not be zero")]
+    InvalidSpendValidationBudget,
 }
@@ -9,7 +9,11 @@
 use crate::error::{Error, Result};
 use serde::{Deserialize, Serialize};
 use sn_dbc::DbcId;
-use sn_protocol::storage::{ChunkAddress, RegisterAddress};
+use sn_protocol::{
+    storage::{ChunkAddress, DbcAddress, RegisterAddress},
+    NetworkAddress,
+};
+use std::time::Duration;
 use tokio::sync::broadcast;
 
 const NODE_EVENT_CHANNEL_SIZE: usize = 10_000;
@@ -59,6 +63,61 @@ pub enum NodeEvent {
     ChannelClosed,
     /// AutoNAT discovered we are behind a NAT, thus private.
     BehindNat,
+    /// Records have been migrated to a peer which has become responsible for them.
+    RecordsMigrated {
+        /// The peer the records were migrated to
+        peer: NetworkAddress,
+        /// The number of records migrated
+        count: usize,
+    },
+    /// We learnt of a double spend of the DBC at this address through replication,
+    /// and now hold the proof of it.
+    DoubleSpendLearned(DbcAddress),
+    /// We detected a double spend of the DBC at this address, whose proof we now hold and
+    /// propagate.
+    DoubleSpendDetected {
+        /// The address of the DBC double spent
+        address: DbcAddress,
+    },
+    /// We propagated the proof of a double spend of the DBC at this address, which that many of
+    /// the peers targeted acknowledged.
+    DoubleSpendPropagated {
+        /// The address of the DBC double spent
+        address: DbcAddress,
+        /// The number of peers which acknowledged storing the proof
+        acked: usize,
+        /// The number of peers the proof was propagated to
+        targeted: usize,
+    },
+    /// A record has been deleted from local storage, as requested by an operator.
+    RecordDeleted(NetworkAddress),
+    /// A round of anti-entropy with a peer of our close group has completed.
+    AntiEntropyCompleted {
+        /// The peer the records were reconciled with
+        peer: NetworkAddress,
+        /// The number of records the peer was missing, which were replicated to it
+        sent: usize,
+        /// The number of records we were missing, which are being fetched from the peer
+        received: usize,
+    },
+    /// A request we sent has been cancelled, having awaited its response for too long.
+    RequestCancelled {
+        /// The peer the request was sent to
+        peer: NetworkAddress,
+        /// How long the request had been in flight
+        age: Duration,
+    },
+    /// The node is connected to enough peers to serve requests, which it deferred till now.
+    ReadyToServe,
+    /// A lookup of the peers closest to this target, probing the network, has completed.
+    ClosestPeersFound {
+        /// The address the lookup targeted
+        target: NetworkAddress,
+        /// The number of peers found
+        count: usize,
+        /// How long the lookup took, in milliseconds
+        elapsed_ms: u64,
+    },
 }
 
 impl NodeEvent {
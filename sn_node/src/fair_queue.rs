@@ -0,0 +1,175 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use libp2p::PeerId;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+};
+use tokio::sync::{Notify, OwnedSemaphorePermit, Semaphore};
+
+/// The default maximum number of requests a node handles concurrently.
+pub const DEFAULT_MAX_CONCURRENT_REQUESTS: usize = 64;
+
+// The maximum number of requests of a single peer awaiting a handling slot.
+const MAX_QUEUED_REQUESTS_PER_PEER: usize = 256;
+
+/// Requests awaiting one of a limited number of handling slots. Slots are handed out round-robin
+/// across the peers with requests queued, rather than in arrival order, so that a peer flooding
+/// the node with requests can't starve the others.
+pub(crate) struct FairRequestQueue<T> {
+    queues: Arc<Mutex<PeerQueues<T>>>,
+    queued: Arc<Notify>,
+    slots: Arc<Semaphore>,
+}
+
+// Not derived, as that would require the requests to be `Clone` too.
+impl<T> Clone for FairRequestQueue<T> {
+    fn clone(&self) -> Self {
+        Self {
+            queues: self.queues.clone(),
+            queued: self.queued.clone(),
+            slots: self.slots.clone(),
+        }
+    }
+}
+
+// The requests queued per peer, along with the order in which the peers take their turn.
+struct PeerQueues<T> {
+    requests: HashMap<PeerId, VecDeque<T>>,
+    turns: VecDeque<PeerId>,
+}
+
+impl<T> PeerQueues<T> {
+    fn push(&mut self, peer: PeerId, request: T) -> Result<(), T> {
+        let requests = self.requests.entry(peer).or_default();
+        if requests.len() >= MAX_QUEUED_REQUESTS_PER_PEER {
+            return Err(request);
+        }
+        if requests.is_empty() {
+            self.turns.push_back(peer);
+        }
+        requests.push_back(request);
+        Ok(())
+    }
+
+    // Take the oldest request of the peer whose turn it is, the peer then going to the back of
+    // the line if it has more requests queued.
+    fn pop(&mut self) -> Option<(PeerId, T)> {
+        let peer = self.turns.pop_front()?;
+        let requests = self.requests.get_mut(&peer)?;
+        let request = requests.pop_front()?;
+        if requests.is_empty() {
+            let _ = self.requests.remove(&peer);
+        } else {
+            self.turns.push_back(peer);
+        }
+        Some((peer, request))
+    }
+}
+
+impl<T> FairRequestQueue<T> {
+    pub(crate) fn new(max_concurrent: usize) -> Self {
+        Self {
+            queues: Arc::new(Mutex::new(PeerQueues {
+                requests: HashMap::new(),
+                turns: VecDeque::new(),
+            })),
+            queued: Arc::new(Notify::new()),
+            slots: Arc::new(Semaphore::new(max_concurrent.max(1))),
+        }
+    }
+
+    /// Queue a request of the `peer`. The request is given back should the peer already have
+    /// too many requests queued.
+    pub(crate) fn push(&self, peer: PeerId, request: T) -> Result<(), T> {
+        let pushed = match self.queues.lock() {
+            Ok(mut queues) => queues.push(peer, request),
+            Err(_) => {
+                error!("The request queue lock is poisoned");
+                Err(request)
+            }
+        };
+        if pushed.is_ok() {
+            self.queued.notify_one();
+        }
+        pushed
+    }
+
+    /// Wait for a handling slot and a request to handle in it. The slot is released when the
+    /// returned permit is dropped.
+    pub(crate) async fn next(&self) -> Option<(OwnedSemaphorePermit, PeerId, T)> {
+        let permit = self.slots.clone().acquire_owned().await.ok()?;
+        loop {
+            let next = match self.queues.lock() {
+                Ok(mut queues) => queues.pop(),
+                Err(_) => {
+                    error!("The request queue lock is poisoned");
+                    return None;
+                }
+            };
+            match next {
+                Some((peer, request)) => return Some((permit, peer, request)),
+                None => self.queued.notified().await,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn flooding_peer_does_not_starve_others() -> eyre::Result<()> {
+        let queue = FairRequestQueue::new(1);
+        let flooder = PeerId::random();
+        let other = PeerId::random();
+
+        for i in 0..100 {
+            queue
+                .push(flooder, i)
+                .map_err(|_| eyre::eyre!("request {i} not queued"))?;
+        }
+        queue
+            .push(other, 1000)
+            .map_err(|_| eyre::eyre!("request not queued"))?;
+
+        let next = || tokio::time::timeout(Duration::from_secs(1), queue.next());
+
+        let (permit, peer, request) = next().await?.ok_or_else(|| eyre::eyre!("no request"))?;
+        assert_eq!((peer, request), (flooder, 0));
+
+        // the only slot is taken till the first request is handled
+        assert!(next().await.is_err());
+        drop(permit);
+
+        // then the other peer's request is handled next, ahead of the flooder's backlog
+        let (permit, peer, request) = next().await?.ok_or_else(|| eyre::eyre!("no request"))?;
+        assert_eq!((peer, request), (other, 1000));
+        drop(permit);
+
+        let (_permit, peer, request) = next().await?.ok_or_else(|| eyre::eyre!("no request"))?;
+        assert_eq!((peer, request), (flooder, 1));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn requests_beyond_the_per_peer_limit_are_given_back() {
+        let queue = FairRequestQueue::new(1);
+        let flooder = PeerId::random();
+
+        for i in 0..MAX_QUEUED_REQUESTS_PER_PEER {
+            assert_eq!(queue.push(flooder, i), Ok(()));
+        }
+        assert_eq!(queue.push(flooder, 0), Err(0));
+        assert_eq!(queue.push(PeerId::random(), 0), Ok(()));
+    }
+}
@@ -6,15 +6,30 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::Node;
+use crate::{
+    replication::{
+        keys_missing_from, record_key_filter_of, records_within_close_group_of,
+        MAX_RECORD_KEYS_MISSING_PER_RESPONSE,
+    },
+    Node,
+};
+use libp2p::{kad::Record, PeerId};
+use rand::seq::IteratorRandom;
 use sn_dbc::{SignedSpend, Token};
+use sn_networking::{Error as NetworkError, CLOSE_GROUP_SIZE};
 use sn_protocol::{
     error::{Error, Result},
-    messages::ReplicatedData,
-    storage::{try_deserialize_record, ChunkWithPayment, DbcAddress, RecordHeader, RecordKind},
+    messages::{BloomFilter, ReplicatedData},
+    storage::{try_deserialize_record, DbcAddress, RecordEnvelope, RecordHeader, RecordKind},
     NetworkAddress, PrettyPrintRecordKey,
 };
-use sn_registers::SignedRegister;
+use sn_registers::{EntryHash, RegisterAddress, RegisterOp, RegisterVersion, SignedRegister};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+/// The number of the peers holding a spend which must return it identically for it to be
+/// accepted, by default. One short of the close group, for a single peer being offline or slow
+/// not to fail the validation of valid spends.
+pub const DEFAULT_SPEND_QUORUM: usize = CLOSE_GROUP_SIZE - 1;
 
 impl Node {
     /// Get the current storecost in nanos from our local kademlia store
@@ -35,11 +50,28 @@ impl Node {
         re_attempt: bool,
     ) -> Result<SignedSpend> {
         let key = NetworkAddress::from_dbc_address(address).to_record_key();
-        let record = self
+        let record = match self
             .network
-            .get_record_from_network(key, None, re_attempt)
+            .get_record_with_quorum(key, self.spend_quorum, re_attempt)
             .await
-            .map_err(|_| Error::SpendNotFound(address))?;
+        {
+            Ok(record) => record,
+            Err(NetworkError::SplitRecord(copies)) => {
+                let variants = spend_variants(address, &copies);
+                return match variants.as_slice() {
+                    [(spend, count)] if *count >= self.spend_quorum => {
+                        trace!("Spend get for address: {address:?} successful despite a split");
+                        Ok(spend.clone())
+                    }
+                    [] | [_] => Err(Error::SpendNotFound(address)),
+                    _ => {
+                        error!("Peers disagree on the spend at {address:?}: {variants:?}");
+                        Err(Error::SpendDivergence { address, variants })
+                    }
+                };
+            }
+            Err(_) => return Err(Error::SpendNotFound(address)),
+        };
         debug!(
             "Got record from the network, {:?}",
             PrettyPrintRecordKey::from(record.key.clone())
@@ -48,10 +80,9 @@ impl Node {
             RecordHeader::from_record(&record).map_err(|_| Error::SpendNotFound(address))?;
 
         if let RecordKind::DbcSpend = header.kind {
-            match try_deserialize_record::<Vec<SignedSpend>>(&record)
-                .map_err(|_| Error::SpendNotFound(address))?
-                .as_slice()
-            {
+            let spends = try_deserialize_record::<Vec<SignedSpend>>(&record)
+                .map_err(|_| Error::SpendNotFound(address))?;
+            match verified_spends(address, spends).as_slice() {
                 [one, two, ..] => {
                     error!("Found double spend for {address:?}");
                     Err(Error::DoubleSpendAttempt(
@@ -89,30 +120,253 @@ impl Node {
             .get_record_from_network(record_key, None, false)
             .await
             .map_err(|_| error.clone())?;
+        let data = RecordEnvelope::try_from_record(&record)
+            .and_then(|envelope| envelope.try_into_data())
+            .map_err(|_| error)?;
+        trace!("Replicating {:?} data at {address:?}", data.record_kind());
+
+        Ok(data)
+    }
+
+    /// Get the ops of the Register held in our local RecordStore which are missing from a
+    /// replica whose current entries are `known_heads`.
+    pub(crate) async fn get_register_delta(
+        &self,
+        address: RegisterAddress,
+        known_heads: BTreeSet<EntryHash>,
+    ) -> Result<BTreeSet<RegisterOp>> {
+        let register = self.get_local_signed_register(address).await?;
+        let ops = register.ops_unknown_to(&known_heads);
+        trace!("Register {address:?} delta has {} ops", ops.len());
+        Ok(ops)
+    }
+
+    /// Get the `RegisterVersion` of our replica of the Register at `address`.
+    pub(crate) async fn get_register_version(
+        &self,
+        address: RegisterAddress,
+    ) -> Result<RegisterVersion> {
+        let register = self.get_local_signed_register(address).await?;
+        let version = register.version();
+        trace!("Register {address:?} is at version {version:?}");
+        Ok(version)
+    }
+
+    /// Get the `SignedRegister` held in our local RecordStore at `address`.
+    async fn get_local_signed_register(&self, address: RegisterAddress) -> Result<SignedRegister> {
+        let error = Error::RegisterNotFound(Box::new(address));
+        let key = NetworkAddress::from_register_address(address).to_record_key();
+        let record = self
+            .network
+            .get_local_record(&key)
+            .await
+            .map_err(|_| error.clone())?
+            .ok_or(error.clone())?;
         let header = RecordHeader::from_record(&record).map_err(|_| error.clone())?;
+        if !matches!(header.kind, RecordKind::Register) {
+            return Err(Error::RecordKindMismatch(RecordKind::Register));
+        }
+
+        try_deserialize_record::<SignedRegister>(&record).map_err(|_| error)
+    }
+
+    /// Check whether we hold the record at `address` in our local RecordStore.
+    pub(crate) async fn has_record(&self, address: &NetworkAddress) -> bool {
+        let key = address.to_record_key();
+        match self.network.is_key_present_locally(&key).await {
+            Ok(present) => present,
+            Err(err) => {
+                warn!("Could not check the presence of record {address:?}: {err:?}");
+                false
+            }
+        }
+    }
+
+    /// Build a `BloomFilter` of the record keys held in our local RecordStore.
+    pub(crate) async fn get_record_key_filter(&self) -> Result<BloomFilter> {
+        let addresses = self
+            .network
+            .get_all_local_record_addresses()
+            .await
+            .map_err(|_| Error::RecordKeyFilterFailed)?;
+
+        trace!(
+            "Building record key filter over {} local keys",
+            addresses.len()
+        );
+        Ok(record_key_filter_of(&addresses))
+    }
+
+    /// Get the keys of the records held in our local RecordStore which are missing from `filter`,
+    /// and which have the `requester` within their close group as per our view of the network.
+    /// At most `MAX_RECORD_KEYS_MISSING_PER_RESPONSE` of them are returned, picked at random, for
+    /// the keys the requester declines to fetch not to hold back the others.
+    pub(crate) async fn get_record_keys_missing_from(
+        &self,
+        requester: PeerId,
+        filter: &BloomFilter,
+    ) -> Result<Vec<NetworkAddress>> {
+        let addresses = self
+            .network
+            .get_all_local_record_addresses()
+            .await
+            .map_err(|_| Error::RecordKeyFilterFailed)?;
+        let mut all_peers = self
+            .network
+            .get_all_local_peers()
+            .await
+            .map_err(|_| Error::RecordKeyFilterFailed)?;
+        // the requester may not be in our routing table yet
+        if !all_peers.contains(&requester) {
+            all_peers.push(requester);
+        }
 
-        match header.kind {
-            RecordKind::Chunk => {
-                let chunk_with_payment =
-                    try_deserialize_record::<ChunkWithPayment>(&record).map_err(|_| error)?;
-                trace!(
-                    "Replicating chunk with address {:?}",
-                    chunk_with_payment.chunk.address()
-                );
+        let missing = keys_missing_from(addresses, filter);
+        let missing = records_within_close_group_of(missing, all_peers, requester)
+            .map_err(|_| Error::RecordKeyFilterFailed)?;
+        trace!(
+            "{} local keys are missing from the filter of {requester:?}",
+            missing.len()
+        );
+        Ok(missing.into_iter().choose_multiple(
+            &mut rand::thread_rng(),
+            MAX_RECORD_KEYS_MISSING_PER_RESPONSE,
+        ))
+    }
+}
 
-                Ok(ReplicatedData::Chunk(chunk_with_payment))
+// The `spends` which are validly signed spends of the dbc at the `address`, in their order,
+// the others having been forged by whoever returned them.
+fn verified_spends(address: DbcAddress, spends: Vec<SignedSpend>) -> Vec<SignedSpend> {
+    spends
+        .into_iter()
+        .filter(|spend| {
+            let verified = DbcAddress::from_dbc_id(spend.dbc_id()) == address
+                && spend.verify(spend.spent_tx_hash()).is_ok();
+            if !verified {
+                warn!("Ignoring a spend at {address:?} which failed verification: {spend:?}");
             }
+            verified
+        })
+        .collect()
+}
 
-            RecordKind::DbcSpend => {
-                let spends =
-                    try_deserialize_record::<Vec<SignedSpend>>(&record).map_err(|_| error)?;
-                Ok(ReplicatedData::DbcSpend(spends))
+// The distinct verified spends at the `address` held by the diverging `copies` of its record,
+// each along with the number of peers which returned it, the most returned first.
+fn spend_variants(
+    address: DbcAddress,
+    copies: &[(Record, HashSet<PeerId>)],
+) -> Vec<(SignedSpend, usize)> {
+    let mut peer_spends: HashMap<PeerId, Vec<SignedSpend>> = HashMap::new();
+    for (copy, peers) in copies {
+        match try_deserialize_record::<Vec<SignedSpend>>(copy) {
+            Ok(spends) => {
+                let spends = verified_spends(address, spends);
+                for peer in peers {
+                    let peer_spends = peer_spends.entry(*peer).or_default();
+                    for spend in &spends {
+                        if !peer_spends.contains(spend) {
+                            peer_spends.push(spend.clone());
+                        }
+                    }
+                }
             }
-            RecordKind::Register => {
-                let register =
-                    try_deserialize_record::<SignedRegister>(&record).map_err(|_| error)?;
-                Ok(ReplicatedData::Register(register))
+            Err(err) => warn!("Ignoring a copy of a spend which failed to deserialise: {err:?}"),
+        }
+    }
+
+    let mut variants: Vec<(SignedSpend, usize)> = vec![];
+    for (peer, spends) in peer_spends {
+        debug!("Peer {peer:?} returned the spends {spends:?}");
+        for spend in spends {
+            match variants.iter_mut().find(|(variant, _)| *variant == spend) {
+                Some((_, count)) => *count += 1,
+                None => variants.push((spend, 1)),
             }
         }
     }
+    // the most returned first, ties being broken by the order of the spends
+    variants.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then_with(|| a.cmp(b)));
+    variants
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sn_protocol::{storage::try_serialize_record, test_utils::spend_of};
+
+    fn copy_of(spends: &[SignedSpend]) -> eyre::Result<Record> {
+        let address = DbcAddress::from_dbc_id(spends[0].dbc_id());
+        Ok(Record::new(
+            NetworkAddress::from_dbc_address(address).to_record_key(),
+            try_serialize_record(&spends, RecordKind::DbcSpend)?,
+        ))
+    }
+
+    #[test]
+    fn peers_disagreeing_on_a_spend_are_enumerated_by_variant() -> eyre::Result<()> {
+        let derived_sk = bls::SecretKey::random();
+        let spend_a = spend_of(&derived_sk, b"a");
+        let spend_b = spend_of(&derived_sk, b"b");
+        let address = DbcAddress::from_dbc_id(spend_a.dbc_id());
+        let peers = |count| (0..count).map(|_| PeerId::random()).collect::<HashSet<_>>();
+
+        // two peers return spend A, and two spend B
+        let copies = [
+            (copy_of(std::slice::from_ref(&spend_a))?, peers(2)),
+            (copy_of(std::slice::from_ref(&spend_b))?, peers(2)),
+        ];
+        let mut expected = vec![(spend_a.clone(), 2), (spend_b.clone(), 2)];
+        expected.sort();
+        assert_eq!(spend_variants(address, &copies), expected);
+        // whatever the order of the copies
+        let reversed = [copies[1].clone(), copies[0].clone()];
+        assert_eq!(spend_variants(address, &reversed), expected);
+
+        // peers holding the proof of the double spend back both spends
+        let copies = [
+            (copy_of(std::slice::from_ref(&spend_a))?, peers(3)),
+            (copy_of(&[spend_a.clone(), spend_b.clone()])?, peers(1)),
+        ];
+        assert_eq!(
+            spend_variants(address, &copies),
+            vec![(spend_a.clone(), 4), (spend_b, 1)]
+        );
+
+        // copies diverging otherwise than by their spends hold a single variant
+        let copies = [
+            (copy_of(std::slice::from_ref(&spend_a))?, peers(3)),
+            (copy_of(&[spend_a.clone(), spend_a.clone()])?, peers(1)),
+        ];
+        assert_eq!(spend_variants(address, &copies).len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn forged_spends_returned_by_peers_are_not_variants() -> eyre::Result<()> {
+        let derived_sk = bls::SecretKey::random();
+        let spend = spend_of(&derived_sk, b"a");
+        let address = DbcAddress::from_dbc_id(spend.dbc_id());
+        let peers = |count| (0..count).map(|_| PeerId::random()).collect::<HashSet<_>>();
+
+        // a spend signed by another key than that of the dbc
+        let mut forged = spend_of(&derived_sk, b"b");
+        forged.derived_key_sig = bls::SecretKey::random().sign(forged.spend.to_bytes());
+        // a validly signed spend of another dbc
+        let other = spend_of(&bls::SecretKey::random(), b"c");
+
+        let copies = [
+            (copy_of(std::slice::from_ref(&spend))?, peers(3)),
+            (copy_of(std::slice::from_ref(&forged))?, peers(1)),
+            (copy_of(&[spend.clone(), other.clone()])?, peers(1)),
+        ];
+        assert_eq!(spend_variants(address, &copies), vec![(spend.clone(), 4)]);
+        assert_eq!(
+            verified_spends(address, vec![forged, spend.clone(), other]),
+            vec![spend]
+        );
+
+        Ok(())
+    }
 }
@@ -44,22 +44,44 @@
 extern crate tracing;
 
 mod api;
+mod config;
+mod delete_validation;
 mod error;
 mod event;
+mod fair_queue;
 mod get_validation;
 mod log_markers;
+mod merge_stats;
+mod peer_allowlist;
+mod probe_target;
+mod propagation;
 mod put_validation;
+mod readiness;
 mod replication;
-mod spends;
 
 pub use self::{
     api::RunningNode,
+    config::{NodeConfig, DEFAULT_ANTI_ENTROPY_INTERVAL},
     event::{NodeEvent, NodeEventsChannel, NodeEventsReceiver},
+    fair_queue::DEFAULT_MAX_CONCURRENT_REQUESTS,
+    get_validation::DEFAULT_SPEND_QUORUM,
     log_markers::Marker,
+    merge_stats::MergeStats,
+    probe_target::ProbeTargetStrategy,
+    propagation::PropagationStrategy,
+    put_validation::{DEFAULT_MAX_CONCURRENT_SPEND_VALIDATIONS, DEFAULT_SPEND_VALIDATION_BUDGET},
+    replication::{ReplicationReport, ReplicationStatus},
 };
 
-use libp2p::Multiaddr;
-use sn_networking::Network;
+use bls::PublicKey;
+use fair_queue::FairRequestQueue;
+use libp2p::{Multiaddr, PeerId};
+use merge_stats::MergeLog;
+use put_validation::SpendValidationPermits;
+use readiness::Readiness;
+use sn_networking::{MsgResponder, Network};
+use sn_protocol::{messages::Request, storage::RecordKind};
+use std::{collections::HashSet, sync::Arc};
 
 /// `Node` represents a single node in the distributed network. It handles
 /// network events, processes incoming requests, interacts with the data
@@ -70,4 +92,23 @@ pub struct Node {
     events_channel: NodeEventsChannel,
     /// Peers that are dialed at startup of node.
     initial_peers: Vec<Multiaddr>,
+    /// Limits the number of spends being validated at once, and the time spent validating each.
+    spend_validation_permits: SpendValidationPermits,
+    /// The number of the peers holding a spend which must return it identically for it to be
+    /// accepted.
+    spend_quorum: usize,
+    /// Keys of the operators allowed to delete records from our local storage.
+    delete_operators: Arc<Vec<PublicKey>>,
+    /// When set, only these peers have their requests served.
+    peer_allowlist: Arc<Option<HashSet<PeerId>>>,
+    /// When set, only records of these kinds are stored.
+    accepted_kinds: Arc<Option<HashSet<RecordKind>>>,
+    /// Requests of peers awaiting to be handled, fairly across the peers.
+    request_queue: FairRequestQueue<(Request, MsgResponder)>,
+    /// Which peers the double spends we detect are propagated to.
+    double_spend_propagation: PropagationStrategy,
+    /// Whether we're connected to enough peers to serve requests.
+    readiness: Readiness,
+    /// The most expensive merges of the Registers we store.
+    merge_log: MergeLog,
 }
@@ -0,0 +1,177 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use sn_registers::{RegisterAddress, SignedRegister};
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+// Merges taking longer than this are logged as expensive.
+const EXPENSIVE_MERGE_DURATION: Duration = Duration::from_millis(100);
+// Merges leaving more branches than this are logged as expensive.
+const EXPENSIVE_MERGE_BRANCHES: usize = 64;
+// Number of Registers whose merges are kept track of, the cheapest being dropped first.
+const MAX_TRACKED_REGISTERS: usize = 128;
+
+/// How expensive merging a replica of a Register into ours was.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MergeStats {
+    /// How long the merge took, the verification of the merged replica included
+    pub duration: Duration,
+    /// Number of branches of the Register once merged
+    pub branches: usize,
+}
+
+/// Merge the `incoming` replica into the `local` one, verifying it, and return how expensive
+/// the merge was.
+pub(crate) fn timed_merge(
+    local: &mut SignedRegister,
+    incoming: SignedRegister,
+) -> Result<MergeStats, sn_registers::Error> {
+    let start = Instant::now();
+    local.verified_merge(incoming)?;
+    let branches = local.version().head_count;
+    Ok(MergeStats {
+        duration: start.elapsed(),
+        branches,
+    })
+}
+
+/// The most expensive merge of each of the Registers merged by the node, for operators to spot
+/// abusive or buggy Registers.
+#[derive(Clone, Default)]
+pub(crate) struct MergeLog {
+    merges: Arc<Mutex<HashMap<RegisterAddress, MergeStats>>>,
+}
+
+impl MergeLog {
+    /// Record a merge of the Register at `address`, logging it if expensive.
+    pub(crate) fn record(&self, address: RegisterAddress, stats: MergeStats) {
+        if stats.duration > EXPENSIVE_MERGE_DURATION || stats.branches > EXPENSIVE_MERGE_BRANCHES {
+            warn!(
+                "Expensive merge of Register {address:?}: took {:?}, leaving {} branches",
+                stats.duration, stats.branches
+            );
+        }
+
+        let mut merges = match self.merges.lock() {
+            Ok(merges) => merges,
+            Err(_) => {
+                error!("The merge log lock is poisoned");
+                return;
+            }
+        };
+        let worst = merges.entry(address).or_insert(stats);
+        if stats.duration > worst.duration {
+            *worst = stats;
+        }
+        if merges.len() > MAX_TRACKED_REGISTERS {
+            let cheapest = merges
+                .iter()
+                .min_by_key(|(_, stats)| stats.duration)
+                .map(|(address, _)| *address);
+            if let Some(cheapest) = cheapest {
+                let _ = merges.remove(&cheapest);
+            }
+        }
+    }
+
+    /// The `n` Registers whose merges took the longest, slowest first, along with their most
+    /// expensive merge.
+    pub(crate) fn most_expensive(&self, n: usize) -> Vec<(RegisterAddress, MergeStats)> {
+        let mut merges: Vec<_> = match self.merges.lock() {
+            Ok(merges) => merges
+                .iter()
+                .map(|(address, stats)| (*address, *stats))
+                .collect(),
+            Err(_) => {
+                error!("The merge log lock is poisoned");
+                return vec![];
+            }
+        };
+        merges.sort_by_key(|(_, stats)| Reverse(stats.duration));
+        merges.truncate(n);
+        merges
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bls::SecretKey;
+    use eyre::Result;
+    use sn_registers::{Permissions, Register};
+    use std::collections::BTreeSet;
+    use xor_name::XorName;
+
+    // A replica of a new Register of the owner, and one with `branches` entries written
+    // concurrently to it.
+    fn register_with_branches(
+        owner_sk: &SecretKey,
+        branches: usize,
+    ) -> Result<(SignedRegister, SignedRegister)> {
+        let mut register = Register::new(
+            owner_sk.public_key(),
+            XorName::random(&mut rand::thread_rng()),
+            Permissions::new_owner_only(),
+            None,
+        );
+        let empty = register.clone().into_signed(owner_sk)?;
+        let mut branched = empty.clone();
+        for i in 0..branches {
+            let (_, mut op) =
+                register.write(format!("branch {i}").into_bytes(), BTreeSet::new())?;
+            op.sign_with(owner_sk);
+            branched.add_op(op)?;
+        }
+        Ok((empty, branched))
+    }
+
+    #[test]
+    fn merging_a_register_of_many_branches_is_flagged_as_expensive() -> Result<()> {
+        let owner_sk = SecretKey::random();
+        let log = MergeLog::default();
+
+        let (mut local, incoming) = register_with_branches(&owner_sk, 1)?;
+        let cheap = timed_merge(&mut local, incoming)?;
+        assert_eq!(cheap.branches, 1);
+        log.record(*local.address(), cheap);
+
+        let (mut local, incoming) = register_with_branches(&owner_sk, 200)?;
+        let expensive = timed_merge(&mut local, incoming)?;
+        assert_eq!(expensive.branches, 200);
+        log.record(*local.address(), expensive);
+
+        let most_expensive = log.most_expensive(1);
+        assert_eq!(most_expensive, vec![(*local.address(), expensive)]);
+        assert_eq!(log.most_expensive(10).len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn only_the_most_expensive_merge_of_a_register_is_kept() {
+        let log = MergeLog::default();
+        let address = RegisterAddress::new(
+            XorName::random(&mut rand::thread_rng()),
+            SecretKey::random().public_key(),
+        );
+        let stats = |millis, branches| MergeStats {
+            duration: Duration::from_millis(millis),
+            branches,
+        };
+
+        log.record(address, stats(20, 3));
+        log.record(address, stats(50, 7));
+        log.record(address, stats(10, 1));
+        assert_eq!(log.most_expensive(5), vec![(address, stats(50, 7))]);
+    }
+}
@@ -0,0 +1,78 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use libp2p::PeerId;
+use sn_protocol::{
+    error::Error as ProtocolError,
+    messages::{Request, Response},
+    NetworkAddress,
+};
+use std::collections::HashSet;
+
+// The response rejecting the `request` of the `peer`, should we have an `allowlist` it isn't on.
+pub(crate) fn unauthorized_response(
+    request: &Request,
+    peer: PeerId,
+    allowlist: Option<&HashSet<PeerId>>,
+) -> Option<Response> {
+    match allowlist {
+        Some(allowlist) if !allowlist.contains(&peer) => {
+            warn!("Rejecting request {request:?} from peer {peer:?}, not on our allowlist");
+            let peer = Box::new(NetworkAddress::from_peer(peer));
+            Some(request.error_response(ProtocolError::Unauthorized(peer)))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sn_protocol::{
+        messages::{Query, QueryResponse},
+        storage::ChunkAddress,
+    };
+    use xor_name::XorName;
+
+    fn has_record_query() -> Request {
+        Request::Query(Query::HasRecord(NetworkAddress::from_chunk_address(
+            ChunkAddress::new(XorName::random(&mut rand::thread_rng())),
+        )))
+    }
+
+    #[test]
+    fn allowed_peers_requests_are_served() {
+        let peer = PeerId::random();
+        let allowlist: HashSet<_> = [PeerId::random(), peer].into_iter().collect();
+
+        assert_eq!(
+            unauthorized_response(&has_record_query(), peer, Some(&allowlist)),
+            None
+        );
+        // without an allowlist, the node is open to any peer
+        assert_eq!(
+            unauthorized_response(&has_record_query(), PeerId::random(), None),
+            None
+        );
+    }
+
+    #[test]
+    fn requests_of_peers_not_on_the_allowlist_are_rejected() {
+        let allowlist: HashSet<_> = [PeerId::random()].into_iter().collect();
+        let intruder = PeerId::random();
+
+        let expected_error =
+            ProtocolError::Unauthorized(Box::new(NetworkAddress::from_peer(intruder)));
+        assert_eq!(
+            unauthorized_response(&has_record_query(), intruder, Some(&allowlist)),
+            Some(Response::Query(QueryResponse::HasRecord(Err(
+                expected_error
+            ))))
+        );
+    }
+}
@@ -0,0 +1,132 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use libp2p::PeerId;
+use sn_protocol::NetworkAddress;
+use std::{fmt, str::FromStr};
+
+/// How the node picks the target of the `get_closest` queries probing the network.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProbeTargetStrategy {
+    /// Probe a random address, refreshing the routing table across the whole address space.
+    #[default]
+    Random,
+    /// Probe our own address, refreshing the peers around the node's responsibility region.
+    NearSelf,
+    /// Alternate between a random address and our own.
+    Mixed,
+}
+
+impl FromStr for ProbeTargetStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "random" => Ok(Self::Random),
+            "near-self" => Ok(Self::NearSelf),
+            "mixed" => Ok(Self::Mixed),
+            other => Err(format!(
+                "Unknown probe target strategy {other:?}, expected one of random, near-self or mixed"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ProbeTargetStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let strategy = match self {
+            Self::Random => "random",
+            Self::NearSelf => "near-self",
+            Self::Mixed => "mixed",
+        };
+        write!(f, "{strategy}")
+    }
+}
+
+/// Picks the successive probe targets of a node according to its `ProbeTargetStrategy`.
+pub(crate) struct ProbeTargets {
+    strategy: ProbeTargetStrategy,
+    our_peer_id: PeerId,
+    probes: usize,
+}
+
+impl ProbeTargets {
+    pub(crate) fn new(strategy: ProbeTargetStrategy, our_peer_id: PeerId) -> Self {
+        Self {
+            strategy,
+            our_peer_id,
+            probes: 0,
+        }
+    }
+
+    /// The target of the next probe.
+    ///
+    /// Kademlia hashes the targets into its key space, so the closest we can target to
+    /// ourselves is our own address, i.e. the self-lookup returning our close group.
+    pub(crate) fn next_target(&mut self) -> NetworkAddress {
+        let near_self = match self.strategy {
+            ProbeTargetStrategy::Random => false,
+            ProbeTargetStrategy::NearSelf => true,
+            ProbeTargetStrategy::Mixed => self.probes % 2 == 1,
+        };
+        self.probes = self.probes.wrapping_add(1);
+
+        if near_self {
+            NetworkAddress::from_peer(self.our_peer_id)
+        } else {
+            NetworkAddress::from_peer(PeerId::random())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    const PROBES: usize = 20;
+
+    fn targets(strategy: ProbeTargetStrategy, our_peer_id: PeerId) -> Vec<NetworkAddress> {
+        let mut targets = ProbeTargets::new(strategy, our_peer_id);
+        (0..PROBES).map(|_| targets.next_target()).collect()
+    }
+
+    #[test]
+    fn each_strategy_probes_the_expected_targets() {
+        let our_peer_id = PeerId::random();
+        let ourself = NetworkAddress::from_peer(our_peer_id);
+
+        // random targets are spread over the address space
+        let random = targets(ProbeTargetStrategy::Random, our_peer_id);
+        assert!(!random.contains(&ourself));
+        assert_eq!(random.iter().collect::<HashSet<_>>().len(), PROBES);
+
+        let near_self = targets(ProbeTargetStrategy::NearSelf, our_peer_id);
+        assert!(near_self.iter().all(|target| target == &ourself));
+
+        // mixed alternates, starting with a random target
+        let mixed = targets(ProbeTargetStrategy::Mixed, our_peer_id);
+        for (i, target) in mixed.iter().enumerate() {
+            assert_eq!(target == &ourself, i % 2 == 1);
+        }
+        let random_ones: HashSet<_> = mixed.iter().step_by(2).collect();
+        assert_eq!(random_ones.len(), PROBES / 2);
+    }
+
+    #[test]
+    fn strategy_round_trips_through_its_name() {
+        for strategy in [
+            ProbeTargetStrategy::Random,
+            ProbeTargetStrategy::NearSelf,
+            ProbeTargetStrategy::Mixed,
+        ] {
+            assert_eq!(strategy.to_string().parse(), Ok(strategy));
+        }
+        assert!("nearest".parse::<ProbeTargetStrategy>().is_err());
+    }
+}
@@ -0,0 +1,243 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use libp2p::{kad::K_VALUE, PeerId};
+use sn_networking::CLOSE_GROUP_SIZE;
+use sn_protocol::NetworkAddress;
+use std::{collections::HashSet, fmt, future::Future, str::FromStr};
+
+/// The number of times the proof of a double spend is put to the targeted peers which haven't
+/// acknowledged it yet.
+pub(crate) const DOUBLE_SPEND_PROPAGATION_ATTEMPTS: usize = 3;
+
+/// Which peers a node propagates the proof of a double spend it detected to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PropagationStrategy {
+    /// Propagate to the close group of the spent dbc, which is responsible for it.
+    #[default]
+    CloseGroup,
+    /// Propagate to that many of the closest peers to the spent dbc, at least its close group,
+    /// for the double spend to be known beyond the peers responsible for it.
+    Neighbourhood(usize),
+    /// Don't propagate, e.g. for private networks where forks are handled externally.
+    Disabled,
+}
+
+impl FromStr for PropagationStrategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "close-group" => Ok(Self::CloseGroup),
+            None if s == "neighbourhood" => Ok(Self::Neighbourhood(K_VALUE.get())),
+            None if s == "disabled" => Ok(Self::Disabled),
+            Some(("neighbourhood", peers)) => peers
+                .parse()
+                .map(Self::Neighbourhood)
+                .map_err(|err| format!("Invalid neighbourhood size {peers:?}: {err}")),
+            _ => Err(format!(
+                "Unknown propagation strategy {s:?}, expected one of close-group, \
+                 neighbourhood[:<peers>] or disabled"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for PropagationStrategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CloseGroup => write!(f, "close-group"),
+            Self::Neighbourhood(peers) => write!(f, "neighbourhood:{peers}"),
+            Self::Disabled => write!(f, "disabled"),
+        }
+    }
+}
+
+impl PropagationStrategy {
+    /// The peers to propagate a double spend at `address` to, i.e. the closest of the
+    /// `known_peers` to it, ourselves excluded. In a small network, that's all the peers we know.
+    pub(crate) fn targets(
+        &self,
+        known_peers: Vec<PeerId>,
+        address: &NetworkAddress,
+        our_peer_id: PeerId,
+    ) -> Vec<PeerId> {
+        let count = match self {
+            Self::CloseGroup => CLOSE_GROUP_SIZE,
+            Self::Neighbourhood(peers) => (*peers).max(CLOSE_GROUP_SIZE),
+            Self::Disabled => return vec![],
+        };
+        let peers: Vec<NetworkAddress> = known_peers
+            .into_iter()
+            .filter(|peer| *peer != our_peer_id)
+            .map(NetworkAddress::from_peer)
+            .collect();
+        address
+            .closest(&peers, count)
+            .into_iter()
+            .filter_map(NetworkAddress::as_peer_id)
+            .collect()
+    }
+}
+
+// Put the proof of a double spend to the `targets` with `put_to`, which returns the peers which
+// acknowledged it, putting it again to the peers which haven't till `quorum` of the targets have,
+// within `attempts`. Returns the peers which acknowledged it.
+pub(crate) async fn propagate_until_acked<F, Fut>(
+    targets: &[PeerId],
+    quorum: usize,
+    attempts: usize,
+    mut put_to: F,
+) -> HashSet<PeerId>
+where
+    F: FnMut(Vec<PeerId>) -> Fut,
+    Fut: Future<Output = HashSet<PeerId>>,
+{
+    let mut acked = HashSet::new();
+    for attempt in 1..=attempts {
+        let missing: Vec<PeerId> = targets
+            .iter()
+            .filter(|peer| !acked.contains(*peer))
+            .copied()
+            .collect();
+        if acked.len() >= quorum || missing.is_empty() {
+            break;
+        }
+        if attempt > 1 {
+            debug!(
+                "Only {} of {} peers acknowledged the double spend, retrying to the others",
+                acked.len(),
+                targets.len()
+            );
+        }
+        acked.extend(
+            put_to(missing.clone())
+                .await
+                .into_iter()
+                .filter(|peer| missing.contains(peer)),
+        );
+    }
+    acked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sn_protocol::storage::DbcAddress;
+    use xor_name::XorName;
+
+    // Check the `targets` are the `count` closest `candidates` to the `address`.
+    fn assert_closest(
+        targets: &[PeerId],
+        candidates: &[PeerId],
+        address: &NetworkAddress,
+        count: usize,
+    ) {
+        assert_eq!(targets.len(), count.min(candidates.len()));
+        let distance = |peer: &PeerId| address.distance(&NetworkAddress::from_peer(*peer));
+        let farthest_target = targets.iter().map(distance).max();
+        let closest_other = candidates
+            .iter()
+            .filter(|peer| !targets.contains(peer))
+            .map(distance)
+            .min();
+        if let (Some(farthest_target), Some(closest_other)) = (farthest_target, closest_other) {
+            assert!(farthest_target < closest_other);
+        }
+    }
+
+    #[test]
+    fn each_strategy_targets_the_expected_peers() {
+        let our_peer_id = PeerId::random();
+        let others: Vec<PeerId> = (0..40).map(|_| PeerId::random()).collect();
+        let known_peers: Vec<PeerId> = others.iter().copied().chain([our_peer_id]).collect();
+        let address = NetworkAddress::from_dbc_address(DbcAddress::new(XorName::random(
+            &mut rand::thread_rng(),
+        )));
+
+        let targets = |strategy: PropagationStrategy| {
+            let targets = strategy.targets(known_peers.clone(), &address, our_peer_id);
+            assert!(!targets.contains(&our_peer_id));
+            targets
+        };
+
+        let close_group = targets(PropagationStrategy::CloseGroup);
+        assert_closest(&close_group, &others, &address, CLOSE_GROUP_SIZE);
+
+        let neighbourhood = targets(PropagationStrategy::Neighbourhood(20));
+        assert_closest(&neighbourhood, &others, &address, 20);
+
+        // the neighbourhood always covers the close group
+        let small_neighbourhood = targets(PropagationStrategy::Neighbourhood(2));
+        assert_eq!(small_neighbourhood, close_group);
+
+        assert!(targets(PropagationStrategy::Disabled).is_empty());
+
+        // we propagate to the peers we know of, however few
+        let few = &others[..3];
+        let targets = PropagationStrategy::CloseGroup.targets(few.to_vec(), &address, our_peer_id);
+        assert_closest(&targets, few, &address, 3);
+    }
+
+    #[test]
+    fn strategy_round_trips_through_its_name() {
+        for strategy in [
+            PropagationStrategy::CloseGroup,
+            PropagationStrategy::Neighbourhood(24),
+            PropagationStrategy::Disabled,
+        ] {
+            assert_eq!(strategy.to_string().parse(), Ok(strategy));
+        }
+        assert_eq!(
+            "neighbourhood".parse(),
+            Ok(PropagationStrategy::Neighbourhood(K_VALUE.get()))
+        );
+        assert!("neighbourhood:many".parse::<PropagationStrategy>().is_err());
+        assert!("everyone".parse::<PropagationStrategy>().is_err());
+    }
+
+    #[tokio::test]
+    async fn propagation_is_retried_to_the_peers_which_did_not_ack() {
+        let targets: Vec<PeerId> = (0..CLOSE_GROUP_SIZE).map(|_| PeerId::random()).collect();
+        let quorum = CLOSE_GROUP_SIZE;
+        let mut puts = vec![];
+
+        // only the first three peers acknowledge the first put, the others the second one
+        let acked = propagate_until_acked(&targets, quorum, 3, |peers| {
+            puts.push(peers.clone());
+            let acking: HashSet<PeerId> = match puts.len() {
+                1 => peers.into_iter().take(3).collect(),
+                _ => peers.into_iter().collect(),
+            };
+            async move { acking }
+        })
+        .await;
+
+        assert_eq!(acked, targets.iter().copied().collect());
+        assert_eq!(puts.len(), 2);
+        assert_eq!(puts[0], targets);
+        assert_eq!(puts[1], targets[3..]);
+
+        // no further put once the quorum acknowledged it, nor beyond the attempts
+        let mut puts = 0;
+        let acked = propagate_until_acked(&targets, 3, 3, |peers| {
+            puts += 1;
+            async move { peers.into_iter().take(3).collect() }
+        })
+        .await;
+        assert_eq!((acked.len(), puts), (3, 1));
+
+        let mut puts = 0;
+        let acked = propagate_until_acked(&targets, quorum, 3, |_| {
+            puts += 1;
+            async { HashSet::new() }
+        })
+        .await;
+        assert_eq!((acked.len(), puts), (0, 3));
+    }
+}
@@ -7,17 +7,20 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::{
-    spends::{aggregate_spends, check_parent_spends},
-    Node,
+    merge_stats::timed_merge,
+    propagation::{propagate_until_acked, DOUBLE_SPEND_PROPAGATION_ATTEMPTS},
+    Node, NodeEvent, PropagationStrategy,
 };
-use libp2p::kad::Record;
-use sn_dbc::{DbcId, DbcTransaction, Hash, SignedSpend, Token};
+use futures::{stream, StreamExt, TryStreamExt};
+use libp2p::{kad::Record, PeerId};
+use sn_dbc::{DbcId, DbcTransaction, SignedSpend, Token};
+use sn_networking::{close_group_majority, Error as NetworkError};
 use sn_protocol::{
     error::Error as ProtocolError,
-    messages::{CmdOk, MerkleTreeNodesType, PaymentProof},
+    messages::{CmdOk, MerkleTreeNodesType, PaymentProof, RegisterBatch},
     storage::{
         try_deserialize_record, try_serialize_record, ChunkWithPayment, DbcAddress, RecordHeader,
-        RecordKind,
+        RecordKind, SignedData,
     },
     NetworkAddress, PrettyPrintRecordKey,
 };
@@ -25,11 +28,135 @@ use sn_registers::SignedRegister;
 use sn_transfers::{
     dbc_genesis::{is_genesis_parent_tx, GENESIS_DBC},
     payment_proof::validate_payment_proof,
+    spends::{aggregate_spends, check_parent_spends, verify_fee_output_id, verify_spend},
+};
+use std::{
+    collections::{BTreeSet, HashSet},
+    future::Future,
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{
+    sync::{OwnedSemaphorePermit, Semaphore},
+    task::JoinSet,
 };
-use std::collections::{BTreeSet, HashSet};
-use tokio::task::JoinSet;
 use xor_name::XorName;
 
+// Check the node stores records of the given `kind`, i.e. it accepts any kind or `kind` is among
+// the `accepted` ones.
+pub(crate) fn verify_kind_accepted(
+    kind: &RecordKind,
+    accepted: Option<&HashSet<RecordKind>>,
+) -> Result<(), ProtocolError> {
+    match accepted {
+        Some(accepted) if !accepted.contains(kind) => {
+            debug!("Not storing a record of kind {kind}, which this node does not accept");
+            Err(ProtocolError::KindNotAccepted(kind.clone()))
+        }
+        _ => Ok(()),
+    }
+}
+
+// The error returned when storing a record locally failed with `err`: our quota of records of its
+// kind being reached is reported as is, any other failure as `not_stored`.
+fn local_put_error(err: NetworkError, not_stored: ProtocolError) -> ProtocolError {
+    match err {
+        NetworkError::Protocol(err @ ProtocolError::KindQuotaExceeded { .. }) => err,
+        _ => not_stored,
+    }
+}
+
+// The id of the dbc the `signed_spends` are of, which must all be of the same dbc, and be no more
+// than we validate at once.
+pub(crate) fn common_dbc_id(signed_spends: &[SignedSpend]) -> Result<DbcId, ProtocolError> {
+    if signed_spends.len() > MAX_SPENDS_PER_PUT {
+        return Err(ProtocolError::TooManySpends {
+            count: signed_spends.len(),
+            limit: MAX_SPENDS_PER_PUT,
+        });
+    }
+    let (first, others) = match signed_spends.split_first() {
+        Some(split) => split,
+        None => return Err(ProtocolError::SpendIsEmpty),
+    };
+    let dbc_id = *first.dbc_id();
+    if others.iter().any(|spend| spend.dbc_id() != &dbc_id) {
+        return Err(ProtocolError::SpendDbcIdMismatch(DbcAddress::from_dbc_id(
+            &dbc_id,
+        )));
+    }
+    Ok(dbc_id)
+}
+
+/// The default maximum number of spends a node validates concurrently.
+pub const DEFAULT_MAX_CONCURRENT_SPEND_VALIDATIONS: usize = 16;
+
+/// The default time a node spends validating a spend before giving up on it.
+pub const DEFAULT_SPEND_VALIDATION_BUDGET: Duration = Duration::from_secs(60);
+
+// The maximum number of spends of a dbc validated at once, i.e. a double spend proof.
+const MAX_SPENDS_PER_PUT: usize = 2;
+
+// The maximum number of parent spends fetched and checked to validate a spend, i.e. of inputs of
+// the tx the spent dbc was created in, bounding the work a single spend costs us.
+const MAX_SPEND_PARENTS: usize = 256;
+
+// The maximum number of parent spends of a spend fetched from the network concurrently.
+const MAX_CONCURRENT_PARENT_FETCHES: usize = 8;
+
+// How long a spend waits for a validation slot before being rejected as `Overloaded`.
+const SPEND_VALIDATION_QUEUE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Permits for validating spends, which is expensive (it fetches and checks the parent spends),
+/// so that a burst of spends can't starve the cheaper chunk and register operations.
+/// Spends beyond the limit queue for a permit, and are rejected if they can't get one in time.
+/// Validating a spend is bounded by a time `budget` as well, so that a spend with many inputs
+/// can't hold a permit indefinitely.
+#[derive(Clone)]
+pub(crate) struct SpendValidationPermits {
+    semaphore: Arc<Semaphore>,
+    queue_timeout: Duration,
+    budget: Duration,
+}
+
+impl SpendValidationPermits {
+    pub(crate) fn new(max_concurrent: usize, budget: Duration) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(max_concurrent.max(1))),
+            queue_timeout: SPEND_VALIDATION_QUEUE_TIMEOUT,
+            budget,
+        }
+    }
+
+    // Run the `validation`, aborting it should it not complete within our budget.
+    async fn within_budget<T>(
+        &self,
+        validation: impl Future<Output = Result<T, ProtocolError>>,
+    ) -> Result<T, ProtocolError> {
+        match tokio::time::timeout(self.budget, validation).await {
+            Ok(result) => result,
+            Err(_elapsed) => {
+                warn!(
+                    "Aborted a spend validation exceeding our budget of {:?}",
+                    self.budget
+                );
+                Err(ProtocolError::ValidationBudgetExceeded)
+            }
+        }
+    }
+
+    async fn acquire(&self) -> Result<OwnedSemaphorePermit, ProtocolError> {
+        match tokio::time::timeout(self.queue_timeout, self.semaphore.clone().acquire_owned()).await
+        {
+            Ok(Ok(permit)) => Ok(permit),
+            _ => {
+                warn!("Timed out waiting for a spend validation permit");
+                Err(ProtocolError::Overloaded)
+            }
+        }
+    }
+}
+
 impl Node {
     /// Validate and store a record to the RecordStore
     pub(crate) async fn validate_and_store_record(
@@ -38,6 +165,7 @@ impl Node {
         validate_payment: bool,
     ) -> Result<CmdOk, ProtocolError> {
         let record_header = RecordHeader::from_record(&record)?;
+        verify_kind_accepted(&record_header.kind, self.accepted_kinds.as_ref().as_ref())?;
 
         match record_header.kind {
             RecordKind::Chunk => {
@@ -53,8 +181,12 @@ impl Node {
                     return Err(ProtocolError::RecordKeyMismatch);
                 }
 
-                self.validate_and_store_chunk(chunk_with_payment, validate_payment)
-                    .await
+                self.validate_and_store_chunk(
+                    chunk_with_payment,
+                    validate_payment,
+                    record.publisher,
+                )
+                .await
             }
             RecordKind::DbcSpend => {
                 let signed_spends = try_deserialize_record::<Vec<SignedSpend>>(&record)?;
@@ -68,7 +200,8 @@ impl Node {
                     return Err(ProtocolError::RecordKeyMismatch);
                 }
 
-                self.validate_and_store_spends(signed_spends).await
+                self.validate_and_store_spends(signed_spends, record.publisher)
+                    .await
             }
             RecordKind::Register => {
                 let register = try_deserialize_record::<SignedRegister>(&record)?;
@@ -82,16 +215,33 @@ impl Node {
                     );
                     return Err(ProtocolError::RecordKeyMismatch);
                 }
-                self.validate_and_store_register(register).await
+                self.validate_and_store_register(register, record.publisher)
+                    .await
+            }
+            RecordKind::SignedData => {
+                let data = try_deserialize_record::<SignedData>(&record)?;
+
+                // check if the deserialized value's SignedDataAddress matches the record's key
+                let key = NetworkAddress::from_signed_data_address(data.address()).to_record_key();
+                if record.key != key {
+                    warn!(
+                        "Record's key does not match with the value's SignedDataAddress, ignoring PUT."
+                    );
+                    return Err(ProtocolError::RecordKeyMismatch);
+                }
+                self.validate_and_store_signed_data(data, record.publisher)
+                    .await
             }
         }
     }
 
-    /// Validate and store a `ChunkWithPayment` to the RecordStore
+    /// Validate and store a `ChunkWithPayment` to the RecordStore.
+    /// `origin` is the peer which published the chunk, if known.
     pub(crate) async fn validate_and_store_chunk(
         &self,
         chunk_with_payment: ChunkWithPayment,
         validate_payment_amount: bool,
+        origin: Option<PeerId>,
     ) -> Result<CmdOk, ProtocolError> {
         let chunk_addr = *chunk_with_payment.chunk.address();
         let chunk_name = *chunk_with_payment.chunk.name();
@@ -125,26 +275,28 @@ impl Node {
         let record = Record {
             key,
             value: try_serialize_record(&chunk_with_payment, RecordKind::Chunk)?,
-            publisher: None,
+            publisher: origin,
             expires: None,
         };
 
         // finally store the Record directly into the local storage
         debug!("Storing chunk {chunk_name:?} as Record locally");
-        self.network.put_local_record(record).map_err(|err| {
+        self.network.put_local_record(record).await.map_err(|err| {
             warn!("Error while locally storing Chunk as a Record{err}");
-            ProtocolError::ChunkNotStored(chunk_name)
+            local_put_error(err, ProtocolError::ChunkNotStored(chunk_name))
         })?;
         self.events_channel
-            .broadcast(crate::NodeEvent::ChunkStored(chunk_addr));
+            .broadcast(NodeEvent::ChunkStored(chunk_addr));
 
         Ok(CmdOk::StoredSuccessfully)
     }
 
-    /// Validate and store a `Register` to the RecordStore
+    /// Validate and store a `Register` to the RecordStore.
+    /// `origin` is the peer which published the register, if known.
     pub(crate) async fn validate_and_store_register(
         &self,
         register: SignedRegister,
+        origin: Option<PeerId>,
     ) -> Result<CmdOk, ProtocolError> {
         let reg_addr = register.address();
         debug!("Validating and storing register {reg_addr:?}");
@@ -172,45 +324,100 @@ impl Node {
         let record = Record {
             key,
             value: try_serialize_record(&updated_register, RecordKind::Register)?,
-            publisher: None,
+            publisher: origin,
             expires: None,
         };
         debug!("Storing register {reg_addr:?} as Record locally");
-        self.network.put_local_record(record).map_err(|err| {
+        self.network.put_local_record(record).await.map_err(|err| {
             warn!("Error while locally storing register as a Record {err}");
-            ProtocolError::RegisterNotStored(Box::new(*reg_addr))
+            local_put_error(err, ProtocolError::RegisterNotStored(Box::new(*reg_addr)))
+        })?;
+
+        Ok(CmdOk::StoredSuccessfully)
+    }
+
+    /// Validate and store each of the Registers of the `batch` as if it were put on its own,
+    /// returning their results in the order of the batch.
+    /// `origin` is the peer which sent the batch.
+    pub(crate) async fn validate_and_store_register_batch(
+        &self,
+        batch: RegisterBatch,
+        origin: PeerId,
+    ) -> Result<Vec<Result<CmdOk, ProtocolError>>, ProtocolError> {
+        verify_kind_accepted(&RecordKind::Register, self.accepted_kinds.as_ref().as_ref())?;
+
+        let mut results = Vec::with_capacity(batch.registers.len());
+        for register in batch.registers {
+            results.push(
+                self.validate_and_store_register(register, Some(origin))
+                    .await,
+            );
+        }
+        Ok(results)
+    }
+
+    /// Validate and store a `SignedData` to the RecordStore.
+    /// `origin` is the peer which published the data, if known.
+    pub(crate) async fn validate_and_store_signed_data(
+        &self,
+        data: SignedData,
+        origin: Option<PeerId>,
+    ) -> Result<CmdOk, ProtocolError> {
+        let address = data.address();
+        debug!("Validating and storing signed data {address:?}");
+
+        if let Err(err) = data.verify() {
+            warn!("Signed data {address:?} is not signed by its owner");
+            return Err(err);
+        }
+
+        let key = NetworkAddress::from_signed_data_address(address).to_record_key();
+        let local_record = self.network.get_local_record(&key).await.map_err(|err| {
+            warn!("Error while checking if signed data's key is present locally {err}");
+            ProtocolError::SignedDataNotStored(address)
+        })?;
+
+        // Signed data is immutable, so we only accept the very data we already hold
+        if let Some(local_record) = local_record {
+            let local_data = try_deserialize_record::<SignedData>(&local_record)?;
+            if local_data == data {
+                debug!("Signed data {address:?} already exists, not overwriting");
+                return Ok(CmdOk::DataAlreadyPresent);
+            }
+            warn!("Different signed data is already stored at {address:?}");
+            return Err(ProtocolError::SignedDataAlreadyStored(address));
+        }
+
+        let record = Record {
+            key,
+            value: try_serialize_record(&data, RecordKind::SignedData)?,
+            publisher: origin,
+            expires: None,
+        };
+        debug!("Storing signed data {address:?} as Record locally");
+        self.network.put_local_record(record).await.map_err(|err| {
+            warn!("Error while locally storing signed data as a Record {err}");
+            local_put_error(err, ProtocolError::SignedDataNotStored(address))
         })?;
 
         Ok(CmdOk::StoredSuccessfully)
     }
 
-    /// Validate and store `Vec<SignedSpend>` to the RecordStore
+    /// Validate and store `Vec<SignedSpend>` to the RecordStore.
+    /// `origin` is the peer which published the spends, if known.
     pub(crate) async fn validate_and_store_spends(
         &self,
         signed_spends: Vec<SignedSpend>,
+        origin: Option<PeerId>,
     ) -> Result<CmdOk, ProtocolError> {
+        // hold a permit until the spends are validated and stored
+        let _permit = self.spend_validation_permits.acquire().await?;
+
         // make sure that the dbc_ids match
-        let dbc_id = if let Some((first, elements)) = signed_spends.split_first() {
-            let common_dbc_id = *first.dbc_id();
-            if elements
-                .iter()
-                .all(|spend| spend.dbc_id() == &common_dbc_id)
-            {
-                common_dbc_id
-            } else {
-                let err = Err(ProtocolError::SpendNotStored(
-                    "found SignedSpends with differing dbc_ids".to_string(),
-                ));
-                error!("{err:?}");
-                return err;
-            }
-        } else {
-            let err = Err(ProtocolError::SpendNotStored(
-                "Spend was not provided".to_string(),
-            ));
-            warn!("Empty vec provided to validate and store spend, {err:?}");
-            return err;
-        };
+        let dbc_id = common_dbc_id(&signed_spends).map_err(|err| {
+            warn!("Not storing the spends provided: {err:?}");
+            err
+        })?;
         let dbc_addr = DbcAddress::from_dbc_id(&dbc_id);
 
         let key = NetworkAddress::from_dbc_address(dbc_addr).to_record_key();
@@ -232,9 +439,10 @@ impl Node {
                 err
             })?;
 
-        // validate the signed spends against the network and the local copy
+        // validate the signed spends against the network and the local copy, within our budget
         let validated_spends = match self
-            .signed_spend_validation(signed_spends, dbc_id, present_locally)
+            .spend_validation_permits
+            .within_budget(self.signed_spend_validation(signed_spends, dbc_id, present_locally))
             .await?
         {
             Some(spends) => spends,
@@ -248,30 +456,98 @@ impl Node {
         let record = Record {
             key,
             value: try_serialize_record(&validated_spends, RecordKind::DbcSpend)?,
-            publisher: None,
+            publisher: origin,
             expires: None,
         };
-        self.network.put_local_record(record).map_err(|_| {
-            let err = ProtocolError::SpendNotStored(format!("Cannot PUT Spend with {dbc_addr:?}"));
-            error!("Cannot put spend {err:?}");
-            err
-        })?;
+        self.network
+            .put_local_record(record.clone())
+            .await
+            .map_err(|err| {
+                error!("Cannot put spend with {dbc_addr:?}: {err:?}");
+                let not_stored =
+                    ProtocolError::SpendNotStored(format!("Cannot PUT Spend with {dbc_addr:?}"));
+                local_put_error(err, not_stored)
+            })?;
 
-        // Notify the sender of any double spend
-        if validated_spends.len() > 1 {
+        // Propagate any double spend, and notify the sender of it
+        if let Some(proof) = double_spend_proof(&validated_spends) {
             warn!("Got a double spend for the SpendDbc PUT with dbc_id {dbc_id:?}",);
-            let mut proof = validated_spends.iter();
-            if let (Some(spend_one), Some(spend_two)) = (proof.next(), proof.next()) {
-                return Err(ProtocolError::DoubleSpendAttempt(
-                    Box::new(spend_one.to_owned()),
-                    Box::new(spend_two.to_owned()),
-                ))?;
-            }
+            self.events_channel
+                .broadcast(NodeEvent::DoubleSpendDetected { address: dbc_addr });
+            self.propagate_double_spend(record, dbc_addr);
+            return Err(proof);
         }
 
         Ok(CmdOk::StoredSuccessfully)
     }
 
+    // Put the `record` holding the proof of a double spend at `dbc_addr` to the peers picked by
+    // our `PropagationStrategy`, without waiting. Storing it, they detect the double spend too.
+    // It's put again to the peers which didn't acknowledge it, till a majority of them did.
+    fn propagate_double_spend(&self, record: Record, dbc_addr: DbcAddress) {
+        if self.double_spend_propagation == PropagationStrategy::Disabled {
+            debug!("Not propagating the double spend at {dbc_addr:?}");
+            return;
+        }
+
+        let node = self.clone();
+        let _handle = tokio::spawn(async move {
+            let known_peers = match node.network.get_all_local_peers().await {
+                Ok(peers) => peers,
+                Err(err) => {
+                    warn!("Could not get the peers to propagate the double spend at {dbc_addr:?} to: {err:?}");
+                    return;
+                }
+            };
+            let address = NetworkAddress::from_dbc_address(dbc_addr);
+            let peers =
+                node.double_spend_propagation
+                    .targets(known_peers, &address, node.network.peer_id);
+            debug!(
+                "Propagating the double spend at {dbc_addr:?} to {} peers",
+                peers.len()
+            );
+            if peers.is_empty() {
+                return;
+            }
+            let quorum = close_group_majority().min(peers.len());
+            let acked = propagate_until_acked(
+                &peers,
+                quorum,
+                DOUBLE_SPEND_PROPAGATION_ATTEMPTS,
+                |missing| {
+                    let network = node.network.clone();
+                    let record = record.clone();
+                    async move {
+                        network
+                            .put_record_to_acked(record, missing)
+                            .await
+                            .unwrap_or_else(|err| {
+                                warn!(
+                                    "Failed to propagate the double spend at {dbc_addr:?}: {err:?}"
+                                );
+                                HashSet::new()
+                            })
+                    }
+                },
+            )
+            .await;
+            if acked.len() < quorum {
+                warn!(
+                    "Only {} of the {} peers targeted acknowledged the double spend at {dbc_addr:?}",
+                    acked.len(),
+                    peers.len()
+                );
+            }
+            node.events_channel
+                .broadcast(NodeEvent::DoubleSpendPropagated {
+                    address: dbc_addr,
+                    acked: acked.len(),
+                    targeted: peers.len(),
+                });
+        });
+    }
+
     /// Perform validations on the provided `ChunkWithPayment`.
     async fn chunk_payment_validation(
         &self,
@@ -317,11 +593,10 @@ impl Node {
         }
 
         if let Some(tx) = payment_tx {
-            let acceptable_fee = self
-                .network
-                .get_local_storecost()
-                .await
-                .map_err(|_| ProtocolError::ChunkNotStored(addr_name))?;
+            let acceptable_fee = self.network.get_local_storecost().await.map_err(|err| {
+                warn!("Error while getting our storecost to validate a payment: {err}");
+                ProtocolError::GetStoreCostFailed
+            })?;
             // Check if the fee output id and amount are correct, as well as verify
             // the payment proof corresponds to the fee output and that
             // the fee is sufficient for this chunk.
@@ -385,7 +660,8 @@ impl Node {
 
         // merge the two registers
         let mut merged_register = local_register.clone();
-        merged_register.verified_merge(register.to_owned())?;
+        let stats = timed_merge(&mut merged_register, register.to_owned())?;
+        self.merge_log.record(*reg_addr, stats);
         if merged_register == local_register {
             debug!("Register with addr {reg_addr:?} is the same as the local version");
             Ok(None)
@@ -473,10 +749,8 @@ impl Node {
         // if so aggregate the spends and return just 2 spends.
         let signed_spends = match signed_spends.len() {
             0 => {
-                let err = ProtocolError::SpendNotStored("No valid Spend found locally".to_string());
-                debug!("No valid spends found locally while validating Spend PUT {err}");
-
-                return Err(err);
+                debug!("No valid spends found locally while validating Spend PUT");
+                return Err(ProtocolError::SpendIsEmpty);
             }
             1 => {
                 debug!(
@@ -484,60 +758,19 @@ impl Node {
             );
                 let signed_spend = match signed_spends.pop() {
                     Some(signed_spends) => signed_spends,
-                    None => {
-                        return Err(ProtocolError::SpendNotStored(
-                            "No valid Spend found".to_string(),
-                        ));
-                    }
+                    None => return Err(ProtocolError::SpendIsEmpty),
                 };
 
-                // check the spend
-                if let Err(e) = signed_spend.verify(signed_spend.spent_tx_hash()) {
-                    return Err(ProtocolError::SpendSignatureInvalid(format!(
-                        "while verifying spend for {:?}: {e:?}",
-                        signed_spend.dbc_id()
-                    )));
-                }
-
-                // If this is a storage payment, then verify FeeOutput's id is the expected.
-                verify_fee_output_id(&signed_spend.spent_tx(), false)?;
-
-                // Get parents
-                let mut parent_spends = BTreeSet::new();
-                if is_genesis_parent_tx(&signed_spend.spend.dbc_creation_tx)
-                    && signed_spend.dbc_id() == &GENESIS_DBC.id
-                {
-                    trace!("GENESIS_DBC {dbc_addr:?} doesn't have a parent");
-                } else {
-                    trace!(
-                        "Checking dbc {dbc_addr:?} parent transaction {:?}",
-                        signed_spend.spend.dbc_creation_tx
-                    );
-                    for parent_input in &signed_spend.spend.dbc_creation_tx.inputs {
-                        let parent_dbc_address = DbcAddress::from_dbc_id(&parent_input.dbc_id());
-                        trace!(
-                            "Checking parent input at {:?} - {:?}",
-                            parent_input.dbc_id(),
-                            parent_dbc_address
-                        );
-                        let _ = parent_spends.insert(
-                            self.get_spend_from_network(parent_dbc_address, true)
-                                .await?,
-                        );
-                    }
-                }
+                // check the spend, and if this is a storage payment, that the FeeOutput's id is the expected.
+                verify_spend(&signed_spend)?;
 
                 // Check parents
-                check_parent_spends(&parent_spends, &signed_spend)?;
+                self.validate_spend_parents(&signed_spend, dbc_addr).await?;
 
                 // check the network if any spend has happened for the same dbc_id
                 // Does not return an error, instead the Vec<SignedSpend> is returned.
                 let mut spends =
-                    if let Ok(spend) = self.get_spend_from_network(dbc_addr, false).await {
-                        vec![spend]
-                    } else {
-                        vec![]
-                    };
+                    spends_held_by_network(self.get_spend_from_network(dbc_addr, false).await);
                 // aggregate the spends from the network with our own
                 spends.push(signed_spend);
                 aggregate_spends(spends, dbc_id)
@@ -554,34 +787,91 @@ impl Node {
 
         Ok(Some(signed_spends))
     }
-}
 
-// If the given TX is a storage payment, i.e. contains a fee output, then verify FeeOutput's id is
-// the expected. The fee output id is expected to be built from hashing: root_hash + input DBCs ids.
-// This requirement makes it possible for this output to be used as an input in a network
-// rewards/farming reclaiming TX, by making its spend location deterministic, analogous to
-// how an output DBC Id works for regular outputs.
-// If 'required' was set to 'true' then the fee output must be non-zero and valid.
-fn verify_fee_output_id(spent_tx: &DbcTransaction, required: bool) -> Result<(), ProtocolError> {
-    let fee = &spent_tx.fee;
-    debug!("Verification fee: {fee:?}");
-    if fee.is_free() {
-        if required {
-            return Err(ProtocolError::PaymentProofInvalidFeeOutput(fee.id));
+    /// Fetch the parent spends of the `signed_spend` at `dbc_addr` from the network, and check
+    /// them against it.
+    async fn validate_spend_parents(
+        &self,
+        signed_spend: &SignedSpend,
+        dbc_addr: DbcAddress,
+    ) -> Result<(), ProtocolError> {
+        // Get parents
+        let mut parent_spends = BTreeSet::new();
+        if is_genesis_parent_tx(&signed_spend.spend.dbc_creation_tx)
+            && signed_spend.dbc_id() == &GENESIS_DBC.id
+        {
+            trace!("GENESIS_DBC {dbc_addr:?} doesn't have a parent");
+        } else {
+            trace!(
+                "Checking dbc {dbc_addr:?} parent transaction {:?}",
+                signed_spend.spend.dbc_creation_tx
+            );
+            let inputs = &signed_spend.spend.dbc_creation_tx.inputs;
+            if inputs.len() > MAX_SPEND_PARENTS {
+                return Err(ProtocolError::SpendParentTxInvalid(format!(
+                    "The parent tx of {dbc_addr:?} has {} inputs, more than the {MAX_SPEND_PARENTS} we check",
+                    inputs.len()
+                )));
+            }
+            let parent_ids = inputs.iter().map(|input| input.dbc_id());
+            parent_spends = fetch_parent_spends(parent_ids, |parent_dbc_address| {
+                self.get_spend_from_network(parent_dbc_address, true)
+            })
+            .await?;
         }
-    } else {
-        let mut fee_id_bytes = fee.root_hash.slice().to_vec();
-        spent_tx
-            .inputs
-            .iter()
-            .for_each(|input| fee_id_bytes.extend(&input.dbc_id().to_bytes()));
-
-        if fee.id != Hash::hash(&fee_id_bytes) {
-            return Err(ProtocolError::PaymentProofInvalidFeeOutput(fee.id));
+
+        // Check parents
+        check_parent_spends(&parent_spends, signed_spend)?;
+        Ok(())
+    }
+}
+
+// Fetch the spends of the parent dbcs with `get_spend`, up to `MAX_CONCURRENT_PARENT_FETCHES`
+// at once, failing as soon as one of them fails.
+async fn fetch_parent_spends<F, Fut>(
+    parent_ids: impl IntoIterator<Item = DbcId>,
+    get_spend: F,
+) -> Result<BTreeSet<SignedSpend>, ProtocolError>
+where
+    F: Fn(DbcAddress) -> Fut,
+    Fut: Future<Output = Result<SignedSpend, ProtocolError>>,
+{
+    let fetches: Vec<_> = parent_ids
+        .into_iter()
+        .map(|parent_id| {
+            let parent_dbc_address = DbcAddress::from_dbc_id(&parent_id);
+            trace!("Checking parent input at {parent_id:?} - {parent_dbc_address:?}");
+            get_spend(parent_dbc_address)
+        })
+        .collect();
+    stream::iter(fetches)
+        .buffer_unordered(MAX_CONCURRENT_PARENT_FETCHES)
+        .try_collect()
+        .await
+}
+
+// The spends of a dbc held by the network, out of the `result` of getting it, be it a single
+// spend, the proof of a double spend or the diverging spends of the peers.
+fn spends_held_by_network(result: Result<SignedSpend, ProtocolError>) -> Vec<SignedSpend> {
+    match result {
+        Ok(spend) => vec![spend],
+        Err(ProtocolError::DoubleSpendAttempt(one, two)) => vec![*one, *two],
+        Err(ProtocolError::SpendDivergence { variants, .. }) => {
+            variants.into_iter().map(|(spend, _)| spend).collect()
         }
+        Err(_) => vec![],
     }
+}
 
-    Ok(())
+// The proof of a double spend, should the validated `spends` hold more than one spend of the dbc.
+fn double_spend_proof(spends: &[SignedSpend]) -> Option<ProtocolError> {
+    match spends {
+        [spend_one, spend_two, ..] => Some(ProtocolError::DoubleSpendAttempt(
+            Box::new(spend_one.to_owned()),
+            Box::new(spend_two.to_owned()),
+        )),
+        _ => None,
+    }
 }
 
 // Check if the fee output id and amount are correct, as well as verify the payment proof audit
@@ -617,11 +907,601 @@ fn verify_fee_output_and_proof(
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
+    use crate::replication::tests::fetch_replicated;
+    use bytes::Bytes;
+    use libp2p::kad::RecordKey;
     use proptest::prelude::*;
-    use sn_dbc::{FeeOutput, Token};
+    use sn_dbc::{DerivedKey, FeeOutput, Hash, Token};
+    use sn_protocol::{storage::Chunk, test_utils::spend_of, AddressKind};
+    use sn_registers::{Permissions, Register};
     use sn_transfers::payment_proof::build_payment_proofs;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn spend_validation_waits_for_a_permit() -> eyre::Result<()> {
+        let permits = SpendValidationPermits::new(1, DEFAULT_SPEND_VALIDATION_BUDGET);
+        let first = permits.acquire().await?;
+
+        let permits_clone = permits.clone();
+        let mut second = tokio::spawn(async move { permits_clone.acquire().await });
+        assert!(
+            tokio::time::timeout(Duration::from_millis(200), &mut second)
+                .await
+                .is_err(),
+            "second validation should wait while the first is ongoing"
+        );
+
+        drop(first);
+        let second = tokio::time::timeout(Duration::from_secs(1), second).await??;
+        assert!(second.is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn chunk_only_node_accepts_chunks_and_rejects_registers() -> eyre::Result<()> {
+        let accepted: HashSet<_> = [RecordKind::Chunk].into_iter().collect();
+
+        let chunk = Chunk::new(Bytes::from_static(b"chunk"));
+        let chunk_record = Record::new(
+            NetworkAddress::from_chunk_address(*chunk.address()).to_record_key(),
+            try_serialize_record(
+                &ChunkWithPayment {
+                    chunk,
+                    payment: PaymentProof {
+                        spent_ids: vec![],
+                        audit_trail: vec![],
+                        path: vec![],
+                    },
+                },
+                RecordKind::Chunk,
+            )?,
+        );
+        let header = RecordHeader::from_record(&chunk_record)?;
+        assert_eq!(verify_kind_accepted(&header.kind, Some(&accepted)), Ok(()));
+
+        let owner_sk = bls::SecretKey::random();
+        let register = Register::new(
+            owner_sk.public_key(),
+            XorName::random(&mut rand::thread_rng()),
+            Permissions::new_owner_only(),
+            None,
+        )
+        .into_signed(&owner_sk)?;
+        let register_record = Record::new(
+            NetworkAddress::from_register_address(*register.address()).to_record_key(),
+            try_serialize_record(&register, RecordKind::Register)?,
+        );
+        let header = RecordHeader::from_record(&register_record)?;
+        assert_eq!(
+            verify_kind_accepted(&header.kind, Some(&accepted)),
+            Err(ProtocolError::KindNotAccepted(RecordKind::Register))
+        );
+
+        // by default, records of any kind are accepted
+        assert_eq!(verify_kind_accepted(&header.kind, None), Ok(()));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn kind_quota_rejects_new_records_of_that_kind_only() -> eyre::Result<()> {
+        let root_dir = assert_fs::TempDir::new()?;
+        let node = offline_node_with(
+            root_dir.path().to_path_buf(),
+            sn_networking::NetworkConfig {
+                local: true,
+                ..Default::default()
+            },
+            [(AddressKind::RegisterAddress, 1)].into_iter().collect(),
+        )?;
+        let register_record = || -> eyre::Result<Record> {
+            let owner_sk = bls::SecretKey::random();
+            let register = Register::new(
+                owner_sk.public_key(),
+                XorName::random(&mut rand::thread_rng()),
+                Permissions::new_owner_only(),
+                None,
+            )
+            .into_signed(&owner_sk)?;
+            Ok(Record::new(
+                NetworkAddress::from_register_address(*register.address()).to_record_key(),
+                try_serialize_record(&register, RecordKind::Register)?,
+            ))
+        };
+
+        // fill the quota of registers
+        let first = register_record()?;
+        assert_eq!(
+            node.validate_and_store_record(first.clone(), false).await,
+            Ok(CmdOk::StoredSuccessfully)
+        );
+        assert_eq!(
+            node.validate_and_store_record(register_record()?, false)
+                .await,
+            Err(ProtocolError::KindQuotaExceeded {
+                kind: AddressKind::RegisterAddress,
+            })
+        );
+
+        // the register already held can still be put
+        assert!(node.validate_and_store_record(first, false).await.is_ok());
+
+        // while records of other kinds are still stored
+        let data = SignedData::new(
+            XorName::random(&mut rand::thread_rng()),
+            &bls::SecretKey::random(),
+            Bytes::from_static(b"data"),
+        );
+        assert_eq!(
+            node.validate_and_store_signed_data(data, None).await,
+            Ok(CmdOk::StoredSuccessfully)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn spend_validation_is_rejected_when_overloaded() {
+        let permits = SpendValidationPermits {
+            semaphore: Arc::new(Semaphore::new(1)),
+            queue_timeout: Duration::from_millis(50),
+            budget: DEFAULT_SPEND_VALIDATION_BUDGET,
+        };
+        let _first = permits.acquire().await;
+
+        assert!(matches!(
+            permits.acquire().await,
+            Err(ProtocolError::Overloaded)
+        ));
+    }
+
+    #[tokio::test]
+    async fn spend_validation_exceeding_the_budget_is_aborted() {
+        let permits = SpendValidationPermits::new(1, Duration::from_millis(50));
+        let slow_validation = async {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(())
+        };
+        assert_eq!(
+            permits.within_budget(slow_validation).await,
+            Err(ProtocolError::ValidationBudgetExceeded)
+        );
+
+        // while validations completing in time are unaffected
+        assert_eq!(permits.within_budget(async { Ok(()) }).await, Ok(()));
+        assert_eq!(
+            permits
+                .within_budget(async { Err::<(), _>(ProtocolError::SpendIsEmpty) })
+                .await,
+            Err(ProtocolError::SpendIsEmpty)
+        );
+    }
+
+    #[tokio::test]
+    async fn parent_spends_are_fetched_concurrently() -> eyre::Result<()> {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let parents: HashMap<DbcAddress, SignedSpend> = (0..MAX_CONCURRENT_PARENT_FETCHES + 4)
+            .map(|_| {
+                let spend = spend_of(&bls::SecretKey::random(), b"parent");
+                (DbcAddress::from_dbc_id(spend.dbc_id()), spend)
+            })
+            .collect();
+        let parent_ids: Vec<DbcId> = parents.values().map(|spend| *spend.dbc_id()).collect();
+        let in_flight = AtomicUsize::new(0);
+        let max_in_flight = AtomicUsize::new(0);
+        let get_spend = |address: DbcAddress| {
+            let (parents, in_flight, max_in_flight) = (&parents, &in_flight, &max_in_flight);
+            async move {
+                let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                let _ = max_in_flight.fetch_max(current, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(100)).await;
+                let _ = in_flight.fetch_sub(1, Ordering::SeqCst);
+                parents
+                    .get(&address)
+                    .cloned()
+                    .ok_or(ProtocolError::SpendNotFound(address))
+            }
+        };
+
+        let fetched = fetch_parent_spends(parent_ids.clone(), get_spend).await?;
+        assert_eq!(fetched, parents.values().cloned().collect());
+        // as many at once as we allow, but no more
+        assert_eq!(
+            max_in_flight.load(Ordering::SeqCst),
+            MAX_CONCURRENT_PARENT_FETCHES
+        );
+
+        // a parent missing from the network fails the whole fetch
+        let missing = spend_of(&bls::SecretKey::random(), b"missing");
+        let missing_address = DbcAddress::from_dbc_id(missing.dbc_id());
+        let mut with_missing = parent_ids;
+        with_missing.push(*missing.dbc_id());
+        assert_eq!(
+            fetch_parent_spends(with_missing, get_spend).await,
+            Err(ProtocolError::SpendNotFound(missing_address))
+        );
+
+        Ok(())
+    }
+
+    // A node which isn't connected to any network, storing records in `root_dir`.
+    pub(crate) fn offline_node(root_dir: std::path::PathBuf) -> eyre::Result<Node> {
+        offline_node_with(
+            root_dir,
+            sn_networking::NetworkConfig {
+                local: true,
+                ..Default::default()
+            },
+            Default::default(),
+        )
+    }
+
+    // A node with the given `network_config` and `per_kind_quota`, which isn't connected to any
+    // peer.
+    fn offline_node_with(
+        root_dir: std::path::PathBuf,
+        network_config: sn_networking::NetworkConfig,
+        per_kind_quota: HashMap<AddressKind, u64>,
+    ) -> eyre::Result<Node> {
+        let (network, _events_receiver, mut swarm_driver) = sn_networking::SwarmDriver::new(
+            libp2p::identity::Keypair::generate_ed25519(),
+            "127.0.0.1:0".parse()?,
+            root_dir,
+            network_config,
+        )?;
+        swarm_driver.set_kind_quotas(per_kind_quota);
+        let _handle = tokio::spawn(swarm_driver.run());
+        Ok(Node {
+            network,
+            events_channel: Default::default(),
+            initial_peers: vec![],
+            spend_validation_permits: SpendValidationPermits::new(
+                DEFAULT_MAX_CONCURRENT_SPEND_VALIDATIONS,
+                DEFAULT_SPEND_VALIDATION_BUDGET,
+            ),
+            spend_quorum: crate::DEFAULT_SPEND_QUORUM,
+            delete_operators: Default::default(),
+            peer_allowlist: Default::default(),
+            accepted_kinds: Default::default(),
+            request_queue: crate::fair_queue::FairRequestQueue::new(1),
+            double_spend_propagation: PropagationStrategy::Disabled,
+            readiness: crate::readiness::Readiness::new(0),
+            merge_log: Default::default(),
+        })
+    }
+
+    #[tokio::test]
+    async fn detecting_a_double_spend_emits_an_event() -> eyre::Result<()> {
+        let root_dir = assert_fs::TempDir::new()?;
+        let node = offline_node(root_dir.path().to_path_buf())?;
+        let mut events = node.events_channel.subscribe();
+
+        let derived_sk = bls::SecretKey::random();
+        let address = DbcAddress::from_dbc_id(&DerivedKey::new(derived_sk.clone()).dbc_id());
+        let spends = vec![spend_of(&derived_sk, b"one"), spend_of(&derived_sk, b"two")];
+        assert!(matches!(
+            node.validate_and_store_spends(spends, None).await,
+            Err(ProtocolError::DoubleSpendAttempt(..))
+        ));
+
+        match events.try_recv()? {
+            NodeEvent::DoubleSpendDetected { address: detected } => assert_eq!(detected, address),
+            other => eyre::bail!("expected a double spend to be detected, got {other:?}"),
+        }
+
+        Ok(())
+    }
+
+    // A record of the given `kind` at `key`, holding the `data` as is, whether valid or not.
+    fn record_of<T: serde::Serialize>(
+        key: RecordKey,
+        data: &T,
+        kind: RecordKind,
+    ) -> eyre::Result<Record> {
+        Ok(Record::new(key, try_serialize_record(data, kind)?))
+    }
+
+    fn random_register_address() -> NetworkAddress {
+        NetworkAddress::from_register_address(sn_registers::RegisterAddress::new(
+            XorName::random(&mut rand::thread_rng()),
+            bls::SecretKey::random().public_key(),
+        ))
+    }
+
+    #[tokio::test]
+    async fn record_at_another_key_is_rejected_with_key_mismatch() -> eyre::Result<()> {
+        let root_dir = assert_fs::TempDir::new()?;
+        let node = offline_node(root_dir.path().to_path_buf())?;
+
+        let data = SignedData::new(
+            XorName::random(&mut rand::thread_rng()),
+            &bls::SecretKey::random(),
+            Bytes::from_static(b"data"),
+        );
+        let record = record_of(
+            random_register_address().to_record_key(),
+            &data,
+            RecordKind::SignedData,
+        )?;
+        assert_eq!(
+            node.validate_and_store_record(record, false).await,
+            Err(ProtocolError::RecordKeyMismatch)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn record_of_a_kind_not_accepted_is_rejected() -> eyre::Result<()> {
+        let root_dir = assert_fs::TempDir::new()?;
+        let mut node = offline_node(root_dir.path().to_path_buf())?;
+        node.accepted_kinds = Arc::new(Some([RecordKind::Chunk].into_iter().collect()));
+
+        let data = SignedData::new(
+            XorName::random(&mut rand::thread_rng()),
+            &bls::SecretKey::random(),
+            Bytes::from_static(b"data"),
+        );
+        let record = record_of(
+            NetworkAddress::from_signed_data_address(data.address()).to_record_key(),
+            &data,
+            RecordKind::SignedData,
+        )?;
+        assert_eq!(
+            node.validate_and_store_record(record, false).await,
+            Err(ProtocolError::KindNotAccepted(RecordKind::SignedData))
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn unpaid_chunk_is_rejected_without_payment_inputs() -> eyre::Result<()> {
+        let root_dir = assert_fs::TempDir::new()?;
+        let node = offline_node(root_dir.path().to_path_buf())?;
+
+        let chunk = Chunk::new(Bytes::from_static(b"chunk"));
+        let name = *chunk.name();
+        let record = record_of(
+            NetworkAddress::from_chunk_address(*chunk.address()).to_record_key(),
+            &ChunkWithPayment {
+                chunk,
+                payment: PaymentProof {
+                    spent_ids: vec![],
+                    audit_trail: vec![],
+                    path: vec![],
+                },
+            },
+            RecordKind::Chunk,
+        )?;
+        assert_eq!(
+            node.validate_and_store_record(record, true).await,
+            Err(ProtocolError::PaymentProofWithoutInputs(name))
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn empty_spends_are_rejected() -> eyre::Result<()> {
+        let root_dir = assert_fs::TempDir::new()?;
+        let node = offline_node(root_dir.path().to_path_buf())?;
+
+        let key = NetworkAddress::from_dbc_address(DbcAddress::new(XorName::random(
+            &mut rand::thread_rng(),
+        )))
+        .to_record_key();
+        let record = record_of(key, &Vec::<SignedSpend>::new(), RecordKind::DbcSpend)?;
+        assert_eq!(
+            node.validate_and_store_record(record, false).await,
+            Err(ProtocolError::SpendIsEmpty)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn more_spends_than_validated_at_once_are_rejected() -> eyre::Result<()> {
+        let root_dir = assert_fs::TempDir::new()?;
+        let node = offline_node(root_dir.path().to_path_buf())?;
+
+        let derived_sk = bls::SecretKey::random();
+        let spends: Vec<_> = (0..=MAX_SPENDS_PER_PUT)
+            .map(|i| spend_of(&derived_sk, &i.to_be_bytes()))
+            .collect();
+        let key = NetworkAddress::from_dbc_address(DbcAddress::from_dbc_id(spends[0].dbc_id()))
+            .to_record_key();
+        let record = record_of(key, &spends, RecordKind::DbcSpend)?;
+        assert_eq!(
+            node.validate_and_store_record(record, false).await,
+            Err(ProtocolError::TooManySpends {
+                count: MAX_SPENDS_PER_PUT + 1,
+                limit: MAX_SPENDS_PER_PUT,
+            })
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn spends_of_different_dbcs_are_rejected() -> eyre::Result<()> {
+        let root_dir = assert_fs::TempDir::new()?;
+        let node = offline_node(root_dir.path().to_path_buf())?;
+
+        let spend = spend_of(&bls::SecretKey::random(), b"one");
+        let other = spend_of(&bls::SecretKey::random(), b"other");
+        let address = DbcAddress::from_dbc_id(spend.dbc_id());
+        assert_eq!(
+            node.validate_and_store_spends(vec![spend, other], None)
+                .await,
+            Err(ProtocolError::SpendDbcIdMismatch(address))
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn register_not_signed_by_its_owner_is_rejected() -> eyre::Result<()> {
+        let root_dir = assert_fs::TempDir::new()?;
+        let node = offline_node(root_dir.path().to_path_buf())?;
+
+        let register = Register::new(
+            bls::SecretKey::random().public_key(),
+            XorName::random(&mut rand::thread_rng()),
+            Permissions::new_owner_only(),
+            None,
+        );
+        let signature = bls::SecretKey::random().sign(register.bytes()?);
+        let register = SignedRegister::new(register, signature);
+        let address = *register.address();
+        let record = record_of(
+            NetworkAddress::from_register_address(address).to_record_key(),
+            &register,
+            RecordKind::Register,
+        )?;
+        assert_eq!(
+            node.validate_and_store_record(record, false).await,
+            Err(ProtocolError::RegisterInvalid(Box::new(address)))
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn forged_signed_data_is_rejected() -> eyre::Result<()> {
+        let root_dir = assert_fs::TempDir::new()?;
+        let node = offline_node(root_dir.path().to_path_buf())?;
+
+        // the fields of a `SignedData`, the value being swapped under the owner's signature
+        #[derive(serde::Serialize)]
+        struct Forged {
+            owner: bls::PublicKey,
+            name: XorName,
+            value: Bytes,
+            signature: bls::Signature,
+        }
+        let owner_sk = bls::SecretKey::random();
+        let name = XorName::random(&mut rand::thread_rng());
+        let data = SignedData::new(name, &owner_sk, Bytes::from_static(b"data"));
+        let forged = Forged {
+            owner: owner_sk.public_key(),
+            name,
+            value: Bytes::from_static(b"forged"),
+            signature: owner_sk.sign(SignedData::bytes_for_signing(
+                &owner_sk.public_key(),
+                &name,
+                b"data",
+            )),
+        };
+        let record = record_of(
+            NetworkAddress::from_signed_data_address(data.address()).to_record_key(),
+            &forged,
+            RecordKind::SignedData,
+        )?;
+        assert_eq!(
+            node.validate_and_store_record(record, false).await,
+            Err(ProtocolError::SignedDataInvalid(data.address()))
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn other_signed_data_at_a_stored_address_is_rejected() -> eyre::Result<()> {
+        let root_dir = assert_fs::TempDir::new()?;
+        let node = offline_node(root_dir.path().to_path_buf())?;
+
+        let owner_sk = bls::SecretKey::random();
+        let name = XorName::random(&mut rand::thread_rng());
+        let data = SignedData::new(name, &owner_sk, Bytes::from_static(b"data"));
+        assert_eq!(
+            node.validate_and_store_signed_data(data.clone(), None)
+                .await,
+            Ok(CmdOk::StoredSuccessfully)
+        );
+
+        let other = SignedData::new(name, &owner_sk, Bytes::from_static(b"other"));
+        assert_eq!(
+            node.validate_and_store_signed_data(other, None).await,
+            Err(ProtocolError::SignedDataAlreadyStored(data.address()))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn double_spends_held_by_the_network_are_aggregated() -> eyre::Result<()> {
+        let derived_sk = bls::SecretKey::random();
+        let dbc_id = DerivedKey::new(derived_sk.clone()).dbc_id();
+        let address = DbcAddress::from_dbc_id(&dbc_id);
+        let one = spend_of(&derived_sk, b"one");
+        let two = spend_of(&derived_sk, b"two");
+
+        let held = spends_held_by_network(Err(ProtocolError::SpendDivergence {
+            address,
+            variants: vec![(one.clone(), 3), (two.clone(), 1)],
+        }));
+        assert_eq!(held, vec![one.clone(), two.clone()]);
+        assert!(double_spend_proof(&aggregate_spends(held, dbc_id)).is_some());
+
+        let held = spends_held_by_network(Err(ProtocolError::DoubleSpendAttempt(
+            Box::new(one.clone()),
+            Box::new(two.clone()),
+        )));
+        assert_eq!(held, vec![one.clone(), two]);
+
+        assert_eq!(spends_held_by_network(Ok(one.clone())), vec![one]);
+        assert!(spends_held_by_network(Err(ProtocolError::SpendNotFound(address))).is_empty());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn joining_node_learns_an_existing_double_spend_proof() -> eyre::Result<()> {
+        let (holder_dir, joining_dir) = (assert_fs::TempDir::new()?, assert_fs::TempDir::new()?);
+        let holder = offline_node(holder_dir.path().to_path_buf())?;
+        let joining = offline_node(joining_dir.path().to_path_buf())?;
+        let mut events = joining.events_channel.subscribe();
+
+        // the proof of a double spend, as held by the close group
+        let derived_sk = bls::SecretKey::random();
+        let address = DbcAddress::from_dbc_id(&DerivedKey::new(derived_sk.clone()).dbc_id());
+        let spends = vec![spend_of(&derived_sk, b"one"), spend_of(&derived_sk, b"two")];
+        assert!(matches!(
+            holder.validate_and_store_spends(spends.clone(), None).await,
+            Err(ProtocolError::DoubleSpendAttempt(..))
+        ));
+
+        // is replicated to the joining node, which stores both spends of it
+        let key = NetworkAddress::from_dbc_address(address).to_record_key();
+        fetch_replicated(
+            &joining,
+            &holder,
+            vec![NetworkAddress::from_record_key(key.clone())],
+        )
+        .await?;
+        let record = joining
+            .network
+            .get_local_record(&key)
+            .await?
+            .ok_or_else(|| eyre::eyre!("the replicated spends were not stored"))?;
+        let stored: Vec<SignedSpend> = try_deserialize_record(&record)?;
+        assert_eq!(stored.len(), 2);
+        assert!(spends.iter().all(|spend| stored.contains(spend)));
+
+        // and tells of the double spend it learnt of
+        let mut learned = false;
+        while let Ok(event) = events.try_recv() {
+            learned |= matches!(event, NodeEvent::DoubleSpendLearned(at) if at == address);
+        }
+        assert!(learned, "the double spend at {address:?} was not learnt of");
+
+        Ok(())
+    }
 
     proptest! {
         #[test]
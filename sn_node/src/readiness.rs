@@ -0,0 +1,131 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use libp2p::PeerId;
+use sn_protocol::{
+    error::Error as ProtocolError,
+    messages::{Request, Response},
+};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+
+/// Whether the node has joined the network enough to serve requests, i.e. it's connected to
+/// at least `min_peers` of the peers in its routing table.
+/// Once ready, the node stays so, even should it lose peers.
+#[derive(Clone)]
+pub(crate) struct Readiness {
+    min_peers: usize,
+    ready: Arc<AtomicBool>,
+}
+
+impl Readiness {
+    pub(crate) fn new(min_peers: usize) -> Self {
+        Self {
+            min_peers,
+            ready: Arc::new(AtomicBool::new(min_peers == 0)),
+        }
+    }
+
+    pub(crate) fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::Acquire)
+    }
+
+    /// Update with the number of peers of our routing table we're connected to.
+    /// Returns `true` if we just became ready.
+    pub(crate) fn update(&self, connected_peers: usize) -> bool {
+        if connected_peers < self.min_peers {
+            return false;
+        }
+        !self.ready.swap(true, Ordering::AcqRel)
+    }
+}
+
+// The response deferring the `request` of the `peer` till we're ready to serve it.
+// We always serve the requests we send to ourselves.
+pub(crate) fn not_ready_response(
+    request: &Request,
+    peer: PeerId,
+    our_peer_id: PeerId,
+    readiness: &Readiness,
+) -> Option<Response> {
+    if readiness.is_ready() || peer == our_peer_id {
+        return None;
+    }
+    debug!("Deferring request {request:?} from peer {peer:?}, not ready to serve yet");
+    Some(request.error_response(ProtocolError::NotReady))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sn_protocol::{
+        messages::{Query, QueryResponse},
+        storage::ChunkAddress,
+        NetworkAddress,
+    };
+    use xor_name::XorName;
+
+    fn has_record_query() -> Request {
+        Request::Query(Query::HasRecord(NetworkAddress::from_chunk_address(
+            ChunkAddress::new(XorName::random(&mut rand::thread_rng())),
+        )))
+    }
+
+    #[test]
+    fn requests_are_deferred_till_enough_peers_are_connected() {
+        let our_peer_id = PeerId::random();
+        let peer = PeerId::random();
+        let readiness = Readiness::new(5);
+        let not_ready = Some(Response::Query(QueryResponse::HasRecord(Err(
+            ProtocolError::NotReady,
+        ))));
+
+        assert_eq!(
+            not_ready_response(&has_record_query(), peer, our_peer_id, &readiness),
+            not_ready
+        );
+        // our own requests are served regardless
+        assert_eq!(
+            not_ready_response(&has_record_query(), our_peer_id, our_peer_id, &readiness),
+            None
+        );
+
+        assert!(!readiness.update(4));
+        assert_eq!(
+            not_ready_response(&has_record_query(), peer, our_peer_id, &readiness),
+            not_ready
+        );
+
+        // we become ready once, and stay so
+        assert!(readiness.update(5));
+        assert!(!readiness.update(6));
+        assert!(!readiness.update(1));
+        assert_eq!(
+            not_ready_response(&has_record_query(), peer, our_peer_id, &readiness),
+            None
+        );
+    }
+
+    #[test]
+    fn node_without_a_threshold_serves_right_away() {
+        let readiness = Readiness::new(0);
+        assert!(readiness.is_ready());
+        assert!(!readiness.update(0));
+        assert_eq!(
+            not_ready_response(
+                &has_record_query(),
+                PeerId::random(),
+                PeerId::random(),
+                &readiness
+            ),
+            None
+        );
+    }
+}
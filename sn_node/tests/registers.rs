@@ -0,0 +1,108 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+mod common;
+
+use common::{get_client, init_logging};
+
+use sn_client::{tagged_register_meta, ClientRegister};
+use sn_registers::{Permissions, Register};
+
+use assert_fs::TempDir;
+use eyre::Result;
+use std::collections::BTreeSet;
+use xor_name::XorName;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn register_from_snapshot_is_created_on_sync() -> Result<()> {
+    init_logging();
+
+    let client = get_client().await;
+
+    // a pre-built register, as an app would ship it
+    let mut snapshot = Register::new(
+        client.signer_pk(),
+        XorName(rand::random()),
+        Permissions::new_owner_only(),
+        None,
+    );
+    let (first, _op) = snapshot.write(b"seed".to_vec(), BTreeSet::new())?;
+    let (_second, _op) = snapshot.write(b"document".to_vec(), [first].into())?;
+    let address = *snapshot.address();
+
+    let mut reg = ClientRegister::from_snapshot(client.clone(), snapshot.clone())?;
+    reg.sync(true).await?;
+
+    let stored = client.get_register(address).await?;
+    assert_eq!(stored.size(), snapshot.size());
+    assert_eq!(stored.read(), snapshot.read());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn register_history_follows_the_write_order() -> Result<()> {
+    init_logging();
+
+    let client = get_client().await;
+    let (name, tag) = (XorName(rand::random()), 1);
+    let mut reg = client
+        .create_register(tagged_register_meta(name, tag), true)
+        .await?;
+    let written = [&b"first"[..], b"second", b"third"];
+    let mut hashes = vec![];
+    for entry in written {
+        reg.write_online(entry, true).await?;
+        hashes.extend(reg.read().into_iter().map(|(hash, _)| hash));
+    }
+
+    let history = client.get_register_history(name, tag).await?;
+    let expected: Vec<_> = hashes
+        .into_iter()
+        .zip(written.iter().map(|entry| entry.to_vec()))
+        .collect();
+    assert_eq!(history, expected);
+
+    // a Register of the same name but another tag is another Register
+    assert!(client.get_register_history(name, tag + 1).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn pending_writes_are_flushed_after_recovering_from_a_crash() -> Result<()> {
+    init_logging();
+
+    let client = get_client().await;
+    let wal_dir = TempDir::new()?;
+    let wal_path = wal_dir.path().join("register.wal");
+
+    let mut reg = ClientRegister::create_online(client.clone(), XorName(rand::random()), true)
+        .await?
+        .with_write_ahead_log(wal_path.clone())?;
+    reg.write(b"staged")?;
+    reg.write(b"staged too")?;
+    let address = *reg.address();
+    let expected = reg.read();
+
+    // the client crashes before pushing the staged writes
+    drop(reg);
+
+    let mut recovered = ClientRegister::recover(client.clone(), wal_path.clone())?;
+    recovered.push(true).await?;
+
+    let stored = client.get_register(address).await?;
+    assert_eq!(stored.read(), expected);
+
+    // the log was kept up to date by the push, leaving no op to flush after the next crash
+    let recovered = ClientRegister::recover(client, wal_path)?;
+    assert_eq!(recovered.read(), expected);
+    assert_eq!(recovered.pending_ops(), 0);
+
+    Ok(())
+}
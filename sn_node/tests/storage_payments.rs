@@ -206,6 +206,30 @@ async fn storage_payment_chunk_upload_succeeds() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test(flavor = "multi_thread")]
+async fn rooted_file_is_read_back_from_its_root_alone() -> Result<()> {
+    let paying_wallet_balance = 50_000_000_000_002;
+    let paying_wallet_dir = TempDir::new()?;
+
+    let (client, paying_wallet) =
+        get_client_and_wallet(paying_wallet_dir.path(), paying_wallet_balance).await?;
+    let mut wallet_client = WalletClient::new(client.clone(), paying_wallet);
+
+    let (files_api, content_bytes, _content_addr, chunks) = random_content(&client)?;
+
+    let (proofs, _cost) = wallet_client
+        .pay_for_storage(chunks.iter().map(|c| c.name()), true)
+        .await?;
+
+    let root = files_api
+        .store_file_rooted(content_bytes.clone(), &proofs, true)
+        .await?;
+
+    assert_eq!(files_api.get_file_rooted(&root).await?, content_bytes);
+
+    Ok(())
+}
+
 #[tokio::test(flavor = "multi_thread")]
 async fn storage_payment_chunk_upload_fails() -> Result<()> {
     let paying_wallet_balance = 50_000_000_000_003;
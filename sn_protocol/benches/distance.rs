@@ -0,0 +1,75 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use libp2p::PeerId;
+use sn_protocol::{AddressInterner, NetworkAddress};
+
+// Ranking a candidate list against one address, e.g. when selecting the close group.
+fn distances(c: &mut Criterion) {
+    let mut group = c.benchmark_group("distances");
+    let address = NetworkAddress::from_peer(PeerId::random());
+
+    for num_targets in [20, 200, 2000] {
+        let targets: Vec<_> = (0..num_targets)
+            .map(|_| NetworkAddress::from_peer(PeerId::random()))
+            .collect();
+
+        group.bench_with_input(
+            BenchmarkId::new("repeated distance", num_targets),
+            &targets,
+            |b, targets| {
+                b.iter(|| {
+                    targets
+                        .iter()
+                        .map(|target| black_box(&address).distance(target))
+                        .collect::<Vec<_>>()
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("distances_to", num_targets),
+            &targets,
+            |b, targets| b.iter(|| black_box(&address).distances_to(targets)),
+        );
+    }
+
+    group.finish();
+}
+
+// Cloning the candidate list of a routing step, e.g. to rank it or to send a request to each.
+fn clones(c: &mut Criterion) {
+    let mut group = c.benchmark_group("clones");
+
+    for num_targets in [20, 200, 2000] {
+        let targets: Vec<_> = (0..num_targets)
+            .map(|_| NetworkAddress::from_peer(PeerId::random()))
+            .collect();
+        let mut interner = AddressInterner::new();
+        let interned: Vec<_> = targets
+            .iter()
+            .map(|target| interner.intern(target.clone()))
+            .collect();
+
+        group.bench_with_input(
+            BenchmarkId::new("plain", num_targets),
+            &targets,
+            |b, targets| b.iter(|| black_box(targets).clone()),
+        );
+        group.bench_with_input(
+            BenchmarkId::new("interned", num_targets),
+            &interned,
+            |b, interned| b.iter(|| black_box(interned).clone()),
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, distances, clones);
+criterion_main!(benches);
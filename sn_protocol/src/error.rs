@@ -7,8 +7,8 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::{
-    storage::{ChunkAddress, DbcAddress, RecordKind, RegisterAddress},
-    NetworkAddress,
+    storage::{ChunkAddress, DbcAddress, RecordKind, RegisterAddress, SignedDataAddress},
+    AddressKind, NetworkAddress,
 };
 use serde::{Deserialize, Serialize};
 use sn_dbc::{Hash, SignedSpend, Token};
@@ -40,6 +40,17 @@ pub enum Error {
     #[error("The Register was already created by another owner: {0:?}")]
     RegisterAlreadyClaimed(bls::PublicKey),
 
+    // ---------- signed data errors
+    #[error("Signed data not found: {0:?}")]
+    SignedDataNotFound(SignedDataAddress),
+    #[error("Signed data was not stored: {0:?}")]
+    SignedDataNotStored(SignedDataAddress),
+    #[error("Signed data is not signed by its owner: {0:?}")]
+    SignedDataInvalid(SignedDataAddress),
+    /// Signed data is immutable, so other data can't be stored at the same address.
+    #[error("Different signed data is already stored at {0:?}")]
+    SignedDataAlreadyStored(SignedDataAddress),
+
     // ---------- spend errors
     #[error("Spend not found: {0:?}")]
     SpendNotFound(DbcAddress),
@@ -47,12 +58,40 @@ pub enum Error {
     SpendNotStored(String),
     #[error("A double spend was detected. Two diverging signed spends: {0:?}, {1:?}")]
     DoubleSpendAttempt(Box<SignedSpend>, Box<SignedSpend>),
+    /// The peers holding the spend of a dbc returned different spends, each along with the
+    /// number of peers which returned it.
+    #[error("The peers holding the spend at {address:?} disagree on it: {variants:?}")]
+    SpendDivergence {
+        address: DbcAddress,
+        variants: Vec<(SignedSpend, usize)>,
+    },
     #[error("Spend signature is invalid: {0}")]
     SpendSignatureInvalid(String),
     #[error("Invalid Parent Tx: {0}")]
     SpendParentTxInvalid(String),
     #[error("Dbc Spend is empty")]
     SpendIsEmpty,
+    /// The spends stored together must all be of the same dbc, the first one being at the address.
+    #[error("Spends of different dbcs were provided along the spend at {0:?}")]
+    SpendDbcIdMismatch(DbcAddress),
+    /// More spends of a dbc were provided at once than a node validates.
+    #[error("{count} spends were provided at once, more than the {limit} a node validates")]
+    TooManySpends {
+        /// The number of spends provided
+        count: usize,
+        /// The maximum number of spends validated at once
+        limit: usize,
+    },
+    /// The node has too many spends being validated to take on another one at the moment.
+    #[error("The node is overloaded with spend validations")]
+    Overloaded,
+    /// The validation of the spend could not complete within the node's time budget, e.g. its
+    /// parent spends being slow to fetch from a busy network, which a later attempt may overcome.
+    #[error("The spend could not be validated within the node's time budget")]
+    ValidationBudgetExceeded,
+    /// The node is yet to be connected to enough peers to serve requests.
+    #[error("The node is not ready to serve requests yet")]
+    NotReady,
 
     // ---------- payment errors
     /// Failed to get the storecost from kademlia store
@@ -94,6 +133,61 @@ pub enum Error {
         /// Address of the missing data
         address: Box<NetworkAddress>,
     },
+    /// Could not build the filter of record keys held locally.
+    #[error("Failed to build the filter of locally held record keys")]
+    RecordKeyFilterFailed,
+
+    // ---------- operator errors
+    /// The operator isn't authorized by the node to delete records, or its signature is invalid.
+    #[error("The operator {0:?} is not authorized to delete records")]
+    DeleteNotAuthorized(bls::PublicKey),
+    /// The deletion of a record was requested past its expiry.
+    #[error("The deletion of the record expired")]
+    DeleteExpired,
+    /// The record could not be removed from the local storage.
+    #[error("Record was not deleted: {0:?}")]
+    RecordNotDeleted(Box<NetworkAddress>),
+
+    // ---------- access errors
+    /// The peer isn't on the allowlist of the node, which doesn't serve its requests.
+    #[error("The peer {0:?} is not authorized to send requests to this node")]
+    Unauthorized(Box<NetworkAddress>),
+
+    // ---------- message errors
+    /// Could not Serialize/Deserialize a request, or the envelope it's sent in.
+    #[error("Could not Serialize/Deserialize Request")]
+    RequestParsingFailed,
+
+    #[error("Could not sign the response with the node's keypair")]
+    ResponseSigningFailed,
+
+    #[error("The response is not signed by the peer {0:?}")]
+    ResponseSignatureInvalid(Box<NetworkAddress>),
+
+    #[error("Could not compress the response")]
+    ResponseCompressionFailed,
+
+    #[error("Could not decompress the response")]
+    ResponseDecompressionFailed,
+
+    /// The payload is larger than the peer it's sent to accepts, as advertised by the peer.
+    #[error("A payload of {size} bytes exceeds the limit of {limit} bytes of the peer")]
+    PayloadExceedsPeerLimit {
+        /// The size of the payload
+        size: usize,
+        /// The maximum size of the payloads the peer accepts
+        limit: usize,
+    },
+
+    // ---------- address errors
+    /// The bytes can't be those of an address of this kind.
+    #[error("Invalid bytes for a {kind:?} address: {reason}")]
+    AddressBytesInvalid {
+        /// The kind of address the bytes were expected to be of
+        kind: AddressKind,
+        /// Why the bytes are invalid
+        reason: String,
+    },
 
     // ---------- record errors
     // Could not Serialize/Deserialize RecordHeader from Record
@@ -108,4 +202,181 @@ pub enum Error {
     // The RecordKind that was obtained did not match with the expected one
     #[error("The RecordKind obtained from the Record did not match with the expected kind: {0}")]
     RecordKindMismatch(RecordKind),
+    /// The node doesn't store records of this kind.
+    #[error("The node does not store records of kind {0}")]
+    KindNotAccepted(RecordKind),
+    /// The node already stores as many records of this kind as its quota allows.
+    #[error("The node reached its quota of records of kind {kind:?}")]
+    KindQuotaExceeded {
+        /// The kind of the address of the rejected record
+        kind: AddressKind,
+    },
+}
+
+impl Error {
+    /// Returns `true` if the error is transient, e.g. the data has not reached the node yet or the
+    /// node could not serve the request at this time, and the request is worth retrying.
+    /// Errors such as invalid signatures, double spends or denied access are terminal.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::ChunkNotFound(_)
+            | Error::ChunkNotStored(_)
+            | Error::RegisterNotStored(_)
+            | Error::RegisterNotFound(_)
+            | Error::SignedDataNotFound(_)
+            | Error::SignedDataNotStored(_)
+            | Error::SpendNotFound(_)
+            | Error::SpendNotStored(_)
+            | Error::GetStoreCostFailed
+            | Error::SignStoreCostFailed
+            | Error::ReplicatedDataNotFound { .. }
+            | Error::RecordKeyFilterFailed
+            | Error::RecordNotDeleted(_)
+            | Error::Overloaded
+            | Error::ValidationBudgetExceeded
+            | Error::NotReady => true,
+            Error::RegisterInvalid(_)
+            | Error::RegisterError(_)
+            | Error::RegisterAlreadyClaimed(_)
+            | Error::SignedDataInvalid(_)
+            | Error::SignedDataAlreadyStored(_)
+            | Error::DeleteNotAuthorized(_)
+            | Error::DeleteExpired
+            | Error::Unauthorized(_)
+            | Error::RequestParsingFailed
+            | Error::ResponseSigningFailed
+            | Error::ResponseSignatureInvalid(_)
+            | Error::ResponseCompressionFailed
+            | Error::ResponseDecompressionFailed
+            | Error::PayloadExceedsPeerLimit { .. }
+            | Error::DoubleSpendAttempt(_, _)
+            | Error::SpendDivergence { .. }
+            | Error::SpendSignatureInvalid(_)
+            | Error::SpendParentTxInvalid(_)
+            | Error::SpendIsEmpty
+            | Error::SpendDbcIdMismatch(_)
+            | Error::TooManySpends { .. }
+            | Error::PaymentProofInsufficientAmount { .. }
+            | Error::PaymentProofTxMismatch(_)
+            | Error::PaymentProofWithoutInputs(_)
+            | Error::PaymentProofInvalidFeeOutput(_)
+            | Error::InvalidPaymentProof { .. }
+            | Error::UtxoSerialisationFailed
+            | Error::UtxoDecryptionFailed
+            | Error::AddressBytesInvalid { .. }
+            | Error::RecordHeaderParsingFailed
+            | Error::RecordParsingFailed
+            | Error::RecordKeyMismatch
+            | Error::RecordKindMismatch(_)
+            | Error::KindNotAccepted(_)
+            | Error::KindQuotaExceeded { .. } => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::random_signed_spend;
+    use bls::SecretKey;
+    use sn_dbc::rand;
+    use sn_registers::User;
+
+    #[test]
+    fn retryable_classification() {
+        let name = XorName::random(&mut rand::thread_rng());
+        let reg_addr = Box::new(RegisterAddress::new(name, SecretKey::random().public_key()));
+
+        let retryable = vec![
+            Error::ChunkNotFound(ChunkAddress::new(name)),
+            Error::ChunkNotStored(name),
+            Error::RegisterNotStored(reg_addr.clone()),
+            Error::RegisterNotFound(reg_addr.clone()),
+            Error::SignedDataNotFound(SignedDataAddress::new(name)),
+            Error::SignedDataNotStored(SignedDataAddress::new(name)),
+            Error::SpendNotFound(DbcAddress::new(name)),
+            Error::SpendNotStored("not stored".to_string()),
+            Error::GetStoreCostFailed,
+            Error::SignStoreCostFailed,
+            Error::ReplicatedDataNotFound {
+                holder: Box::new(NetworkAddress::from_chunk_address(ChunkAddress::new(name))),
+                address: Box::new(NetworkAddress::from_chunk_address(ChunkAddress::new(name))),
+            },
+            Error::RecordKeyFilterFailed,
+            Error::RecordNotDeleted(Box::new(NetworkAddress::from_chunk_address(
+                ChunkAddress::new(name),
+            ))),
+            Error::Overloaded,
+            Error::ValidationBudgetExceeded,
+            Error::NotReady,
+        ];
+        for error in retryable {
+            assert!(error.is_retryable(), "{error:?} should be retryable");
+        }
+
+        let terminal = vec![
+            Error::RegisterInvalid(reg_addr),
+            Error::RegisterError(sn_registers::Error::AccessDenied(User::Anyone)),
+            Error::RegisterAlreadyClaimed(SecretKey::random().public_key()),
+            Error::SignedDataInvalid(SignedDataAddress::new(name)),
+            Error::SignedDataAlreadyStored(SignedDataAddress::new(name)),
+            Error::DeleteNotAuthorized(SecretKey::random().public_key()),
+            Error::DeleteExpired,
+            Error::Unauthorized(Box::new(NetworkAddress::from_chunk_address(
+                ChunkAddress::new(name),
+            ))),
+            Error::RequestParsingFailed,
+            Error::ResponseSigningFailed,
+            Error::ResponseSignatureInvalid(Box::new(NetworkAddress::from_chunk_address(
+                ChunkAddress::new(name),
+            ))),
+            Error::ResponseCompressionFailed,
+            Error::ResponseDecompressionFailed,
+            Error::PayloadExceedsPeerLimit {
+                size: 2048,
+                limit: 1024,
+            },
+            Error::DoubleSpendAttempt(
+                Box::new(random_signed_spend()),
+                Box::new(random_signed_spend()),
+            ),
+            Error::SpendDivergence {
+                address: DbcAddress::new(name),
+                variants: vec![(random_signed_spend(), 2), (random_signed_spend(), 2)],
+            },
+            Error::SpendSignatureInvalid("invalid".to_string()),
+            Error::SpendParentTxInvalid("invalid".to_string()),
+            Error::SpendIsEmpty,
+            Error::SpendDbcIdMismatch(DbcAddress::new(name)),
+            Error::TooManySpends { count: 3, limit: 2 },
+            Error::PaymentProofInsufficientAmount {
+                paid: Token::zero(),
+                expected: Token::from_nano(1),
+            },
+            Error::PaymentProofTxMismatch(name),
+            Error::PaymentProofWithoutInputs(name),
+            Error::PaymentProofInvalidFeeOutput(Hash::default()),
+            Error::InvalidPaymentProof {
+                addr_name: name,
+                reason: "invalid".to_string(),
+            },
+            Error::UtxoSerialisationFailed,
+            Error::UtxoDecryptionFailed,
+            Error::AddressBytesInvalid {
+                kind: AddressKind::ChunkAddress,
+                reason: "invalid".to_string(),
+            },
+            Error::RecordHeaderParsingFailed,
+            Error::RecordParsingFailed,
+            Error::RecordKeyMismatch,
+            Error::RecordKindMismatch(RecordKind::Chunk),
+            Error::KindNotAccepted(RecordKind::Register),
+            Error::KindQuotaExceeded {
+                kind: AddressKind::RegisterAddress,
+            },
+        ];
+        for error in terminal {
+            assert!(!error.is_retryable(), "{error:?} should be terminal");
+        }
+    }
 }
@@ -0,0 +1,209 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::NetworkAddress;
+use std::{
+    borrow::Borrow,
+    collections::HashSet,
+    fmt::{self, Debug, Display, Formatter},
+    hash::{Hash, Hasher},
+    ops::Deref,
+    sync::Arc,
+};
+
+/// A shared `NetworkAddress`, which is cheap to clone as all its clones share the same storage.
+///
+/// It compares, orders and hashes exactly as the `NetworkAddress` it holds, which it also
+/// derefs to, so it can be used in place of one, e.g. to look it up in a `HashSet<InternedAddress>`.
+#[derive(Clone)]
+pub struct InternedAddress(Arc<NetworkAddress>);
+
+impl InternedAddress {
+    /// Share the given `NetworkAddress`, without going through an `AddressInterner`.
+    pub fn new(address: NetworkAddress) -> Self {
+        Self(Arc::new(address))
+    }
+
+    /// Return a `NetworkAddress` copy of the shared address.
+    pub fn to_network_address(&self) -> NetworkAddress {
+        self.0.as_ref().clone()
+    }
+}
+
+impl Deref for InternedAddress {
+    type Target = NetworkAddress;
+
+    fn deref(&self) -> &NetworkAddress {
+        &self.0
+    }
+}
+
+impl Borrow<NetworkAddress> for InternedAddress {
+    fn borrow(&self) -> &NetworkAddress {
+        &self.0
+    }
+}
+
+impl From<NetworkAddress> for InternedAddress {
+    fn from(address: NetworkAddress) -> Self {
+        Self::new(address)
+    }
+}
+
+impl PartialEq for InternedAddress {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0) || self.0 == other.0
+    }
+}
+
+impl Eq for InternedAddress {}
+
+impl PartialEq<NetworkAddress> for InternedAddress {
+    fn eq(&self, other: &NetworkAddress) -> bool {
+        self.0.as_ref() == other
+    }
+}
+
+impl PartialOrd for InternedAddress {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for InternedAddress {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+impl Hash for InternedAddress {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state)
+    }
+}
+
+impl Debug for InternedAddress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Debug::fmt(&self.0, f)
+    }
+}
+
+impl Display for InternedAddress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+/// A pool of `InternedAddress`es, so that repeated references to the same address,
+/// e.g. the peers seen while routing, share their storage.
+#[derive(Default)]
+pub struct AddressInterner {
+    addresses: HashSet<InternedAddress>,
+}
+
+impl AddressInterner {
+    /// Create an empty pool.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the pooled `InternedAddress` equal to `address`, adding it to the pool if needed.
+    pub fn intern(&mut self, address: NetworkAddress) -> InternedAddress {
+        if let Some(interned) = self.addresses.get(&address) {
+            return interned.clone();
+        }
+        let interned = InternedAddress::new(address);
+        let _ = self.addresses.insert(interned.clone());
+        interned
+    }
+
+    /// The number of addresses in the pool.
+    pub fn len(&self) -> usize {
+        self.addresses.len()
+    }
+
+    /// Returns `true` if there are no addresses in the pool.
+    pub fn is_empty(&self) -> bool {
+        self.addresses.is_empty()
+    }
+
+    /// Remove the addresses which are no longer referenced outside of the pool.
+    pub fn prune(&mut self) {
+        self.addresses
+            .retain(|interned| Arc::strong_count(&interned.0) > 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::ChunkAddress;
+    use libp2p::PeerId;
+    use std::collections::hash_map::DefaultHasher;
+    use xor_name::XorName;
+
+    fn hash_of<T: Hash + ?Sized>(value: &T) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn interned_addresses_compare_and_hash_as_the_plain_ones() {
+        let mut rng = bls::rand::thread_rng();
+        let mut interner = AddressInterner::new();
+        let addresses = [
+            NetworkAddress::from_peer(PeerId::random()),
+            NetworkAddress::from_chunk_address(ChunkAddress::new(XorName::random(&mut rng))),
+            NetworkAddress::from_peer(PeerId::random()),
+        ];
+
+        let interned: Vec<_> = addresses
+            .iter()
+            .map(|address| interner.intern(address.clone()))
+            .collect();
+
+        for (plain, interned) in addresses.iter().zip(&interned) {
+            assert_eq!(interned, plain);
+            assert_eq!(hash_of(interned), hash_of(plain));
+            assert_eq!(&interned.to_network_address(), plain);
+            assert_eq!(interned.distance(plain), plain.distance(plain));
+        }
+        for (i, j) in [(0, 1), (1, 2), (0, 2)] {
+            assert_eq!(
+                interned[i].cmp(&interned[j]),
+                addresses[i].cmp(&addresses[j])
+            );
+            assert_ne!(interned[i], interned[j]);
+        }
+
+        // plain addresses can be looked up among interned ones
+        let set: HashSet<_> = interned.iter().cloned().collect();
+        assert!(addresses.iter().all(|address| set.contains(address)));
+    }
+
+    #[test]
+    fn interning_shares_the_storage_of_equal_addresses() {
+        let mut interner = AddressInterner::new();
+        let peer = NetworkAddress::from_peer(PeerId::random());
+
+        let first = interner.intern(peer.clone());
+        let second = interner.intern(peer);
+        assert!(Arc::ptr_eq(&first.0, &second.0));
+        assert_eq!(interner.len(), 1);
+
+        // addresses still referenced are kept
+        interner.prune();
+        assert_eq!(interner.len(), 1);
+
+        drop(first);
+        drop(second);
+        interner.prune();
+        assert!(interner.is_empty());
+    }
+}
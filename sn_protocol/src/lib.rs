@@ -12,15 +12,26 @@ pub mod error;
 pub mod messages;
 /// Storage types for spends, chunks and registers.
 pub mod storage;
+/// Factories of valid data for the tests of this crate and of its dependents.
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
 
-use self::storage::{ChunkAddress, DbcAddress, RegisterAddress};
-use bytes::Bytes;
+mod interned;
+
+pub use self::interned::{AddressInterner, InternedAddress};
+
+use self::{
+    error::{Error, Result},
+    storage::{ChunkAddress, DbcAddress, RegisterAddress, SignedDataAddress},
+};
+use bls::PK_SIZE;
 use libp2p::{
     kad::{KBucketDistance as Distance, KBucketKey as Key, RecordKey},
     PeerId,
 };
 use serde::{Deserialize, Serialize};
 use std::fmt::{self, Debug, Display, Formatter};
+use xor_name::{XorName, XOR_NAME_LEN};
 
 /// This is the address in the network by which proximity/distance
 /// to other items (whether nodes or data chunks) are calculated.
@@ -42,9 +53,97 @@ pub enum NetworkAddress {
     RegisterAddress(RegisterAddress),
     /// The NetworkAddress is representing a RecordKey.
     RecordKey(Vec<u8>),
+    /// The NetworkAddress is representing a SignedDataAddress.
+    SignedDataAddress(SignedDataAddress),
+}
+
+/// The kind of a `NetworkAddress`, i.e. its variant without its content.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum AddressKind {
+    /// A `NetworkAddress::PeerId`
+    PeerId,
+    /// A `NetworkAddress::ChunkAddress`
+    ChunkAddress,
+    /// A `NetworkAddress::DbcAddress`
+    DbcAddress,
+    /// A `NetworkAddress::RegisterAddress`
+    RegisterAddress,
+    /// A `NetworkAddress::RecordKey`
+    RecordKey,
+    /// A `NetworkAddress::SignedDataAddress`
+    SignedDataAddress,
 }
 
 impl NetworkAddress {
+    /// Reconstruct a `NetworkAddress` of the given `kind` from its raw `bytes`, i.e. those
+    /// returned by `as_bytes`, the xorname of chunk, dbc and signed data addresses.
+    ///
+    /// A register's xorname is a hash of its meta and owner, which can't be reversed: its bytes
+    /// are instead its meta followed by its owner's public key, as in `RegisterAddress::to_hex`.
+    pub fn from_bytes(kind: AddressKind, bytes: &[u8]) -> Result<Self> {
+        let invalid = |reason: String| Error::AddressBytesInvalid { kind, reason };
+        let xorname = || -> Result<XorName> {
+            let name: [u8; XOR_NAME_LEN] = bytes.try_into().map_err(|_| {
+                invalid(format!(
+                    "expected a {XOR_NAME_LEN} bytes xorname, got {} bytes",
+                    bytes.len()
+                ))
+            })?;
+            Ok(XorName(name))
+        };
+
+        let address = match kind {
+            AddressKind::PeerId => {
+                let peer_id = PeerId::from_bytes(bytes)
+                    .map_err(|err| invalid(format!("not a peer id: {err}")))?;
+                NetworkAddress::from_peer(peer_id)
+            }
+            AddressKind::ChunkAddress => {
+                NetworkAddress::from_chunk_address(ChunkAddress::new(xorname()?))
+            }
+            AddressKind::DbcAddress => {
+                NetworkAddress::from_dbc_address(DbcAddress::new(xorname()?))
+            }
+            AddressKind::RegisterAddress => {
+                if bytes.len() != XOR_NAME_LEN + PK_SIZE {
+                    return Err(invalid(format!(
+                        "expected a {XOR_NAME_LEN} bytes meta followed by a {PK_SIZE} bytes \
+                         owner key, got {} bytes",
+                        bytes.len()
+                    )));
+                }
+                let (meta, owner) = bytes.split_at(XOR_NAME_LEN);
+                let mut meta_bytes = [0; XOR_NAME_LEN];
+                meta_bytes.copy_from_slice(meta);
+                let mut owner_bytes = [0; PK_SIZE];
+                owner_bytes.copy_from_slice(owner);
+                let owner = bls::PublicKey::from_bytes(owner_bytes)
+                    .map_err(|err| invalid(format!("invalid owner key: {err}")))?;
+                NetworkAddress::from_register_address(RegisterAddress::new(
+                    XorName(meta_bytes),
+                    owner,
+                ))
+            }
+            AddressKind::RecordKey => NetworkAddress::RecordKey(bytes.to_vec()),
+            AddressKind::SignedDataAddress => {
+                NetworkAddress::from_signed_data_address(SignedDataAddress::new(xorname()?))
+            }
+        };
+        Ok(address)
+    }
+
+    /// Return the kind of this `NetworkAddress`.
+    pub fn kind(&self) -> AddressKind {
+        match self {
+            NetworkAddress::PeerId(_) => AddressKind::PeerId,
+            NetworkAddress::ChunkAddress(_) => AddressKind::ChunkAddress,
+            NetworkAddress::DbcAddress(_) => AddressKind::DbcAddress,
+            NetworkAddress::RegisterAddress(_) => AddressKind::RegisterAddress,
+            NetworkAddress::RecordKey(_) => AddressKind::RecordKey,
+            NetworkAddress::SignedDataAddress(_) => AddressKind::SignedDataAddress,
+        }
+    }
+
     /// Return a `NetworkAddress` representation of the `ChunkAddress`.
     pub fn from_chunk_address(chunk_address: ChunkAddress) -> Self {
         NetworkAddress::ChunkAddress(chunk_address)
@@ -60,6 +159,11 @@ impl NetworkAddress {
         NetworkAddress::RegisterAddress(register_address)
     }
 
+    /// Return a `NetworkAddress` representation of the `SignedDataAddress`.
+    pub fn from_signed_data_address(signed_data_address: SignedDataAddress) -> Self {
+        NetworkAddress::SignedDataAddress(signed_data_address)
+    }
+
     /// Return a `NetworkAddress` representation of the `PeerId` by encapsulating its bytes.
     pub fn from_peer(peer_id: PeerId) -> Self {
         NetworkAddress::PeerId(peer_id.to_bytes())
@@ -79,6 +183,9 @@ impl NetworkAddress {
             NetworkAddress::RegisterAddress(register_address) => {
                 register_address.xorname().0.to_vec()
             }
+            NetworkAddress::SignedDataAddress(signed_data_address) => {
+                signed_data_address.xorname().0.to_vec()
+            }
         }
     }
 
@@ -110,6 +217,9 @@ impl NetworkAddress {
                 RecordKey::new(&register_address.xorname())
             }
             NetworkAddress::DbcAddress(dbc_address) => RecordKey::new(dbc_address.xorname()),
+            NetworkAddress::SignedDataAddress(signed_data_address) => {
+                RecordKey::new(signed_data_address.xorname())
+            }
             NetworkAddress::PeerId(bytes) => RecordKey::new(bytes),
         }
     }
@@ -129,7 +239,53 @@ impl NetworkAddress {
         self.as_kbucket_key().distance(&other.as_kbucket_key())
     }
 
+    /// Compute the distances to each of the `targets`, in the same order.
+    /// Our own key is only computed once, which is cheaper than calling `distance` for each target.
+    pub fn distances_to(&self, targets: &[NetworkAddress]) -> Vec<Distance> {
+        let key = self.as_kbucket_key();
+        targets
+            .iter()
+            .map(|target| key.distance(&target.as_kbucket_key()))
+            .collect()
+    }
+
+    /// Sort the `addrs` by increasing distance to `self`.
+    /// Ties, i.e. addresses of the same bytes, are broken by their bytes then their kind,
+    /// so that the order doesn't depend on the one of the `addrs`.
+    pub fn sort_by_distance(&self, addrs: &mut [NetworkAddress]) {
+        let key = self.as_kbucket_key();
+        addrs.sort_by_cached_key(|addr| {
+            (
+                key.distance(&addr.as_kbucket_key()),
+                addr.as_bytes(),
+                addr.kind(),
+            )
+        });
+    }
+
+    /// Return the `n` closest of the `addrs` to `self`, by increasing distance, with ties broken
+    /// as by `sort_by_distance`.
+    pub fn closest<'a>(&self, addrs: &'a [NetworkAddress], n: usize) -> Vec<&'a NetworkAddress> {
+        let key = self.as_kbucket_key();
+        let mut closest: Vec<_> = addrs
+            .iter()
+            .map(|addr| {
+                let order = (
+                    key.distance(&addr.as_kbucket_key()),
+                    addr.as_bytes(),
+                    addr.kind(),
+                );
+                (order, addr)
+            })
+            .collect();
+        closest.sort_by(|(a, _), (b, _)| a.cmp(b));
+        closest.into_iter().take(n).map(|(_, addr)| addr).collect()
+    }
+
     // NB: Leaving this here as to demonstrate what we can do with this.
+    // It can't be exposed as is: libp2p-kad doesn't re-export `KeyBytes` from its private
+    // `kbucket` module, nor give access to the bits of a `Distance`, so the key can neither be
+    // named in our API nor be converted into bytes to build a `NetworkAddress` from.
     // /// Return the uniquely determined key with the given distance to `self`.
     // ///
     // /// This implements the following equivalence:
@@ -157,6 +313,10 @@ impl Debug for NetworkAddress {
                 "NetworkAddress::RegisterAddress({:?} - ",
                 register_address.xorname()
             ),
+            NetworkAddress::SignedDataAddress(signed_data_address) => format!(
+                "NetworkAddress::SignedDataAddress({:?} - ",
+                signed_data_address.xorname()
+            ),
             NetworkAddress::RecordKey(_) => "NetworkAddress::RecordKey(".to_string(),
         };
         write!(
@@ -182,6 +342,9 @@ impl Display for NetworkAddress {
             NetworkAddress::RegisterAddress(addr) => {
                 write!(f, "NetworkAddress::RegisterAddress({addr:?})")
             }
+            NetworkAddress::SignedDataAddress(addr) => {
+                write!(f, "NetworkAddress::SignedDataAddress({addr:?})")
+            }
             NetworkAddress::RecordKey(key) => {
                 write!(f, "NetworkAddress::RecordKey({})", hex::encode(key))
             }
@@ -203,11 +366,17 @@ impl From<RecordKey> for PrettyPrintRecordKey {
     }
 }
 
+impl PrettyPrintRecordKey {
+    /// The first and last 6 hex chars of the key, for it not to flood the logs.
+    /// Keys of up to 7 bytes are printed in full.
+    pub fn short(&self) -> String {
+        PrettyPrintRecordKeyRef(&self.0).short()
+    }
+}
+
 impl std::fmt::Display for PrettyPrintRecordKey {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let b: Vec<u8> = self.0.as_ref().to_vec();
-        let record_key_b = Bytes::from(b);
-        write!(f, "{:64x}", record_key_b)
+        write!(f, "{}", PrettyPrintRecordKeyRef(&self.0))
     }
 }
 
@@ -216,3 +385,233 @@ impl std::fmt::Debug for PrettyPrintRecordKey {
         write!(f, "{}", self)
     }
 }
+
+/// Same as `PrettyPrintRecordKey`, but borrowing the `RecordKey`, to format it without cloning it.
+#[derive(Clone, Copy)]
+pub struct PrettyPrintRecordKeyRef<'a>(&'a RecordKey);
+
+impl<'a> From<&'a RecordKey> for PrettyPrintRecordKeyRef<'a> {
+    fn from(key: &'a RecordKey) -> Self {
+        PrettyPrintRecordKeyRef(key)
+    }
+}
+
+impl<'a> PrettyPrintRecordKeyRef<'a> {
+    /// The first and last 6 hex chars of the key, see `PrettyPrintRecordKey::short`.
+    pub fn short(&self) -> String {
+        let hex = hex::encode(self.0.as_ref());
+        if hex.len() <= 14 {
+            return hex;
+        }
+        format!("{}..{}", &hex[..6], &hex[hex.len() - 6..])
+    }
+}
+
+impl<'a> std::fmt::Display for PrettyPrintRecordKeyRef<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for byte in self.0.as_ref() {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> std::fmt::Debug for PrettyPrintRecordKeyRef<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distances_to_matches_distance() {
+        let mut rng = bls::rand::thread_rng();
+        let address = NetworkAddress::from_peer(PeerId::random());
+        let targets: Vec<_> = (0..100)
+            .map(|i| {
+                if i % 2 == 0 {
+                    NetworkAddress::from_peer(PeerId::random())
+                } else {
+                    NetworkAddress::from_chunk_address(ChunkAddress::new(XorName::random(&mut rng)))
+                }
+            })
+            .collect();
+
+        let expected: Vec<_> = targets
+            .iter()
+            .map(|target| address.distance(target))
+            .collect();
+
+        assert_eq!(address.distances_to(&targets), expected);
+        assert!(address.distances_to(&[]).is_empty());
+    }
+
+    #[test]
+    fn addresses_are_sorted_by_distance_with_ties_broken_by_bytes_and_kind() {
+        let chunk =
+            |byte| NetworkAddress::from_chunk_address(ChunkAddress::new(XorName([byte; 32])));
+        let target = chunk(0);
+        let dbc_3 = NetworkAddress::from_dbc_address(DbcAddress::new(XorName([3; 32])));
+        let record_key_3 = NetworkAddress::from_record_key(RecordKey::new(&[3; 32]));
+
+        // by the distances of the SHA-256 hashes of the bytes to the one of `[0; 32]`,
+        // and the three addresses of bytes `[3; 32]` being at the same distance, by kind
+        let expected = vec![
+            chunk(3),
+            dbc_3.clone(),
+            record_key_3.clone(),
+            chunk(2),
+            chunk(1),
+            chunk(6),
+            chunk(5),
+            chunk(4),
+        ];
+
+        let mut addrs = vec![
+            chunk(1),
+            record_key_3,
+            chunk(2),
+            chunk(4),
+            dbc_3,
+            chunk(5),
+            chunk(3),
+            chunk(6),
+        ];
+        let closest: Vec<NetworkAddress> = target.closest(&addrs, 4).into_iter().cloned().collect();
+        assert_eq!(closest, expected[..4]);
+        assert_eq!(target.closest(&addrs, 20).len(), addrs.len());
+
+        target.sort_by_distance(&mut addrs);
+        assert_eq!(addrs, expected);
+        addrs.reverse();
+        target.sort_by_distance(&mut addrs);
+        assert_eq!(addrs, expected);
+    }
+
+    #[test]
+    fn record_key_short_form_keeps_its_ends() {
+        let mut bytes = [0x77; 32];
+        bytes[..3].copy_from_slice(&[0xab, 0xc1, 0x23]);
+        bytes[29..].copy_from_slice(&[0xde, 0xf4, 0x56]);
+        let key = PrettyPrintRecordKey::from(RecordKey::new(&bytes));
+
+        assert_eq!(key.short(), "abc123..def456");
+        assert_eq!(key.to_string(), hex::encode(bytes));
+
+        let key = PrettyPrintRecordKey::from(RecordKey::new(&[0xab, 0xcd]));
+        assert_eq!(key.short(), "abcd");
+    }
+
+    #[test]
+    fn borrowed_and_owned_record_keys_print_the_same() {
+        let key = NetworkAddress::from_chunk_address(ChunkAddress::new(XorName::random(
+            &mut bls::rand::thread_rng(),
+        )))
+        .to_record_key();
+        let owned = PrettyPrintRecordKey::from(key.clone());
+        let borrowed = PrettyPrintRecordKeyRef::from(&key);
+
+        assert_eq!(borrowed.to_string(), owned.to_string());
+        assert_eq!(format!("{borrowed:?}"), format!("{owned:?}"));
+        assert_eq!(borrowed.short(), owned.short());
+        assert_eq!(borrowed.to_string(), hex::encode(key.as_ref()));
+    }
+
+    #[test]
+    fn each_constructor_yields_the_expected_kind() {
+        let name = XorName::random(&mut bls::rand::thread_rng());
+        let owner = bls::SecretKey::random().public_key();
+
+        let kinds = [
+            (
+                NetworkAddress::from_peer(PeerId::random()),
+                AddressKind::PeerId,
+            ),
+            (
+                NetworkAddress::from_chunk_address(ChunkAddress::new(name)),
+                AddressKind::ChunkAddress,
+            ),
+            (
+                NetworkAddress::from_dbc_address(DbcAddress::new(name)),
+                AddressKind::DbcAddress,
+            ),
+            (
+                NetworkAddress::from_register_address(RegisterAddress::new(name, owner)),
+                AddressKind::RegisterAddress,
+            ),
+            (
+                NetworkAddress::from_record_key(RecordKey::new(&name)),
+                AddressKind::RecordKey,
+            ),
+            (
+                NetworkAddress::from_signed_data_address(SignedDataAddress::new(name)),
+                AddressKind::SignedDataAddress,
+            ),
+        ];
+        for (address, kind) in kinds {
+            assert_eq!(address.kind(), kind, "{address:?}");
+        }
+    }
+
+    #[test]
+    fn addresses_round_trip_through_their_kind_and_bytes() -> Result<()> {
+        let mut rng = bls::rand::thread_rng();
+        let name = XorName::random(&mut rng);
+        let owner = bls::SecretKey::random().public_key();
+
+        for address in [
+            NetworkAddress::from_chunk_address(ChunkAddress::new(name)),
+            NetworkAddress::from_dbc_address(DbcAddress::new(name)),
+            NetworkAddress::from_signed_data_address(SignedDataAddress::new(name)),
+            NetworkAddress::from_peer(PeerId::random()),
+            NetworkAddress::from_record_key(RecordKey::new(&name)),
+        ] {
+            assert_eq!(
+                NetworkAddress::from_bytes(address.kind(), &address.as_bytes())?,
+                address
+            );
+        }
+
+        // a register is rebuilt from its meta and owner rather than its hashed xorname
+        let register = RegisterAddress::new(name, owner);
+        let address = NetworkAddress::from_register_address(register);
+        let mut bytes = register.meta().0.to_vec();
+        bytes.extend_from_slice(&owner.to_bytes());
+        assert_eq!(NetworkAddress::from_bytes(address.kind(), &bytes)?, address);
+        assert!(matches!(
+            NetworkAddress::from_bytes(address.kind(), &address.as_bytes()),
+            Err(Error::AddressBytesInvalid {
+                kind: AddressKind::RegisterAddress,
+                ..
+            })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn address_bytes_of_the_wrong_length_are_rejected() {
+        for kind in [
+            AddressKind::ChunkAddress,
+            AddressKind::DbcAddress,
+            AddressKind::SignedDataAddress,
+            AddressKind::RegisterAddress,
+        ] {
+            let result = NetworkAddress::from_bytes(kind, &[0; 31]);
+            match result {
+                Err(Error::AddressBytesInvalid {
+                    kind: invalid_kind,
+                    reason,
+                }) => {
+                    assert_eq!(invalid_kind, kind);
+                    assert!(reason.contains("31 bytes"), "{reason}");
+                }
+                other => panic!("Expected invalid bytes for {kind:?}, got {other:?}"),
+            }
+        }
+        assert!(NetworkAddress::from_bytes(AddressKind::PeerId, &[0; 32]).is_err());
+    }
+}
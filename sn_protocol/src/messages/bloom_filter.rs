@@ -0,0 +1,188 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use serde::{Deserialize, Serialize};
+use xor_name::XorName;
+
+/// The false positive rate used when building a filter of the record keys held by a node.
+pub const RECORD_KEY_FILTER_FP_RATE: f64 = 0.01;
+
+/// The maximum number of hashes of a key, bounding the work of querying a filter received from a
+/// peer. Filters built with a false positive rate as low as 1e-9 use 30 of them.
+const MAX_FILTER_HASHES: u32 = 32;
+
+/// A compact, probabilistic set of record keys.
+///
+/// A `BloomFilter` never reports a held key as absent, but it can report a key that was never
+/// inserted as present. The rate of such false positives is chosen at construction: a lower rate
+/// costs more bits per key. When used to estimate which keys a peer is missing, a false positive
+/// means a key is wrongly assumed to be held by the peer; these are expected to be picked up by a
+/// later round of replication.
+///
+/// Filters received from peers are checked to be well formed when deserialised, so that querying
+/// them can neither panic nor cost more than `MAX_FILTER_HASHES` hashes per key.
+#[derive(Clone, Debug, Eq, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(try_from = "UncheckedBloomFilter")]
+pub struct BloomFilter {
+    bits: Vec<u8>,
+    num_bits: u64,
+    num_hashes: u32,
+}
+
+// A `BloomFilter` as deserialised, before its fields are checked to be consistent.
+#[derive(Deserialize)]
+struct UncheckedBloomFilter {
+    bits: Vec<u8>,
+    num_bits: u64,
+    num_hashes: u32,
+}
+
+impl TryFrom<UncheckedBloomFilter> for BloomFilter {
+    type Error = &'static str;
+
+    fn try_from(filter: UncheckedBloomFilter) -> Result<Self, Self::Error> {
+        if filter.num_bits == 0 {
+            return Err("a bloom filter must have at least one bit");
+        }
+        if filter.bits.len() as u64 != filter.num_bits.div_ceil(8) {
+            return Err("the bits of a bloom filter must hold exactly its number of bits");
+        }
+        if !(1..=MAX_FILTER_HASHES).contains(&filter.num_hashes) {
+            return Err("a bloom filter must use between 1 and 32 hashes");
+        }
+        Ok(Self {
+            bits: filter.bits,
+            num_bits: filter.num_bits,
+            num_hashes: filter.num_hashes,
+        })
+    }
+}
+
+impl BloomFilter {
+    /// Create an empty filter sized to hold `expected_items` with the given false positive rate.
+    pub fn new(expected_items: usize, false_positive_rate: f64) -> Self {
+        let items = expected_items.max(1) as f64;
+        let rate = false_positive_rate.clamp(f64::MIN_POSITIVE, 0.5);
+        let ln2 = std::f64::consts::LN_2;
+
+        let num_bits = ((-items * rate.ln()) / (ln2 * ln2)).ceil().max(8.0) as u64;
+        let num_hashes = ((num_bits as f64 / items) * ln2)
+            .round()
+            .clamp(1.0, MAX_FILTER_HASHES as f64) as u32;
+
+        Self {
+            bits: vec![0; num_bits.div_ceil(8) as usize],
+            num_bits,
+            num_hashes,
+        }
+    }
+
+    /// Insert the given key into the filter.
+    pub fn insert(&mut self, key: &[u8]) {
+        for index in self.bit_indexes(key) {
+            self.bits[(index / 8) as usize] |= 1 << (index % 8);
+        }
+    }
+
+    /// Returns `true` if the key may have been inserted, `false` if it definitely was not.
+    pub fn contains(&self, key: &[u8]) -> bool {
+        self.bit_indexes(key)
+            .all(|index| self.bits[(index / 8) as usize] & (1 << (index % 8)) != 0)
+    }
+
+    // Derive the bit positions of a key using double hashing over its sha3 digest.
+    fn bit_indexes(&self, key: &[u8]) -> impl Iterator<Item = u64> {
+        let digest = XorName::from_content(key).0;
+        let mut h1 = [0u8; 8];
+        let mut h2 = [0u8; 8];
+        h1.copy_from_slice(&digest[..8]);
+        h2.copy_from_slice(&digest[8..16]);
+        let h1 = u64::from_le_bytes(h1);
+        let h2 = u64::from_le_bytes(h2);
+        let num_bits = self.num_bits;
+
+        (0..self.num_hashes as u64).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % num_bits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filter_reports_held_keys_and_mostly_rejects_others() {
+        let mut rng = bls::rand::thread_rng();
+        let held: Vec<XorName> = (0..1000).map(|_| XorName::random(&mut rng)).collect();
+
+        let mut filter = BloomFilter::new(held.len(), RECORD_KEY_FILTER_FP_RATE);
+        for key in &held {
+            filter.insert(&key.0);
+        }
+
+        assert!(held.iter().all(|key| filter.contains(&key.0)));
+
+        let false_positives = (0..1000)
+            .filter(|_| filter.contains(&XorName::random(&mut rng).0))
+            .count();
+        // expected ~1%, allow generous slack to keep the test deterministic enough
+        assert!(
+            false_positives < 50,
+            "got {false_positives} false positives"
+        );
+    }
+
+    #[test]
+    fn malformed_filters_are_rejected_when_deserialised() -> Result<(), rmp_serde::encode::Error> {
+        let filter = BloomFilter::new(10, RECORD_KEY_FILTER_FP_RATE);
+        let bytes = rmp_serde::to_vec(&filter)?;
+        assert_eq!(
+            rmp_serde::from_slice::<BloomFilter>(&bytes).ok(),
+            Some(filter)
+        );
+
+        #[derive(Serialize)]
+        struct Fields {
+            bits: Vec<u8>,
+            num_bits: u64,
+            num_hashes: u32,
+        }
+        let malformed = [
+            // no bits, which would divide by zero
+            Fields {
+                bits: vec![],
+                num_bits: 0,
+                num_hashes: 1,
+            },
+            // fewer bits than claimed, which would index out of bounds
+            Fields {
+                bits: vec![0; 2],
+                num_bits: 1024,
+                num_hashes: 1,
+            },
+            // more hashes than ever needed, which would burn CPU
+            Fields {
+                bits: vec![0; 1],
+                num_bits: 8,
+                num_hashes: u32::MAX,
+            },
+            Fields {
+                bits: vec![0; 1],
+                num_bits: 8,
+                num_hashes: 0,
+            },
+        ];
+        for fields in malformed {
+            let bytes = rmp_serde::to_vec_named(&fields)?;
+            assert!(rmp_serde::from_slice::<BloomFilter>(&bytes).is_err());
+            let bytes = rmp_serde::to_vec(&fields)?;
+            assert!(rmp_serde::from_slice::<BloomFilter>(&bytes).is_err());
+        }
+
+        Ok(())
+    }
+}
@@ -6,7 +6,9 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::NetworkAddress;
+use super::{CmdResponse, RegisterBatch};
+use crate::{error::Error, NetworkAddress};
+use bls::{PublicKey, SecretKey, Signature};
 use serde::{Deserialize, Serialize};
 // TODO: remove this dependency and define these types herein.
 pub use sn_dbc::{DbcId, Hash};
@@ -30,6 +32,29 @@ pub enum Cmd {
         #[debug(skip)]
         keys: Vec<NetworkAddress>,
     },
+    /// Operator cmd instructing a node to remove the record at the given address from its local
+    /// storage, e.g. to comply with a data-removal request.
+    ///
+    /// Only nodes which authorized the operator key act upon it, and the record remains held by
+    /// any other node, thus this is a best-effort local removal rather than a network guarantee.
+    /// The cmd is signed for a single node, and expires, so it can't be replayed to other nodes,
+    /// nor later on.
+    DeleteRecord {
+        /// Address of the record to remove.
+        address: NetworkAddress,
+        /// Time past which the cmd is rejected, in seconds since the UNIX epoch.
+        expires_at: u64,
+        /// Key of the operator requesting the removal.
+        operator: PublicKey,
+        /// Signature of the operator, see `Cmd::delete_record_bytes_for_signing`.
+        #[debug(skip)]
+        signature: Signature,
+    },
+    /// Create the Registers of the batch, which share the close group of its `dst`.
+    ///
+    /// Each Register is validated and stored as if it were put on its own, the response holding
+    /// a result per Register, in the order of the batch.
+    RegisterBatch(#[debug(skip)] RegisterBatch),
 }
 
 impl Cmd {
@@ -37,8 +62,55 @@ impl Cmd {
     pub fn dst(&self) -> NetworkAddress {
         match self {
             Cmd::Replicate { holder, .. } => holder.clone(),
+            Cmd::DeleteRecord { address, .. } => address.clone(),
+            Cmd::RegisterBatch(batch) => batch.dst.clone(),
+        }
+    }
+
+    /// The response to this cmd failing with `error`.
+    pub fn error_response(&self, error: Error) -> CmdResponse {
+        match self {
+            Cmd::Replicate { .. } => CmdResponse::Replicate(Err(error)),
+            Cmd::DeleteRecord { .. } => CmdResponse::DeleteRecord(Err(error)),
+            Cmd::RegisterBatch(_) => CmdResponse::RegisterBatch(Err(error)),
+        }
+    }
+
+    /// Create a `DeleteRecord` cmd for the record at `address`, signed by the operator for the
+    /// node at `target` till `expires_at`, in seconds since the UNIX epoch.
+    pub fn new_delete_record(
+        address: NetworkAddress,
+        target: &NetworkAddress,
+        expires_at: u64,
+        operator_sk: &SecretKey,
+    ) -> Self {
+        let signature = operator_sk.sign(Self::delete_record_bytes_for_signing(
+            &address, target, expires_at,
+        ));
+        Cmd::DeleteRecord {
+            address,
+            expires_at,
+            operator: operator_sk.public_key(),
+            signature,
         }
     }
+
+    /// The bytes an operator signs to request the node at `target` to remove the record at
+    /// `address` till `expires_at`.
+    pub fn delete_record_bytes_for_signing(
+        address: &NetworkAddress,
+        target: &NetworkAddress,
+        expires_at: u64,
+    ) -> Vec<u8> {
+        let target = target.as_bytes();
+        let mut bytes = b"DeleteRecord".to_vec();
+        // the target being of variable length, it's prefixed with its length
+        bytes.extend((target.len() as u64).to_be_bytes());
+        bytes.extend(target);
+        bytes.extend(expires_at.to_be_bytes());
+        bytes.extend(address.as_bytes());
+        bytes
+    }
 }
 
 impl std::fmt::Display for Cmd {
@@ -52,6 +124,19 @@ impl std::fmt::Display for Cmd {
                     keys.len()
                 )
             }
+            Cmd::DeleteRecord {
+                address, operator, ..
+            } => {
+                write!(f, "Cmd::DeleteRecord({address:?} by {operator:?})")
+            }
+            Cmd::RegisterBatch(batch) => {
+                write!(
+                    f,
+                    "Cmd::RegisterBatch({:?} of {} registers)",
+                    batch.dst,
+                    batch.registers.len()
+                )
+            }
         }
     }
 }
@@ -0,0 +1,177 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Compression of the large responses sent to peers.
+//!
+//! Peers advertise they accept compressed responses in the agent version they exchange on
+//! connection, so that peers of versions predating the compression are sent plain responses.
+
+use super::Response;
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+
+// Marks the acceptance of compressed responses in an agent version.
+const COMPRESSED_RESPONSES_MARKER: &str = " compressed-responses";
+// Brotli quality responses are compressed with, favouring speed as they're compressed on the fly.
+const RESPONSE_COMPRESSION_QUALITY: i32 = 4;
+// The largest response decompressed, which is the largest the request_response codec accepts,
+// so that a small compressed response can't expand to exhaust the memory of the requester.
+const MAX_DECOMPRESSED_RESPONSE_SIZE: u64 = 10 * 1024 * 1024;
+
+/// Append to our `agent_version` that we accept compressed responses.
+pub fn advertise_response_compression(agent_version: &str) -> String {
+    format!("{agent_version}{COMPRESSED_RESPONSES_MARKER}")
+}
+
+/// Whether a peer accepts compressed responses, as advertised in its `agent_version`.
+pub fn accepts_compressed_responses(agent_version: &str) -> bool {
+    agent_version.contains(COMPRESSED_RESPONSES_MARKER)
+}
+
+/// A `Response` compressed by the responding node, to be decompressed by the requester.
+#[derive(custom_debug::Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompressedResponse {
+    /// The brotli compressed serialisation of the `Response`.
+    #[debug(skip)]
+    response: Vec<u8>,
+}
+
+impl CompressedResponse {
+    /// Compresses the `response`.
+    pub fn new(response: &Response) -> Result<Self> {
+        let serialised =
+            rmp_serde::to_vec(response).map_err(|_| Error::ResponseCompressionFailed)?;
+        Self::from_serialised(&serialised)
+    }
+
+    /// Returns the `Response` once decompressed.
+    /// Fails should it decompress to more than `MAX_DECOMPRESSED_RESPONSE_SIZE` bytes.
+    pub fn decompress(&self) -> Result<Response> {
+        let mut serialised = vec![];
+        let _ = brotli::Decompressor::new(&self.response[..], 4096)
+            .take(MAX_DECOMPRESSED_RESPONSE_SIZE + 1)
+            .read_to_end(&mut serialised)
+            .map_err(|_| Error::ResponseDecompressionFailed)?;
+        if serialised.len() as u64 > MAX_DECOMPRESSED_RESPONSE_SIZE {
+            return Err(Error::ResponseDecompressionFailed);
+        }
+        rmp_serde::from_slice(&serialised).map_err(|_| Error::ResponseDecompressionFailed)
+    }
+
+    fn from_serialised(serialised: &[u8]) -> Result<Self> {
+        let params = brotli::enc::BrotliEncoderParams {
+            quality: RESPONSE_COMPRESSION_QUALITY,
+            ..Default::default()
+        };
+        let mut response = vec![];
+        let _ = brotli::BrotliCompress(&mut &serialised[..], &mut response, &params)
+            .map_err(|_| Error::ResponseCompressionFailed)?;
+        Ok(Self { response })
+    }
+}
+
+impl Response {
+    /// Compresses the response should it take more than `threshold` bytes once serialised,
+    /// smaller ones not being worth the cost of compressing them.
+    pub fn compressed_above(self, threshold: usize) -> Result<Self> {
+        let serialised = rmp_serde::to_vec(&self).map_err(|_| Error::ResponseCompressionFailed)?;
+        if serialised.len() <= threshold {
+            return Ok(self);
+        }
+        Ok(Response::Compressed(CompressedResponse::from_serialised(
+            &serialised,
+        )?))
+    }
+
+    /// Returns the response as it was before being compressed, if it was.
+    pub fn decompressed(self) -> Result<Self> {
+        match self {
+            Response::Compressed(compressed) => compressed.decompress(),
+            response => Ok(response),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        messages::{advertise_max_payload, advertised_max_payload, QueryResponse},
+        storage::ChunkAddress,
+        NetworkAddress,
+    };
+    use xor_name::XorName;
+
+    // A response listing the same address `count` times.
+    fn missing_keys_response(count: usize) -> Response {
+        let address = NetworkAddress::from_chunk_address(ChunkAddress::new(XorName::random(
+            &mut bls::rand::thread_rng(),
+        )));
+        Response::Query(QueryResponse::RecordKeysMissingFrom(Ok(vec![
+            address;
+            count
+        ])))
+    }
+
+    #[test]
+    fn large_responses_are_compressed_and_decompressed() -> Result<()> {
+        let response = missing_keys_response(1000);
+        let compressed = response.clone().compressed_above(1024)?;
+        assert!(matches!(compressed, Response::Compressed(_)));
+
+        let size = |response: &Response| rmp_serde::to_vec(response).map(|bytes| bytes.len());
+        assert!(size(&compressed).ok() < size(&response).ok());
+        assert_eq!(compressed.decompressed()?, response);
+
+        Ok(())
+    }
+
+    #[test]
+    fn small_responses_are_not_compressed() -> Result<()> {
+        let response = missing_keys_response(1);
+        assert_eq!(response.clone().compressed_above(1024)?, response);
+        assert_eq!(response.clone().decompressed()?, response);
+
+        Ok(())
+    }
+
+    #[test]
+    fn corrupted_responses_fail_to_decompress() {
+        let compressed = Response::Compressed(CompressedResponse {
+            response: b"not brotli".to_vec(),
+        });
+        assert!(matches!(
+            compressed.decompressed(),
+            Err(Error::ResponseDecompressionFailed)
+        ));
+    }
+
+    #[test]
+    fn responses_decompressing_beyond_the_limit_are_rejected() -> Result<()> {
+        let bomb = vec![0; MAX_DECOMPRESSED_RESPONSE_SIZE as usize + 1];
+        let compressed = CompressedResponse::from_serialised(&bomb)?;
+        assert!(compressed.response.len() < 64 * 1024);
+        assert!(matches!(
+            compressed.decompress(),
+            Err(Error::ResponseDecompressionFailed)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn acceptance_of_compressed_responses_is_advertised() {
+        let legacy = "safe/client/0.1.0";
+        assert!(!accepts_compressed_responses(legacy));
+
+        let agent_version = advertise_max_payload(&advertise_response_compression(legacy), 1024);
+        assert!(accepts_compressed_responses(&agent_version));
+        assert_eq!(advertised_max_payload(&agent_version), Some(1024));
+    }
+}
@@ -0,0 +1,102 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{Request, Response};
+use crate::error::Result;
+use serde::{Deserialize, Serialize};
+use xor_name::XorName;
+
+/// A `Request` as sent over the wire, along with the id correlating it with its `Response`.
+///
+/// Changing the envelopes changes the wire format, so the version of the req/response protocol
+/// advertised by the nodes has to be bumped along.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RequestMsg {
+    /// Id of the request, chosen by the requester and echoed in the `ResponseMsg`.
+    pub request_id: u64,
+    /// The request itself.
+    pub request: Request,
+}
+
+/// A `Response` as sent over the wire, echoing the id of the request it responds to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResponseMsg {
+    /// Id of the request this responds to.
+    pub request_id: u64,
+    /// The response itself.
+    pub response: Response,
+}
+
+impl RequestMsg {
+    /// Wraps the `request` with the given id.
+    pub fn new(request_id: u64, request: Request) -> Self {
+        Self {
+            request_id,
+            request,
+        }
+    }
+
+    /// Key identifying the content of the request, regardless of its id, so that the same
+    /// request sent several times, e.g. when retried, can be deduplicated.
+    pub fn dedup_key(&self) -> Result<XorName> {
+        self.request.dedup_key()
+    }
+
+    /// Wraps the `response` to this request, echoing its id.
+    pub fn response(&self, response: Response) -> ResponseMsg {
+        ResponseMsg {
+            request_id: self.request_id,
+            response,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        error::Error,
+        messages::{Query, QueryResponse},
+        NetworkAddress,
+    };
+
+    fn has_record_query() -> Request {
+        let name = XorName::random(&mut bls::rand::thread_rng());
+        Request::Query(Query::HasRecord(NetworkAddress::from_chunk_address(
+            crate::storage::ChunkAddress::new(name),
+        )))
+    }
+
+    #[test]
+    fn request_id_round_trips_and_is_echoed() -> Result<()> {
+        let msg = RequestMsg::new(42, has_record_query());
+        let bytes = rmp_serde::to_vec(&msg).map_err(|_| Error::RequestParsingFailed)?;
+        let received: RequestMsg =
+            rmp_serde::from_slice(&bytes).map_err(|_| Error::RequestParsingFailed)?;
+        assert_eq!(received, msg);
+
+        let response = received.response(Response::Query(QueryResponse::HasRecord(Ok(true))));
+        assert_eq!(response.request_id, 42);
+
+        Ok(())
+    }
+
+    #[test]
+    fn dedup_key_depends_on_content_only() -> Result<()> {
+        let request = has_record_query();
+        let first = RequestMsg::new(1, request.clone());
+        let retry = RequestMsg::new(2, request);
+        assert_ne!(first, retry);
+        assert_eq!(first.dedup_key()?, retry.dedup_key()?);
+
+        let other = RequestMsg::new(1, has_record_query());
+        assert_ne!(first.dedup_key()?, other.dedup_key()?);
+
+        Ok(())
+    }
+}
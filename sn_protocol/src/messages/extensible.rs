@@ -0,0 +1,262 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Deserialization of enums tolerating the variants added by newer versions of the protocol.
+//!
+//! The variants added to such an enum must be newtype variants, appended after the existing ones,
+//! so that older decoders can tell where their payload ends, whether the variants are tagged by
+//! name or by index.
+
+use serde::{
+    de::{self, EnumAccess, MapAccess, SeqAccess, VariantAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::{fmt, marker::PhantomData};
+
+/// An enum whose unknown variants are decoded into a catch-all variant rather than failing.
+pub(crate) trait ExtensibleEnum: Sized {
+    /// Name of the enum.
+    const NAME: &'static str;
+    /// Names of the known variants, in declaration order.
+    const VARIANTS: &'static [&'static str];
+
+    /// Decode the known variant at `index` of `VARIANTS`.
+    fn known_variant<'de, A: VariantAccess<'de>>(
+        index: usize,
+        variant: A,
+    ) -> Result<Self, A::Error>;
+
+    /// The catch-all for a variant unknown to us, with its tag, i.e. its name or index,
+    /// and its payload re-encoded with MessagePack.
+    fn unknown_variant(tag: String, bytes: Vec<u8>) -> Self;
+}
+
+/// Implements `ExtensibleEnum` and `Deserialize` for an enum, given all its newtype variants in
+/// declaration order and its catch-all variant. Listing the variants once, and matching them
+/// exhaustively, a variant added to the enum but not to the list fails to compile.
+macro_rules! impl_extensible_enum {
+    ($name:ident { $($variant:ident),+ $(,)? } else $unknown:ident) => {
+        impl $crate::messages::extensible::ExtensibleEnum for $name {
+            const NAME: &'static str = stringify!($name);
+            const VARIANTS: &'static [&'static str] = &[$(stringify!($variant)),+];
+
+            fn known_variant<'de, A: serde::de::VariantAccess<'de>>(
+                index: usize,
+                variant: A,
+            ) -> std::result::Result<Self, A::Error> {
+                let mut remaining = index;
+                $(
+                    if remaining == 0 {
+                        return variant.newtype_variant().map(Self::$variant);
+                    }
+                    remaining -= 1;
+                )+
+                Err(<A::Error as serde::de::Error>::custom(format!(
+                    "no variant of index {index} in {}",
+                    stringify!($name)
+                )))
+            }
+
+            fn unknown_variant(tag: String, bytes: Vec<u8>) -> Self {
+                Self::$unknown { tag, bytes }
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(
+                deserializer: D,
+            ) -> std::result::Result<Self, D::Error> {
+                $crate::messages::extensible::deserialize_extensible(deserializer)
+            }
+        }
+
+        // Fails to compile if a variant of the enum is missing from the list.
+        const _: fn(&$name) = |value| match value {
+            $($name::$variant(_) => {})+
+            $name::$unknown { .. } => {}
+        };
+    };
+}
+
+pub(crate) use impl_extensible_enum;
+
+/// Deserialize an `ExtensibleEnum`, to be called from its `Deserialize` impl.
+pub(crate) fn deserialize_extensible<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: ExtensibleEnum,
+{
+    deserializer.deserialize_enum(T::NAME, T::VARIANTS, ExtensibleVisitor(PhantomData))
+}
+
+struct ExtensibleVisitor<T>(PhantomData<T>);
+
+impl<'de, T: ExtensibleEnum> Visitor<'de> for ExtensibleVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "enum {}", T::NAME)
+    }
+
+    fn visit_enum<A: EnumAccess<'de>>(self, data: A) -> Result<T, A::Error> {
+        let (tag, variant) = data.variant::<Tag>()?;
+        let known = match &tag {
+            Tag::Index(index) => usize::try_from(*index)
+                .ok()
+                .filter(|index| *index < T::VARIANTS.len()),
+            Tag::Name(name) => T::VARIANTS.iter().position(|known| known == name),
+        };
+        if let Some(index) = known {
+            return T::known_variant(index, variant);
+        }
+
+        let payload: Captured = variant.newtype_variant()?;
+        let bytes = rmp_serde::to_vec(&payload).map_err(de::Error::custom)?;
+        let tag = match tag {
+            Tag::Index(index) => index.to_string(),
+            Tag::Name(name) => name,
+        };
+        Ok(T::unknown_variant(tag, bytes))
+    }
+}
+
+// The tag of a variant, as the format encodes it.
+enum Tag {
+    Index(u64),
+    Name(String),
+}
+
+impl<'de> Deserialize<'de> for Tag {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct TagVisitor;
+
+        impl<'de> Visitor<'de> for TagVisitor {
+            type Value = Tag;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a variant name or index")
+            }
+
+            fn visit_u64<E: de::Error>(self, index: u64) -> Result<Tag, E> {
+                Ok(Tag::Index(index))
+            }
+
+            fn visit_str<E: de::Error>(self, name: &str) -> Result<Tag, E> {
+                Ok(Tag::Name(name.to_string()))
+            }
+
+            fn visit_bytes<E: de::Error>(self, name: &[u8]) -> Result<Tag, E> {
+                Ok(Tag::Name(String::from_utf8_lossy(name).into_owned()))
+            }
+        }
+
+        deserializer.deserialize_identifier(TagVisitor)
+    }
+}
+
+// Any value of a self-describing format, captured to be re-encoded.
+enum Captured {
+    Unit,
+    Bool(bool),
+    I64(i64),
+    U64(u64),
+    F64(f64),
+    Str(String),
+    Bytes(Vec<u8>),
+    Seq(Vec<Captured>),
+    Map(Vec<(Captured, Captured)>),
+}
+
+impl Serialize for Captured {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Captured::Unit => serializer.serialize_unit(),
+            Captured::Bool(value) => serializer.serialize_bool(*value),
+            Captured::I64(value) => serializer.serialize_i64(*value),
+            Captured::U64(value) => serializer.serialize_u64(*value),
+            Captured::F64(value) => serializer.serialize_f64(*value),
+            Captured::Str(value) => serializer.serialize_str(value),
+            Captured::Bytes(value) => serializer.serialize_bytes(value),
+            Captured::Seq(values) => serializer.collect_seq(values),
+            Captured::Map(entries) => {
+                serializer.collect_map(entries.iter().map(|(key, value)| (key, value)))
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Captured {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct CapturedVisitor;
+
+        impl<'de> Visitor<'de> for CapturedVisitor {
+            type Value = Captured;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("any value")
+            }
+
+            fn visit_unit<E: de::Error>(self) -> Result<Captured, E> {
+                Ok(Captured::Unit)
+            }
+
+            fn visit_none<E: de::Error>(self) -> Result<Captured, E> {
+                Ok(Captured::Unit)
+            }
+
+            fn visit_some<D: Deserializer<'de>>(
+                self,
+                deserializer: D,
+            ) -> Result<Captured, D::Error> {
+                Captured::deserialize(deserializer)
+            }
+
+            fn visit_bool<E: de::Error>(self, value: bool) -> Result<Captured, E> {
+                Ok(Captured::Bool(value))
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Captured, E> {
+                Ok(Captured::I64(value))
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Captured, E> {
+                Ok(Captured::U64(value))
+            }
+
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<Captured, E> {
+                Ok(Captured::F64(value))
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Captured, E> {
+                Ok(Captured::Str(value.to_string()))
+            }
+
+            fn visit_bytes<E: de::Error>(self, value: &[u8]) -> Result<Captured, E> {
+                Ok(Captured::Bytes(value.to_vec()))
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Captured, A::Error> {
+                let mut values = vec![];
+                while let Some(value) = seq.next_element()? {
+                    values.push(value);
+                }
+                Ok(Captured::Seq(values))
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Captured, A::Error> {
+                let mut entries = vec![];
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
+                }
+                Ok(Captured::Map(entries))
+            }
+        }
+
+        deserializer.deserialize_any(CapturedVisitor)
+    }
+}
@@ -7,26 +7,42 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 //! Data messages and their possible responses.
+mod bloom_filter;
 mod cmd;
+mod compressed_response;
+mod envelope;
+mod extensible;
 mod node_id;
+mod payload_limit;
 mod query;
 mod register;
 mod response;
+mod signed_response;
 mod utxo;
 
 pub use self::{
+    bloom_filter::{BloomFilter, RECORD_KEY_FILTER_FP_RATE},
     cmd::{Cmd, Hash, MerkleTreeNodesType, PaymentProof},
+    compressed_response::{
+        accepts_compressed_responses, advertise_response_compression, CompressedResponse,
+    },
+    envelope::{RequestMsg, ResponseMsg},
     node_id::NodeId,
+    payload_limit::{
+        advertise_max_payload, advertised_max_payload, batches_within_limit, check_payload_fits,
+        payload_size, request_size, MAX_REQUEST_SIZE,
+    },
     query::Query,
-    register::RegisterCmd,
+    register::{RegisterBatch, RegisterCmd},
     response::{CmdOk, CmdResponse, QueryResponse},
+    signed_response::SignedResponse,
     utxo::{Transfer, Utxo},
 };
 
 use super::NetworkAddress;
 use crate::{
     error::{Error, Result},
-    storage::{ChunkWithPayment, DbcAddress},
+    storage::{ChunkWithPayment, DbcAddress, RecordKind, SignedData},
 };
 use serde::{Deserialize, Serialize};
 use sn_dbc::SignedSpend;
@@ -50,6 +66,10 @@ pub enum Response {
     Cmd(CmdResponse),
     /// The response to a query.
     Query(QueryResponse),
+    /// A response signed by the responding node.
+    Signed(SignedResponse),
+    /// A large response compressed by the responding node.
+    Compressed(CompressedResponse),
 }
 
 #[derive(custom_debug::Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
@@ -60,6 +80,8 @@ pub enum ReplicatedData {
     DbcSpend(Vec<SignedSpend>),
     /// A signed register
     Register(SignedRegister),
+    /// Data signed by its owner
+    SignedData(SignedData),
 }
 
 impl Request {
@@ -70,6 +92,21 @@ impl Request {
             Request::Query(query) => query.dst(),
         }
     }
+
+    /// The response to this request failing with `error`, without it being handled.
+    pub fn error_response(&self, error: Error) -> Response {
+        match self {
+            Request::Cmd(cmd) => Response::Cmd(cmd.error_response(error)),
+            Request::Query(query) => Response::Query(query.error_response(error)),
+        }
+    }
+
+    /// Key identifying the content of the request, so that identical requests can be
+    /// deduplicated.
+    pub fn dedup_key(&self) -> Result<XorName> {
+        let bytes = rmp_serde::to_vec(self).map_err(|_| Error::RequestParsingFailed)?;
+        Ok(XorName::from_content(&bytes))
+    }
 }
 
 impl ReplicatedData {
@@ -85,10 +122,21 @@ impl ReplicatedData {
                 }
             }
             Self::Register(register) => register.address().xorname(),
+            Self::SignedData(data) => *data.address().xorname(),
         };
         Ok(name)
     }
 
+    /// Return the kind of the record the data is stored as.
+    pub fn record_kind(&self) -> RecordKind {
+        match self {
+            Self::Chunk(_) => RecordKind::Chunk,
+            Self::DbcSpend(_) => RecordKind::DbcSpend,
+            Self::Register(_) => RecordKind::Register,
+            Self::SignedData(_) => RecordKind::SignedData,
+        }
+    }
+
     /// Return the dst.
     pub fn dst(&self) -> Result<NetworkAddress> {
         let dst = match self {
@@ -101,6 +149,7 @@ impl ReplicatedData {
                 }
             }
             Self::Register(register) => NetworkAddress::from_register_address(*register.address()),
+            Self::SignedData(data) => NetworkAddress::from_signed_data_address(data.address()),
         };
         Ok(dst)
     }
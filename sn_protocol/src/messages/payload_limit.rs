@@ -0,0 +1,186 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Discovery of the maximum size of the payloads peers accept.
+//!
+//! Peers advertise it in the agent version they exchange on connection, for the senders to
+//! cap what they send them, rather than having it fail at the transport layer.
+//! Sizes are those of the CBOR encoding requests are sent over the wire with.
+
+use super::Request;
+use crate::error::{Error, Result};
+use serde::Serialize;
+
+/// Maximum size of the `RequestMsg`s the CBOR codec of the request/response protocol reads,
+/// hence the maximum payload we accept.
+pub const MAX_REQUEST_SIZE: usize = 1024 * 1024;
+
+// Marks the maximum payload in an agent version.
+const MAX_PAYLOAD_MARKER: &str = " max-payload=";
+// Upper bound of the size of the header of an encoded sequence.
+const SEQ_HEADER_MAX_SIZE: usize = 9;
+
+// A `RequestMsg` borrowing its request, encoded as the `RequestMsg` itself is.
+#[derive(Serialize)]
+struct RequestMsgRef<'a> {
+    request_id: u64,
+    request: &'a Request,
+}
+
+/// Append the maximum size, in bytes, of the payloads we accept to our `agent_version`.
+pub fn advertise_max_payload(agent_version: &str, max_payload: usize) -> String {
+    format!("{agent_version}{MAX_PAYLOAD_MARKER}{max_payload}")
+}
+
+/// The maximum size of the payloads a peer accepts, as advertised in its `agent_version`.
+/// Peers of versions predating the advertisement don't advertise one.
+pub fn advertised_max_payload(agent_version: &str) -> Option<usize> {
+    let (_, max_payload) = agent_version.rsplit_once(MAX_PAYLOAD_MARKER)?;
+    max_payload.parse().ok()
+}
+
+/// The size of the `payload` once encoded as it is over the wire.
+pub fn payload_size<T: Serialize>(payload: &T) -> Result<usize> {
+    cbor4ii::serde::to_vec(Vec::new(), payload)
+        .map(|bytes| bytes.len())
+        .map_err(|_| Error::RequestParsingFailed)
+}
+
+/// The size of the `RequestMsg` the `request` is sent in, whatever its id, which is what the
+/// limits of peers apply to.
+pub fn request_size(request: &Request) -> Result<usize> {
+    payload_size(&RequestMsgRef {
+        request_id: u64::MAX,
+        request,
+    })
+}
+
+/// Check a payload of `size` bytes fits within the `limit` of a peer.
+pub fn check_payload_fits(size: usize, limit: usize) -> Result<()> {
+    if size > limit {
+        return Err(Error::PayloadExceedsPeerLimit { size, limit });
+    }
+    Ok(())
+}
+
+/// Split the `items` into batches of up to `max_items` each, in order, each batch fitting within
+/// `limit` bytes once encoded.
+/// Errors if one of the items doesn't fit within the `limit` on its own.
+pub fn batches_within_limit<T: Serialize>(
+    items: Vec<T>,
+    limit: usize,
+    max_items: usize,
+) -> Result<Vec<Vec<T>>> {
+    let mut batches = vec![];
+    let mut batch = vec![];
+    let mut batch_size = SEQ_HEADER_MAX_SIZE;
+    for item in items {
+        let size = payload_size(&item)?;
+        check_payload_fits(SEQ_HEADER_MAX_SIZE + size, limit)?;
+        if batch.len() == max_items || batch_size + size > limit {
+            batches.push(std::mem::take(&mut batch));
+            batch_size = SEQ_HEADER_MAX_SIZE;
+        }
+        batch.push(item);
+        batch_size += size;
+    }
+    if !batch.is_empty() {
+        batches.push(batch);
+    }
+    Ok(batches)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        messages::{Cmd, RequestMsg},
+        storage::ChunkAddress,
+        NetworkAddress,
+    };
+    use xor_name::XorName;
+
+    #[test]
+    fn max_payload_is_read_back_from_the_agent_version() {
+        let agent_version = advertise_max_payload("safe/node/0.1.0", 65 * 1024);
+        assert!(agent_version.starts_with("safe/node/0.1.0"));
+        assert_eq!(advertised_max_payload(&agent_version), Some(65 * 1024));
+
+        assert_eq!(advertised_max_payload("safe/node/0.1.0"), None);
+        assert_eq!(
+            advertised_max_payload("safe/node/0.1.0 max-payload=plenty"),
+            None
+        );
+    }
+
+    #[test]
+    fn batches_adapt_to_the_limit_of_the_peer() -> Result<()> {
+        let mut rng = bls::rand::thread_rng();
+        let keys: Vec<NetworkAddress> = (0..100)
+            .map(|_| {
+                NetworkAddress::from_chunk_address(ChunkAddress::new(XorName::random(&mut rng)))
+            })
+            .collect();
+
+        // a generous peer takes them in batches of the maximum number of items
+        let batches = batches_within_limit(keys.clone(), 1024 * 1024, 40)?;
+        let sizes: Vec<_> = batches.iter().map(Vec::len).collect();
+        assert_eq!(sizes, vec![40, 40, 20]);
+
+        // a peer of a smaller limit gets smaller batches, each within its limit
+        let limit = 1000;
+        let batches = batches_within_limit(keys.clone(), limit, 40)?;
+        assert!(batches.len() > 3);
+        for batch in &batches {
+            assert!(payload_size(batch)? <= limit);
+        }
+        assert_eq!(batches.concat(), keys);
+
+        // a key too large for the peer on its own can't be sent
+        let too_small = payload_size(&keys[0])?;
+        assert_eq!(
+            batches_within_limit(keys, too_small, 40),
+            Err(Error::PayloadExceedsPeerLimit {
+                size: SEQ_HEADER_MAX_SIZE + too_small,
+                limit: too_small,
+            })
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn requests_are_measured_as_sent_over_the_wire() -> Result<()> {
+        let holder = NetworkAddress::from_chunk_address(ChunkAddress::new(XorName::random(
+            &mut bls::rand::thread_rng(),
+        )));
+        let request = Request::Cmd(Cmd::Replicate {
+            holder: holder.clone(),
+            keys: vec![holder],
+        });
+        let sent = cbor4ii::serde::to_vec(Vec::new(), &RequestMsg::new(u64::MAX, request.clone()))
+            .map_err(|_| Error::RequestParsingFailed)?;
+        assert_eq!(request_size(&request)?, sent.len());
+        // the envelope is accounted for
+        assert!(request_size(&request)? > payload_size(&request)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn payloads_over_the_limit_are_rejected() {
+        assert_eq!(check_payload_fits(1024, 1024), Ok(()));
+        assert_eq!(
+            check_payload_fits(1025, 1024),
+            Err(Error::PayloadExceedsPeerLimit {
+                size: 1025,
+                limit: 1024
+            })
+        );
+    }
+}
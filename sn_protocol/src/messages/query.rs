@@ -6,9 +6,15 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::NetworkAddress;
+use crate::{
+    error::Error,
+    messages::{BloomFilter, QueryResponse},
+    NetworkAddress,
+};
 
 use serde::{Deserialize, Serialize};
+use sn_registers::{EntryHash, RegisterAddress};
+use std::collections::BTreeSet;
 
 /// Data queries - retrieving data and inspecting their structure.
 ///
@@ -33,6 +39,53 @@ pub enum Query {
         /// Address of the data to be fetched
         address: NetworkAddress,
     },
+    /// Retrieve a [`BloomFilter`] of the record keys held by the node at the given address.
+    ///
+    /// This should eventually lead to a [`RecordKeyFilter`] response.
+    ///
+    /// [`BloomFilter`]: crate::messages::BloomFilter
+    /// [`RecordKeyFilter`]: super::QueryResponse::RecordKeyFilter
+    RecordKeyFilter(NetworkAddress),
+    /// Retrieve the keys of the records held by the node at the given address which are missing
+    /// from the provided [`BloomFilter`] of the requester's record keys, and which the requester
+    /// is responsible for. The node returns a page of those keys only, the others being left to
+    /// later queries.
+    ///
+    /// This should eventually lead to a [`RecordKeysMissingFrom`] response.
+    ///
+    /// [`BloomFilter`]: crate::messages::BloomFilter
+    /// [`RecordKeysMissingFrom`]: super::QueryResponse::RecordKeysMissingFrom
+    RecordKeysMissingFrom {
+        /// Address of the node holding the records
+        address: NetworkAddress,
+        /// Filter of the record keys held by the requester
+        filter: BloomFilter,
+    },
+    /// Retrieve the ops of the Register at the given address which are missing from a replica
+    /// whose current entries are `known_heads`.
+    ///
+    /// This should eventually lead to a [`RegisterDelta`] response.
+    ///
+    /// [`RegisterDelta`]: super::QueryResponse::RegisterDelta
+    GetRegisterDelta {
+        /// Address of the Register
+        address: RegisterAddress,
+        /// Hashes of the current entries of the requester's replica
+        known_heads: BTreeSet<EntryHash>,
+    },
+    /// Retrieve the version of the replica of the Register at the given address,
+    /// to compare replicas without fetching them.
+    ///
+    /// This should eventually lead to a [`RegisterVersion`] response.
+    ///
+    /// [`RegisterVersion`]: super::QueryResponse::RegisterVersion
+    GetRegisterVersion(RegisterAddress),
+    /// Check whether the node holds the record at the given address, without fetching it.
+    ///
+    /// This should eventually lead to a [`HasRecord`] response.
+    ///
+    /// [`HasRecord`]: super::QueryResponse::HasRecord
+    HasRecord(NetworkAddress),
 }
 
 impl Query {
@@ -41,6 +94,26 @@ impl Query {
         match self {
             Query::GetStoreCost(address) => address.clone(),
             Query::GetReplicatedData { address, .. } => address.clone(),
+            Query::RecordKeyFilter(address) => address.clone(),
+            Query::RecordKeysMissingFrom { address, .. } => address.clone(),
+            Query::GetRegisterDelta { address, .. } => {
+                NetworkAddress::from_register_address(*address)
+            }
+            Query::GetRegisterVersion(address) => NetworkAddress::from_register_address(*address),
+            Query::HasRecord(address) => address.clone(),
+        }
+    }
+
+    /// The response to this query failing with `error`.
+    pub fn error_response(&self, error: Error) -> QueryResponse {
+        match self {
+            Query::GetStoreCost(_) => QueryResponse::GetStoreCost(Err(error)),
+            Query::GetReplicatedData { .. } => QueryResponse::GetReplicatedData(Err(error)),
+            Query::RecordKeyFilter(_) => QueryResponse::RecordKeyFilter(Err(error)),
+            Query::RecordKeysMissingFrom { .. } => QueryResponse::RecordKeysMissingFrom(Err(error)),
+            Query::GetRegisterDelta { .. } => QueryResponse::RegisterDelta(Err(error)),
+            Query::GetRegisterVersion(_) => QueryResponse::RegisterVersion(Err(error)),
+            Query::HasRecord(_) => QueryResponse::HasRecord(Err(error)),
         }
     }
 }
@@ -57,6 +130,28 @@ impl std::fmt::Display for Query {
                     "Query::GetReplicatedData({requester:?} querying {address:?})"
                 )
             }
+            Query::RecordKeyFilter(address) => {
+                write!(f, "Query::RecordKeyFilter({address:?})")
+            }
+            Query::RecordKeysMissingFrom { address, .. } => {
+                write!(f, "Query::RecordKeysMissingFrom({address:?})")
+            }
+            Query::GetRegisterDelta {
+                address,
+                known_heads,
+            } => {
+                write!(
+                    f,
+                    "Query::GetRegisterDelta({address:?} with {} known heads)",
+                    known_heads.len()
+                )
+            }
+            Query::GetRegisterVersion(address) => {
+                write!(f, "Query::GetRegisterVersion({address:?})")
+            }
+            Query::HasRecord(address) => {
+                write!(f, "Query::HasRecord({address:?})")
+            }
         }
     }
 }
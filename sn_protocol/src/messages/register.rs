@@ -6,7 +6,8 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use sn_registers::{Register, RegisterAddress, RegisterOp};
+use crate::NetworkAddress;
+use sn_registers::{Register, RegisterAddress, RegisterOp, SignedRegister};
 
 use serde::{Deserialize, Serialize};
 
@@ -34,3 +35,13 @@ impl RegisterCmd {
         }
     }
 }
+
+/// Registers to create which share a close group, sent to each of its peers as a single cmd
+/// rather than as a put per Register.
+#[derive(Eq, PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub struct RegisterBatch {
+    /// The address the close group of the Registers was looked up for.
+    pub dst: NetworkAddress,
+    /// The Registers to create, each signed by its owner.
+    pub registers: Vec<SignedRegister>,
+}
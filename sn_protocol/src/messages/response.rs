@@ -6,14 +6,23 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::{error::Result, messages::ReplicatedData, NetworkAddress};
+use super::extensible::impl_extensible_enum;
+use crate::{
+    error::Result,
+    messages::{BloomFilter, ReplicatedData},
+    NetworkAddress,
+};
 use serde::{Deserialize, Serialize};
 use sn_dbc::Token;
-use std::fmt::Debug;
+use sn_registers::{RegisterOp, RegisterVersion};
+use std::{collections::BTreeSet, fmt::Debug};
 
 /// The response to a query, containing the query result.
+///
+/// New variants must be newtype variants appended after the existing ones, for the nodes and
+/// clients not knowing of them yet to decode them as `Unknown`.
 #[allow(clippy::large_enum_variant)]
-#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, custom_debug::Debug)]
+#[derive(Clone, PartialEq, Eq, Serialize, custom_debug::Debug)]
 pub enum QueryResponse {
     /// The store cost in nanos for storing the next record, and the node's singature over that cost.
     GetStoreCost(Result<Token>),
@@ -23,21 +32,189 @@ pub enum QueryResponse {
     ///
     /// [`GetReplicatedData`]: crate::messages::Query::GetReplicatedData
     GetReplicatedData(Result<(NetworkAddress, ReplicatedData)>),
+    /// Response to [`RecordKeyFilter`]
+    ///
+    /// [`RecordKeyFilter`]: crate::messages::Query::RecordKeyFilter
+    RecordKeyFilter(Result<BloomFilter>),
+    /// Response to [`RecordKeysMissingFrom`]
+    ///
+    /// [`RecordKeysMissingFrom`]: crate::messages::Query::RecordKeysMissingFrom
+    RecordKeysMissingFrom(Result<Vec<NetworkAddress>>),
+    /// Response to [`GetRegisterDelta`]
+    ///
+    /// [`GetRegisterDelta`]: crate::messages::Query::GetRegisterDelta
+    RegisterDelta(Result<BTreeSet<RegisterOp>>),
+    /// Response to [`GetRegisterVersion`], along with the address of the responding node
+    ///
+    /// [`GetRegisterVersion`]: crate::messages::Query::GetRegisterVersion
+    RegisterVersion(Result<(NetworkAddress, RegisterVersion)>),
+    /// Response to [`HasRecord`], true if the node holds the record
+    ///
+    /// [`HasRecord`]: crate::messages::Query::HasRecord
+    HasRecord(Result<bool>),
+    /// A response of a newer version of the protocol, unknown to us, with the name or index of
+    /// its variant and its MessagePack encoded content. It's never sent.
+    #[serde(skip_serializing)]
+    Unknown {
+        /// The name or index of the variant
+        tag: String,
+        /// The MessagePack encoding of the content of the variant
+        bytes: Vec<u8>,
+    },
 }
 
+impl_extensible_enum!(QueryResponse {
+    GetStoreCost,
+    GetReplicatedData,
+    RecordKeyFilter,
+    RecordKeysMissingFrom,
+    RegisterDelta,
+    RegisterVersion,
+    HasRecord,
+} else Unknown);
+
 /// The response to a Cmd, containing the query result.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+///
+/// As with `QueryResponse`, new variants must be newtype variants appended after the existing ones.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub enum CmdResponse {
     //
     // ===== Replication =====
     //
     /// Response to replication cmd
     Replicate(Result<()>),
+    /// Response to [`DeleteRecord`]
+    ///
+    /// [`DeleteRecord`]: crate::messages::Cmd::DeleteRecord
+    DeleteRecord(Result<()>),
+    /// Response to [`RegisterBatch`], with the result of storing each of its Registers, in the
+    /// order of the batch.
+    ///
+    /// [`RegisterBatch`]: crate::messages::Cmd::RegisterBatch
+    RegisterBatch(Result<Vec<Result<CmdOk>>>),
+    /// A response of a newer version of the protocol, unknown to us, with the name or index of
+    /// its variant and its MessagePack encoded content. It's never sent.
+    #[serde(skip_serializing)]
+    Unknown {
+        /// The name or index of the variant
+        tag: String,
+        /// The MessagePack encoding of the content of the variant
+        bytes: Vec<u8>,
+    },
 }
 
+impl_extensible_enum!(CmdResponse { Replicate, DeleteRecord, RegisterBatch } else Unknown);
+
 /// The Ok variant of a CmdResponse
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CmdOk {
     StoredSuccessfully,
     DataAlreadyPresent,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+    use serde::de::DeserializeOwned;
+
+    // `QueryResponse` as a newer version of the protocol could define it.
+    #[allow(clippy::large_enum_variant, dead_code)]
+    #[derive(Serialize)]
+    enum FutureQueryResponse {
+        GetStoreCost(Result<Token>),
+        GetReplicatedData(Result<(NetworkAddress, ReplicatedData)>),
+        RecordKeyFilter(Result<BloomFilter>),
+        RecordKeysMissingFrom(Result<Vec<NetworkAddress>>),
+        RegisterDelta(Result<BTreeSet<RegisterOp>>),
+        RegisterVersion(Result<(NetworkAddress, RegisterVersion)>),
+        HasRecord(Result<bool>),
+        RecordAge(Result<(u64, String)>),
+    }
+
+    // `CmdResponse` as a newer version of the protocol could define it.
+    #[allow(dead_code)]
+    #[derive(Serialize)]
+    enum FutureCmdResponse {
+        Replicate(Result<()>),
+        DeleteRecord(Result<()>),
+        RegisterBatch(Result<Vec<Result<CmdOk>>>),
+        Compact(Vec<u32>),
+    }
+
+    // Responses are sent with the CBOR codec of libp2p's request_response.
+    fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>> {
+        cbor4ii::serde::to_vec(Vec::new(), value).map_err(|_| Error::RequestParsingFailed)
+    }
+
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+        cbor4ii::serde::from_slice(bytes).map_err(|_| Error::RequestParsingFailed)
+    }
+
+    // Signed and compressed responses, as well as the content of unknown variants, are encoded
+    // with MessagePack.
+    fn rmp_encode<T: Serialize>(value: &T) -> Result<Vec<u8>> {
+        rmp_serde::to_vec(value).map_err(|_| Error::RequestParsingFailed)
+    }
+
+    fn rmp_decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+        rmp_serde::from_slice(bytes).map_err(|_| Error::RequestParsingFailed)
+    }
+
+    #[test]
+    fn unknown_query_response_is_decoded_as_unknown() -> Result<()> {
+        let future = FutureQueryResponse::RecordAge(Ok((42, "seconds".to_string())));
+
+        match decode(&encode(&future)?)? {
+            QueryResponse::Unknown { tag, bytes } => {
+                assert_eq!(tag, "RecordAge");
+                let content: Result<(u64, String)> = rmp_decode(&bytes)?;
+                assert_eq!(content, Ok((42, "seconds".to_string())));
+            }
+            other => panic!("Expected an unknown response, got {other:?}"),
+        }
+
+        // within a signed response, with the variant tagged by its index instead
+        let content: Result<(u64, String)> = Ok((42, "seconds".to_string()));
+        let indexed = rmp_encode(&std::collections::BTreeMap::from([(7u32, content)]))?;
+        let decoded: QueryResponse = rmp_decode(&indexed)?;
+        assert!(matches!(decoded, QueryResponse::Unknown { tag, .. } if tag == "7"));
+
+        // the variants we know of are unaffected
+        let known = FutureQueryResponse::HasRecord(Ok(true));
+        let decoded: QueryResponse = decode(&encode(&known)?)?;
+        assert_eq!(decoded, QueryResponse::HasRecord(Ok(true)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_cmd_response_is_decoded_as_unknown() -> Result<()> {
+        match decode(&encode(&FutureCmdResponse::Compact(vec![1, 2, 3]))?)? {
+            CmdResponse::Unknown { tag, bytes } => {
+                assert_eq!(tag, "Compact");
+                assert_eq!(rmp_decode::<Vec<u32>>(&bytes)?, vec![1, 2, 3]);
+            }
+            other => panic!("Expected an unknown response, got {other:?}"),
+        }
+
+        let known = FutureCmdResponse::DeleteRecord(Err(Error::Overloaded));
+        let decoded: CmdResponse = decode(&encode(&known)?)?;
+        assert_eq!(decoded, CmdResponse::DeleteRecord(Err(Error::Overloaded)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn known_responses_round_trip() -> Result<()> {
+        let address = NetworkAddress::from_peer(libp2p::PeerId::random());
+        for response in [
+            QueryResponse::HasRecord(Ok(false)),
+            QueryResponse::RecordKeysMissingFrom(Ok(vec![address])),
+            QueryResponse::GetStoreCost(Err(Error::GetStoreCostFailed)),
+        ] {
+            assert_eq!(decode::<QueryResponse>(&encode(&response)?)?, response);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,132 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::Response;
+use crate::{
+    error::{Error, Result},
+    NetworkAddress,
+};
+use libp2p::{
+    identity::{Keypair, PublicKey},
+    PeerId,
+};
+use serde::{Deserialize, Serialize};
+
+/// Prefix of the bytes a `SignedResponse` is signed over, so that it can't be mistaken for
+/// another signature made with the identity key of the node.
+const SIGNED_RESPONSE_DOMAIN: &[u8] = b"sn_signed_response";
+
+/// A `Response` signed with the identity key of the responding node, so that the requester can
+/// verify the response genuinely comes from the peer it sent the request to.
+/// The id of the `RequestMsg` responded to is signed along with the response, for a response to
+/// an earlier request not to be replayed as the response to another one.
+#[derive(custom_debug::Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedResponse {
+    /// The serialised `Response`. The bytes are signed as is, so that the signature doesn't
+    /// depend on the serialisation being reproducible.
+    #[debug(skip)]
+    response: Vec<u8>,
+    /// The protobuf encoding of the responding node's public key.
+    #[debug(skip)]
+    public_key: Vec<u8>,
+    #[debug(skip)]
+    signature: Vec<u8>,
+}
+
+impl SignedResponse {
+    /// Signs the `response` to the `RequestMsg` of id `request_id` with the node's `keypair`.
+    pub fn new(response: &Response, request_id: u64, keypair: &Keypair) -> Result<Self> {
+        let response = rmp_serde::to_vec(response).map_err(|_| Error::ResponseSigningFailed)?;
+        let signature = keypair
+            .sign(&bytes_for_signing(&response, request_id))
+            .map_err(|_| Error::ResponseSigningFailed)?;
+        Ok(Self {
+            response,
+            public_key: keypair.public().encode_protobuf(),
+            signature,
+        })
+    }
+
+    /// Returns the `Response` if it has been signed by the `signer` in response to the
+    /// `RequestMsg` of id `request_id`.
+    pub fn verify(&self, signer: &PeerId, request_id: u64) -> Result<Response> {
+        let invalid =
+            || Error::ResponseSignatureInvalid(Box::new(NetworkAddress::from_peer(*signer)));
+
+        let public_key = PublicKey::try_decode_protobuf(&self.public_key).map_err(|_| invalid())?;
+        if public_key.to_peer_id() != *signer
+            || !public_key.verify(
+                &bytes_for_signing(&self.response, request_id),
+                &self.signature,
+            )
+        {
+            return Err(invalid());
+        }
+        rmp_serde::from_slice(&self.response).map_err(|_| invalid())
+    }
+}
+
+// The bytes the serialised `response` to the `RequestMsg` of id `request_id` is signed over.
+fn bytes_for_signing(response: &[u8], request_id: u64) -> Vec<u8> {
+    [SIGNED_RESPONSE_DOMAIN, &request_id.to_be_bytes(), response].concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::messages::{CmdResponse, QueryResponse};
+
+    #[test]
+    fn signed_response_is_accepted_from_its_signer() -> Result<()> {
+        let keypair = Keypair::generate_ed25519();
+        let response = Response::Query(QueryResponse::GetStoreCost(Err(Error::GetStoreCostFailed)));
+
+        let signed = SignedResponse::new(&response, 42, &keypair)?;
+        assert_eq!(signed.verify(&keypair.public().to_peer_id(), 42)?, response);
+
+        Ok(())
+    }
+
+    #[test]
+    fn tampered_or_forged_responses_are_rejected() -> Result<()> {
+        let keypair = Keypair::generate_ed25519();
+        let peer = keypair.public().to_peer_id();
+        let response = Response::Cmd(CmdResponse::Replicate(Ok(())));
+        let signed = SignedResponse::new(&response, 42, &keypair)?;
+
+        // the response was altered on its way
+        let mut tampered = signed.clone();
+        let other_response = Response::Cmd(CmdResponse::DeleteRecord(Ok(())));
+        tampered.response = SignedResponse::new(&other_response, 42, &keypair)?.response;
+        assert!(matches!(
+            tampered.verify(&peer, 42),
+            Err(Error::ResponseSignatureInvalid(_))
+        ));
+
+        // an interceptor re-signs the response with its own key
+        let forged = SignedResponse::new(&response, 42, &Keypair::generate_ed25519())?;
+        assert!(matches!(
+            forged.verify(&peer, 42),
+            Err(Error::ResponseSignatureInvalid(_))
+        ));
+
+        // a genuine response from another node is not one from the peer we asked
+        assert!(matches!(
+            signed.verify(&PeerId::random(), 42),
+            Err(Error::ResponseSignatureInvalid(_))
+        ));
+
+        // a genuine response to an earlier request is replayed as the response to another one
+        assert!(matches!(
+            signed.verify(&peer, 43),
+            Err(Error::ResponseSignatureInvalid(_))
+        ));
+
+        Ok(())
+    }
+}
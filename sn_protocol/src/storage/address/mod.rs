@@ -8,6 +8,7 @@
 
 mod chunk;
 mod dbc;
+mod signed_data;
 
-pub use self::{chunk::ChunkAddress, dbc::DbcAddress};
+pub use self::{chunk::ChunkAddress, dbc::DbcAddress, signed_data::SignedDataAddress};
 pub use sn_registers::RegisterAddress;
@@ -0,0 +1,35 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use bls::PublicKey;
+use serde::{Deserialize, Serialize};
+use std::hash::Hash;
+use xor_name::XorName;
+
+/// Address of a `SignedData`, derived from its owner and name.
+#[derive(Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize, Debug)]
+pub struct SignedDataAddress(XorName);
+
+impl SignedDataAddress {
+    /// Construct a `SignedDataAddress` given an `XorName`.
+    pub fn new(name: XorName) -> Self {
+        Self(name)
+    }
+
+    /// Construct the `SignedDataAddress` of the data named `name` owned by `owner`.
+    pub fn from_owner_and_name(owner: &PublicKey, name: &XorName) -> Self {
+        let mut bytes = owner.to_bytes().to_vec();
+        bytes.extend_from_slice(&name.0);
+        Self::new(XorName::from_content(&bytes))
+    }
+
+    /// Return the name, which is the hash of the owner and name of the data.
+    pub fn xorname(&self) -> &XorName {
+        &self.0
+    }
+}
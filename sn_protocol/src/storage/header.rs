@@ -6,7 +6,7 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::error::Error;
+use crate::{error::Error, AddressKind};
 use libp2p::kad::Record;
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
@@ -16,11 +16,12 @@ pub struct RecordHeader {
     pub kind: RecordKind,
 }
 
-#[derive(Debug, Eq, PartialEq, Clone)]
+#[derive(Debug, Eq, PartialEq, Clone, Hash)]
 pub enum RecordKind {
     Chunk,
     DbcSpend,
     Register,
+    SignedData,
 }
 
 impl Serialize for RecordKind {
@@ -32,6 +33,7 @@ impl Serialize for RecordKind {
             Self::Chunk => serializer.serialize_u32(0),
             Self::DbcSpend => serializer.serialize_u32(1),
             Self::Register => serializer.serialize_u32(2),
+            Self::SignedData => serializer.serialize_u32(3),
         }
     }
 }
@@ -46,12 +48,25 @@ impl<'de> Deserialize<'de> for RecordKind {
             0 => Ok(Self::Chunk),
             1 => Ok(Self::DbcSpend),
             2 => Ok(Self::Register),
+            3 => Ok(Self::SignedData),
             _ => Err(serde::de::Error::custom(
                 "Unexpected integer for RecordKind variant",
             )),
         }
     }
 }
+impl RecordKind {
+    /// The kind of the address a record of this kind is stored at.
+    pub fn address_kind(&self) -> AddressKind {
+        match self {
+            Self::Chunk => AddressKind::ChunkAddress,
+            Self::DbcSpend => AddressKind::DbcAddress,
+            Self::Register => AddressKind::RegisterAddress,
+            Self::SignedData => AddressKind::SignedDataAddress,
+        }
+    }
+}
+
 impl Display for RecordKind {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "RecordKind({self:?})")
@@ -70,8 +85,11 @@ impl RecordHeader {
     }
 
     pub fn from_record(record: &Record) -> Result<Self, Error> {
-        Self::try_deserialize(&record.value[..RecordHeader::SIZE + 1])
-            .map_err(|_| Error::RecordHeaderParsingFailed)
+        let bytes = record
+            .value
+            .get(..RecordHeader::SIZE + 1)
+            .ok_or(Error::RecordHeaderParsingFailed)?;
+        Self::try_deserialize(bytes)
     }
 }
 
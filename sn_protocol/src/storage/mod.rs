@@ -9,9 +9,13 @@
 mod address;
 mod chunks;
 mod header;
+mod record_envelope;
+mod signed_data;
 
 pub use self::{
-    address::{ChunkAddress, DbcAddress, RegisterAddress},
+    address::{ChunkAddress, DbcAddress, RegisterAddress, SignedDataAddress},
     chunks::{Chunk, ChunkWithPayment},
     header::{try_deserialize_record, try_serialize_record, RecordHeader, RecordKind},
+    record_envelope::RecordEnvelope,
+    signed_data::SignedData,
 };
@@ -0,0 +1,288 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{ChunkWithPayment, DbcAddress, RecordHeader, RecordKind, SignedData};
+use crate::{
+    error::{Error, Result},
+    messages::ReplicatedData,
+    NetworkAddress,
+};
+use bytes::Bytes;
+use libp2p::kad::Record;
+use sn_dbc::SignedSpend;
+use sn_registers::SignedRegister;
+
+/// Any kind of data as stored in the DHT, i.e. its address, its kind and its serialised
+/// payload, so that storage, replication and anti-entropy can handle all data alike.
+///
+/// It is stored as a `Record` at the key of its address, the `RecordHeader` of its kind followed
+/// by its payload. The payload is validated against its kind, and its address against the record
+/// key, when read from a `Record`. The data is typed back with `try_into_data`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordEnvelope {
+    /// Address of the data.
+    pub address: NetworkAddress,
+    /// Kind of the data.
+    pub kind: RecordKind,
+    /// The serialised data.
+    pub payload: Bytes,
+}
+
+impl RecordEnvelope {
+    /// Wrap the `data` to be stored.
+    pub fn from_data(data: &ReplicatedData) -> Result<Self> {
+        let payload = match data {
+            ReplicatedData::Chunk(chunk) => rmp_serde::to_vec(chunk),
+            ReplicatedData::DbcSpend(spends) => rmp_serde::to_vec(spends),
+            ReplicatedData::Register(register) => rmp_serde::to_vec(register),
+            ReplicatedData::SignedData(data) => rmp_serde::to_vec(data),
+        }
+        .map_err(|_| Error::RecordParsingFailed)?;
+
+        Ok(Self {
+            address: address_of(data)?,
+            kind: data.record_kind(),
+            payload: Bytes::from(payload),
+        })
+    }
+
+    /// Read the data held by the `record`, checking it's valid for its kind and stored at the
+    /// key of its address.
+    pub fn try_from_record(record: &Record) -> Result<Self> {
+        let kind = RecordHeader::from_record(record)?.kind;
+        let payload = record
+            .value
+            .get(RecordHeader::SIZE..)
+            .ok_or(Error::RecordParsingFailed)?;
+        let data = validated_data(&kind, payload)?;
+        let address = address_of(&data)?;
+        if address.to_record_key() != record.key {
+            return Err(Error::RecordKeyMismatch);
+        }
+
+        Ok(Self {
+            address,
+            kind,
+            payload: Bytes::copy_from_slice(payload),
+        })
+    }
+
+    /// The `Record` to store the data as.
+    pub fn into_record(self) -> Result<Record> {
+        let mut value = RecordHeader { kind: self.kind }.try_serialize()?;
+        value.extend(self.payload);
+        Ok(Record::new(self.address.to_record_key(), value))
+    }
+
+    /// The data held, checking it's valid for its kind and at the address of the envelope.
+    pub fn try_into_data(&self) -> Result<ReplicatedData> {
+        let data = validated_data(&self.kind, &self.payload)?;
+        if address_of(&data)? != self.address {
+            return Err(Error::RecordKeyMismatch);
+        }
+        Ok(data)
+    }
+}
+
+// Deserialise the `payload` as data of the `kind`, checking it's valid as such.
+// The spends' signatures are left to the nodes to verify, along the parent spends.
+fn validated_data(kind: &RecordKind, payload: &[u8]) -> Result<ReplicatedData> {
+    let deserialise_failed = |_| Error::RecordParsingFailed;
+    match kind {
+        RecordKind::Chunk => {
+            let chunk: ChunkWithPayment =
+                rmp_serde::from_slice(payload).map_err(deserialise_failed)?;
+            Ok(ReplicatedData::Chunk(chunk))
+        }
+        RecordKind::DbcSpend => {
+            let spends: Vec<SignedSpend> =
+                rmp_serde::from_slice(payload).map_err(deserialise_failed)?;
+            let first = spends.first().ok_or(Error::SpendIsEmpty)?;
+            if spends.iter().any(|spend| spend.dbc_id() != first.dbc_id()) {
+                return Err(Error::SpendDbcIdMismatch(DbcAddress::from_dbc_id(
+                    first.dbc_id(),
+                )));
+            }
+            Ok(ReplicatedData::DbcSpend(spends))
+        }
+        RecordKind::Register => {
+            let register: SignedRegister =
+                rmp_serde::from_slice(payload).map_err(deserialise_failed)?;
+            if register.verify().is_err() {
+                return Err(Error::RegisterInvalid(Box::new(*register.address())));
+            }
+            Ok(ReplicatedData::Register(register))
+        }
+        RecordKind::SignedData => {
+            let data: SignedData = rmp_serde::from_slice(payload).map_err(deserialise_failed)?;
+            data.verify()?;
+            Ok(ReplicatedData::SignedData(data))
+        }
+    }
+}
+
+// The address the `data` is stored at.
+fn address_of(data: &ReplicatedData) -> Result<NetworkAddress> {
+    let address = match data {
+        ReplicatedData::Chunk(chunk) => NetworkAddress::from_chunk_address(*chunk.chunk.address()),
+        ReplicatedData::DbcSpend(spends) => {
+            let spend = spends.first().ok_or(Error::SpendIsEmpty)?;
+            NetworkAddress::from_dbc_address(DbcAddress::from_dbc_id(spend.dbc_id()))
+        }
+        ReplicatedData::Register(register) => {
+            NetworkAddress::from_register_address(*register.address())
+        }
+        ReplicatedData::SignedData(data) => {
+            NetworkAddress::from_signed_data_address(data.address())
+        }
+    };
+    Ok(address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        messages::PaymentProof,
+        storage::{try_serialize_record, Chunk, ChunkAddress, SignedDataAddress},
+        test_utils::random_signed_spend,
+    };
+    use bls::SecretKey;
+    use sn_dbc::rand;
+    use sn_registers::{Permissions, Register};
+    use xor_name::XorName;
+
+    fn data_of_each_kind() -> Result<Vec<ReplicatedData>> {
+        let chunk = ChunkWithPayment {
+            chunk: Chunk::new(Bytes::from_static(b"chunk")),
+            payment: PaymentProof {
+                spent_ids: vec![],
+                audit_trail: vec![],
+                path: vec![],
+            },
+        };
+        let owner_sk = SecretKey::random();
+        let register = Register::new(
+            owner_sk.public_key(),
+            XorName::random(&mut rand::thread_rng()),
+            Permissions::default(),
+            None,
+        )
+        .into_signed(&owner_sk)?;
+        let data = SignedData::new(
+            XorName::random(&mut rand::thread_rng()),
+            &owner_sk,
+            Bytes::from_static(b"signed"),
+        );
+
+        Ok(vec![
+            ReplicatedData::Chunk(chunk),
+            ReplicatedData::DbcSpend(vec![random_signed_spend()]),
+            ReplicatedData::Register(register),
+            ReplicatedData::SignedData(data),
+        ])
+    }
+
+    #[test]
+    fn each_kind_of_data_round_trips_through_the_envelope() -> Result<()> {
+        for data in data_of_each_kind()? {
+            let envelope = RecordEnvelope::from_data(&data)?;
+            let record = envelope.clone().into_record()?;
+            assert_eq!(RecordHeader::from_record(&record)?.kind, data.record_kind());
+
+            let read = RecordEnvelope::try_from_record(&record)?;
+            assert_eq!(read, envelope);
+            assert_eq!(read.try_into_data()?, data);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn records_not_at_the_key_of_their_data_are_rejected() -> Result<()> {
+        for data in data_of_each_kind()? {
+            let mut record = RecordEnvelope::from_data(&data)?.into_record()?;
+            record.key = NetworkAddress::from_chunk_address(ChunkAddress::new(XorName::random(
+                &mut rand::thread_rng(),
+            )))
+            .to_record_key();
+            assert_eq!(
+                RecordEnvelope::try_from_record(&record),
+                Err(Error::RecordKeyMismatch)
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_data_of_each_kind_is_rejected() -> Result<()> {
+        let record_of = |address: NetworkAddress, value: Vec<u8>| -> Record {
+            Record::new(address.to_record_key(), value)
+        };
+
+        // a payload which isn't of the kind in the header
+        let data = ReplicatedData::DbcSpend(vec![random_signed_spend()]);
+        let envelope = RecordEnvelope::from_data(&data)?;
+        let record = record_of(
+            envelope.address,
+            try_serialize_record(&envelope.payload, RecordKind::Chunk)?,
+        );
+        assert_eq!(
+            RecordEnvelope::try_from_record(&record),
+            Err(Error::RecordParsingFailed)
+        );
+
+        // spends of different dbcs
+        let spends = vec![random_signed_spend(), random_signed_spend()];
+        let address = DbcAddress::from_dbc_id(spends[0].dbc_id());
+        let record = record_of(
+            NetworkAddress::from_dbc_address(address),
+            try_serialize_record(&spends, RecordKind::DbcSpend)?,
+        );
+        assert_eq!(
+            RecordEnvelope::try_from_record(&record),
+            Err(Error::SpendDbcIdMismatch(address))
+        );
+
+        // a register signed by someone else than its owner
+        let owner_sk = SecretKey::random();
+        let base = Register::new(
+            owner_sk.public_key(),
+            XorName::random(&mut rand::thread_rng()),
+            Permissions::default(),
+            None,
+        );
+        let forged_signature = SecretKey::random().sign(base.bytes()?);
+        let register = SignedRegister::new(base, forged_signature);
+        let address = *register.address();
+        let record = record_of(
+            NetworkAddress::from_register_address(address),
+            try_serialize_record(&register, RecordKind::Register)?,
+        );
+        assert_eq!(
+            RecordEnvelope::try_from_record(&record),
+            Err(Error::RegisterInvalid(Box::new(address)))
+        );
+
+        // signed data whose value was altered after being signed
+        let owner = owner_sk.public_key();
+        let name = XorName::random(&mut rand::thread_rng());
+        let signature = owner_sk.sign(SignedData::bytes_for_signing(&owner, &name, b"signed"));
+        let altered = (owner, name, Bytes::from_static(b"altered"), signature);
+        let address = SignedDataAddress::from_owner_and_name(&owner, &name);
+        let record = record_of(
+            NetworkAddress::from_signed_data_address(address),
+            try_serialize_record(&altered, RecordKind::SignedData)?,
+        );
+        assert_eq!(
+            RecordEnvelope::try_from_record(&record),
+            Err(Error::SignedDataInvalid(address))
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,153 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::SignedDataAddress;
+use crate::error::{Error, Result};
+use bls::{PublicKey, SecretKey, Signature};
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+use xor_name::XorName;
+
+/// Immutable data signed by its owner, addressed by the owner's public key and a name.
+///
+/// This sits between `Chunk`s, which are anonymous and addressed by their content, and
+/// Registers, which are owned but mutable.
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, custom_debug::Debug)]
+pub struct SignedData {
+    owner: PublicKey,
+    name: XorName,
+    #[debug(skip)]
+    value: Bytes,
+    #[debug(skip)]
+    signature: Signature,
+}
+
+impl SignedData {
+    /// Create the data named `name` holding `value`, signed by the owner's `owner_sk`.
+    pub fn new(name: XorName, owner_sk: &SecretKey, value: Bytes) -> Self {
+        let owner = owner_sk.public_key();
+        let signature = owner_sk.sign(Self::bytes_for_signing(&owner, &name, &value));
+        Self {
+            owner,
+            name,
+            value,
+            signature,
+        }
+    }
+
+    /// The bytes the owner signs, binding the value to the address of the data.
+    pub fn bytes_for_signing(owner: &PublicKey, name: &XorName, value: &[u8]) -> Vec<u8> {
+        let mut bytes = SignedDataAddress::from_owner_and_name(owner, name)
+            .xorname()
+            .0
+            .to_vec();
+        bytes.extend_from_slice(value);
+        bytes
+    }
+
+    /// Return the address of the data.
+    pub fn address(&self) -> SignedDataAddress {
+        SignedDataAddress::from_owner_and_name(&self.owner, &self.name)
+    }
+
+    /// Return the owner of the data.
+    pub fn owner(&self) -> &PublicKey {
+        &self.owner
+    }
+
+    /// Return the name of the data.
+    pub fn name(&self) -> &XorName {
+        &self.name
+    }
+
+    /// Return the value of the data.
+    pub fn value(&self) -> &Bytes {
+        &self.value
+    }
+
+    /// Check the data is signed by its owner.
+    pub fn verify(&self) -> Result<()> {
+        let bytes = Self::bytes_for_signing(&self.owner, &self.name, &self.value);
+        if self.owner.verify(&self.signature, bytes) {
+            Ok(())
+        } else {
+            Err(Error::SignedDataInvalid(self.address()))
+        }
+    }
+
+    /// Check the data is signed by its owner and is the one stored at `address`.
+    pub fn verify_with_address(&self, address: SignedDataAddress) -> Result<()> {
+        if self.address() != address {
+            return Err(Error::RecordKeyMismatch);
+        }
+        self.verify()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::{try_deserialize_record, try_serialize_record, RecordKind};
+    use crate::NetworkAddress;
+    use libp2p::kad::Record;
+
+    #[test]
+    fn signed_data_round_trips_through_a_record() -> Result<()> {
+        let owner_sk = SecretKey::random();
+        let name = XorName::random(&mut bls::rand::thread_rng());
+        let data = SignedData::new(name, &owner_sk, Bytes::from_static(b"profile"));
+        let address = SignedDataAddress::from_owner_and_name(&owner_sk.public_key(), &name);
+        assert_eq!(data.address(), address);
+
+        let record = Record::new(
+            NetworkAddress::from_signed_data_address(address).to_record_key(),
+            try_serialize_record(&data, RecordKind::SignedData)?,
+        );
+        let fetched: SignedData = try_deserialize_record(&record)?;
+        assert_eq!(fetched.verify_with_address(address), Ok(()));
+        assert_eq!(fetched, data);
+        assert_eq!(fetched.value(), &Bytes::from_static(b"profile"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn forged_signed_data_is_rejected() {
+        let owner_sk = SecretKey::random();
+        let name = XorName::random(&mut bls::rand::thread_rng());
+        let data = SignedData::new(name, &owner_sk, Bytes::from_static(b"profile"));
+
+        // a value swapped under the owner's signature
+        let mut tampered = data.clone();
+        tampered.value = Bytes::from_static(b"forged");
+        assert_eq!(
+            tampered.verify(),
+            Err(Error::SignedDataInvalid(data.address()))
+        );
+
+        // data claiming to be the owner's but signed by someone else
+        let forger_sk = SecretKey::random();
+        let mut forged = SignedData::new(name, &forger_sk, Bytes::from_static(b"forged"));
+        forged.owner = owner_sk.public_key();
+        assert_eq!(
+            forged.verify(),
+            Err(Error::SignedDataInvalid(data.address()))
+        );
+
+        // genuine data of another address
+        let other = SignedData::new(
+            XorName::random(&mut bls::rand::thread_rng()),
+            &owner_sk,
+            Bytes::from_static(b"profile"),
+        );
+        assert_eq!(
+            other.verify_with_address(data.address()),
+            Err(Error::RecordKeyMismatch)
+        );
+    }
+}
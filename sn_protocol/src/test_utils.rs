@@ -0,0 +1,31 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use bls::SecretKey;
+use sn_dbc::{DerivedKey, Hash, SignedSpend, Spend, Token};
+
+/// A spend of the dbc of `derived_sk`, for the given `reason`, validly signed by its owner.
+pub fn spend_of(derived_sk: &SecretKey, reason: &[u8]) -> SignedSpend {
+    let spend = Spend {
+        dbc_id: DerivedKey::new(derived_sk.clone()).dbc_id(),
+        spent_tx: Default::default(),
+        reason: Hash::hash(reason),
+        token: Token::zero(),
+        dbc_creation_tx: Default::default(),
+    };
+    let derived_key_sig = derived_sk.sign(spend.to_bytes());
+    SignedSpend {
+        spend,
+        derived_key_sig,
+    }
+}
+
+/// A spend of a random dbc, see `spend_of`.
+pub fn random_signed_spend() -> SignedSpend {
+    spend_of(&SecretKey::random(), b"spend")
+}
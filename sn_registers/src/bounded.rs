@@ -0,0 +1,314 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{error::Result, Entry, EntryHash, Error, Register, RegisterAddress, RegisterOp, User};
+
+use bls::{PublicKey, SecretKey, Signature};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use xor_name::XorName;
+
+/// Operation removing the oldest entries of a `BoundedRegister`, to be applied to its replicas,
+/// and carried by its `SignedRegister` along with its `RegisterOp`s.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PruneOp {
+    /// Address of the Register the entries are pruned from.
+    pub(crate) address: RegisterAddress,
+    /// Hashes of the entries pruned.
+    pub(crate) pruned: BTreeSet<EntryHash>,
+    /// The user who pruned the entries, by writing the entry exceeding the cap.
+    pub(crate) source: User,
+    /// The signature of source on hash(address, pruned, source) required to apply the op.
+    pub(crate) signature: Option<Signature>,
+}
+
+impl PruneOp {
+    /// Hashes of the entries pruned.
+    pub fn pruned(&self) -> &BTreeSet<EntryHash> {
+        &self.pruned
+    }
+
+    /// The user who pruned the entries.
+    pub fn source(&self) -> User {
+        self.source
+    }
+
+    /// Returns a bytes version of the PruneOp used for signing: the 32-byte SHA3 digest of its
+    /// address, source and pruned entries, set apart from the digests `RegisterOp`s are signed on.
+    pub fn bytes_for_signing(&self) -> Vec<u8> {
+        let owner = self.address.owner.to_bytes();
+        let source = self.source.to_bytes();
+        let mut parts: Vec<&[u8]> = vec![b"PruneOp", &self.address.meta.0, &owner, &source];
+        parts.extend(self.pruned.iter().map(|hash| hash.0.as_slice()));
+        XorName::from_content_parts(&parts).0.to_vec()
+    }
+
+    /// Add signature to the PruneOp using provided secret key.
+    pub fn sign_with(&mut self, sk: &SecretKey) {
+        self.source = User::Key(sk.public_key());
+        self.signature = Some(sk.sign(self.bytes_for_signing()));
+    }
+
+    /// Check signature of the PruneOp against provided public key.
+    pub fn verify_signature(&self, pk: &PublicKey) -> Result<()> {
+        let sig = self.signature.as_ref().ok_or(Error::MissingSignature)?;
+        if !pk.verify(sig, self.bytes_for_signing()) {
+            return Err(Error::InvalidSignature);
+        }
+        Ok(())
+    }
+}
+
+/// A Register holding at most `cap` entries, e.g. a rolling log of events.
+///
+/// Writing an entry which takes the Register over its cap prunes the oldest entries, in lamport
+/// order, and returns the `PruneOp` to be applied to the other replicas along with the `RegisterOp`,
+/// and added to the `SignedRegister` with `SignedRegister::add_prune_op`, which drops the ops of the
+/// pruned entries.
+/// Current entries are never pruned, so a Register with more than `cap` branches exceeds its cap.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BoundedRegister {
+    register: Register,
+    cap: usize,
+}
+
+impl BoundedRegister {
+    /// Bound the provided Register to `cap` entries. Entries it holds beyond the cap are only
+    /// pruned on the next write.
+    pub fn new(register: Register, cap: usize) -> Self {
+        Self { register, cap }
+    }
+
+    /// Return the maximum number of entries held.
+    pub fn cap(&self) -> usize {
+        self.cap
+    }
+
+    /// Return the underlying Register.
+    pub fn register(&self) -> &Register {
+        &self.register
+    }
+
+    /// Write an entry atop of `children`, signing the ops with `sk`, and prune the oldest entries
+    /// should the Register exceed its cap.
+    pub fn write(
+        &mut self,
+        entry: Entry,
+        children: BTreeSet<EntryHash>,
+        sk: &SecretKey,
+    ) -> Result<(EntryHash, RegisterOp, Option<PruneOp>)> {
+        let source = User::Key(sk.public_key());
+        self.register.check_user_permissions(source)?;
+
+        let (hash, mut op) = self.register.write(entry, children)?;
+        op.sign_with(sk);
+        self.register.retain_author(&op);
+
+        let pruned = self.excess_entries();
+        if pruned.is_empty() {
+            return Ok((hash, op, None));
+        }
+        let mut prune_op = PruneOp {
+            address: *self.register.address(),
+            pruned,
+            source,
+            signature: None,
+        };
+        prune_op.sign_with(sk);
+        self.register.prune(&prune_op.pruned);
+
+        Ok((hash, op, Some(prune_op)))
+    }
+
+    /// Apply an op writing an entry, from another replica.
+    pub fn apply_op(&mut self, op: RegisterOp) -> Result<()> {
+        self.register.apply_op(op)
+    }
+
+    /// Apply an op pruning entries, from another replica, see `Register::apply_prune_op`.
+    pub fn apply_prune_op(&mut self, op: PruneOp) -> Result<()> {
+        self.register.apply_prune_op(&op)
+    }
+
+    // The oldest entries beyond the cap, in lamport order, leaving out the current entries.
+    fn excess_entries(&self) -> BTreeSet<EntryHash> {
+        let entries = self.register.entries_topological();
+        let excess = entries.len().saturating_sub(self.cap);
+        let current = self.register.heads();
+        entries
+            .into_iter()
+            .map(|(hash, _, _)| hash)
+            .filter(|hash| !current.contains(hash))
+            .take(excess)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Permissions;
+    use xor_name::XorName;
+
+    fn bounded_register(owner_sk: &SecretKey, cap: usize) -> BoundedRegister {
+        let register = Register::new(
+            owner_sk.public_key(),
+            XorName::random(&mut rand::thread_rng()),
+            Permissions::new_owner_only(),
+            None,
+        );
+        BoundedRegister::new(register, cap)
+    }
+
+    #[test]
+    fn bounded_register_keeps_the_most_recent_entries() -> eyre::Result<()> {
+        let owner_sk = SecretKey::random();
+        let cap = 5;
+        let mut register = bounded_register(&owner_sk, cap);
+        let mut replica = register.clone();
+
+        let mut written = vec![];
+        let mut children = BTreeSet::new();
+        for i in 0..cap + 5 {
+            let entry = format!("event {i}").into_bytes();
+            let (hash, op, prune_op) = register.write(entry.clone(), children, &owner_sk)?;
+            // entries are only pruned once the cap is exceeded
+            assert_eq!(prune_op.is_some(), i >= cap);
+
+            replica.apply_op(op)?;
+            if let Some(prune_op) = prune_op {
+                assert_eq!(prune_op.pruned().len(), 1);
+                replica.apply_prune_op(prune_op)?;
+            }
+            written.push(entry);
+            children = [hash].into();
+        }
+
+        let held: Vec<Entry> = register
+            .register()
+            .entries_topological()
+            .into_iter()
+            .map(|(_, entry, _)| entry)
+            .collect();
+        assert_eq!(held, written[written.len() - cap..].to_vec());
+        assert_eq!(register.register().size(), cap as u64);
+        assert_eq!(
+            register.register().read(),
+            [(
+                children.into_iter().next().ok_or(eyre::eyre!("no entry"))?,
+                written[written.len() - 1].clone()
+            )]
+            .into()
+        );
+
+        // the replica applying the ops converges
+        assert_eq!(replica, register);
+
+        Ok(())
+    }
+
+    #[test]
+    fn forged_prune_ops_are_rejected() -> eyre::Result<()> {
+        let owner_sk = SecretKey::random();
+        let mut register = bounded_register(&owner_sk, 1);
+        let mut replica = register.clone();
+
+        let (first, op1, _) = register.write(b"first".to_vec(), BTreeSet::new(), &owner_sk)?;
+        let (_, op2, prune_op) = register.write(b"second".to_vec(), [first].into(), &owner_sk)?;
+        replica.apply_op(op1)?;
+        replica.apply_op(op2)?;
+        let prune_op = prune_op.ok_or(eyre::eyre!("the first entry should be pruned"))?;
+
+        // signed by someone not allowed to write to the register
+        let mut forged = prune_op.clone();
+        forged.sign_with(&SecretKey::random());
+        assert!(matches!(
+            replica.apply_prune_op(forged),
+            Err(Error::AccessDenied(_))
+        ));
+
+        // pruning the current entry instead
+        let mut forged = prune_op.clone();
+        forged.pruned = replica
+            .register()
+            .read()
+            .into_iter()
+            .map(|(hash, _)| hash)
+            .collect();
+        forged.sign_with(&owner_sk);
+        assert!(matches!(
+            replica.apply_prune_op(forged),
+            Err(Error::InvalidOp(_))
+        ));
+
+        replica.apply_prune_op(prune_op)?;
+        assert_eq!(replica, register);
+
+        Ok(())
+    }
+
+    #[test]
+    fn prune_ops_are_carried_by_the_signed_register() -> eyre::Result<()> {
+        let owner_sk = SecretKey::random();
+        let cap = 3;
+        let mut register = bounded_register(&owner_sk, cap);
+        let mut signed = register.register().clone().into_signed(&owner_sk)?;
+
+        let mut stale = signed.clone();
+        let mut children = BTreeSet::new();
+        for i in 0..cap + 2 {
+            let entry = format!("event {i}").into_bytes();
+            let (hash, op, prune_op) = register.write(entry, children, &owner_sk)?;
+            signed.add_op(op.clone())?;
+            stale.add_op(op)?;
+            if let Some(prune_op) = prune_op {
+                signed.add_prune_op(prune_op)?;
+            }
+            children = [hash].into();
+        }
+
+        // the ops of the pruned entries are dropped, and the prune ops survive a round trip
+        assert_eq!(signed.prune_ops().len(), 2);
+        let signed: crate::SignedRegister = rmp_serde::from_slice(&rmp_serde::to_vec(&signed)?)?;
+        assert!(signed.verify().is_ok());
+        let rebuilt = signed.clone().register()?;
+        assert_eq!(rebuilt.size(), cap as u64);
+        assert_eq!(rebuilt.read(), register.register().read());
+        assert_eq!(
+            rebuilt.entries_topological(),
+            register.register().entries_topological()
+        );
+
+        // a replica still holding the ops of the pruned entries drops them on merging
+        assert_eq!(stale.version().op_count, cap + 2);
+        stale.merge(signed.clone())?;
+        assert_eq!(stale, signed);
+
+        // a prune op of the current entry is rejected
+        let mut forged = signed
+            .prune_ops()
+            .iter()
+            .next()
+            .cloned()
+            .ok_or(eyre::eyre!("no op"))?;
+        forged.pruned = register
+            .register()
+            .read()
+            .into_iter()
+            .map(|(hash, _)| hash)
+            .collect();
+        forged.sign_with(&owner_sk);
+        let mut signed = signed;
+        assert!(matches!(
+            signed.add_prune_op(forged),
+            Err(Error::InvalidOp(_))
+        ));
+
+        Ok(())
+    }
+}
@@ -24,6 +24,15 @@ pub enum Error {
         /// Targeted Register's address
         reg_addr: Box<RegisterAddress>,
     },
+    /// The op was signed for another Register than the one it's verified for, e.g. relayed to
+    /// the wrong Register
+    #[error("The Register operation is destined for {got}, not the expected Register {expected}")]
+    AddressMismatch {
+        /// The Register the op was verified for
+        expected: Box<RegisterAddress>,
+        /// The Register the op is destined for
+        got: Box<RegisterAddress>,
+    },
     /// Entry is too big to fit inside a register
     #[error("Entry is too big to fit inside a register: {size}, max: {max}")]
     EntryTooBig {
@@ -38,6 +47,9 @@ pub enum Error {
     /// Cannot add another entry since the register entry cap has been reached.
     #[error("Cannot add another entry since the register entry cap has been reached: {0}")]
     TooManyEntries(usize),
+    /// Cannot add another entry since the register holds as many entries as its owner allows.
+    #[error("Cannot add another entry since the register is full, its owner allows: {0}")]
+    RegisterFull(u64),
     /// Entry could not be found on the data
     #[error("Requested entry not found {0}")]
     NoSuchEntry(EntryHash),
@@ -62,6 +74,9 @@ pub enum Error {
         requested: Box<RegisterAddress>,
         got: Box<RegisterAddress>,
     },
+    /// The Register operation is malformed
+    #[error("Invalid Register operation: {0}")]
+    InvalidOp(String),
     /// The provided String can't be deserialized as a RegisterAddress
     #[error("Failed to deserialize hex RegisterAddress")]
     HexDeserializeFailed,
@@ -7,6 +7,7 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 mod address;
+mod bounded;
 pub(crate) mod error;
 mod metadata;
 mod permissions;
@@ -16,9 +17,10 @@ mod register_op;
 
 pub use self::{
     address::RegisterAddress,
+    bounded::{BoundedRegister, PruneOp},
     error::Error,
-    metadata::{Entry, EntryHash},
+    metadata::{Entry, EntryHash, EntryMeta},
     permissions::{Permissions, User},
-    register::{Register, SignedRegister},
-    register_op::RegisterOp,
+    register::{MergePolicy, Register, RegisterVersion, SignedRegister},
+    register_op::{sign_ops, RegisterOp, RegisterOpFormat},
 };
@@ -6,12 +6,24 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+use crate::User;
+
 use serde::{Deserialize, Serialize};
 use std::fmt::{Debug, Display, Formatter, Result as FmtResult};
 
 /// An entry in a Register (note that the `vec<u8>` is size limited: `MAX_REG_ENTRY_SIZE`)
 pub type Entry = Vec<u8>;
 
+/// Provenance of a register entry, derived from the op which wrote it.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EntryMeta {
+    /// The user who wrote the entry.
+    pub author: User,
+    /// Logical timestamp of the entry: one more than the highest lamport of the entries it
+    /// was written atop of, i.e. entries written from the root have a lamport of 1.
+    pub lamport: u64,
+}
+
 /// Hash of the register entry. Logging as the same format of `XorName`.
 #[derive(Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct EntryHash(pub crdts::merkle_reg::Hash);
@@ -18,6 +18,16 @@ pub enum User {
     Key(bls::PublicKey),
 }
 
+impl User {
+    /// Returns a bytes version of the User, as part of the bytes ops are signed on.
+    pub(crate) fn to_bytes(self) -> Vec<u8> {
+        match self {
+            User::Anyone => vec![0],
+            User::Key(key) => [&[1][..], &key.to_bytes()].concat(),
+        }
+    }
+}
+
 /// Register permissions
 /// Everyone can read a Register, all data is public on safe network.
 /// The Default value is nobody can write.
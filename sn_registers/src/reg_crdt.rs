@@ -8,12 +8,11 @@
 
 use crate::{error::Result, Entry, EntryHash, Error, RegisterAddress, RegisterOp, User};
 
-use crdts::{merkle_reg::MerkleReg, CmRDT, CvRDT};
+use crdts::merkle_reg::{Hash, Node as MerkleDagEntry};
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::BTreeSet,
+    collections::{BTreeMap, BTreeSet},
     fmt::{self, Debug, Display, Formatter},
-    hash::Hash,
 };
 
 /// Register data type as a CRDT with Access Control
@@ -22,17 +21,207 @@ pub(crate) struct RegisterCrdt {
     /// Address on the network of this piece of data
     address: RegisterAddress,
     /// CRDT to store the actual data, i.e. the items of the Register.
-    data: MerkleReg<Entry>,
+    data: EntryDag,
+}
+
+// The DAG of the entries of a Register, each written atop of its children, as `crdts`'
+// `MerkleReg` is, and serialized as it is. Unlike it, entries can be pruned from it, their hashes
+// being remembered for them not to be brought back by merging replicas still holding them, nor
+// to leave the entries written atop of them orphaned.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd)]
+#[serde(from = "EncodedEntryDag")]
+struct EntryDag {
+    /// The current entries, i.e. those no entry was written atop of
+    roots: BTreeSet<Hash>,
+    /// The entries whose children are all held or pruned
+    dag: BTreeMap<Hash, MerkleDagEntry<Entry>>,
+    /// The entries some children of which are yet to be applied
+    orphans: BTreeMap<Hash, MerkleDagEntry<Entry>>,
+    /// The hashes of the entries pruned
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pruned: BTreeSet<Hash>,
+    /// The orphans waiting for each of the children they are missing, so that applying an entry
+    /// only checks the orphans written atop of it. It's rebuilt from the orphans when decoded.
+    #[serde(skip)]
+    waiting: BTreeMap<Hash, BTreeSet<Hash>>,
+}
+
+// The fields of an `EntryDag` as encoded, out of which its index of orphans is rebuilt.
+#[derive(Deserialize)]
+struct EncodedEntryDag {
+    roots: BTreeSet<Hash>,
+    dag: BTreeMap<Hash, MerkleDagEntry<Entry>>,
+    orphans: BTreeMap<Hash, MerkleDagEntry<Entry>>,
+    #[serde(default)]
+    pruned: BTreeSet<Hash>,
+}
+
+impl From<EncodedEntryDag> for EntryDag {
+    fn from(encoded: EncodedEntryDag) -> Self {
+        let mut dag = Self {
+            roots: encoded.roots,
+            dag: encoded.dag,
+            orphans: encoded.orphans,
+            pruned: encoded.pruned,
+            waiting: BTreeMap::new(),
+        };
+        let orphans: Vec<(Hash, BTreeSet<Hash>)> = dag
+            .orphans
+            .iter()
+            .map(|(hash, node)| (*hash, node.children.clone()))
+            .collect();
+        for (hash, children) in orphans {
+            dag.wait_for_missing(hash, &children);
+        }
+        dag
+    }
+}
+
+impl EntryDag {
+    fn apply(&mut self, node: MerkleDagEntry<Entry>) {
+        let hash = node.hash();
+        if self.dag.contains_key(&hash)
+            || self.orphans.contains_key(&hash)
+            || self.pruned.contains(&hash)
+        {
+            return;
+        }
+
+        if self.all_seen(&node.children) {
+            self.insert(hash, node);
+            self.resolve_orphans([hash]);
+        } else {
+            self.wait_for_missing(hash, &node.children);
+            let _ = self.orphans.insert(hash, node);
+        }
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.prune(&other.pruned);
+        for node in other.dag.into_values().chain(other.orphans.into_values()) {
+            self.apply(node);
+        }
+    }
+
+    fn prune(&mut self, hashes: &BTreeSet<Hash>) {
+        let mut removed = BTreeMap::new();
+        for hash in hashes {
+            let _ = self.roots.remove(hash);
+            if let Some(node) = self.dag.remove(hash) {
+                let _ = removed.insert(*hash, node);
+            }
+            if let Some(node) = self.orphans.remove(hash) {
+                self.stop_waiting(*hash, &node.children);
+            }
+            let _ = self.pruned.insert(*hash);
+        }
+        self.promote_uncovered_children(&removed);
+        // orphans written atop of the pruned entries are orphans no more
+        self.resolve_orphans(hashes.iter().copied());
+    }
+
+    // Make current the entries the `removed` ones were written atop of, unless an entry still held
+    // was written atop of them, be it through removed entries, for them not to be lost to reads.
+    fn promote_uncovered_children(&mut self, removed: &BTreeMap<Hash, MerkleDagEntry<Entry>>) {
+        if removed.is_empty() {
+            return;
+        }
+        let mut covered = BTreeSet::new();
+        let mut to_visit: Vec<Hash> = self
+            .dag
+            .values()
+            .flat_map(|node| node.children.iter().copied())
+            .collect();
+        while let Some(hash) = to_visit.pop() {
+            if covered.insert(hash) {
+                if let Some(node) = removed.get(&hash) {
+                    to_visit.extend(node.children.iter().copied());
+                }
+            }
+        }
+
+        for node in removed.values() {
+            for child in &node.children {
+                if self.dag.contains_key(child) && !covered.contains(child) {
+                    let _ = self.roots.insert(*child);
+                }
+            }
+        }
+    }
+
+    fn node(&self, hash: Hash) -> Option<&MerkleDagEntry<Entry>> {
+        self.dag.get(&hash).or_else(|| self.orphans.get(&hash))
+    }
+
+    fn read(&self) -> impl Iterator<Item = (Hash, &MerkleDagEntry<Entry>)> {
+        self.roots
+            .iter()
+            .filter_map(|root| self.dag.get(root).map(|node| (*root, node)))
+    }
+
+    fn all_seen(&self, hashes: &BTreeSet<Hash>) -> bool {
+        hashes
+            .iter()
+            .all(|hash| self.dag.contains_key(hash) || self.pruned.contains(hash))
+    }
+
+    // Insert a node all the children of which have been seen, which thus is a current entry.
+    fn insert(&mut self, hash: Hash, node: MerkleDagEntry<Entry>) {
+        for child in &node.children {
+            let _ = self.roots.remove(child);
+        }
+        let _ = self.roots.insert(hash);
+        let _ = self.dag.insert(hash, node);
+    }
+
+    // Index the orphan at `hash` under each of its `children` yet to be seen.
+    fn wait_for_missing(&mut self, hash: Hash, children: &BTreeSet<Hash>) {
+        for child in children {
+            if !self.dag.contains_key(child) && !self.pruned.contains(child) {
+                let _ = self.waiting.entry(*child).or_default().insert(hash);
+            }
+        }
+    }
+
+    // Remove the orphan at `hash` from the index, e.g. once it's pruned.
+    fn stop_waiting(&mut self, hash: Hash, children: &BTreeSet<Hash>) {
+        for child in children {
+            if let Some(waiting) = self.waiting.get_mut(child) {
+                let _ = waiting.remove(&hash);
+                if waiting.is_empty() {
+                    let _ = self.waiting.remove(child);
+                }
+            }
+        }
+    }
+
+    // Insert the orphans waiting for the entries at `seen`, now held or pruned, which have seen
+    // all their children, then those waiting for the orphans inserted, and so on.
+    fn resolve_orphans(&mut self, seen: impl IntoIterator<Item = Hash>) {
+        let mut to_visit: Vec<Hash> = seen.into_iter().collect();
+        while let Some(hash) = to_visit.pop() {
+            for orphan in self.waiting.remove(&hash).unwrap_or_default() {
+                if !matches!(self.orphans.get(&orphan), Some(node) if self.all_seen(&node.children))
+                {
+                    continue;
+                }
+                if let Some(node) = self.orphans.remove(&orphan) {
+                    self.insert(orphan, node);
+                    to_visit.push(orphan);
+                }
+            }
+        }
+    }
 }
 
 impl Display for RegisterCrdt {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
         write!(f, "(")?;
-        for (i, entry) in self.data.read().values().enumerate() {
+        for (i, (_, node)) in self.data.read().enumerate() {
             if i > 0 {
                 write!(f, ", ")?;
             }
-            write!(f, "<{entry:?}>")?;
+            write!(f, "<{:?}>", node.value)?;
         }
         write!(f, ")")
     }
@@ -43,7 +232,7 @@ impl RegisterCrdt {
     pub(crate) fn new(address: RegisterAddress) -> Self {
         Self {
             address,
-            data: MerkleReg::new(),
+            data: EntryDag::default(),
         }
     }
 
@@ -57,9 +246,9 @@ impl RegisterCrdt {
         self.data.merge(other.data);
     }
 
-    /// Returns total number of items in the register.
+    /// Returns total number of items in the register, but those pruned.
     pub(crate) fn size(&self) -> u64 {
-        (self.data.num_nodes() + self.data.num_orphans()) as u64
+        (self.data.dag.len() + self.data.orphans.len()) as u64
     }
 
     /// Write a new entry to the `RegisterCrdt`, returning the hash
@@ -72,13 +261,15 @@ impl RegisterCrdt {
     ) -> Result<(EntryHash, RegisterOp)> {
         let address = *self.address();
 
-        let children_array: BTreeSet<[u8; 32]> = children.iter().map(|itr| itr.0).collect();
-        let crdt_op = self.data.write(entry, children_array);
-        self.data.apply(crdt_op.clone());
-        let hash = crdt_op.hash();
-
+        let crdt_op = MerkleDagEntry {
+            children: children.iter().map(|itr| itr.0).collect(),
+            value: entry,
+        };
         // We return the operation as it may need to be broadcasted to other replicas
-        let op = RegisterOp::new(address, crdt_op, source, None);
+        let op = RegisterOp::try_new(address, crdt_op, source, None)?;
+
+        self.data.apply(op.crdt_op.clone());
+        let hash = op.crdt_op.hash();
 
         Ok((EntryHash(hash), op))
     }
@@ -95,6 +286,8 @@ impl RegisterCrdt {
                 reg_addr: Box::new(self.address),
             });
         }
+        // Ops deserialised from peers weren't validated at construction
+        op.validate()?;
 
         // Apply the CRDT operation to the Register
         self.data.apply(op.crdt_op);
@@ -107,21 +300,96 @@ impl RegisterCrdt {
         self.data.node(hash.0).map(|node| &node.value)
     }
 
+    /// Get the DAG node, i.e. the CRDT op, which wrote the entry with the provided `hash`.
+    pub(crate) fn node(&self, hash: EntryHash) -> Option<&MerkleDagEntry<Entry>> {
+        self.data.node(hash.0)
+    }
+
+    /// Returns `true` if the DAG holds an entry with the provided `hash`, or has pruned it.
+    pub(crate) fn is_known(&self, hash: &EntryHash) -> bool {
+        self.contains(hash) || self.data.pruned.contains(&hash.0)
+    }
+
+    /// Returns `true` if the DAG holds an entry with the provided `hash`.
+    pub(crate) fn contains(&self, hash: &EntryHash) -> bool {
+        self.data.node(hash.0).is_some()
+    }
+
     /// Read current entries (multiple entries occur on concurrent writes).
     pub(crate) fn read(&self) -> BTreeSet<(EntryHash, Entry)> {
         self.data
             .read()
-            .hashes_and_nodes()
             .map(|(hash, node)| (EntryHash(hash), node.value.clone()))
             .collect()
     }
+
+    /// Iterate over all the entries held in the register, including those overwritten.
+    pub(crate) fn entries(&self) -> impl Iterator<Item = &Entry> {
+        self.data.dag.values().map(|node| &node.value)
+    }
+
+    /// Remove the entries with the provided `hashes` from the DAG. The entries written atop of
+    /// them are kept, and `lamport` then only accounts for the entries still held. Pruned entries
+    /// are never brought back, whether applied again or merged in from another replica.
+    pub(crate) fn prune(&mut self, hashes: &BTreeSet<EntryHash>) {
+        let hashes = hashes.iter().map(|hash| hash.0).collect();
+        self.data.prune(&hashes);
+    }
+
+    /// Iterate over the DAG nodes, i.e. the CRDT ops, of all the entries held in the register,
+    /// orphans included.
+    pub(crate) fn nodes(&self) -> impl Iterator<Item = &MerkleDagEntry<Entry>> {
+        self.data.dag.values().chain(self.data.orphans.values())
+    }
+
+    /// Iterate over all the entries held in the register along with their hashes.
+    pub(crate) fn hashes_and_entries(&self) -> impl Iterator<Item = (EntryHash, &Entry)> {
+        self.data
+            .dag
+            .iter()
+            .map(|(hash, node)| (EntryHash(*hash), &node.value))
+    }
+
+    /// The hashes of the entries the entry with the provided `hash` was written atop of.
+    pub(crate) fn children(&self, hash: EntryHash) -> Option<BTreeSet<EntryHash>> {
+        self.data.node(hash.0).map(|node| {
+            node.children
+                .iter()
+                .map(|child| EntryHash(*child))
+                .collect()
+        })
+    }
+
+    /// Lamport timestamp of an entry: one more than the highest lamport of its children.
+    /// Only the children present in the DAG are taken into account, and entries not found
+    /// have a lamport of 0. Computed lamports are memoised in `lamports`.
+    pub(crate) fn lamport(&self, hash: EntryHash, lamports: &mut BTreeMap<EntryHash, u64>) -> u64 {
+        if let Some(lamport) = lamports.get(&hash) {
+            return *lamport;
+        }
+        let lamport = match self.data.node(hash.0) {
+            Some(node) => {
+                1 + node
+                    .children
+                    .iter()
+                    .map(|child| self.lamport(EntryHash(*child), lamports))
+                    .max()
+                    .unwrap_or(0)
+            }
+            None => 0,
+        };
+        let _ = lamports.insert(hash, lamport);
+        lamport
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    use crate::register::MAX_REG_NUM_ENTRIES;
     use bls::SecretKey;
+    use crdts::{merkle_reg::MerkleReg, CmRDT};
     use xor_name::XorName;
 
     #[test]
@@ -160,4 +428,162 @@ mod tests {
 
         Ok(())
     }
+
+    fn chain(crdt: &mut RegisterCrdt, length: usize) -> Result<Vec<EntryHash>> {
+        let mut hashes: Vec<EntryHash> = vec![];
+        for i in 0..length {
+            let children = hashes.last().copied().into_iter().collect();
+            let (hash, _) = crdt.write(vec![i as u8], children, User::Anyone)?;
+            hashes.push(hash);
+        }
+        Ok(hashes)
+    }
+
+    #[test]
+    fn pruned_entries_are_not_brought_back() -> Result<()> {
+        let address = RegisterAddress {
+            meta: XorName::random(&mut rand::thread_rng()),
+            owner: SecretKey::random().public_key(),
+        };
+        let mut full = RegisterCrdt::new(address);
+        let hashes = chain(&mut full, 3)?;
+
+        let mut pruned = full.clone();
+        pruned.prune(&[hashes[0]].into());
+        assert_eq!(pruned.size(), 2);
+        assert!(!pruned.contains(&hashes[0]));
+        assert_eq!(pruned.read(), full.read());
+
+        // neither by merging a replica still holding it, nor by applying its op again
+        pruned.merge(full.clone());
+        assert!(!pruned.contains(&hashes[0]));
+        assert_eq!(pruned.size(), 2);
+        let mut rewritten = RegisterCrdt::new(address);
+        let (_, op) = rewritten.write(vec![0], BTreeSet::new(), User::Anyone)?;
+        pruned.apply_op(op)?;
+        assert!(!pruned.contains(&hashes[0]));
+
+        // while a replica merging the pruned one prunes it too
+        full.merge(pruned.clone());
+        assert_eq!(full, pruned);
+
+        // an entry written atop of a pruned one isn't orphaned, even when applied afterwards
+        let mut late = RegisterCrdt::new(address);
+        late.prune(&[hashes[0]].into());
+        let mut writer = RegisterCrdt::new(address);
+        let (_, op) = writer.write(vec![1], [hashes[0]].into(), User::Anyone)?;
+        late.apply_op(op)?;
+        assert_eq!(
+            late.read()
+                .into_iter()
+                .map(|(hash, _)| hash)
+                .collect::<Vec<_>>(),
+            vec![hashes[1]]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn malformed_ops_are_neither_written_nor_applied() -> Result<()> {
+        let address = RegisterAddress {
+            meta: XorName::random(&mut rand::thread_rng()),
+            owner: SecretKey::random().public_key(),
+        };
+        let too_many_parents: BTreeSet<EntryHash> = (0..=MAX_REG_NUM_ENTRIES)
+            .map(|_| EntryHash(XorName::random(&mut rand::thread_rng()).0))
+            .collect();
+
+        let mut crdt = RegisterCrdt::new(address);
+        assert!(matches!(
+            crdt.write(vec![0x1], too_many_parents.clone(), User::Anyone),
+            Err(Error::InvalidOp(_))
+        ));
+        assert_eq!(crdt.size(), 0);
+
+        // an op built without validation, as deserialised from a peer
+        let op = RegisterOp::new(
+            address,
+            MerkleDagEntry {
+                children: too_many_parents.iter().map(|hash| hash.0).collect(),
+                value: vec![0x1],
+            },
+            User::Anyone,
+            None,
+        );
+        assert!(matches!(crdt.apply_op(op), Err(Error::InvalidOp(_))));
+        assert_eq!(crdt.size(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn orphans_are_resolved_once_their_children_are_seen() -> eyre::Result<()> {
+        let address = RegisterAddress {
+            meta: XorName::random(&mut rand::thread_rng()),
+            owner: SecretKey::random().public_key(),
+        };
+        let mut writer = RegisterCrdt::new(address);
+        let hashes = chain(&mut writer, 10)?;
+        let nodes: Vec<MerkleDagEntry<Entry>> = hashes
+            .iter()
+            .filter_map(|hash| writer.node(*hash).cloned())
+            .collect();
+
+        // applied newest first, each entry is orphaned till the first one is applied
+        let mut crdt = RegisterCrdt::new(address);
+        for node in nodes.iter().skip(1).rev() {
+            crdt.data.apply(node.clone());
+        }
+        assert_eq!(crdt.data.orphans.len(), 9);
+        assert!(crdt.read().is_empty());
+
+        // the orphans decoded wait for the first entry as well
+        let mut decoded: RegisterCrdt = rmp_serde::from_slice(&rmp_serde::to_vec(&crdt)?)?;
+        assert_eq!(decoded, crdt);
+
+        for crdt in [&mut crdt, &mut decoded] {
+            crdt.data.apply(nodes[0].clone());
+            assert!(crdt.data.orphans.is_empty());
+            assert!(crdt.data.waiting.is_empty());
+            assert_eq!(crdt.read(), writer.read());
+        }
+
+        // pruning the entry an orphan waits for resolves it too
+        let mut crdt = RegisterCrdt::new(address);
+        crdt.data.apply(nodes[1].clone());
+        crdt.prune(&[hashes[0]].into());
+        assert!(crdt.data.waiting.is_empty());
+        assert_eq!(
+            crdt.read()
+                .into_iter()
+                .map(|(hash, _)| hash)
+                .collect::<Vec<_>>(),
+            vec![hashes[1]]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn entry_dag_is_serialised_as_a_merkle_reg() -> eyre::Result<()> {
+        let mut merkle_reg = MerkleReg::new();
+        let mut dag = EntryDag::default();
+        let first = merkle_reg.write(vec![0x1], BTreeSet::new());
+        let second = merkle_reg.write(vec![0x2], [first.hash()].into());
+        // the second entry is applied first, thus orphaned till the first one is
+        for node in [second.clone(), first.clone()] {
+            merkle_reg.apply(node.clone());
+            dag.apply(node);
+        }
+        let orphan = merkle_reg.write(vec![0x3], [[0; 32]].into());
+        merkle_reg.apply(orphan.clone());
+        dag.apply(orphan);
+
+        let bytes = rmp_serde::to_vec(&merkle_reg)?;
+        assert_eq!(rmp_serde::to_vec(&dag)?, bytes);
+        assert_eq!(rmp_serde::from_slice::<EntryDag>(&bytes)?, dag);
+
+        Ok(())
+    }
 }
@@ -7,21 +7,22 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::{
-    error::Result, reg_crdt::RegisterCrdt, Entry, EntryHash, Error, Permissions, RegisterAddress,
-    RegisterOp, User,
+    error::Result, reg_crdt::RegisterCrdt, Entry, EntryHash, EntryMeta, Error, Permissions,
+    PruneOp, RegisterAddress, RegisterOp, RegisterOpFormat, User,
 };
 
 use bls::{PublicKey, SecretKey, Signature};
+use crdts::merkle_reg::Node as MerkleDagEntry;
 use self_encryption::MIN_ENCRYPTABLE_BYTES;
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
 use xor_name::XorName;
 
 /// Arbitrary maximum size of a register entry.
-const MAX_REG_ENTRY_SIZE: usize = MIN_ENCRYPTABLE_BYTES / 3; // 1024 bytes
+pub(crate) const MAX_REG_ENTRY_SIZE: usize = MIN_ENCRYPTABLE_BYTES / 3; // 1024 bytes
 
 /// Maximum number of entries of a register.
-const MAX_REG_NUM_ENTRIES: u16 = 1024;
+pub(crate) const MAX_REG_NUM_ENTRIES: u16 = 1024;
 
 /// A Register on the SAFE Network
 #[derive(Clone, Eq, PartialEq, PartialOrd, Hash, Serialize, Deserialize, Debug)]
@@ -32,6 +33,71 @@ pub struct Register {
     /// Depending on the permissions, the owner can allow other users to write to the register
     /// Everyone can always read the Register because all data is public
     permissions: Permissions,
+    /// The author of each entry, retained from the signed op which wrote it
+    #[serde(default)]
+    authors: BTreeMap<EntryHash, Authorship>,
+    /// How concurrent branches are resolved when reading the Register
+    #[serde(default)]
+    merge_policy: MergePolicy,
+    /// Maximum number of entries the owner allows, below the maximum of any Register
+    #[serde(default)]
+    max_entries: Option<u64>,
+}
+
+/// Prefix of the bytes signed by the owner of a Register whose merge policy or cap aren't the
+/// defaults, see `Register::bytes`.
+const SIGNED_SETTINGS_PREFIX: &[u8] = b"RegisterSettingsV1";
+
+// The part of a Register signed by its owner, encoded as the Register was before it held
+// anything else, see `Register::bytes`.
+#[derive(Serialize)]
+struct SignedContent<'a> {
+    crdt: &'a RegisterCrdt,
+    permissions: &'a Permissions,
+}
+
+/// The author of an entry along with their signature of the op which wrote it, so that the
+/// authorship handed over by another replica can be verified before being retained.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Hash)]
+struct Authorship {
+    source: User,
+    signature: Option<Signature>,
+    format: RegisterOpFormat,
+}
+
+impl Authorship {
+    fn of(op: &RegisterOp) -> Self {
+        Self {
+            source: op.source,
+            signature: op.signature.clone(),
+            format: op.format,
+        }
+    }
+
+    // Whether this authorship is retained over the `other` one, for an entry written by several
+    // users: a user's key over anyone, the smallest key otherwise, so that all replicas converge.
+    fn precedes(&self, other: &Self) -> bool {
+        match (self.source, other.source) {
+            (User::Key(_), User::Anyone) => true,
+            (User::Anyone, User::Key(_)) => false,
+            _ => self < other,
+        }
+    }
+}
+
+/// How a Register resolves the concurrent branches, i.e. the multiple current entries,
+/// resulting from replicas written concurrently.
+/// Replicas holding the same ops read the same entries, whichever way the ops were applied or
+/// merged. The branches losing to the entry read under `LastWriterWins` are dropped on merges,
+/// for them not to count towards the cap of the Register, see `Register::merge`.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Hash)]
+pub enum MergePolicy {
+    /// Keep all the branches, leaving their resolution to the readers.
+    #[default]
+    KeepBranches,
+    /// Only read the entry with the highest lamport timestamp, the highest hash among those,
+    /// out of the current entries. The entries of the other branches are dropped on merges.
+    LastWriterWins,
 }
 
 /// A Signed Register on the SAFE Network
@@ -45,6 +111,41 @@ pub struct SignedRegister {
     /// operations to apply on this register,
     /// they contain a signature of the writer
     ops: BTreeSet<RegisterOp>,
+    /// operations pruning entries of this register, see `BoundedRegister`,
+    /// the ops of the entries pruned being dropped
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    prune_ops: BTreeSet<PruneOp>,
+}
+
+/// Summary of the state of a SignedRegister replica, which allows comparing replicas
+/// without transferring nor materializing them.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+pub struct RegisterVersion {
+    /// Number of distinct ops held by the replica
+    pub op_count: usize,
+    /// Number of current entries, i.e. branches, of the replica
+    pub head_count: usize,
+    /// Hash over all the ops held by the replica; replicas holding the same ops share it
+    pub state_hash: XorName,
+}
+
+impl RegisterVersion {
+    // The version of a replica holding the provided CRDT `ops`.
+    fn of_ops<'a>(ops: impl Iterator<Item = &'a MerkleDagEntry<Entry>>) -> Self {
+        let mut hashes: BTreeSet<[u8; 32]> = BTreeSet::new();
+        let mut children: BTreeSet<[u8; 32]> = BTreeSet::new();
+        for op in ops {
+            let _ = hashes.insert(op.hash());
+            children.extend(op.children.iter().copied());
+        }
+        let parts: Vec<&[u8]> = hashes.iter().map(|hash| hash.as_slice()).collect();
+
+        Self {
+            op_count: hashes.len(),
+            head_count: hashes.difference(&children).count(),
+            state_hash: XorName::from_content_parts(&parts),
+        }
+    }
 }
 
 impl SignedRegister {
@@ -54,6 +155,7 @@ impl SignedRegister {
             base_register,
             signature,
             ops: BTreeSet::new(),
+            prune_ops: BTreeSet::new(),
         }
     }
 
@@ -71,6 +173,9 @@ impl SignedRegister {
         for op in &self.ops {
             self.base_register.check_register_op(op)?;
         }
+        for op in &self.prune_ops {
+            self.base_register.check_prune_op(op)?;
+        }
         Ok(())
     }
 
@@ -87,9 +192,18 @@ impl SignedRegister {
     /// Return the Register after applying all the operations
     pub fn register(self) -> Result<Register> {
         let mut register = self.base_register;
+        // the entries pruned are applied first, for the entries written atop of them to be
+        // applied without their ops, which were dropped
+        for op in &self.prune_ops {
+            register.prune(&op.pruned);
+        }
+        // the ops merged in from other replicas may exceed the cap of the register, the entries
+        // beyond it are dropped once all are applied, as merging the replicas would
         for op in self.ops {
-            register.apply_op(op)?;
+            register.apply_op_beyond_cap(op)?;
         }
+        register.drop_losing_branches();
+        register.drop_excess_entries();
         Ok(register)
     }
 
@@ -98,7 +212,10 @@ impl SignedRegister {
         if self.base_register != other.base_register {
             return Err(Error::DifferentBaseRegister);
         }
-        self.ops.extend(other.ops.into_iter());
+        self.ops.extend(other.ops);
+        self.prune_ops.extend(other.prune_ops);
+        self.drop_pruned_ops();
+        self.drop_migrated_ops();
         Ok(())
     }
 
@@ -109,8 +226,7 @@ impl SignedRegister {
             return Err(Error::DifferentBaseRegister);
         }
         other.verify()?;
-        self.ops.extend(other.ops.into_iter());
-        Ok(())
+        self.merge(other)
     }
 
     /// Return the address.
@@ -127,21 +243,191 @@ impl SignedRegister {
     pub fn add_op(&mut self, op: RegisterOp) -> Result<()> {
         self.base_register.check_register_op(&op)?;
         self.ops.insert(op);
+        self.drop_pruned_ops();
+        self.drop_migrated_ops();
         Ok(())
     }
+
+    /// Migrate the ops signed under the legacy `RegisterOpFormat::V0`, which still verify under
+    /// that format, to the current one, see `RegisterOp::migrate`. Their legacy signature is checked, then they
+    /// are signed anew with the provided secret key, which must be allowed to write to the
+    /// Register. Replicas this one is merged into drop the legacy ops it supersedes.
+    pub fn migrate_legacy_ops(&mut self, sk: &SecretKey) -> Result<()> {
+        self.base_register
+            .check_user_permissions(User::Key(sk.public_key()))?;
+        let (legacy, current): (BTreeSet<RegisterOp>, BTreeSet<RegisterOp>) =
+            std::mem::take(&mut self.ops)
+                .into_iter()
+                .partition(|op| op.format == RegisterOpFormat::V0);
+        self.ops = current;
+        for mut op in legacy {
+            op.migrate(sk)?;
+            self.ops.insert(op);
+        }
+        Ok(())
+    }
+
+    /// Check and add an op pruning entries to the SignedRegister, dropping the ops of the entries
+    /// it prunes. It may not prune the current entries.
+    pub fn add_prune_op(&mut self, op: PruneOp) -> Result<()> {
+        self.base_register.check_prune_op(&op)?;
+        let hashes: BTreeSet<[u8; 32]> = self.ops.iter().map(|op| op.crdt_op.hash()).collect();
+        let children: BTreeSet<[u8; 32]> = self
+            .ops
+            .iter()
+            .flat_map(|op| op.crdt_op.children.iter().copied())
+            .collect();
+        if op
+            .pruned
+            .iter()
+            .any(|hash| hashes.contains(&hash.0) && !children.contains(&hash.0))
+        {
+            return Err(Error::InvalidOp(
+                "op prunes current entries of the register".to_string(),
+            ));
+        }
+        self.prune_ops.insert(op);
+        self.drop_pruned_ops();
+        Ok(())
+    }
+
+    /// Return the ops pruning entries held by the SignedRegister.
+    pub fn prune_ops(&self) -> &BTreeSet<PruneOp> {
+        &self.prune_ops
+    }
+
+    // Drop the ops of the entries pruned by our prune ops.
+    fn drop_pruned_ops(&mut self) {
+        if self.prune_ops.is_empty() {
+            return;
+        }
+        let pruned: BTreeSet<EntryHash> = self
+            .prune_ops
+            .iter()
+            .flat_map(|op| op.pruned.iter().copied())
+            .collect();
+        self.ops
+            .retain(|op| !pruned.contains(&EntryHash(op.crdt_op.hash())));
+    }
+
+    // Drop the legacy ops whose entry is also written by an op of the current format, i.e. the
+    // legacy ops migrated by a replica merged in.
+    fn drop_migrated_ops(&mut self) {
+        let current: BTreeSet<[u8; 32]> = self
+            .ops
+            .iter()
+            .filter(|op| op.format != RegisterOpFormat::V0)
+            .map(|op| op.crdt_op.hash())
+            .collect();
+        self.ops.retain(|op| {
+            op.format != RegisterOpFormat::V0 || !current.contains(&op.crdt_op.hash())
+        });
+    }
+
+    /// Return the `RegisterVersion` of this replica.
+    pub fn version(&self) -> RegisterVersion {
+        RegisterVersion::of_ops(self.ops.iter().map(|op| &op.crdt_op))
+    }
+
+    /// Return the ops a replica whose current entries are `known_heads` is missing to catch up
+    /// with us, see `ops_unknown_to`, in dependency order, i.e. each op comes after the ops of
+    /// the entries it was written atop of, so that they can be applied one by one.
+    pub fn ops_since(&self, known_heads: &BTreeSet<EntryHash>) -> Vec<RegisterOp> {
+        let mut missing: BTreeMap<EntryHash, RegisterOp> = self
+            .ops_unknown_to(known_heads)
+            .into_iter()
+            .map(|op| (EntryHash(op.crdt_op.hash()), op))
+            .collect();
+
+        let mut ordered = Vec::with_capacity(missing.len());
+        while !missing.is_empty() {
+            let ready: Vec<EntryHash> = missing
+                .iter()
+                .filter(|(_, op)| {
+                    op.crdt_op
+                        .children
+                        .iter()
+                        .all(|child| !missing.contains_key(&EntryHash(*child)))
+                })
+                .map(|(hash, _)| *hash)
+                .collect();
+            if ready.is_empty() {
+                break;
+            }
+            for hash in ready {
+                ordered.extend(missing.remove(&hash));
+            }
+        }
+        ordered
+    }
+
+    /// Return the ops a replica whose current entries are `known_heads` is missing, i.e. all our
+    /// ops but those which wrote the `known_heads` and their ancestors.
+    /// Heads we don't have an op for are ignored, thus their ancestors may be returned.
+    pub fn ops_unknown_to(&self, known_heads: &BTreeSet<EntryHash>) -> BTreeSet<RegisterOp> {
+        let ops_by_hash: BTreeMap<EntryHash, &RegisterOp> = self
+            .ops
+            .iter()
+            .map(|op| (EntryHash(op.crdt_op.hash()), op))
+            .collect();
+
+        let mut known = BTreeSet::new();
+        let mut to_visit: Vec<EntryHash> = known_heads.iter().copied().collect();
+        while let Some(hash) = to_visit.pop() {
+            if !known.insert(hash) {
+                continue;
+            }
+            if let Some(op) = ops_by_hash.get(&hash) {
+                to_visit.extend(op.crdt_op.children.iter().map(|child| EntryHash(*child)));
+            }
+        }
+
+        ops_by_hash
+            .into_iter()
+            .filter(|(hash, _)| !known.contains(hash))
+            .map(|(_, op)| op.clone())
+            .collect()
+    }
 }
 
 impl Register {
-    /// Create a new Register
-    pub fn new(owner: PublicKey, meta: XorName, mut permissions: Permissions) -> Self {
+    /// Create a new Register, holding no more than `max_entries` entries if provided, below the
+    /// maximum number of entries of any Register. Further writes and ops then fail with
+    /// `Error::RegisterFull`, while the entries beyond the cap merged in from other replicas are
+    /// dropped, see `merge`.
+    pub fn new(
+        owner: PublicKey,
+        meta: XorName,
+        mut permissions: Permissions,
+        max_entries: Option<u64>,
+    ) -> Self {
         let address = RegisterAddress { meta, owner };
         permissions.writers.insert(User::Key(owner));
         Self {
             crdt: RegisterCrdt::new(address),
             permissions,
+            authors: BTreeMap::new(),
+            merge_policy: MergePolicy::default(),
+            max_entries,
         }
     }
 
+    /// Set how concurrent branches are resolved when reading the Register. The policy is part of the
+    /// Register, hence is to be set before signing it, so that all its replicas apply it.
+    pub fn set_merge_policy(&mut self, policy: MergePolicy) {
+        self.merge_policy = policy;
+    }
+
+    /// Return how concurrent branches are resolved when reading the Register.
+    pub fn merge_policy(&self) -> MergePolicy {
+        self.merge_policy
+    }
+
+    /// Return the cap on the number of entries of the Register, if any.
+    pub fn max_entries(&self) -> Option<u64> {
+        self.max_entries
+    }
+
     /// Sign a Register and return the signature, makes sure the signer is the owner in the process
     pub fn sign(&self, secret_key: &SecretKey) -> Result<Signature> {
         if self.owner() != secret_key.public_key() {
@@ -154,8 +440,23 @@ impl Register {
 
     /// Returns a bytes version of the Register used for signing
     /// Use this API when you want to sign a Register withtout providing a secret key to the Register API
+    ///
+    /// Only the entries and permissions are signed, as the whole Register was before it held
+    /// anything else, so that the Registers signed then still verify. The authors retained by a
+    /// replica aren't signed, as they change with the ops applied. The merge policy and cap are
+    /// signed too when they aren't the defaults, after the `SIGNED_SETTINGS_PREFIX`.
     pub fn bytes(&self) -> Result<Vec<u8>> {
-        bincode::serialize(self).map_err(|_| Error::SerialisationFailed)
+        let content = SignedContent {
+            crdt: &self.crdt,
+            permissions: &self.permissions,
+        };
+        let encoded = if self.merge_policy == MergePolicy::default() && self.max_entries.is_none() {
+            bincode::serialize(&content)
+        } else {
+            bincode::serialize(&(content, self.merge_policy, self.max_entries))
+                .map(|bytes| [SIGNED_SETTINGS_PREFIX, bytes.as_slice()].concat())
+        };
+        encoded.map_err(|_| Error::SerialisationFailed)
     }
 
     /// Sign a Register into a SignedRegister
@@ -167,7 +468,7 @@ impl Register {
     #[cfg(test)]
     pub fn new_owned(owner: PublicKey, meta: XorName) -> Self {
         let permissions = Default::default();
-        Self::new(owner, meta, permissions)
+        Self::new(owner, meta, permissions, None)
     }
 
     /// Return the address.
@@ -185,6 +486,12 @@ impl Register {
         self.crdt.size()
     }
 
+    /// Return the `RegisterVersion` of this replica, out of the ops of the entries it holds,
+    /// for it to be compared with that of a `SignedRegister` replica.
+    pub fn version(&self) -> RegisterVersion {
+        RegisterVersion::of_ops(self.crdt.nodes())
+    }
+
     /// Return a value corresponding to the provided 'hash', if present.
     pub fn get(&self, hash: EntryHash) -> Result<&Entry> {
         self.crdt.get(hash).ok_or(Error::NoSuchEntry(hash))
@@ -195,9 +502,109 @@ impl Register {
         self.crdt.get(hash).cloned().ok_or(Error::NoSuchEntry(hash))
     }
 
+    /// Returns `true` if the register holds an entry with the provided 'hash', including
+    /// entries which have since been overwritten, without reading the entries.
+    pub fn contains(&self, hash: &EntryHash) -> bool {
+        self.crdt.contains(hash)
+    }
+
     /// Read the last entry, or entries when there are branches, if the register is not empty.
+    /// Branches are resolved as per the `MergePolicy` of the register.
     pub fn read(&self) -> BTreeSet<(EntryHash, Entry)> {
-        self.crdt.read()
+        let heads = self.crdt.read();
+        match self.merge_policy {
+            MergePolicy::KeepBranches => heads,
+            MergePolicy::LastWriterWins => {
+                let mut lamports = BTreeMap::new();
+                heads
+                    .into_iter()
+                    .max_by_key(|(hash, _)| (self.crdt.lamport(*hash, &mut lamports), *hash))
+                    .into_iter()
+                    .collect()
+            }
+        }
+    }
+
+    /// The hashes of the current entries, whatever the `MergePolicy` of the register.
+    pub(crate) fn heads(&self) -> BTreeSet<EntryHash> {
+        self.crdt.read().into_iter().map(|(hash, _)| hash).collect()
+    }
+
+    /// Read the last entry, or entries when there are branches, along with the `EntryMeta`
+    /// of each of them, i.e. its author and lamport timestamp.
+    ///
+    /// The metadata is retained when a signed op is applied to this replica, or merged in from
+    /// another replica which retained it once its signature is verified. It is `None` for entries
+    /// whose op wasn't retained, e.g. entries written locally whose op hasn't been applied back
+    /// from the network yet.
+    pub fn read_with_meta(&self) -> BTreeSet<(EntryHash, Entry, Option<EntryMeta>)> {
+        let mut lamports = BTreeMap::new();
+        self.read()
+            .into_iter()
+            .map(|(hash, entry)| {
+                let meta = self.authors.get(&hash).map(|authorship| EntryMeta {
+                    author: authorship.source,
+                    lamport: self.crdt.lamport(hash, &mut lamports),
+                });
+                (hash, entry, meta)
+            })
+            .collect()
+    }
+
+    /// Read all the entries held in the register, including those overwritten, along with their
+    /// lamport timestamp. Entries are ordered by lamport, then by hash, so that each entry comes
+    /// after the entries it was written atop of.
+    pub fn entries_topological(&self) -> Vec<(EntryHash, Entry, u64)> {
+        let mut lamports = BTreeMap::new();
+        let mut entries: Vec<_> = self
+            .crdt
+            .hashes_and_entries()
+            .map(|(hash, entry)| (hash, entry.clone(), self.crdt.lamport(hash, &mut lamports)))
+            .collect();
+        entries.sort_by_key(|(hash, _, lamport)| (*lamport, *hash));
+        entries
+    }
+
+    /// Return the author of the entry corresponding to the provided 'hash', if its op was
+    /// retained, see `read_with_meta`.
+    pub fn author(&self, hash: EntryHash) -> Option<User> {
+        self.authors.get(&hash).map(|authorship| authorship.source)
+    }
+
+    /// Return the hashes of the entries the entry corresponding to the provided 'hash' was
+    /// written atop of, if present.
+    pub fn children(&self, hash: EntryHash) -> Result<BTreeSet<EntryHash>> {
+        self.crdt.children(hash).ok_or(Error::NoSuchEntry(hash))
+    }
+
+    /// Verify a Register, e.g. one deserialized from a snapshot rather than built from signed ops:
+    /// it must hold no more than the maximum number of entries nor its own cap, none of them too
+    /// big, and the authorship of each of them verified.
+    pub fn verify(&self) -> Result<()> {
+        let reg_size = self.crdt.size();
+        if reg_size > MAX_REG_NUM_ENTRIES.into() {
+            return Err(Error::TooManyEntries(reg_size as usize));
+        }
+        if let Some(max_entries) = self.max_entries {
+            if reg_size > max_entries {
+                return Err(Error::RegisterFull(max_entries));
+            }
+        }
+        for entry in self.crdt.entries() {
+            if entry.len() > MAX_REG_ENTRY_SIZE {
+                return Err(Error::EntryTooBig {
+                    size: entry.len(),
+                    max: MAX_REG_ENTRY_SIZE,
+                });
+            }
+        }
+        for (hash, authorship) in &self.authors {
+            let op = self
+                .op_of(*hash, authorship)
+                .ok_or(Error::NoSuchEntry(*hash))?;
+            self.check_register_op(&op)?;
+        }
+        Ok(())
     }
 
     /// Return the permission.
@@ -215,19 +622,89 @@ impl Register {
         children: BTreeSet<EntryHash>,
     ) -> Result<(EntryHash, RegisterOp)> {
         self.check_entry_and_reg_sizes(&entry)?;
+        self.check_cap()?;
         self.crdt.write(entry, children, User::Key(self.owner()))
     }
 
     /// Apply a signed data CRDT operation.
+    /// Applying an op again, or that of an entry pruned, is allowed whatever the number of
+    /// entries held, as it adds no entry.
     pub fn apply_op(&mut self, op: RegisterOp) -> Result<()> {
-        self.check_entry_and_reg_sizes(&op.crdt_op.value)?;
+        if !self.crdt.is_known(&EntryHash(op.crdt_op.hash())) {
+            self.check_cap()?;
+        }
+        self.apply_op_beyond_cap(op)
+    }
+
+    // Apply a signed op whatever our cap, the entries beyond it to be dropped afterwards.
+    fn apply_op_beyond_cap(&mut self, op: RegisterOp) -> Result<()> {
+        if self.crdt.is_known(&EntryHash(op.crdt_op.hash())) {
+            self.check_entry_size(&op.crdt_op.value)?;
+        } else {
+            self.check_entry_and_reg_sizes(&op.crdt_op.value)?;
+        }
         self.check_register_op(&op)?;
-        self.crdt.apply_op(op)
+        self.crdt.apply_op(op.clone())?;
+        self.retain_author(&op);
+        Ok(())
+    }
+
+    /// Apply an op pruning entries, see `BoundedRegister`. It must be signed by a user allowed to
+    /// write to the Register, and may not prune current entries.
+    pub fn apply_prune_op(&mut self, op: &PruneOp) -> Result<()> {
+        self.check_prune_op(op)?;
+        if self.heads().iter().any(|hash| op.pruned.contains(hash)) {
+            return Err(Error::InvalidOp(
+                "op prunes current entries of the register".to_string(),
+            ));
+        }
+        self.prune(&op.pruned);
+        Ok(())
+    }
+
+    /// Check an op pruning entries is destined for this Register, and signed by a user allowed to
+    /// write to it.
+    pub fn check_prune_op(&self, op: &PruneOp) -> Result<()> {
+        if op.address != *self.address() {
+            return Err(Error::RegisterAddrMismatch {
+                dst_addr: Box::new(op.address),
+                reg_addr: Box::new(*self.address()),
+            });
+        }
+        self.check_user_permissions(op.source)?;
+        match op.source {
+            User::Key(pk) => op.verify_signature(&pk),
+            User::Anyone => Err(Error::MissingSignature),
+        }
+    }
+
+    /// Remove the entries with the provided hashes, see `BoundedRegister`.
+    pub(crate) fn prune(&mut self, hashes: &BTreeSet<EntryHash>) {
+        self.crdt.prune(hashes);
+        self.authors.retain(|hash, _| !hashes.contains(hash));
     }
 
     /// Merge another Register into this one.
+    /// Should the entries of both exceed our cap, the entries beyond it in topological order, i.e.
+    /// the last ones written, are dropped, see `entries_topological`.
+    /// The authors retained by the other Register are only retained once their signature of the
+    /// op which wrote their entry is verified.
+    /// Under `MergePolicy::LastWriterWins`, the entries of the branches losing to the entry read
+    /// are dropped first, as they are never read again.
     pub fn merge(&mut self, other: Self) {
         self.crdt.merge(other.crdt);
+        for (hash, authorship) in other.authors {
+            if self.authors.get(&hash) == Some(&authorship) {
+                continue;
+            }
+            if let Some(op) = self.op_of(hash, &authorship) {
+                if self.check_register_op(&op).is_ok() {
+                    self.retain_author(&op);
+                }
+            }
+        }
+        self.drop_losing_branches();
+        self.drop_excess_entries();
     }
 
     /// Check if a register op is valid for our current register
@@ -239,7 +716,7 @@ impl Register {
 
         match op.source {
             User::Anyone => Ok(()),
-            User::Key(pk) => op.verify_signature(&pk),
+            User::Key(pk) => op.verify_for_address(&pk, self.address()),
         }
     }
 
@@ -256,9 +733,50 @@ impl Register {
         }
     }
 
+    // Keep a single author per entry, out of the verified `op` which wrote it, see
+    // `Authorship::precedes`.
+    pub(crate) fn retain_author(&mut self, op: &RegisterOp) {
+        let authorship = Authorship::of(op);
+        let hash = EntryHash(op.crdt_op.hash());
+        // the entries pruned have no author retained
+        if !self.crdt.contains(&hash) {
+            return;
+        }
+        match self.authors.get(&hash) {
+            Some(retained) if !authorship.precedes(retained) => {}
+            _ => {
+                let _ = self.authors.insert(hash, authorship);
+            }
+        }
+    }
+
+    // The op which wrote the entry with the provided `hash`, as signed by its retained author.
+    fn op_of(&self, hash: EntryHash, authorship: &Authorship) -> Option<RegisterOp> {
+        let crdt_op = self.crdt.node(hash)?.clone();
+        Some(RegisterOp {
+            address: *self.address(),
+            crdt_op,
+            source: authorship.source,
+            signature: authorship.signature.clone(),
+            format: authorship.format,
+        })
+    }
+
     // Private helper to check the given Entry's size is within define limit,
     // as well as check the Register hasn't already reached the maximum number of entries.
     fn check_entry_and_reg_sizes(&self, entry: &Entry) -> Result<()> {
+        self.check_entry_size(entry)?;
+
+        let reg_size = self.crdt.size();
+        if reg_size >= MAX_REG_NUM_ENTRIES.into() {
+            return Err(Error::TooManyEntries(reg_size as usize));
+        }
+
+        Ok(())
+    }
+
+    // Private helper to check the given Entry's size is within define limit.
+    fn check_entry_size(&self, entry: &Entry) -> Result<()> {
         let size = entry.len();
         if size > MAX_REG_ENTRY_SIZE {
             return Err(Error::EntryTooBig {
@@ -266,20 +784,70 @@ impl Register {
                 max: MAX_REG_ENTRY_SIZE,
             });
         }
+        Ok(())
+    }
 
-        let reg_size = self.crdt.size();
-        if reg_size >= MAX_REG_NUM_ENTRIES.into() {
-            return Err(Error::TooManyEntries(reg_size as usize));
+    // Private helper to check the Register hasn't reached the cap of its owner, if any.
+    fn check_cap(&self) -> Result<()> {
+        if let Some(max_entries) = self.max_entries {
+            if self.crdt.size() >= max_entries {
+                return Err(Error::RegisterFull(max_entries));
+            }
         }
-
         Ok(())
     }
+
+    // Under `MergePolicy::LastWriterWins`, drop the entries of the branches losing to the entry
+    // read, i.e. all but the latter and the entries it was written atop of. The losing entries
+    // can't be read again, the entries written atop of them later on being applied nonetheless.
+    fn drop_losing_branches(&mut self) {
+        if self.merge_policy != MergePolicy::LastWriterWins || self.heads().len() <= 1 {
+            return;
+        }
+        let winner = match self.read().into_iter().next() {
+            Some((hash, _)) => hash,
+            None => return,
+        };
+
+        let mut kept = BTreeSet::new();
+        let mut to_visit = vec![winner];
+        while let Some(hash) = to_visit.pop() {
+            if kept.insert(hash) {
+                to_visit.extend(self.crdt.children(hash).into_iter().flatten());
+            }
+        }
+        let losing: BTreeSet<EntryHash> = self
+            .crdt
+            .hashes_and_entries()
+            .map(|(hash, _)| hash)
+            .filter(|hash| !kept.contains(hash))
+            .collect();
+        self.prune(&losing);
+    }
+
+    // Drop the entries beyond our cap, if any: the last ones in topological order, which replicas
+    // holding the same entries agree on. The ancestors of the entries kept come before them, thus
+    // are kept too.
+    fn drop_excess_entries(&mut self) {
+        if let Some(max_entries) = self.max_entries {
+            let excess: BTreeSet<EntryHash> = self
+                .entries_topological()
+                .into_iter()
+                .skip(max_entries as usize)
+                .map(|(hash, _, _)| hash)
+                .collect();
+            if !excess.is_empty() {
+                self.prune(&excess);
+            }
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::{
-        EntryHash, Error, Permissions, Register, RegisterAddress, Result, User, MAX_REG_NUM_ENTRIES,
+        EntryHash, EntryMeta, Error, MergePolicy, Permissions, Register, RegisterAddress,
+        RegisterOpFormat, Result, SignedRegister, User, MAX_REG_NUM_ENTRIES,
     };
 
     use bls::SecretKey;
@@ -349,7 +917,7 @@ mod tests {
         let perms = Permissions::new_with([authority1, authority2]);
 
         // Instantiate the same Register on two replicas
-        let mut replica1 = Register::new(authority_sk1.public_key(), meta, perms.clone());
+        let mut replica1 = Register::new(authority_sk1.public_key(), meta, perms.clone(), None);
         let mut replica2 = replica1.clone();
 
         // And let's write an item to replica1 with autority1
@@ -381,6 +949,60 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn register_read_with_meta_attributes_author_and_lamport() -> eyre::Result<()> {
+        let owner_sk = SecretKey::random();
+        let owner = User::Key(owner_sk.public_key());
+        let writer_sk = SecretKey::random();
+        let writer = User::Key(writer_sk.public_key());
+
+        let meta: XorName = xor_name::rand::random();
+        let perms = Permissions::new_with([owner, writer]);
+        let mut replica1 = Register::new(owner_sk.public_key(), meta, perms, None);
+        let mut replica2 = replica1.clone();
+
+        // the op of an entry written locally is not retained until it's applied
+        let entry1 = random_register_entry();
+        let (entry1_hash, mut op1) = replica1.write(entry1.clone(), BTreeSet::new())?;
+        op1.sign_with(&owner_sk);
+        assert_eq!(
+            replica1.read_with_meta(),
+            [(entry1_hash, entry1.clone(), None)].into_iter().collect()
+        );
+
+        replica2.apply_op(op1)?;
+        let expected = EntryMeta {
+            author: owner,
+            lamport: 1,
+        };
+        assert_eq!(
+            replica2.read_with_meta(),
+            [(entry1_hash, entry1, Some(expected))]
+                .into_iter()
+                .collect()
+        );
+
+        // another writer writes atop of entry1
+        let entry2 = random_register_entry();
+        let (entry2_hash, mut op2) =
+            replica2.write(entry2.clone(), [entry1_hash].into_iter().collect())?;
+        op2.sign_with(&writer_sk);
+        replica1.apply_op(op2)?;
+
+        let expected = EntryMeta {
+            author: writer,
+            lamport: 2,
+        };
+        assert_eq!(
+            replica1.read_with_meta(),
+            [(entry2_hash, entry2, Some(expected))]
+                .into_iter()
+                .collect()
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn register_get_by_hash() -> eyre::Result<()> {
         let (_, register) = &mut create_reg_replicas(1)[0];
@@ -418,6 +1040,65 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn register_contains_reports_presence_of_entry_hashes() -> eyre::Result<()> {
+        let (_, register) = &mut create_reg_replicas(1)[0];
+
+        let (entry1_hash, _) = register.write(random_register_entry(), BTreeSet::new())?;
+        let children = vec![entry1_hash].into_iter().collect();
+        let (entry2_hash, _) = register.write(random_register_entry(), children)?;
+
+        // overwritten entries are still held by the register
+        assert!(register.contains(&entry1_hash));
+        assert!(register.contains(&entry2_hash));
+        assert!(!register.contains(&EntryHash::default()));
+
+        // entries only written to another replica aren't
+        let (_, other_replica) = &mut create_reg_replicas(1)[0];
+        let (other_hash, _) = other_replica.write(random_register_entry(), BTreeSet::new())?;
+        assert!(!register.contains(&other_hash));
+
+        Ok(())
+    }
+
+    #[test]
+    fn register_entries_topological_orders_entries_after_their_children() -> eyre::Result<()> {
+        let (_, register) = &mut create_reg_replicas(1)[0];
+
+        let (root_hash, _) = register.write(random_register_entry(), BTreeSet::new())?;
+        let (branch1_hash, _) = register.write(random_register_entry(), [root_hash].into())?;
+        let (branch2_hash, _) = register.write(random_register_entry(), [root_hash].into())?;
+        let (merge_hash, _) =
+            register.write(random_register_entry(), [branch1_hash, branch2_hash].into())?;
+
+        let ordered: Vec<_> = register
+            .entries_topological()
+            .into_iter()
+            .map(|(hash, _, lamport)| (hash, lamport))
+            .collect();
+        let (first_branch, second_branch) = if branch1_hash < branch2_hash {
+            (branch1_hash, branch2_hash)
+        } else {
+            (branch2_hash, branch1_hash)
+        };
+        assert_eq!(
+            ordered,
+            vec![
+                (root_hash, 1),
+                (first_branch, 2),
+                (second_branch, 2),
+                (merge_hash, 3)
+            ]
+        );
+        assert_eq!(
+            register.children(merge_hash)?,
+            [branch1_hash, branch2_hash].into()
+        );
+        assert!(register.children(root_hash)?.is_empty());
+
+        Ok(())
+    }
+
     #[test]
     fn register_query_public_perms() -> eyre::Result<()> {
         let meta = xor_name::rand::random();
@@ -494,6 +1175,640 @@ mod tests {
         }
     }
 
+    #[test]
+    fn writes_beyond_the_cap_of_the_register_error() -> eyre::Result<()> {
+        let owner_sk = SecretKey::random();
+        let meta = xor_name::rand::random();
+        let uncapped = Register::new_owned(owner_sk.public_key(), meta);
+        let capped = Register::new(owner_sk.public_key(), meta, Default::default(), Some(3));
+        assert_eq!(capped.max_entries(), Some(3));
+        // the cap is part of what the owner signs
+        assert_ne!(capped.bytes()?, uncapped.bytes()?);
+        assert!(capped.clone().into_signed(&owner_sk)?.verify().is_ok());
+
+        let mut replica = capped.clone();
+        for _ in 0..3 {
+            let _ = replica.write(random_register_entry(), BTreeSet::new())?;
+        }
+        assert_eq!(
+            replica.write(random_register_entry(), BTreeSet::new()),
+            Err(Error::RegisterFull(3))
+        );
+
+        // ops beyond the cap are rejected when applied too
+        let mut writer = uncapped;
+        let mut replica = capped;
+        for i in 0..4 {
+            let (_hash, mut op) = writer.write(random_register_entry(), BTreeSet::new())?;
+            op.sign_with(&owner_sk);
+            let applied = replica.apply_op(op);
+            if i < 3 {
+                assert_eq!(applied, Ok(()));
+            } else {
+                assert_eq!(applied, Err(Error::RegisterFull(3)));
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn merges_beyond_the_cap_of_the_register_drop_the_last_entries() -> eyre::Result<()> {
+        let owner_sk = SecretKey::random();
+        let base = Register::new(
+            owner_sk.public_key(),
+            xor_name::rand::random(),
+            Default::default(),
+            Some(3),
+        );
+        let mut signed = base.clone().into_signed(&owner_sk)?;
+
+        // two replicas concurrently write two entries each, one atop the other
+        let mut replicas = vec![];
+        for _ in 0..2 {
+            let mut replica = base.clone();
+            let (first, mut op) = replica.write(random_register_entry(), BTreeSet::new())?;
+            op.sign_with(&owner_sk);
+            signed.add_op(op)?;
+            let (_, mut op) = replica.write(random_register_entry(), [first].into())?;
+            op.sign_with(&owner_sk);
+            signed.add_op(op)?;
+            replicas.push(replica);
+        }
+        let replica2 = replicas.pop().ok_or(eyre::eyre!("no replica"))?;
+        let replica1 = replicas.pop().ok_or(eyre::eyre!("no replica"))?;
+
+        // merging them in any order keeps the first three entries in topological order
+        let mut merged1 = replica1.clone();
+        merged1.merge(replica2.clone());
+        let mut merged2 = replica2;
+        merged2.merge(replica1);
+        assert_eq!(merged1.size(), 3);
+        assert_eq!(merged1.verify(), Ok(()));
+        assert_eq!(merged1.entries_topological(), merged2.entries_topological());
+
+        // the entries kept no other kept entry was written atop of are read, the entry the one
+        // dropped was written atop of included
+        let kept: BTreeSet<EntryHash> = merged1
+            .entries_topological()
+            .into_iter()
+            .map(|(hash, _, _)| hash)
+            .collect();
+        let mut expected_heads = kept.clone();
+        for hash in &kept {
+            for child in merged1.children(*hash)? {
+                let _ = expected_heads.remove(&child);
+            }
+        }
+        assert_eq!(expected_heads.len(), 2);
+        assert_eq!(heads(&merged1), expected_heads);
+        assert_eq!(merged1.read(), merged2.read());
+
+        // as does the signed register holding all their ops
+        let register = signed.clone().register()?;
+        assert_eq!(register.verify(), Ok(()));
+        assert_eq!(
+            register.entries_topological(),
+            merged1.entries_topological()
+        );
+        assert_eq!(register.read(), merged1.read());
+
+        // and the ops of the entries held, or dropped, can be applied again to the full register
+        let mut replayed = register.clone();
+        for op in signed.ops_unknown_to(&BTreeSet::new()) {
+            replayed.apply_op(op)?;
+        }
+        assert_eq!(replayed.read(), register.read());
+        assert_eq!(replayed.size(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn register_ops_unknown_to_only_returns_missing_ops() -> eyre::Result<()> {
+        let owner_sk = SecretKey::random();
+        let meta = xor_name::rand::random();
+        let base = Register::new_owned(owner_sk.public_key(), meta);
+        let mut signed = base.clone().into_signed(&owner_sk)?;
+
+        // the writer replica creates a chain of three entries, and a concurrent branch
+        let mut writer = base.clone();
+        let mut write = |writer: &mut Register, children| -> eyre::Result<EntryHash> {
+            let (hash, mut op) = writer.write(random_register_entry(), children)?;
+            op.sign_with(&owner_sk);
+            signed.add_op(op)?;
+            Ok(hash)
+        };
+        let first = write(&mut writer, BTreeSet::new())?;
+        let second = write(&mut writer, [first].into())?;
+        let third = write(&mut writer, [second].into())?;
+        let branch = write(&mut writer, BTreeSet::new())?;
+
+        // the reader replica has only synced up to the first entry
+        let mut reader = base;
+        for op in signed.ops_unknown_to(&BTreeSet::new()) {
+            if EntryHash(op.crdt_op.hash()) == first {
+                reader.apply_op(op)?;
+            }
+        }
+        let known_heads = reader.read().into_iter().map(|(hash, _)| hash).collect();
+
+        let delta = signed.ops_unknown_to(&known_heads);
+        let delta_hashes: BTreeSet<_> = delta
+            .iter()
+            .map(|op| EntryHash(op.crdt_op.hash()))
+            .collect();
+        assert_eq!(delta_hashes, [second, third, branch].into());
+
+        for op in delta {
+            reader.apply_op(op)?;
+        }
+        let full = signed.register()?;
+        assert_eq!(reader.crdt, full.crdt);
+        assert_eq!(reader.read(), full.read());
+
+        Ok(())
+    }
+
+    // Apply the `ops` to the `replica` in order, signed by the owner, checking each of them comes
+    // after the ops of the entries it was written atop of.
+    fn apply_in_order(replica: &mut Register, ops: Vec<super::RegisterOp>) -> eyre::Result<()> {
+        for op in ops {
+            for child in &op.crdt_op.children {
+                assert!(
+                    replica.contains(&EntryHash(*child)),
+                    "op applied before its children"
+                );
+            }
+            replica.apply_op(op)?;
+        }
+        Ok(())
+    }
+
+    fn heads(replica: &Register) -> BTreeSet<EntryHash> {
+        replica.read().into_iter().map(|(hash, _)| hash).collect()
+    }
+
+    #[test]
+    fn register_ops_since_returns_the_missing_ops_across_branches_and_merges() -> eyre::Result<()> {
+        let owner_sk = SecretKey::random();
+        let base = Register::new_owned(owner_sk.public_key(), xor_name::rand::random());
+
+        //   a <- b <- d <- f
+        //   a <- c <- d
+        //   e
+        let mut writer = base.clone();
+        let mut full = base.clone().into_signed(&owner_sk)?;
+        let mut write = |children: BTreeSet<EntryHash>| -> eyre::Result<EntryHash> {
+            let (hash, mut op) = writer.write(random_register_entry(), children)?;
+            op.sign_with(&owner_sk);
+            full.add_op(op)?;
+            Ok(hash)
+        };
+        let a = write(BTreeSet::new())?;
+        let b = write([a].into())?;
+        let c = write([a].into())?;
+        let d = write([b, c].into())?;
+        let e = write(BTreeSet::new())?;
+        let f = write([d].into())?;
+        let full_register = full.clone().register()?;
+        assert_eq!(heads(&full_register), [e, f].into());
+
+        let hashes = |ops: &[super::RegisterOp]| -> Vec<EntryHash> {
+            ops.iter().map(|op| EntryHash(op.crdt_op.hash())).collect()
+        };
+
+        // a replica knowing nothing gets all the signed ops
+        let all = full.ops_since(&BTreeSet::new());
+        assert_eq!(
+            hashes(&all).into_iter().collect::<BTreeSet<_>>(),
+            [a, b, c, d, e, f].into()
+        );
+        for op in &all {
+            assert!(op.verify_signature(&owner_sk.public_key()).is_ok());
+        }
+        let mut reader = base.clone();
+        apply_in_order(&mut reader, all)?;
+        assert_eq!(reader.crdt, full_register.crdt);
+
+        // a replica on one of the branches only misses the other branch and what came after
+        let mut reader = base.clone();
+        let on_branch: Vec<_> = full
+            .ops_since(&BTreeSet::new())
+            .into_iter()
+            .filter(|op| [a, b].contains(&EntryHash(op.crdt_op.hash())))
+            .collect();
+        apply_in_order(&mut reader, on_branch)?;
+        let delta = full.ops_since(&heads(&reader));
+        assert_eq!(
+            hashes(&delta).into_iter().collect::<BTreeSet<_>>(),
+            [c, d, e, f].into()
+        );
+        apply_in_order(&mut reader, delta)?;
+        assert_eq!(reader.crdt, full_register.crdt);
+        assert_eq!(reader.read(), full_register.read());
+
+        // an up to date replica misses nothing, and unknown heads are ignored
+        assert!(full.ops_since(&heads(&full_register)).is_empty());
+        let unknown = EntryHash(rand::random());
+        assert_eq!(full.ops_since(&[unknown, f].into()).len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn register_ops_since_reconstructs_random_histories() -> eyre::Result<()> {
+        let mut rng = thread_rng();
+        let owner_sk = SecretKey::random();
+        let base = Register::new_owned(owner_sk.public_key(), xor_name::rand::random());
+
+        for _ in 0..20 {
+            // each entry is written atop a random few of the previous ones
+            let mut writer = base.clone();
+            let mut full = base.clone().into_signed(&owner_sk)?;
+            let mut written = vec![];
+            for _ in 0..30 {
+                let count = rng.gen_range(0..3);
+                let children: BTreeSet<EntryHash> =
+                    written.choose_multiple(&mut rng, count).copied().collect();
+                let (hash, mut op) = writer.write(random_register_entry(), children)?;
+                op.sign_with(&owner_sk);
+                full.add_op(op)?;
+                written.push(hash);
+            }
+
+            // the reader knows a random few of the entries and their ancestors
+            let count = rng.gen_range(0..5);
+            let known: BTreeSet<EntryHash> =
+                written.choose_multiple(&mut rng, count).copied().collect();
+            let mut ancestry = known.clone();
+            let mut to_visit: Vec<EntryHash> = known.into_iter().collect();
+            while let Some(hash) = to_visit.pop() {
+                for child in writer.children(hash)? {
+                    if ancestry.insert(child) {
+                        to_visit.push(child);
+                    }
+                }
+            }
+            let mut reader = base.clone();
+            let held_by_reader = full
+                .ops_since(&BTreeSet::new())
+                .into_iter()
+                .filter(|op| ancestry.contains(&EntryHash(op.crdt_op.hash())))
+                .collect();
+            apply_in_order(&mut reader, held_by_reader)?;
+
+            // exactly the entries the reader doesn't hold are sent over
+            let delta = full.ops_since(&heads(&reader));
+            for op in &delta {
+                assert!(!reader.contains(&EntryHash(op.crdt_op.hash())));
+            }
+            assert_eq!(delta.len(), written.len() - ancestry.len());
+            apply_in_order(&mut reader, delta)?;
+            assert_eq!(reader.crdt, writer.crdt);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn register_verify_checks_entry_authors() -> eyre::Result<()> {
+        let meta = xor_name::rand::random();
+        let owner_sk = SecretKey::random();
+
+        let mut owned = create_reg_replica_with(meta, Some(owner_sk.clone()), None);
+        let (hash, mut op) = owned.write(random_register_entry(), BTreeSet::new())?;
+        op.sign_with(&owner_sk);
+        owned.apply_op(op)?;
+        assert_eq!(owned.verify(), Ok(()));
+
+        // an authorship the author's signature doesn't back
+        let mut forged = owned.clone();
+        let random_sk = SecretKey::random();
+        if let Some(authorship) = forged.authors.get_mut(&hash) {
+            authorship.source = User::Key(random_sk.public_key());
+        }
+        assert!(forged.verify().is_err());
+
+        // a replica at the same address where anyone can write, holding an entry from a random user
+        let mut public = create_reg_replica_with(
+            meta,
+            Some(owner_sk),
+            Some(Permissions::new_anyone_can_write()),
+        );
+        let random_user = User::Key(random_sk.public_key());
+        let (public_hash, mut op) = public.write(random_register_entry(), BTreeSet::new())?;
+        op.source = random_user;
+        public.apply_op(op)?;
+        assert_eq!(public.verify(), Ok(()));
+        assert_eq!(public.author(public_hash), Some(random_user));
+
+        // once merged into the owner-only replica, that entry's author isn't retained, not being
+        // allowed to write
+        owned.merge(public);
+        assert_eq!(owned.author(public_hash), None);
+        assert_eq!(owned.verify(), Ok(()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn authors_signing_an_entry_are_retained_over_anyone() -> eyre::Result<()> {
+        let owner_sk = SecretKey::random();
+        let base = create_reg_replica_with(
+            xor_name::rand::random(),
+            Some(owner_sk.clone()),
+            Some(Permissions::new_anyone_can_write()),
+        );
+
+        // the same entry, written by anyone and by the owner
+        let mut writer = base.clone();
+        let (hash, anonymous_op) = writer.write(random_register_entry(), BTreeSet::new())?;
+        let mut anonymous_op = anonymous_op;
+        anonymous_op.source = User::Anyone;
+        let mut signed_op = anonymous_op.clone();
+        signed_op.sign_with(&owner_sk);
+
+        let mut replica1 = base.clone();
+        replica1.apply_op(anonymous_op.clone())?;
+        replica1.apply_op(signed_op.clone())?;
+        let mut replica2 = base.clone();
+        replica2.apply_op(signed_op)?;
+        replica2.apply_op(anonymous_op.clone())?;
+        let owner = Some(User::Key(owner_sk.public_key()));
+        assert_eq!(replica1.author(hash), owner);
+        assert_eq!(replica2.author(hash), owner);
+
+        // nor does merging in a replica which only retained anyone change it
+        let mut anonymous = base;
+        anonymous.apply_op(anonymous_op)?;
+        assert_eq!(anonymous.author(hash), Some(User::Anyone));
+        replica1.merge(anonymous.clone());
+        assert_eq!(replica1.author(hash), owner);
+        anonymous.merge(replica2);
+        assert_eq!(anonymous.author(hash), owner);
+
+        Ok(())
+    }
+
+    #[test]
+    fn last_writer_wins_merges_converge_to_a_single_entry() -> eyre::Result<()> {
+        let owner_sk = SecretKey::random();
+        let mut base = Register::new_owned(owner_sk.public_key(), xor_name::rand::random());
+        base.set_merge_policy(MergePolicy::LastWriterWins);
+
+        // replica1 writes a single entry, while replica2 concurrently writes two, one atop the other
+        let mut replica1 = base.clone();
+        let (loser, _) = replica1.write(random_register_entry(), BTreeSet::new())?;
+        let mut replica2 = base.clone();
+        let (first, _) = replica2.write(random_register_entry(), BTreeSet::new())?;
+        let (last, _) = replica2.write(random_register_entry(), [first].into())?;
+
+        let mut merged1 = replica1.clone();
+        merged1.merge(replica2.clone());
+        let mut merged2 = replica2;
+        merged2.merge(replica1);
+
+        let heads: Vec<EntryHash> = merged1.read().into_iter().map(|(hash, _)| hash).collect();
+        assert_eq!(heads, vec![last]);
+        assert_eq!(merged1.read(), merged2.read());
+        assert_eq!(merged1, merged2);
+        // the losing branch is dropped, while the winner and the entry it was written atop of
+        // are kept
+        assert!(!merged1.contains(&loser));
+        assert!(merged1.contains(&first));
+        assert_eq!(merged1.size(), 2);
+
+        // on a lamport tie, the highest hash wins
+        let mut replica3 = base.clone();
+        let (tied1, _) = replica3.write(random_register_entry(), BTreeSet::new())?;
+        let mut replica4 = base;
+        let (tied2, _) = replica4.write(random_register_entry(), BTreeSet::new())?;
+        replica3.merge(replica4);
+        let heads: Vec<EntryHash> = replica3.read().into_iter().map(|(hash, _)| hash).collect();
+        assert_eq!(heads, vec![tied1.max(tied2)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn last_writer_wins_applies_to_ops_and_signed_registers() -> eyre::Result<()> {
+        let owner_sk = SecretKey::random();
+        let mut base = Register::new_owned(owner_sk.public_key(), xor_name::rand::random());
+        base.set_merge_policy(MergePolicy::LastWriterWins);
+
+        let mut writer1 = base.clone();
+        let (_, mut op1) = writer1.write(random_register_entry(), BTreeSet::new())?;
+        op1.sign_with(&owner_sk);
+        let mut writer2 = base.clone();
+        let (first, mut op2) = writer2.write(random_register_entry(), BTreeSet::new())?;
+        op2.sign_with(&owner_sk);
+        let (last, mut op3) = writer2.write(random_register_entry(), [first].into())?;
+        op3.sign_with(&owner_sk);
+
+        // replicas applying the ops in any order read the same entry
+        let mut replica1 = base.clone();
+        for op in [op1.clone(), op2.clone(), op3.clone()] {
+            replica1.apply_op(op)?;
+        }
+        let mut replica2 = base.clone();
+        for op in [op3.clone(), op2.clone(), op1.clone()] {
+            replica2.apply_op(op)?;
+        }
+        let heads: Vec<EntryHash> = replica1.read().into_iter().map(|(hash, _)| hash).collect();
+        assert_eq!(heads, vec![last]);
+        assert_eq!(replica1.read(), replica2.read());
+
+        // as do signed registers the ops were added to
+        let mut signed = base.into_signed(&owner_sk)?;
+        for op in [op1, op2, op3] {
+            signed.add_op(op)?;
+        }
+        assert_eq!(signed.register()?.read(), replica1.read());
+
+        Ok(())
+    }
+
+    #[test]
+    fn last_writer_wins_losing_branches_do_not_fill_the_cap() -> eyre::Result<()> {
+        let owner_sk = SecretKey::random();
+        let mut base = Register::new(
+            owner_sk.public_key(),
+            xor_name::rand::random(),
+            Default::default(),
+            Some(3),
+        );
+        base.set_merge_policy(MergePolicy::LastWriterWins);
+
+        // rounds of concurrent writes by two replicas, merged into one another after each round,
+        // only the entries of the winning branch being kept
+        let mut replica1 = base.clone();
+        let mut replica2 = base;
+        for round in 1..=3 {
+            let heads1 = heads(&replica1);
+            let heads2 = heads(&replica2);
+            let (written1, _) = replica1.write(random_register_entry(), heads1)?;
+            let (written2, _) = replica2.write(random_register_entry(), heads2)?;
+            let winner = written1.max(written2);
+
+            replica1.merge(replica2.clone());
+            replica2.merge(replica1.clone());
+            assert_eq!(heads(&replica1), [winner].into());
+            assert_eq!(replica1.read(), replica2.read());
+            assert_eq!(replica1.size(), round);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn keep_branches_merges_preserve_concurrent_entries() -> eyre::Result<()> {
+        let owner_sk = SecretKey::random();
+        let base = Register::new_owned(owner_sk.public_key(), xor_name::rand::random());
+        assert_eq!(base.merge_policy(), MergePolicy::KeepBranches);
+
+        let mut replica1 = base.clone();
+        let (hash1, _) = replica1.write(random_register_entry(), BTreeSet::new())?;
+        let mut replica2 = base;
+        let (hash2, _) = replica2.write(random_register_entry(), BTreeSet::new())?;
+
+        replica1.merge(replica2);
+        let heads: BTreeSet<EntryHash> =
+            replica1.read().into_iter().map(|(hash, _)| hash).collect();
+        assert_eq!(heads, [hash1, hash2].into());
+
+        Ok(())
+    }
+
+    #[test]
+    fn registers_signed_with_the_legacy_layout_verify() -> eyre::Result<()> {
+        use crdts::{merkle_reg::MerkleReg, CmRDT};
+        use serde::Serialize;
+
+        // a Register as it was encoded to be signed before it held anything else
+        #[derive(Serialize)]
+        struct LegacyCrdt {
+            address: RegisterAddress,
+            data: MerkleReg<Vec<u8>>,
+        }
+        #[derive(Serialize)]
+        struct LegacyRegister {
+            crdt: LegacyCrdt,
+            permissions: Permissions,
+        }
+
+        let owner_sk = SecretKey::random();
+        let mut register = Register::new_owned(owner_sk.public_key(), xor_name::rand::random());
+        let entry = random_register_entry();
+        let (hash, mut op) = register.write(entry.clone(), BTreeSet::new())?;
+        let mut data = MerkleReg::new();
+        let node = data.write(entry, BTreeSet::new());
+        data.apply(node);
+        let legacy = LegacyRegister {
+            crdt: LegacyCrdt {
+                address: *register.address(),
+                data,
+            },
+            permissions: register.permissions().clone(),
+        };
+        let legacy_bytes = bincode::serialize(&legacy)?;
+        assert_eq!(register.bytes()?, legacy_bytes);
+        let signature = owner_sk.sign(&legacy_bytes);
+        assert!(SignedRegister::new(register.clone(), signature.clone())
+            .verify()
+            .is_ok());
+
+        // the authors retained by a replica aren't signed
+        op.sign_with(&owner_sk);
+        register.apply_op(op)?;
+        assert_eq!(
+            register.author(hash),
+            Some(User::Key(owner_sk.public_key()))
+        );
+        assert!(SignedRegister::new(register.clone(), signature)
+            .verify()
+            .is_ok());
+
+        // while the merge policy is, once it's not the default
+        let mut last_writer_wins = register.clone();
+        last_writer_wins.set_merge_policy(MergePolicy::LastWriterWins);
+        assert_ne!(last_writer_wins.bytes()?, legacy_bytes);
+        assert!(last_writer_wins.into_signed(&owner_sk)?.verify().is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn legacy_ops_verify_apply_and_are_migrated_by_the_owner() -> eyre::Result<()> {
+        let owner_sk = SecretKey::random();
+        let base = Register::new_owned(owner_sk.public_key(), xor_name::rand::random());
+        let mut writer = base.clone();
+        let (hash, mut op) = writer.write(random_register_entry(), BTreeSet::new())?;
+        op.format = RegisterOpFormat::V0;
+        op.sign_with(&owner_sk);
+
+        // a replica holding a legacy op, as deserialised from a record stored before `V1`,
+        // verifies and applies it without it being migrated
+        let mut legacy = base.clone().into_signed(&owner_sk)?;
+        let _ = legacy.ops.insert(op.clone());
+        assert_eq!(legacy.verify(), Ok(()));
+        assert!(legacy.clone().register()?.contains(&hash));
+
+        let mut replica = base.into_signed(&owner_sk)?;
+        replica.add_op(op)?;
+        assert_eq!(replica, legacy);
+
+        // only a writer may migrate it
+        let mut migrated = legacy.clone();
+        assert!(matches!(
+            migrated.migrate_legacy_ops(&SecretKey::random()),
+            Err(Error::AccessDenied(_))
+        ));
+        migrated.migrate_legacy_ops(&owner_sk)?;
+        assert_eq!(migrated.verify(), Ok(()));
+
+        // the legacy replica merging the migrated one drops the legacy op
+        legacy.merge(migrated.clone())?;
+        assert_eq!(legacy.verify(), Ok(()));
+        assert_eq!(legacy, migrated);
+        assert!(legacy.register()?.contains(&hash));
+
+        Ok(())
+    }
+
+    #[test]
+    fn register_version_reflects_replica_state() -> eyre::Result<()> {
+        let owner_sk = SecretKey::random();
+        let base = Register::new_owned(owner_sk.public_key(), xor_name::rand::random());
+        let mut replica1 = base.clone().into_signed(&owner_sk)?;
+        let mut writer = base;
+
+        let (first, mut op) = writer.write(random_register_entry(), BTreeSet::new())?;
+        op.sign_with(&owner_sk);
+        replica1.add_op(op)?;
+        let mut replica2 = replica1.clone();
+        assert_eq!(replica1.version(), replica2.version());
+
+        // replica2 gets a further entry atop the first one, and a concurrent branch
+        for children in [[first].into(), BTreeSet::new()] {
+            let (_hash, mut op) = writer.write(random_register_entry(), children)?;
+            op.sign_with(&owner_sk);
+            replica2.add_op(op)?;
+        }
+
+        let version1 = replica1.version();
+        let version2 = replica2.version();
+        assert_eq!((version1.op_count, version1.head_count), (1, 1));
+        assert_eq!((version2.op_count, version2.head_count), (3, 2));
+        assert_ne!(version1.state_hash, version2.state_hash);
+
+        // the unsigned Register holding the same entries is at the same version
+        assert_eq!(writer.version(), version2);
+        assert_eq!(replica2.register()?.version(), version2);
+
+        Ok(())
+    }
+
     // Helpers for tests
     fn gen_reg_replicas(
         authority_sk: Option<SecretKey>,
@@ -506,7 +1821,7 @@ mod tests {
                 let authority_sk = authority_sk.clone().unwrap_or_else(SecretKey::random);
                 let authority = authority_sk.public_key();
                 let perms = perms.clone().unwrap_or_default();
-                let register = Register::new(authority, meta, perms);
+                let register = Register::new(authority, meta, perms, None);
                 (authority_sk, register)
             })
             .collect();
@@ -559,7 +1874,7 @@ mod tests {
         (1..max_quantity + 1).prop_map(move |quantity| {
             let mut replicas = Vec::with_capacity(quantity);
             for _ in 0..quantity {
-                let replica = Register::new(owner, xorname, perms.clone());
+                let replica = Register::new(owner, xorname, perms.clone(), None);
 
                 replicas.push(replica);
             }
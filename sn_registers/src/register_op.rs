@@ -6,13 +6,31 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use crate::{error::Result, Entry, Error, RegisterAddress, User};
+use crate::{
+    error::Result,
+    register::{MAX_REG_ENTRY_SIZE, MAX_REG_NUM_ENTRIES},
+    Entry, Error, RegisterAddress, User,
+};
 
 use bls::PublicKey;
 use crdts::merkle_reg::Node as MerkleDagEntry;
 use serde::{Deserialize, Serialize};
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
+use xor_name::XorName;
+
+/// The format of a `RegisterOp`, which determines the bytes its signature is made on.
+#[derive(
+    Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+pub enum RegisterOpFormat {
+    /// The op is signed on a 64-bit SipHash of its content, which isn't collision resistant.
+    /// Such ops still verify under this scheme, and can be migrated to `V1`, see `RegisterOp::migrate`.
+    V0,
+    /// The op is signed on a 256-bit SHA3 digest of its content.
+    #[default]
+    V1,
+}
 
 /// Register mutation operation to apply to Register.
 /// CRDT Data operation applicable to other Register replica.
@@ -26,6 +44,14 @@ pub struct RegisterOp {
     pub(crate) source: User,
     /// The signature of source on hash(address, crdt_op, source) required to apply the op
     pub(crate) signature: Option<bls::Signature>,
+    /// The format of the op, i.e. how the hash signed is computed.
+    /// Ops serialised before the format was introduced are `V0` ops.
+    #[serde(default = "legacy_format")]
+    pub(crate) format: RegisterOpFormat,
+}
+
+fn legacy_format() -> RegisterOpFormat {
+    RegisterOpFormat::V0
 }
 
 impl std::hash::Hash for RegisterOp {
@@ -34,11 +60,12 @@ impl std::hash::Hash for RegisterOp {
         self.crdt_op.hash().hash(state);
         self.source.hash(state);
         self.signature.hash(state);
+        self.format.hash(state);
     }
 }
 
 impl RegisterOp {
-    /// Create a new RegisterOp
+    /// Create a new RegisterOp, of the current `RegisterOpFormat`
     pub fn new(
         address: RegisterAddress,
         crdt_op: MerkleDagEntry<Entry>,
@@ -50,7 +77,39 @@ impl RegisterOp {
             crdt_op,
             source,
             signature,
+            format: RegisterOpFormat::default(),
+        }
+    }
+
+    /// Create a new RegisterOp, validating the crdt_op is well-formed, see `validate`.
+    pub fn try_new(
+        address: RegisterAddress,
+        crdt_op: MerkleDagEntry<Entry>,
+        source: User,
+        signature: Option<bls::Signature>,
+    ) -> Result<Self> {
+        let op = Self::new(address, crdt_op, source, signature);
+        op.validate()?;
+        Ok(op)
+    }
+
+    /// Check the crdt_op is well-formed: it may reference no more parents than a Register can
+    /// hold entries, and its entry may not exceed the maximum size of an entry.
+    /// Returns `Error::InvalidOp` otherwise.
+    pub fn validate(&self) -> Result<()> {
+        let num_children = self.crdt_op.children.len();
+        if num_children > MAX_REG_NUM_ENTRIES.into() {
+            return Err(Error::InvalidOp(format!(
+                "op references {num_children} parents, max: {MAX_REG_NUM_ENTRIES}"
+            )));
+        }
+        let size = self.crdt_op.value.len();
+        if size > MAX_REG_ENTRY_SIZE {
+            return Err(Error::InvalidOp(format!(
+                "op entry is {size} bytes, max: {MAX_REG_ENTRY_SIZE}"
+            )));
         }
+        Ok(())
     }
 
     /// address of the register this op is destined for
@@ -63,6 +122,11 @@ impl RegisterOp {
         self.source
     }
 
+    /// the format of the operation
+    pub fn format(&self) -> RegisterOpFormat {
+        self.format
+    }
+
     /// Add signature to register Op using provided secret key
     pub fn sign_with(&mut self, sk: &bls::SecretKey) {
         self.source = User::Key(sk.public_key());
@@ -83,19 +147,32 @@ impl RegisterOp {
         Ok(())
     }
 
-    /// Returns a bytes version of the RegisterOp used for signing
+    /// Returns a bytes version of the RegisterOp used for signing, as per its `RegisterOpFormat`:
+    /// the 32-byte SHA3 digest of its address, crdt_op hash and source for `V1` ops.
     /// Use this API when you want to sign a RegisterOp withtout providing a secret key to the RegisterOp API
     pub fn bytes_for_signing(&self) -> Vec<u8> {
-        let mut hasher = DefaultHasher::new();
-        self.address.hash(&mut hasher);
-        self.crdt_op.hash().hash(&mut hasher);
-        self.source.hash(&mut hasher);
-        let hash_value = hasher.finish();
-        let bytes = hash_value.to_ne_bytes();
-        bytes.to_vec()
+        match self.format {
+            RegisterOpFormat::V0 => {
+                let mut hasher = DefaultHasher::new();
+                self.address.hash(&mut hasher);
+                self.crdt_op.hash().hash(&mut hasher);
+                self.source.hash(&mut hasher);
+                let hash_value = hasher.finish();
+                let bytes = hash_value.to_ne_bytes();
+                bytes.to_vec()
+            }
+            RegisterOpFormat::V1 => {
+                let owner = self.address.owner.to_bytes();
+                let crdt_op_hash = self.crdt_op.hash();
+                let source = self.source.to_bytes();
+                let parts: [&[u8]; 4] = [&self.address.meta.0, &owner, &crdt_op_hash, &source];
+                XorName::from_content_parts(&parts).0.to_vec()
+            }
+        }
     }
 
-    /// Check signature of register Op against provided public key
+    /// Check signature of register Op against provided public key, on the bytes of the op's
+    /// own `RegisterOpFormat`, so that legacy `V0` ops keep verifying without being migrated.
     pub fn verify_signature(&self, pk: &PublicKey) -> Result<()> {
         let bytes = self.bytes_for_signing();
         let sig = self.signature.as_ref().ok_or(Error::MissingSignature)?;
@@ -104,4 +181,217 @@ impl RegisterOp {
         }
         Ok(())
     }
+
+    /// Check the op is destined for the `expected` Register, and its signature against the
+    /// provided public key, e.g. for an op received from a peer which could have relayed an op
+    /// signed for another Register.
+    pub fn verify_for_address(&self, pk: &PublicKey, expected: &RegisterAddress) -> Result<()> {
+        if self.address != *expected {
+            return Err(Error::AddressMismatch {
+                expected: Box::new(*expected),
+                got: Box::new(self.address),
+            });
+        }
+        self.verify_signature(pk)
+    }
+
+    /// Migrate a legacy `V0` op to the current `RegisterOpFormat`: its `V0` signature is checked
+    /// against the key of its source, then the op is signed anew with the provided secret key.
+    /// Ops of the current format are left untouched.
+    pub fn migrate(&mut self, sk: &bls::SecretKey) -> Result<()> {
+        if self.format != RegisterOpFormat::V0 {
+            return Ok(());
+        }
+        if let User::Key(pk) = self.source {
+            self.verify_signature(&pk)?;
+        }
+        self.format = RegisterOpFormat::default();
+        self.sign_with(sk);
+        Ok(())
+    }
+}
+
+/// Sign all the `ops` with the secret key, setting their source to its public key, as
+/// `RegisterOp::sign_with` does for a single op, each op being checked to verify in debug builds.
+pub fn sign_ops(ops: &mut [RegisterOp], sk: &bls::SecretKey) {
+    for op in ops.iter_mut() {
+        op.sign_with(sk);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use bls::SecretKey;
+    use std::collections::BTreeSet;
+    use xor_name::XorName;
+
+    fn random_address() -> RegisterAddress {
+        RegisterAddress::new(
+            XorName::random(&mut rand::thread_rng()),
+            SecretKey::random().public_key(),
+        )
+    }
+
+    #[test]
+    fn try_new_accepts_well_formed_op() -> Result<()> {
+        let children: BTreeSet<[u8; 32]> = (0..3)
+            .map(|_| XorName::random(&mut rand::thread_rng()).0)
+            .collect();
+        let crdt_op = MerkleDagEntry {
+            children,
+            value: vec![0x1, 0x2],
+        };
+
+        let op = RegisterOp::try_new(random_address(), crdt_op, User::Anyone, None)?;
+        assert_eq!(op.crdt_op.children.len(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn try_new_rejects_too_many_parents() {
+        let children: BTreeSet<[u8; 32]> = (0..=MAX_REG_NUM_ENTRIES)
+            .map(|_| XorName::random(&mut rand::thread_rng()).0)
+            .collect();
+        let crdt_op = MerkleDagEntry {
+            children,
+            value: vec![0x1],
+        };
+
+        assert!(matches!(
+            RegisterOp::try_new(random_address(), crdt_op, User::Anyone, None),
+            Err(Error::InvalidOp(_))
+        ));
+    }
+
+    #[test]
+    fn try_new_rejects_too_big_an_entry() {
+        let crdt_op = MerkleDagEntry {
+            children: BTreeSet::new(),
+            value: vec![0x1; MAX_REG_ENTRY_SIZE + 1],
+        };
+
+        assert!(matches!(
+            RegisterOp::try_new(random_address(), crdt_op, User::Anyone, None),
+            Err(Error::InvalidOp(_))
+        ));
+    }
+
+    fn random_op() -> RegisterOp {
+        let crdt_op = MerkleDagEntry {
+            children: BTreeSet::new(),
+            value: vec![0x1, 0x2],
+        };
+        RegisterOp::new(random_address(), crdt_op, User::Anyone, None)
+    }
+
+    #[test]
+    fn ops_are_signed_on_a_256_bit_digest() {
+        let sk = SecretKey::random();
+        let mut op = random_op();
+        assert_eq!(op.format(), RegisterOpFormat::V1);
+        assert_eq!(op.bytes_for_signing().len(), 32);
+
+        op.sign_with(&sk);
+        assert!(op.verify_signature(&sk.public_key()).is_ok());
+        assert!(matches!(
+            op.verify_signature(&SecretKey::random().public_key()),
+            Err(Error::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn legacy_ops_verify_under_their_own_format_and_can_be_migrated() -> Result<()> {
+        let sk = SecretKey::random();
+        let mut legacy = random_op();
+        legacy.format = RegisterOpFormat::V0;
+        assert_eq!(legacy.bytes_for_signing().len(), 8);
+        legacy.sign_with(&sk);
+        assert!(legacy.verify_signature(&sk.public_key()).is_ok());
+        assert!(matches!(
+            legacy.verify_signature(&SecretKey::random().public_key()),
+            Err(Error::InvalidSignature)
+        ));
+
+        // a legacy op whose signature doesn't verify isn't migrated
+        let mut forged = legacy.clone();
+        forged.source = User::Key(SecretKey::random().public_key());
+        assert!(matches!(forged.migrate(&sk), Err(Error::InvalidSignature)));
+
+        let mut migrated = legacy;
+        migrated.migrate(&sk)?;
+        assert_eq!(migrated.format(), RegisterOpFormat::V1);
+        assert!(migrated.verify_signature(&sk.public_key()).is_ok());
+
+        // a signature made under one format doesn't verify under the other
+        let mut downgraded = migrated;
+        downgraded.format = RegisterOpFormat::V0;
+        assert!(matches!(
+            downgraded.verify_signature(&sk.public_key()),
+            Err(Error::InvalidSignature)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn ops_serialised_without_a_format_are_legacy_ops(
+    ) -> std::result::Result<(), rmp_serde::decode::Error> {
+        #[derive(Serialize)]
+        struct LegacyRegisterOp {
+            address: RegisterAddress,
+            crdt_op: MerkleDagEntry<Entry>,
+            source: User,
+            signature: Option<bls::Signature>,
+        }
+
+        let op = random_op();
+        let legacy = LegacyRegisterOp {
+            address: op.address,
+            crdt_op: op.crdt_op.clone(),
+            source: op.source,
+            signature: None,
+        };
+        let bytes = rmp_serde::to_vec(&legacy).expect("legacy op to serialise");
+        let deserialised: RegisterOp = rmp_serde::from_slice(&bytes)?;
+        assert_eq!(deserialised.format(), RegisterOpFormat::V0);
+        assert_eq!(deserialised.crdt_op, op.crdt_op);
+
+        Ok(())
+    }
+
+    #[test]
+    fn ops_verify_for_the_address_they_were_signed_for_only() {
+        let sk = SecretKey::random();
+        let mut op = random_op();
+        op.sign_with(&sk);
+        let address = op.address();
+        assert!(op.verify_for_address(&sk.public_key(), &address).is_ok());
+
+        let other = random_address();
+        assert!(matches!(
+            op.verify_for_address(&sk.public_key(), &other),
+            Err(Error::AddressMismatch { expected, got })
+                if *expected == other && *got == address
+        ));
+        // the signature is still checked for the expected address
+        assert!(matches!(
+            op.verify_for_address(&SecretKey::random().public_key(), &address),
+            Err(Error::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn ops_are_signed_in_a_batch() {
+        let sk = SecretKey::random();
+        let mut ops: Vec<RegisterOp> = (0..3).map(|_| random_op()).collect();
+        sign_ops(&mut ops, &sk);
+
+        for op in ops {
+            assert_eq!(op.source(), User::Key(sk.public_key()));
+            assert!(op.verify_signature(&sk.public_key()).is_ok());
+        }
+    }
 }
@@ -33,7 +33,10 @@ mod transfer;
 pub(crate) use self::error::{Error, Result};
 pub use self::transfer::{create_storage_payment_transfer, create_transfer};
 
-use sn_dbc::{Dbc, DbcTransaction, DerivationIndex, DerivedKey, PublicAddress, SignedSpend, Token};
+use sn_dbc::{
+    Dbc, DbcId, DbcTransaction, DerivationIndex, DerivedKey, PublicAddress, SignedSpend, Token,
+};
+use sn_protocol::storage::DbcAddress;
 
 /// The input details necessary to
 /// carry out a transfer of tokens.
@@ -67,6 +70,18 @@ pub struct TransferOutputs {
     pub all_spend_requests: Vec<SpendRequest>,
 }
 
+impl TransferOutputs {
+    /// The ids and network addresses of all the dbcs created by the transaction,
+    /// i.e. the dbcs sent to the recipients as well as the change dbc, if any.
+    pub fn outputs(&self) -> Vec<(DbcId, DbcAddress)> {
+        self.tx
+            .outputs
+            .iter()
+            .map(|output| (output.dbc_id, DbcAddress::from_dbc_id(&output.dbc_id)))
+            .collect()
+    }
+}
+
 /// The parameters necessary to send a spend request to the network.
 #[derive(custom_debug::Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct SpendRequest {
@@ -16,5 +16,7 @@ pub mod client_transfers;
 pub mod dbc_genesis;
 /// Storage payment proofs utilities
 pub mod payment_proof;
+/// Spend validation utilities, shared by nodes and clients.
+pub mod spends;
 /// A wallet for network tokens.
 pub mod wallet;
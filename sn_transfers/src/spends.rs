@@ -0,0 +1,315 @@
+// Copyright 2023 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::dbc_genesis::{is_genesis_parent_tx, GENESIS_DBC};
+
+use itertools::Itertools;
+use sn_dbc::{DbcId, DbcTransaction, Hash, SignedSpend};
+use sn_protocol::error::{Error, Result};
+use std::{
+    collections::{BTreeSet, HashSet},
+    iter::Iterator,
+};
+
+/// Aggregates the provided set of spends by,
+/// - Making sure the Spends are unique
+/// - Making sure the DbcId match the provided one
+/// - Verifying the `spent_tx_hash`
+/// - Sorting and returning < 2 spends as output
+pub fn aggregate_spends<I>(spends: I, valid_dbc_id: DbcId) -> Vec<SignedSpend>
+where
+    I: IntoIterator<Item = SignedSpend>,
+{
+    let spends = spends.into_iter().collect::<HashSet<_>>();
+    // on the unique set of SignedSpends, perform the below filter + sort
+    spends
+        .into_iter()
+        // make sure the dbc_id and the signature are valid.
+        .filter(|signed_spend| {
+            // make sure the dbc_ids are the same
+            signed_spend.dbc_id() == &valid_dbc_id
+                // make sure the spent_tx hash matches
+                && signed_spend
+                    .verify(signed_spend.spent_tx_hash())
+                    .is_ok()
+        })
+        // must be ordered to just store 2 of them.
+        .sorted_by(|a, b| a.cmp(b))
+        .take(2)
+        .collect()
+}
+
+/// Validate a spend the way a node does before storing it, given its parent spends and the spends
+/// already recorded in the network for the same dbc_id.
+/// This checks the signature and any fee output of the spend, that the parent spends are the
+/// ones the spent dbc was created from, and that the spend doesn't conflict with the recorded ones.
+pub fn validate_spend(
+    signed_spend: &SignedSpend,
+    parent_spends: &BTreeSet<SignedSpend>,
+    network_spends: Vec<SignedSpend>,
+) -> Result<()> {
+    verify_spend(signed_spend)?;
+    check_parent_spends(parent_spends, signed_spend)?;
+
+    let dbc_id = *signed_spend.dbc_id();
+    let spends = aggregate_spends(
+        network_spends
+            .into_iter()
+            .chain(std::iter::once(signed_spend.clone())),
+        dbc_id,
+    );
+    if let [spend_one, spend_two, ..] = spends.as_slice() {
+        return Err(Error::DoubleSpendAttempt(
+            Box::new(spend_one.to_owned()),
+            Box::new(spend_two.to_owned()),
+        ));
+    }
+
+    Ok(())
+}
+
+/// Verify the signature of the spend, and its fee output id if it's a storage payment.
+pub fn verify_spend(signed_spend: &SignedSpend) -> Result<()> {
+    if let Err(e) = signed_spend.verify(signed_spend.spent_tx_hash()) {
+        return Err(Error::SpendSignatureInvalid(format!(
+            "while verifying spend for {:?}: {e:?}",
+            signed_spend.dbc_id()
+        )));
+    }
+
+    verify_fee_output_id(&signed_spend.spent_tx(), false)
+}
+
+/// If the given TX is a storage payment, i.e. contains a fee output, then verify FeeOutput's id is
+/// the expected. The fee output id is expected to be built from hashing: root_hash + input DBCs ids.
+/// This requirement makes it possible for this output to be used as an input in a network
+/// rewards/farming reclaiming TX, by making its spend location deterministic, analogous to
+/// how an output DBC Id works for regular outputs.
+/// If 'required' was set to 'true' then the fee output must be non-zero and valid.
+pub fn verify_fee_output_id(spent_tx: &DbcTransaction, required: bool) -> Result<()> {
+    let fee = &spent_tx.fee;
+    debug!("Verification fee: {fee:?}");
+    if fee.is_free() {
+        if required {
+            return Err(Error::PaymentProofInvalidFeeOutput(fee.id));
+        }
+    } else {
+        let mut fee_id_bytes = fee.root_hash.slice().to_vec();
+        spent_tx
+            .inputs
+            .iter()
+            .for_each(|input| fee_id_bytes.extend(&input.dbc_id().to_bytes()));
+
+        if fee.id != Hash::hash(&fee_id_bytes) {
+            return Err(Error::PaymentProofInvalidFeeOutput(fee.id));
+        }
+    }
+
+    Ok(())
+}
+
+/// Fetch all parent spends from the network and check them
+/// they should all exist as valid spends for this current spend attempt to be valid
+/// The signed_spend.dbc_id() shall exist among the parent_tx's outputs.
+pub fn check_parent_spends(
+    parent_spends: &BTreeSet<SignedSpend>,
+    signed_spend: &SignedSpend,
+) -> Result<()> {
+    // skip check if the spent DBC is Genesis
+    if is_genesis_parent_tx(&signed_spend.spend.dbc_creation_tx)
+        && signed_spend.dbc_id() == &GENESIS_DBC.id
+    {
+        trace!(
+            "Validated parent_spends because spent DBC is Genesis: {:?}",
+            signed_spend.dbc_id()
+        );
+        return Ok(());
+    }
+
+    // check that the spent DBC is an output of the parent tx
+    if !signed_spend
+        .spend
+        .dbc_creation_tx
+        .outputs
+        .iter()
+        .any(|o| o.dbc_id() == signed_spend.dbc_id())
+    {
+        return Err(Error::SpendParentTxInvalid(format!(
+            "The DBC we're trying to spend: {:?} is not an output of the parent tx: {:?}",
+            signed_spend, signed_spend.spend.dbc_creation_tx
+        )));
+    }
+
+    // check the parent spends
+    trace!("Validating parent_spends for {:?}", signed_spend.dbc_id());
+    validate_parent_spends(signed_spend, parent_spends)?;
+
+    trace!("Validated parent_spends for {:?}", signed_spend.dbc_id());
+    Ok(())
+}
+
+/// The src_tx is the tx where the dbc to spend, was created.
+/// The signed_spend.dbc_id() shall exist among its outputs.
+fn validate_parent_spends(
+    signed_spend: &SignedSpend,
+    parent_spends: &BTreeSet<SignedSpend>,
+) -> Result<()> {
+    // Check that the parent spends are all from the parent tx
+    for parent_spend in parent_spends {
+        let tx_our_dbc_was_created_in = signed_spend.dbc_creation_tx_hash();
+        let tx_its_parents_where_spent_in = parent_spend.spent_tx_hash();
+        if tx_our_dbc_was_created_in != tx_its_parents_where_spent_in {
+            return Err(Error::SpendParentTxInvalid(format!(
+                "One of the parents was spent in another transaction. Expected: {tx_our_dbc_was_created_in:?} Got: {tx_its_parents_where_spent_in:?}"
+            )));
+        }
+    }
+
+    // Here we check that the DBC we're trying to spend was created in a valid tx
+    if let Err(e) = signed_spend
+        .spend
+        .dbc_creation_tx
+        .verify_against_inputs_spent(parent_spends)
+    {
+        return Err(Error::SpendParentTxInvalid(format!(
+            "verification failed for parent tx for {:?}: {e:?}",
+            signed_spend.dbc_id()
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dbc_genesis::create_first_dbc_from_key, wallet::LocalWallet};
+
+    use assert_fs::TempDir;
+    use bls::SecretKey;
+    use eyre::Result;
+    use sn_dbc::{MainKey, Token};
+
+    // A spend of a dbc created by another (non genesis) spend, along with that parent spend.
+    struct SpendWithParent {
+        signed_spend: SignedSpend,
+        parent_spends: BTreeSet<SignedSpend>,
+        // A wallet holding the same dbc, to spend it a second time.
+        twin_wallet: LocalWallet,
+        _dirs: Vec<TempDir>,
+    }
+
+    async fn spend_with_parent() -> Result<SpendWithParent> {
+        let (first_dir, second_dir) = (TempDir::new()?, TempDir::new()?);
+
+        // place the key of the first dbc in the wallet dir, as done for the genesis wallet
+        let first_key = MainKey::random();
+        let first_wallet_dir = first_dir.path().join("wallet");
+        tokio::fs::create_dir_all(&first_wallet_dir).await?;
+        tokio::fs::write(
+            first_wallet_dir.join("main_key"),
+            hex::encode(first_key.to_bytes()),
+        )
+        .await?;
+        let mut first = LocalWallet::load_from(first_dir.path()).await?;
+        first.deposit(vec![create_first_dbc_from_key(&first_key)?]);
+
+        let mut second = LocalWallet::load_from(second_dir.path()).await?;
+        let to = vec![(Token::from_nano(1000), second.address())];
+        let parent_transfer = first.local_send(to, None).await?;
+        second.deposit(parent_transfer.created_dbcs.clone());
+        second.store().await?;
+        let twin_wallet = LocalWallet::load_from(second_dir.path()).await?;
+
+        let to = vec![(Token::from_nano(100), first.address())];
+        let transfer = second.local_send(to, None).await?;
+
+        Ok(SpendWithParent {
+            signed_spend: transfer.all_spend_requests[0].signed_spend.clone(),
+            parent_spends: parent_transfer
+                .all_spend_requests
+                .iter()
+                .map(|request| request.signed_spend.clone())
+                .collect(),
+            twin_wallet,
+            _dirs: vec![first_dir, second_dir],
+        })
+    }
+
+    #[tokio::test]
+    async fn valid_spend_passes_validation() -> Result<()> {
+        let spend = spend_with_parent().await?;
+
+        validate_spend(&spend.signed_spend, &spend.parent_spends, vec![])?;
+        // the very same spend being already recorded is not a double spend
+        validate_spend(
+            &spend.signed_spend,
+            &spend.parent_spends,
+            vec![spend.signed_spend.clone()],
+        )?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn spend_with_invalid_signature_fails_validation() -> Result<()> {
+        let mut spend = spend_with_parent().await?;
+        spend.signed_spend.derived_key_sig = SecretKey::random().sign(b"not the spend");
+
+        let result = validate_spend(&spend.signed_spend, &spend.parent_spends, vec![]);
+        assert!(matches!(result, Err(Error::SpendSignatureInvalid(_))));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn spend_with_wrong_parents_fails_validation() -> Result<()> {
+        let spend = spend_with_parent().await?;
+
+        let result = validate_spend(&spend.signed_spend, &BTreeSet::new(), vec![]);
+        assert!(matches!(result, Err(Error::SpendParentTxInvalid(_))));
+
+        let unrelated = spend_with_parent().await?;
+        let result = validate_spend(&spend.signed_spend, &unrelated.parent_spends, vec![]);
+        assert!(matches!(result, Err(Error::SpendParentTxInvalid(_))));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn double_spend_fails_validation() -> Result<()> {
+        let mut spend = spend_with_parent().await?;
+        let to = vec![(Token::from_nano(200), MainKey::random().public_address())];
+        let other_transfer = spend.twin_wallet.local_send(to, None).await?;
+        let other_spend = other_transfer.all_spend_requests[0].signed_spend.clone();
+        assert_eq!(other_spend.dbc_id(), spend.signed_spend.dbc_id());
+
+        let result = validate_spend(&spend.signed_spend, &spend.parent_spends, vec![other_spend]);
+        assert!(matches!(result, Err(Error::DoubleSpendAttempt(_, _))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_fee_output_fails_validation() {
+        let mut tx = DbcTransaction::empty();
+        tx.fee.token = Token::from_nano(1);
+
+        let result = verify_fee_output_id(&tx, false);
+        assert!(matches!(
+            result,
+            Err(Error::PaymentProofInvalidFeeOutput(_))
+        ));
+
+        let result = verify_fee_output_id(&DbcTransaction::empty(), true);
+        assert!(matches!(
+            result,
+            Err(Error::PaymentProofInvalidFeeOutput(_))
+        ));
+    }
+}
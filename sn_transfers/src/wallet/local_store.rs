@@ -520,6 +520,41 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn transfer_reports_created_and_change_outputs() -> Result<()> {
+        let dir = create_temp_dir();
+        let root_dir = dir.path().to_path_buf();
+
+        let mut sender = LocalWallet::load_from(&root_dir).await?;
+        let sender_dbc =
+            create_first_dbc_from_key(&sender.key).expect("Genesis creation to succeed.");
+        sender.deposit(vec![sender_dbc]);
+
+        let to = vec![(Token::from_nano(100), MainKey::random().public_address())];
+        let transfer = sender.local_send(to, None).await?;
+        let outputs = transfer.outputs();
+
+        let change_dbc = transfer
+            .change_dbc
+            .as_ref()
+            .expect("There to be a change dbc.");
+        let expected_ids: Vec<_> = transfer
+            .created_dbcs
+            .iter()
+            .chain(std::iter::once(change_dbc))
+            .map(|dbc| dbc.id())
+            .collect();
+
+        assert_eq!(transfer.tx.outputs.len(), outputs.len());
+        assert_eq!(expected_ids.len(), outputs.len());
+        for (dbc_id, address) in &outputs {
+            assert!(expected_ids.contains(dbc_id));
+            assert_eq!(&DbcAddress::from_dbc_id(dbc_id), address);
+        }
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn send_wallet_to_and_from_file() -> Result<()> {
         let dir = create_temp_dir();